@@ -0,0 +1,72 @@
+use actix_web::web::{Data, Json, Query};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  utils::{check_private_instance, is_mod_or_admin_opt},
+};
+use lemmy_db_schema::source::comment_revision::CommentRevision;
+use lemmy_db_views_comment::{
+  api::{ListCommentRevisions, ListCommentRevisionsResponse},
+  CommentView,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+/// Lists the edit history of a comment
+pub async fn list_comment_revisions(
+  data: Query<ListCommentRevisions>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListCommentRevisionsResponse>> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  let local_site = site_view.local_site;
+  check_private_instance(&local_user_view, &local_site)?;
+
+  // This also makes sure that the comment is visible to the user
+  let local_user = local_user_view.as_ref().map(|l| l.local_user.clone());
+  let comment_view = CommentView::read(
+    &mut context.pool(),
+    data.comment_id,
+    local_user.as_ref(),
+    site_view.site.instance_id,
+  )
+  .await?;
+
+  let is_creator = local_user_view
+    .as_ref()
+    .is_some_and(|l| l.person.id == comment_view.creator.id);
+  let is_mod_or_admin = is_mod_or_admin_opt(
+    &mut context.pool(),
+    local_user_view.as_ref(),
+    Some(comment_view.community.id),
+  )
+  .await
+  .is_ok();
+  if !(local_site.edit_history_public || is_creator || is_mod_or_admin) {
+    Err(LemmyErrorType::NotAModOrAdmin)?
+  }
+
+  let cursor_data = if let Some(cursor) = &data.page_cursor {
+    Some(CommentRevision::from_cursor(cursor, &mut context.pool()).await?)
+  } else {
+    None
+  };
+
+  let revisions = CommentRevision::list_for_comment(
+    &mut context.pool(),
+    comment_view.comment.id,
+    cursor_data,
+    data.page_back,
+    data.limit,
+  )
+  .await?;
+
+  let next_page = revisions.last().map(CommentRevision::to_cursor);
+  let prev_page = revisions.first().map(CommentRevision::to_cursor);
+
+  Ok(Json(ListCommentRevisionsResponse {
+    revisions,
+    next_page,
+    prev_page,
+  }))
+}
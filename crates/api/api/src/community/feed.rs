@@ -0,0 +1,172 @@
+use activitypub_federation::config::Data;
+use actix_web::web::{Json, Query};
+use chrono::Utc;
+use lemmy_api_utils::{
+  context::LemmyContext,
+  request::check_url_is_public,
+  utils::{check_community_mod_action, is_admin},
+};
+use lemmy_db_schema::{
+  impls::community_feed::MAX_COMMUNITY_FEEDS,
+  newtypes::{PersonId, TagId},
+  source::{
+    community::Community,
+    community_feed::{CommunityFeed, CommunityFeedInsertForm, CommunityFeedUpdateForm},
+    person::Person,
+    tag::Tag,
+  },
+  traits::Crud,
+  utils::DbPool,
+};
+use lemmy_db_views_community::api::{
+  CreateCommunityFeed,
+  DeleteCommunityFeed,
+  EditCommunityFeed,
+  ListCommunityFeeds,
+  ListCommunityFeedsResponse,
+};
+use lemmy_db_views_community_moderator::CommunityModeratorView;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::SuccessResponse;
+use lemmy_utils::{
+  error::{LemmyErrorExt2, LemmyErrorType, LemmyResult},
+  utils::validation::{build_and_check_regex, is_valid_url},
+};
+use url::Url;
+
+pub async fn create_community_feed(
+  data: Json<CreateCommunityFeed>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<CommunityFeed>> {
+  let community = Community::read(&mut context.pool(), data.community_id).await?;
+  check_community_mod_action(&local_user_view, &community, false, &mut context.pool()).await?;
+
+  let feeds = CommunityFeed::list_for_community(&mut context.pool(), community.id).await?;
+  if feeds.len() >= MAX_COMMUNITY_FEEDS {
+    Err(LemmyErrorType::TooManyCommunityFeeds)?
+  }
+  let url = check_feed_options(
+    &community,
+    &local_user_view,
+    &data.url,
+    data.bot_person_id,
+    data.title_filter.as_deref(),
+    data.tag_id,
+    &mut context.pool(),
+  )
+  .await?;
+
+  let form = CommunityFeedInsertForm {
+    title_filter: data.title_filter.clone(),
+    tag_id: data.tag_id,
+    language_id: data.language_id,
+    ..CommunityFeedInsertForm::new(community.id, url.into(), data.bot_person_id)
+  };
+  let feed = CommunityFeed::create(&mut context.pool(), &form).await?;
+
+  Ok(Json(feed))
+}
+
+pub async fn edit_community_feed(
+  data: Json<EditCommunityFeed>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<CommunityFeed>> {
+  let feed = CommunityFeed::read(&mut context.pool(), data.community_feed_id).await?;
+  let community = Community::read(&mut context.pool(), feed.community_id).await?;
+  check_community_mod_action(&local_user_view, &community, false, &mut context.pool()).await?;
+
+  let url = check_feed_options(
+    &community,
+    &local_user_view,
+    &data.url,
+    data.bot_person_id,
+    data.title_filter.as_deref(),
+    data.tag_id,
+    &mut context.pool(),
+  )
+  .await?;
+
+  let form = CommunityFeedUpdateForm {
+    url: Some(url.into()),
+    bot_person_id: Some(data.bot_person_id),
+    title_filter: Some(data.title_filter.clone()),
+    tag_id: Some(data.tag_id),
+    language_id: Some(data.language_id),
+    // Show the result of the new settings
+    last_error: Some(None),
+    last_fetched_at: Some(None),
+    updated_at: Some(Some(Utc::now())),
+  };
+  let feed = CommunityFeed::update(&mut context.pool(), feed.id, &form).await?;
+
+  Ok(Json(feed))
+}
+
+pub async fn delete_community_feed(
+  data: Json<DeleteCommunityFeed>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<SuccessResponse>> {
+  let feed = CommunityFeed::read(&mut context.pool(), data.community_feed_id).await?;
+  let community = Community::read(&mut context.pool(), feed.community_id).await?;
+  check_community_mod_action(&local_user_view, &community, false, &mut context.pool()).await?;
+
+  CommunityFeed::delete(&mut context.pool(), feed.id).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
+
+pub async fn list_community_feeds(
+  data: Query<ListCommunityFeeds>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<ListCommunityFeedsResponse>> {
+  let community = Community::read(&mut context.pool(), data.community_id).await?;
+  check_community_mod_action(&local_user_view, &community, false, &mut context.pool()).await?;
+
+  let feeds = CommunityFeed::list_for_community(&mut context.pool(), community.id).await?;
+
+  Ok(Json(ListCommunityFeedsResponse { feeds }))
+}
+
+/// Validate the feed url, title filter and tag, and check that the bot is a local bot account
+/// which moderates the community. Feeds can only be registered by the bot itself or an admin,
+/// otherwise any mod could publish posts under the name of another user's bot.
+async fn check_feed_options(
+  community: &Community,
+  local_user_view: &LocalUserView,
+  url: &str,
+  bot_person_id: PersonId,
+  title_filter: Option<&str>,
+  tag_id: Option<TagId>,
+  pool: &mut DbPool<'_>,
+) -> LemmyResult<Url> {
+  let url = Url::parse(url)?;
+  is_valid_url(&url)?;
+  // Fetch errors are shown to mods, so don't allow probing the local network
+  check_url_is_public(&url).await?;
+  if title_filter.is_some() {
+    build_and_check_regex(title_filter)?;
+  }
+
+  let bot = Person::read(pool, bot_person_id).await?;
+  if !bot.local || !bot.bot_account || bot.deleted {
+    Err(LemmyErrorType::InvalidFeedBot)?
+  }
+  if bot.id != local_user_view.person.id && is_admin(local_user_view).is_err() {
+    Err(LemmyErrorType::InvalidFeedBot)?
+  }
+  CommunityModeratorView::check_is_community_moderator(pool, community.id, bot.id)
+    .await
+    .with_lemmy_type(LemmyErrorType::InvalidFeedBot)?;
+
+  if let Some(tag_id) = tag_id {
+    let tag = Tag::read(pool, tag_id).await?;
+    if tag.community_id != community.id || tag.deleted {
+      Err(LemmyErrorType::NotFound)?
+    }
+  }
+  Ok(url)
+}
@@ -1,6 +1,7 @@
 pub mod add_mod;
 pub mod ban;
 pub mod block;
+pub mod feed;
 pub mod follow;
 pub mod multi_community_follow;
 pub mod pending_follows;
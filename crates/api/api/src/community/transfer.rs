@@ -106,5 +106,6 @@ pub async fn transfer_community(
     site: None,
     moderators,
     discussion_languages: vec![],
+    backfill: None,
   }))
 }
@@ -0,0 +1,68 @@
+use actix_web::web::{Data, Json};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  send_activity::{ActivityChannel, SendActivityData},
+  utils::slur_regex,
+};
+use lemmy_db_schema::{
+  impls::person_device_key::MAX_DEVICE_KEYS,
+  source::person_device_key::{PersonDeviceKey, PersonDeviceKeyInsertForm},
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person::api::{AddDeviceKey, DeleteDeviceKey, DeviceKeyResponse};
+use lemmy_db_views_site::api::SuccessResponse;
+use lemmy_utils::{
+  error::{LemmyErrorType, LemmyResult},
+  utils::{slurs::check_slurs_opt, validation::is_valid_device_key},
+};
+use url::Url;
+use uuid::Uuid;
+
+pub async fn add_device_key(
+  data: Json<AddDeviceKey>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<DeviceKeyResponse>> {
+  let person = &local_user_view.person;
+  let slur_regex = slur_regex(&context).await?;
+  check_slurs_opt(&data.device_name, &slur_regex)?;
+  is_valid_device_key(&data.public_key, data.device_name.as_deref())?;
+
+  let device_keys = PersonDeviceKey::list_for_person(&mut context.pool(), person.id).await?;
+  if device_keys.len() >= MAX_DEVICE_KEYS {
+    Err(LemmyErrorType::TooManyDeviceKeys)?
+  }
+
+  // Keys are embedded in the user actor, so they only need a fragment id
+  let ap_id = Url::parse(&format!("{}#device-key-{}", person.ap_id, Uuid::new_v4()))?;
+  let form = PersonDeviceKeyInsertForm {
+    device_name: data.device_name.clone(),
+    ..PersonDeviceKeyInsertForm::new(person.id, ap_id.into(), data.public_key.clone())
+  };
+  let device_key = PersonDeviceKey::create(&mut context.pool(), &form).await?;
+
+  // Keys are part of the actor, so other instances need to refetch it
+  ActivityChannel::submit_activity(SendActivityData::UpdateUser(person.clone()), &context)?;
+
+  Ok(Json(DeviceKeyResponse { device_key }))
+}
+
+pub async fn delete_device_key(
+  data: Json<DeleteDeviceKey>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<SuccessResponse>> {
+  PersonDeviceKey::delete(
+    &mut context.pool(),
+    data.device_key_id,
+    local_user_view.person.id,
+  )
+  .await?;
+
+  ActivityChannel::submit_activity(
+    SendActivityData::UpdateUser(local_user_view.person),
+    &context,
+  )?;
+
+  Ok(Json(SuccessResponse::default()))
+}
@@ -0,0 +1,63 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::{
+  context::LemmyContext,
+  send_activity::{ActivityChannel, SendActivityData},
+  utils::check_local_user_valid,
+};
+use lemmy_db_schema::{
+  source::person::{Person, PersonActions, PersonFollowerForm},
+  traits::{Blockable, Crud, Followable},
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person::{
+  api::{FollowPerson, FollowPersonResponse},
+  PersonView,
+};
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+pub async fn follow_person(
+  data: Json<FollowPerson>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<FollowPersonResponse>> {
+  check_local_user_valid(&local_user_view)?;
+  let target_id = data.person_id;
+  let my_person_id = local_user_view.person.id;
+  let local_instance_id = local_user_view.person.instance_id;
+
+  if target_id == my_person_id {
+    Err(LemmyErrorType::CantFollowYourself)?
+  }
+
+  let target = Person::read(&mut context.pool(), target_id).await?;
+
+  if data.follow {
+    // Dont allow following someone who blocked you
+    PersonActions::read_block(&mut context.pool(), target_id, my_person_id).await?;
+
+    // Local follow is accepted immediately, remote follow needs to be federated first
+    let form = PersonFollowerForm::new(target_id, my_person_id, !target.local);
+    PersonActions::follow(&mut context.pool(), &form).await?;
+  } else {
+    PersonActions::unfollow(&mut context.pool(), my_person_id, target_id).await?;
+  }
+
+  // Send the federated follow
+  if !target.local {
+    ActivityChannel::submit_activity(
+      SendActivityData::FollowPerson(target, local_user_view.person.clone(), data.follow),
+      &context,
+    )?;
+  }
+
+  let person_view = PersonView::read(
+    &mut context.pool(),
+    target_id,
+    Some(my_person_id),
+    local_instance_id,
+    false,
+  )
+  .await?;
+  Ok(Json(FollowPersonResponse { person_view }))
+}
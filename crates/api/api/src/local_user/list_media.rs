@@ -1,11 +1,12 @@
 use actix_web::web::{Data, Json, Query};
-use lemmy_api_utils::context::LemmyContext;
-use lemmy_db_schema::traits::PaginationCursorBuilder;
+use lemmy_api_utils::{context::LemmyContext, utils::media_quota};
+use lemmy_db_schema::{source::images::LocalImage, traits::PaginationCursorBuilder};
 use lemmy_db_views_local_image::{
-  api::{ListMedia, ListMediaResponse},
+  api::{ListMedia, ListMediaResponse, MediaUsage},
   LocalImageView,
 };
 use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::SiteView;
 use lemmy_utils::error::LemmyResult;
 
 pub async fn list_media(
@@ -31,9 +32,16 @@ pub async fn list_media(
   let next_page = images.last().map(PaginationCursorBuilder::to_cursor);
   let prev_page = images.first().map(PaginationCursorBuilder::to_cursor);
 
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  let usage = MediaUsage {
+    used_bytes: LocalImage::get_usage(&mut context.pool(), local_user_view.person.id).await?,
+    quota_bytes: media_quota(&local_user_view, &local_site),
+  };
+
   Ok(Json(ListMediaResponse {
     images,
     next_page,
     prev_page,
+    usage: Some(usage),
   }))
 }
@@ -0,0 +1,92 @@
+use activitypub_federation::config::Data;
+use actix_web::web::{Json, Query};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  utils::{check_private_instance, is_admin},
+};
+use lemmy_db_schema::traits::PaginationCursorBuilder;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person::{
+  api::{ListPersonFollows, ListPersonFollowsResponse},
+  impls::PersonQuery,
+  PersonView,
+};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+/// Lists the people who follow the given person.
+pub async fn list_person_followers(
+  data: Query<ListPersonFollows>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListPersonFollowsResponse>> {
+  list_person_follows(data.into_inner(), true, context, local_user_view).await
+}
+
+/// Lists the people who are followed by the given person.
+pub async fn list_person_following(
+  data: Query<ListPersonFollows>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListPersonFollowsResponse>> {
+  list_person_follows(data.into_inner(), false, context, local_user_view).await
+}
+
+async fn list_person_follows(
+  data: ListPersonFollows,
+  followers: bool,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListPersonFollowsResponse>> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  check_private_instance(&local_user_view, &site_view.local_site)?;
+  let my_person_id = local_user_view.as_ref().map(|l| l.person.id);
+
+  // Local users can hide their follows from everyone except themselves and admins
+  let target_user = LocalUserView::read_person(&mut context.pool(), data.person_id)
+    .await
+    .ok();
+  let is_self = my_person_id == Some(data.person_id);
+  let is_admin = local_user_view
+    .as_ref()
+    .map(|l| is_admin(l).is_ok())
+    .unwrap_or_default();
+  if target_user.is_some_and(|t| t.local_user.hide_follows) && !is_self && !is_admin {
+    Err(LemmyErrorType::PersonFollowsHidden)?
+  }
+
+  let cursor_data = if let Some(cursor) = &data.page_cursor {
+    Some(PersonView::from_cursor(cursor, &mut context.pool()).await?)
+  } else {
+    None
+  };
+
+  let (followers_of, followed_by) = if followers {
+    (Some(data.person_id), None)
+  } else {
+    (None, Some(data.person_id))
+  };
+  let persons = PersonQuery {
+    followers_of,
+    followed_by,
+    cursor_data,
+    page_back: data.page_back,
+    limit: data.limit,
+    ..Default::default()
+  }
+  .list(
+    my_person_id,
+    site_view.site.instance_id,
+    &mut context.pool(),
+  )
+  .await?;
+
+  let next_page = persons.last().map(PaginationCursorBuilder::to_cursor);
+  let prev_page = persons.first().map(PaginationCursorBuilder::to_cursor);
+
+  Ok(Json(ListPersonFollowsResponse {
+    persons,
+    next_page,
+    prev_page,
+  }))
+}
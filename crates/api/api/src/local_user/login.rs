@@ -21,6 +21,19 @@ pub async fn login(
   req: HttpRequest,
   context: Data<LemmyContext>,
 ) -> LemmyResult<Json<LoginResponse>> {
+  let local_user_view = check_login(&data, &context).await?;
+
+  let jwt = Claims::generate(local_user_view.local_user.id, req, &context).await?;
+
+  Ok(Json(LoginResponse {
+    jwt: Some(jwt.clone()),
+    verify_email_sent: false,
+    registration_created: false,
+  }))
+}
+
+/// Checks the login credentials without creating a session.
+pub async fn check_login(data: &Login, context: &LemmyContext) -> LemmyResult<LocalUserView> {
   let site_view = SiteView::read_local(&mut context.pool()).await?;
 
   // Fetch that username / email
@@ -53,11 +66,5 @@ pub async fn login(
     )?;
   }
 
-  let jwt = Claims::generate(local_user_view.local_user.id, req, &context).await?;
-
-  Ok(Json(LoginResponse {
-    jwt: Some(jwt.clone()),
-    verify_email_sent: false,
-    registration_created: false,
-  }))
+  Ok(local_user_view)
 }
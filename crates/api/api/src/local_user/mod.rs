@@ -3,14 +3,17 @@ pub mod ban_person;
 pub mod block;
 pub mod change_password;
 pub mod change_password_after_reset;
+pub mod device_key;
 pub mod donation_dialog_shown;
 pub mod export_data;
+pub mod follow_person;
 pub mod generate_totp_secret;
 pub mod get_captcha;
 pub mod list_hidden;
 pub mod list_liked;
 pub mod list_logins;
 pub mod list_media;
+pub mod list_person_follows;
 pub mod list_read;
 pub mod list_saved;
 pub mod login;
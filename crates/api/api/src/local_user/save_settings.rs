@@ -165,6 +165,7 @@ pub async fn save_user_settings(
     show_downvotes: data.show_downvotes,
     show_upvote_percentage: data.show_upvote_percentage,
     show_person_votes: data.show_person_votes,
+    hide_follows: data.hide_follows,
     ..Default::default()
   };
 
@@ -0,0 +1,78 @@
+use actix_web::web::{Data, Json, Query};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  utils::{check_private_instance, is_mod_or_admin_opt},
+};
+use lemmy_db_schema::{
+  source::{post::Post, post_revision::PostRevision},
+  traits::Crud,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_post::{
+  api::{ListPostRevisions, ListPostRevisionsResponse},
+  PostView,
+};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+/// Lists the edit history of a post
+pub async fn list_post_revisions(
+  data: Query<ListPostRevisions>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListPostRevisionsResponse>> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  let local_site = site_view.local_site;
+  check_private_instance(&local_user_view, &local_site)?;
+
+  let post = Post::read(&mut context.pool(), data.post_id).await?;
+  let is_mod_or_admin = is_mod_or_admin_opt(
+    &mut context.pool(),
+    local_user_view.as_ref(),
+    Some(post.community_id),
+  )
+  .await
+  .is_ok();
+
+  // Make sure the post itself is visible to the user
+  let local_user = local_user_view.as_ref().map(|l| l.local_user.clone());
+  PostView::read(
+    &mut context.pool(),
+    post.id,
+    local_user.as_ref(),
+    site_view.site.instance_id,
+    is_mod_or_admin,
+  )
+  .await?;
+
+  let is_creator = local_user_view
+    .as_ref()
+    .is_some_and(|l| Post::is_post_creator(l.person.id, post.creator_id));
+  if !(local_site.edit_history_public || is_creator || is_mod_or_admin) {
+    Err(LemmyErrorType::NotAModOrAdmin)?
+  }
+
+  let cursor_data = if let Some(cursor) = &data.page_cursor {
+    Some(PostRevision::from_cursor(cursor, &mut context.pool()).await?)
+  } else {
+    None
+  };
+
+  let revisions = PostRevision::list_for_post(
+    &mut context.pool(),
+    post.id,
+    cursor_data,
+    data.page_back,
+    data.limit,
+  )
+  .await?;
+
+  let next_page = revisions.last().map(PostRevision::to_cursor);
+  let prev_page = revisions.first().map(PostRevision::to_cursor);
+
+  Ok(Json(ListPostRevisionsResponse {
+    revisions,
+    next_page,
+    prev_page,
+  }))
+}
@@ -3,6 +3,7 @@ pub mod get_link_metadata;
 pub mod hide;
 pub mod like;
 pub mod list_post_likes;
+pub mod list_revisions;
 pub mod lock;
 pub mod mark_many_read;
 pub mod mark_read;
@@ -0,0 +1,65 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use chrono::Utc;
+use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
+use lemmy_db_schema::source::{
+  activity::SentActivity,
+  federation_queue_control::{FederationQueueControl, FederationQueueControlForm},
+  federation_queue_state::FederationQueueState,
+  instance::Instance,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::{
+  AdminEditFederationQueue,
+  FederationQueueView,
+  ListFederationQueuesResponse,
+  SuccessResponse,
+};
+use lemmy_utils::error::LemmyResult;
+
+pub async fn admin_list_federation_queues(
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<ListFederationQueuesResponse>> {
+  is_admin(&local_user_view)?;
+
+  let latest_id = SentActivity::read_latest_id(&mut context.pool()).await?;
+  let queues = FederationQueueState::list_with_control(&mut context.pool())
+    .await?
+    .into_iter()
+    .map(|(instance, state, control)| FederationQueueView {
+      instance,
+      queue_depth: latest_id.0 - state.last_successful_id.map(|i| i.0).unwrap_or(latest_id.0),
+      federation_state: state.into(),
+      control,
+    })
+    .collect();
+
+  Ok(Json(ListFederationQueuesResponse { queues }))
+}
+
+pub async fn admin_edit_federation_queue(
+  data: Json<AdminEditFederationQueue>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  // make sure that the instance and activity exist
+  Instance::read(&mut context.pool(), data.instance_id).await?;
+  if let Some(activity_id) = data.resend_activity_id {
+    SentActivity::read(&mut context.pool(), activity_id).await?;
+  }
+
+  let form = FederationQueueControlForm {
+    instance_id: data.instance_id,
+    paused: data.paused,
+    skip_backlog: data.skip_backlog,
+    reset_fail_count: data.reset_fail_count,
+    resend_activity_id: data.resend_activity_id.map(Some),
+    updated_at: Some(Utc::now()),
+  };
+  FederationQueueControl::upsert(&mut context.pool(), &form).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
@@ -0,0 +1,99 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::{
+  context::LemmyContext,
+  image_moderation::purge_matching_images,
+  utils::is_admin,
+};
+use lemmy_db_schema::{
+  source::{
+    images::{ImageDetails, ImageHashBlock, ImageHashBlockForm},
+    mod_log::admin::{AdminImageModeration, AdminImageModerationForm},
+  },
+  traits::Crud,
+};
+use lemmy_db_schema_file::enums::ImageModerationAction;
+use lemmy_db_views_local_image::api::{
+  BlockImageHash,
+  ImageHashBlockResponse,
+  ListImageHashBlocksResponse,
+  UnblockImageHash,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::SuccessResponse;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+pub async fn block_image_hash(
+  data: Json<BlockImageHash>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<ImageHashBlockResponse>> {
+  is_admin(&local_user_view)?;
+
+  // Only images which were uploaded or proxied after hashing was introduced have a hash
+  let hash = match (data.hash, &data.image_url) {
+    (Some(hash), _) => hash,
+    (None, Some(image_url)) => ImageDetails::read(&mut context.pool(), &image_url.clone().into())
+      .await?
+      .and_then(|details| details.perceptual_hash)
+      .ok_or(LemmyErrorType::NotFound)?,
+    (None, None) => Err(LemmyErrorType::NotFound)?,
+  };
+
+  let form = ImageHashBlockForm {
+    hash,
+    reason: data.reason.clone(),
+  };
+  let image_hash_block = ImageHashBlock::create(&mut context.pool(), &form).await?;
+
+  let mod_log_form = AdminImageModerationForm {
+    admin_person_id: Some(local_user_view.person.id),
+    person_id: None,
+    action: ImageModerationAction::BlockHash,
+    hash: Some(hash),
+    image_url: None,
+    reason: data.reason.clone(),
+  };
+  AdminImageModeration::create(&mut context.pool(), &mod_log_form).await?;
+
+  let purged =
+    purge_matching_images(&image_hash_block, local_user_view.person.id, &context).await?;
+
+  Ok(Json(ImageHashBlockResponse {
+    image_hash_block,
+    purged,
+  }))
+}
+
+pub async fn unblock_image_hash(
+  data: Json<UnblockImageHash>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  ImageHashBlock::delete(&mut context.pool(), data.hash).await?;
+
+  let mod_log_form = AdminImageModerationForm {
+    admin_person_id: Some(local_user_view.person.id),
+    person_id: None,
+    action: ImageModerationAction::UnblockHash,
+    hash: Some(data.hash),
+    image_url: None,
+    reason: data.reason.clone(),
+  };
+  AdminImageModeration::create(&mut context.pool(), &mod_log_form).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
+
+pub async fn list_image_hash_blocks(
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<ListImageHashBlocksResponse>> {
+  is_admin(&local_user_view)?;
+
+  let image_hash_blocks = ImageHashBlock::list(&mut context.pool()).await?;
+
+  Ok(Json(ListImageHashBlocksResponse { image_hash_blocks }))
+}
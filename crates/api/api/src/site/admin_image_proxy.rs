@@ -0,0 +1,17 @@
+use actix_web::web::{Data, Json};
+use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
+use lemmy_db_schema::source::images::RemoteImage;
+use lemmy_db_views_local_image::api::ImageProxyStatsResponse;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_utils::error::LemmyResult;
+
+pub async fn get_image_proxy_stats(
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<ImageProxyStatsResponse>> {
+  is_admin(&local_user_view)?;
+
+  let stats = RemoteImage::cache_stats(&mut context.pool()).await?;
+
+  Ok(Json(ImageProxyStatsResponse { stats }))
+}
@@ -0,0 +1,53 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
+use lemmy_db_schema::{
+  source::{
+    images::ImageDetails,
+    mod_log::admin::{AdminImageModeration, AdminImageModerationForm},
+  },
+  traits::Crud,
+};
+use lemmy_db_schema_file::enums::ImageModerationAction;
+use lemmy_db_views_local_image::api::{ApproveQuarantinedImage, ListQuarantinedImagesResponse};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::SuccessResponse;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+pub async fn approve_quarantined_image(
+  data: Json<ApproveQuarantinedImage>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  let image_url = data.image_url.clone().into();
+  let image = ImageDetails::read(&mut context.pool(), &image_url)
+    .await?
+    .filter(|image| image.quarantined)
+    .ok_or(LemmyErrorType::NotFound)?;
+  ImageDetails::approve(&mut context.pool(), &image.link).await?;
+
+  let mod_log_form = AdminImageModerationForm {
+    admin_person_id: Some(local_user_view.person.id),
+    person_id: None,
+    action: ImageModerationAction::ApproveUpload,
+    hash: image.perceptual_hash,
+    image_url: Some(image_url),
+    reason: data.reason.clone(),
+  };
+  AdminImageModeration::create(&mut context.pool(), &mod_log_form).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
+
+pub async fn list_quarantined_images(
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<ListQuarantinedImagesResponse>> {
+  is_admin(&local_user_view)?;
+
+  let images = ImageDetails::list_quarantined(&mut context.pool()).await?;
+
+  Ok(Json(ListQuarantinedImagesResponse { images }))
+}
@@ -0,0 +1,54 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::{
+  context::LemmyContext,
+  utils::{invalidate_instance_policies, is_admin},
+};
+use lemmy_db_schema::source::{
+  instance::Instance,
+  instance_policy::{InstancePolicy, InstancePolicyForm},
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::{AdminSetInstancePolicy, SuccessResponse};
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+pub async fn admin_set_instance_policy(
+  data: Json<AdminSetInstancePolicy>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  let data = data.into_inner();
+  let rewrite_links_from = data.rewrite_links_from.filter(|l| !l.is_empty());
+  let rewrite_links_to = data.rewrite_links_to.filter(|l| !l.is_empty());
+  if rewrite_links_from.is_some() != rewrite_links_to.is_some() {
+    Err(LemmyErrorType::InvalidLinkRewrite)?
+  }
+
+  let instance_id = Instance::read_or_create(&mut context.pool(), data.instance)
+    .await?
+    .id;
+  let form = InstancePolicyForm {
+    reject_media: data.reject_media.unwrap_or_default(),
+    force_nsfw: data.force_nsfw.unwrap_or_default(),
+    strip_votes: data.strip_votes.unwrap_or_default(),
+    silence: data.silence.unwrap_or_default(),
+    reject_reports: data.reject_reports.unwrap_or_default(),
+    quarantine_communities: data.quarantine_communities.unwrap_or_default(),
+    rewrite_links_from,
+    rewrite_links_to,
+    never_cache_media: data.never_cache_media.unwrap_or_default(),
+    reason: data.reason,
+    ..InstancePolicyForm::new(instance_id)
+  };
+
+  if form.has_rules() {
+    InstancePolicy::upsert(&mut context.pool(), &form).await?;
+  } else {
+    InstancePolicy::delete(&mut context.pool(), instance_id).await?;
+  }
+  invalidate_instance_policies();
+
+  Ok(Json(SuccessResponse::default()))
+}
@@ -0,0 +1,29 @@
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
+use lemmy_db_schema::source::local_user::{LocalUser, LocalUserUpdateForm};
+use lemmy_db_views_local_image::api::AdminSetMediaQuota;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::SuccessResponse;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+
+pub async fn admin_set_media_quota(
+  data: Json<AdminSetMediaQuota>,
+  local_user_view: LocalUserView,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  if data.media_quota_mb.is_some_and(|q| q < 0) {
+    Err(LemmyErrorType::InvalidMediaQuota)?
+  }
+
+  let target = LocalUserView::read_person(&mut context.pool(), data.person_id).await?;
+  let form = LocalUserUpdateForm {
+    media_quota_mb: Some(data.media_quota_mb),
+    ..Default::default()
+  };
+  LocalUser::update(&mut context.pool(), target.local_user.id, &form).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
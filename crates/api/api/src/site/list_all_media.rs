@@ -2,8 +2,9 @@ use actix_web::web::{Data, Json, Query};
 use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
 use lemmy_db_schema::traits::PaginationCursorBuilder;
 use lemmy_db_views_local_image::{
-  api::{ListMedia, ListMediaResponse},
+  api::{ListMedia, ListMediaResponse, ListMediaUploaders, ListMediaUploadersResponse},
   LocalImageView,
+  MediaUploaderView,
 };
 use lemmy_db_views_local_user::LocalUserView;
 use lemmy_utils::error::LemmyResult;
@@ -33,5 +34,23 @@ pub async fn list_all_media(
     images,
     next_page,
     prev_page,
+    usage: None,
   }))
 }
+
+pub async fn list_media_uploaders(
+  data: Query<ListMediaUploaders>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<ListMediaUploadersResponse>> {
+  is_admin(&local_user_view)?;
+
+  let uploaders = MediaUploaderView::list(
+    &mut context.pool(),
+    data.sort.unwrap_or_default(),
+    data.limit,
+  )
+  .await?;
+
+  Ok(Json(ListMediaUploadersResponse { uploaders }))
+}
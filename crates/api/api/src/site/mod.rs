@@ -1,6 +1,12 @@
 pub mod admin_allow_instance;
 pub mod admin_block_instance;
+pub mod admin_federation_queue;
+pub mod admin_image_hash;
+pub mod admin_image_proxy;
+pub mod admin_image_quarantine;
+pub mod admin_instance_policy;
 pub mod admin_list_users;
+pub mod admin_media_quota;
 pub mod federated_instances;
 pub mod leave_admin;
 pub mod list_all_media;
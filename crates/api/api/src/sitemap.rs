@@ -1,14 +1,33 @@
 use actix_web::{
+  body::MessageBody,
   http::header::{self, CacheDirective},
-  web::Data,
+  web::{Data, Path},
   HttpResponse,
 };
-use lemmy_api_utils::context::LemmyContext;
-use lemmy_db_schema::{newtypes::DbUrl, source::post::Post};
-use lemmy_utils::error::LemmyResult;
-use sitemap_rs::{url::Url, url_set::UrlSet};
+use chrono::{DateTime, Months, Utc};
+use lemmy_api_utils::{context::LemmyContext, utils::check_private_instance};
+use lemmy_db_schema::{
+  impls::local_user::LocalUserOptionHelper,
+  newtypes::DbUrl,
+  source::{
+    community::Community,
+    local_user::LocalUser,
+    person::Person,
+    post::Post,
+    sitemap::{Sitemap, SitemapForm},
+  },
+  utils::{DbPool, SITEMAP_LIMIT},
+};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+use sitemap_rs::{sitemap, sitemap_index::SitemapIndex, url::Url, url_set::UrlSet};
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
 use tracing::info;
 
+const COMMUNITIES_SITEMAP: &str = "communities";
+const USERS_SITEMAP: &str = "users";
+
 fn generate_urlset(posts: Vec<(DbUrl, chrono::DateTime<chrono::Utc>)>) -> LemmyResult<UrlSet> {
   let urls = posts
     .into_iter()
@@ -23,26 +42,136 @@ fn generate_urlset(posts: Vec<(DbUrl, chrono::DateTime<chrono::Utc>)>) -> LemmyR
   Ok(UrlSet::new(urls)?)
 }
 
+fn generate_index(
+  sitemaps: Vec<(String, DateTime<Utc>)>,
+  protocol_and_hostname: &str,
+) -> LemmyResult<SitemapIndex> {
+  let sitemaps = sitemaps
+    .into_iter()
+    .map(|(name, last_modified)| {
+      sitemap::Sitemap::new(
+        format!("{protocol_and_hostname}/sitemap/{name}.xml"),
+        Some(last_modified.into()),
+      )
+    })
+    .collect();
+
+  Ok(SitemapIndex::new(sitemaps)?)
+}
+
+fn xml_response(body: impl MessageBody + 'static) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("application/xml")
+    .insert_header(header::CacheControl(vec![CacheDirective::MaxAge(3_600)])) // 1 h
+    .body(body)
+}
+
+/// The sitemap index, which links to the sitemaps for communities, users and the posts of each
+/// month.
 pub async fn get_sitemap(context: Data<LemmyContext>) -> LemmyResult<HttpResponse> {
-  info!("Generating sitemap...",);
-  let posts = Post::list_for_sitemap(&mut context.pool()).await?;
-  info!("Loaded latest {} posts", posts.len());
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  check_private_instance(&None, &local_site)?;
+
+  // Sitemaps are generated by a scheduled task, which hasn't run yet after the first start. In
+  // that case generate them here, but only once so that concurrent requests don't all do it.
+  static INITIAL_UPDATE: OnceCell<()> = OnceCell::const_new();
+  let mut sitemaps = Sitemap::list(&mut context.pool()).await?;
+  if sitemaps.is_empty() && !INITIAL_UPDATE.initialized() {
+    INITIAL_UPDATE
+      .get_or_try_init(|| update_sitemaps(&context))
+      .await?;
+    sitemaps = Sitemap::list(&mut context.pool()).await?;
+  }
+  let sitemaps = sitemaps
+    .into_iter()
+    .map(|(name, _, last_modified)| (name, last_modified))
+    .collect();
 
   let mut buf = Vec::<u8>::new();
-  generate_urlset(posts)?.write(&mut buf)?;
-
-  Ok(
-    HttpResponse::Ok()
-      .content_type("application/xml")
-      .insert_header(header::CacheControl(vec![CacheDirective::MaxAge(3_600)])) // 1 h
-      .body(buf),
-  )
+  generate_index(sitemaps, &context.settings().get_protocol_and_hostname())?.write(&mut buf)?;
+  Ok(xml_response(buf))
+}
+
+pub async fn get_sitemap_file(
+  name: Path<String>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  check_private_instance(&None, &local_site)?;
+
+  let sitemap = Sitemap::read(&mut context.pool(), &name)
+    .await?
+    .ok_or(LemmyErrorType::NotFound)?;
+  Ok(xml_response(sitemap.content))
+}
+
+/// Generate the sitemaps whose urls changed since they were last generated. Post sitemaps are
+/// only regenerated for months with new, edited or removed posts.
+pub async fn update_sitemaps(context: &LemmyContext) -> LemmyResult<()> {
+  info!("Updating sitemaps...");
+  let pool = &mut context.pool();
+  let site = SiteView::read_local(pool).await?.site;
+  let include_nsfw = None::<&LocalUser>.show_nsfw(&site);
+  let mut stale: HashMap<_, _> = Sitemap::list(pool)
+    .await?
+    .into_iter()
+    .map(|(name, url_count, last_modified)| (name, (url_count, last_modified)))
+    .collect();
+
+  for (month, last_modified, post_count) in Post::list_sitemap_months(pool, include_nsfw).await? {
+    let name = format!("posts-{}", month.format("%Y-%m"));
+    let url_count = i32::try_from(post_count.min(SITEMAP_LIMIT))?;
+    if stale.remove(&name) == Some((url_count, last_modified)) {
+      continue;
+    }
+    let month_end = month
+      .checked_add_months(Months::new(1))
+      .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let posts = Post::list_for_sitemap(pool, month, month_end, include_nsfw).await?;
+    save_sitemap(pool, name, posts, url_count, last_modified).await?;
+  }
+
+  let communities = Community::list_for_sitemap(pool, include_nsfw).await?;
+  let users = Person::list_for_sitemap(pool).await?;
+  for (name, urls) in [(COMMUNITIES_SITEMAP, communities), (USERS_SITEMAP, users)] {
+    let Some(last_modified) = urls.iter().map(|(_, date_time)| *date_time).max() else {
+      continue;
+    };
+    let url_count = i32::try_from(urls.len())?;
+    if stale.remove(name) != Some((url_count, last_modified)) {
+      save_sitemap(pool, name.to_string(), urls, url_count, last_modified).await?;
+    }
+  }
+
+  // Remove sitemaps which have no urls anymore
+  let stale: Vec<_> = stale.into_keys().collect();
+  Sitemap::delete(pool, &stale).await?;
+  info!("Finished updating sitemaps");
+  Ok(())
+}
+
+async fn save_sitemap(
+  pool: &mut DbPool<'_>,
+  name: String,
+  urls: Vec<(DbUrl, DateTime<Utc>)>,
+  url_count: i32,
+  last_modified_at: DateTime<Utc>,
+) -> LemmyResult<()> {
+  let mut buf = Vec::<u8>::new();
+  generate_urlset(urls)?.write(&mut buf)?;
+  let form = SitemapForm {
+    name,
+    content: String::from_utf8(buf)?,
+    url_count,
+    last_modified_at,
+  };
+  Sitemap::upsert(pool, &form).await
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
 
-  use crate::sitemap::generate_urlset;
+  use crate::sitemap::{generate_index, generate_urlset};
   use chrono::{DateTime, NaiveDate, Utc};
   use elementtree::Element;
   use lemmy_db_schema::newtypes::DbUrl;
@@ -132,4 +261,60 @@ pub(crate) mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  async fn test_generate_index() -> LemmyResult<()> {
+    let sitemaps = vec![
+      (
+        "communities".to_string(),
+        NaiveDate::from_ymd_opt(2023, 1, 1)
+          .unwrap_or_default()
+          .and_hms_opt(1, 2, 3)
+          .unwrap_or_default()
+          .and_utc(),
+      ),
+      (
+        "posts-2022-12".to_string(),
+        NaiveDate::from_ymd_opt(2022, 12, 31)
+          .unwrap_or_default()
+          .and_hms_opt(9, 10, 11)
+          .unwrap_or_default()
+          .and_utc(),
+      ),
+    ];
+
+    let mut buf = Vec::<u8>::new();
+    generate_index(sitemaps, "https://lemmy.ml")?.write(&mut buf)?;
+    let root = Element::from_reader(buf.as_slice())?;
+
+    assert_eq!(root.tag().name(), "sitemapindex");
+    assert_eq!(root.child_count(), 2);
+    assert!(root
+      .children()
+      .all(|sitemap| sitemap.tag().name() == "sitemap"));
+    assert_eq!(
+      root
+        .children()
+        .filter_map(|n| n.children().find(|element| element.tag().name() == "loc"))
+        .map(Element::text)
+        .collect::<Vec<_>>(),
+      vec![
+        "https://lemmy.ml/sitemap/communities.xml",
+        "https://lemmy.ml/sitemap/posts-2022-12.xml"
+      ]
+    );
+    assert_eq!(
+      root
+        .children()
+        .nth(1)
+        .and_then(|n| n
+          .children()
+          .find(|element| element.tag().name() == "lastmod"))
+        .map(Element::text)
+        .unwrap_or_default(),
+      "2022-12-31T09:10:11+00:00"
+    );
+
+    Ok(())
+  }
 }
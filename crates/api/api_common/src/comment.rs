@@ -1,9 +1,20 @@
 pub use lemmy_db_schema::{
-  newtypes::CommentId,
-  source::comment::{Comment, CommentActions},
+  newtypes::{CommentId, CommentRevisionId},
+  source::{
+    comment::{Comment, CommentActions},
+    comment_revision::CommentRevision,
+  },
 };
 pub use lemmy_db_views_comment::{
-  api::{CommentResponse, GetComment, GetComments, GetCommentsResponse, GetCommentsSlimResponse},
+  api::{
+    CommentResponse,
+    GetComment,
+    GetComments,
+    GetCommentsResponse,
+    GetCommentsSlimResponse,
+    ListCommentRevisions,
+    ListCommentRevisionsResponse,
+  },
   CommentSlimView,
   CommentView,
 };
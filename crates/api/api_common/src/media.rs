@@ -1,12 +1,28 @@
-pub use lemmy_db_schema::source::images::{ImageDetails, LocalImage, RemoteImage};
+pub use lemmy_db_schema::{
+  newtypes::ImageHash,
+  source::images::{ImageDetails, ImageHashBlock, LocalImage, RemoteImage, RemoteImageCacheStats},
+};
 pub use lemmy_db_views_local_image::{
   api::{
+    AdminSetMediaQuota,
+    ApproveQuarantinedImage,
+    BlockImageHash,
     DeleteImageParams,
     ImageGetParams,
+    ImageHashBlockResponse,
     ImageProxyParams,
+    ImageProxyStatsResponse,
+    ListImageHashBlocksResponse,
     ListMedia,
     ListMediaResponse,
+    ListMediaUploaders,
+    ListMediaUploadersResponse,
+    ListQuarantinedImagesResponse,
+    MediaUsage,
+    UnblockImageHash,
     UploadImageResponse,
   },
   LocalImageView,
+  MediaUploaderSortType,
+  MediaUploaderView,
 };
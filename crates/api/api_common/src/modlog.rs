@@ -2,6 +2,7 @@ pub use lemmy_db_schema::{
   newtypes::{
     AdminAllowInstanceId,
     AdminBlockInstanceId,
+    AdminImageModerationId,
     AdminPurgeCommentId,
     AdminPurgeCommunityId,
     AdminPurgePersonId,
@@ -25,6 +26,7 @@ pub use lemmy_db_schema::{
       admin::{
         AdminAllowInstance,
         AdminBlockInstance,
+        AdminImageModeration,
         AdminPurgeComment,
         AdminPurgeCommunity,
         AdminPurgePerson,
@@ -47,10 +49,12 @@ pub use lemmy_db_schema::{
   },
   ModlogActionType,
 };
+pub use lemmy_db_schema_file::enums::ImageModerationAction;
 pub use lemmy_db_views_modlog_combined::{
   api::{GetModlog, GetModlogResponse},
   AdminAllowInstanceView,
   AdminBlockInstanceView,
+  AdminImageModerationView,
   AdminPurgeCommentView,
   AdminPurgeCommunityView,
   AdminPurgePersonView,
@@ -1,6 +1,9 @@
 pub use lemmy_db_schema::{
-  newtypes::PostId,
-  source::post::{Post, PostActions},
+  newtypes::{PostId, PostRevisionId},
+  source::{
+    post::{Post, PostActions},
+    post_revision::PostRevision,
+  },
   PostFeatureType,
 };
 pub use lemmy_db_schema_file::enums::PostListingMode;
@@ -13,6 +16,8 @@ pub use lemmy_db_views_post::{
     GetSiteMetadata,
     GetSiteMetadataResponse,
     LinkMetadata,
+    ListPostRevisions,
+    ListPostRevisionsResponse,
     OpenGraphData,
     PostResponse,
   },
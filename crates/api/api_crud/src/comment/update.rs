@@ -11,7 +11,10 @@ use lemmy_api_utils::{
 use lemmy_db_schema::{
   impls::actor_language::validate_post_language,
   newtypes::PostOrCommentId,
-  source::comment::{Comment, CommentUpdateForm},
+  source::{
+    comment::{Comment, CommentUpdateForm},
+    comment_revision::CommentRevision,
+  },
   traits::Crud,
 };
 use lemmy_db_views_comment::{
@@ -78,6 +81,14 @@ pub async fn update_comment(
 
   plugin_hook_after("after_update_local_comment", &updated_comment)?;
 
+  CommentRevision::create_if_changed(
+    &mut context.pool(),
+    &orig_comment.comment,
+    &updated_comment,
+    local_user_view.person.id,
+  )
+  .await?;
+
   // Do the mentions / recipients
   let updated_comment_content = updated_comment.content.clone();
   let mentions = scrape_text_for_mentions(&updated_comment_content);
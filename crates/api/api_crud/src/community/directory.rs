@@ -0,0 +1,48 @@
+use actix_web::web::{Data, Json, Query};
+use lemmy_api_utils::{context::LemmyContext, utils::check_private_instance};
+use lemmy_db_schema::source::community_directory::CommunityDirectoryEntry;
+use lemmy_db_views_community::api::{ListCommunityDirectory, ListCommunityDirectoryResponse};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::LemmyResult;
+
+pub async fn list_community_directory(
+  data: Query<ListCommunityDirectory>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<ListCommunityDirectoryResponse>> {
+  let local_site = SiteView::read_local(&mut context.pool()).await?;
+
+  check_private_instance(&local_user_view, &local_site.local_site)?;
+
+  let cursor_data = if let Some(cursor) = &data.page_cursor {
+    Some(CommunityDirectoryEntry::from_cursor(cursor, &mut context.pool()).await?)
+  } else {
+    None
+  };
+
+  // Show nsfw content if param is true, or if content_warning exists
+  let show_nsfw = data
+    .show_nsfw
+    .unwrap_or(local_site.site.content_warning.is_some());
+
+  let communities = CommunityDirectoryEntry::list(
+    &mut context.pool(),
+    data.search_term.as_deref(),
+    data.language_id,
+    show_nsfw,
+    cursor_data,
+    data.page_back,
+    data.limit,
+  )
+  .await?;
+
+  let next_page = communities.last().map(CommunityDirectoryEntry::to_cursor);
+  let prev_page = communities.first().map(CommunityDirectoryEntry::to_cursor);
+
+  Ok(Json(ListCommunityDirectoryResponse {
+    communities,
+    next_page,
+    prev_page,
+  }))
+}
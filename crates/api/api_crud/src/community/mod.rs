@@ -5,6 +5,7 @@ use lemmy_utils::error::LemmyResult;
 
 pub mod create;
 pub mod delete;
+pub mod directory;
 pub mod list;
 pub mod remove;
 pub mod update;
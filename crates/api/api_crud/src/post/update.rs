@@ -24,6 +24,7 @@ use lemmy_db_schema::{
   source::{
     community::Community,
     post::{Post, PostUpdateForm},
+    post_revision::PostRevision,
   },
   traits::Crud,
   utils::{diesel_string_update, diesel_url_update},
@@ -163,6 +164,14 @@ pub async fn update_post(
   let updated_post = Post::update(&mut context.pool(), post_id, &post_form).await?;
   plugin_hook_after("after_update_local_post", &post_form)?;
 
+  PostRevision::create_if_changed(
+    &mut context.pool(),
+    &orig_post.post,
+    &updated_post,
+    local_user_view.person.id,
+  )
+  .await?;
+
   // Scan the post body for user mentions, add those rows
   let mentions = scrape_text_for_mentions(&updated_post.body.clone().unwrap_or_default());
   send_local_notifs(
@@ -9,6 +9,7 @@ use lemmy_api_utils::{
 use lemmy_db_schema::{
   source::{
     person::PersonActions,
+    person_device_key::PersonDeviceKey,
     private_message::{PrivateMessage, PrivateMessageInsertForm},
   },
   traits::{Blockable, Crud},
@@ -19,17 +20,33 @@ use lemmy_db_views_private_message::{
   PrivateMessageView,
 };
 use lemmy_email::notifications::send_private_message_email;
-use lemmy_utils::{error::LemmyResult, utils::validation::is_valid_body_field};
+use lemmy_utils::{
+  error::{LemmyErrorType, LemmyResult},
+  utils::validation::{is_valid_body_field, is_valid_encrypted_body_field},
+};
 
 pub async fn create_private_message(
   data: Json<CreatePrivateMessage>,
   context: Data<LemmyContext>,
   local_user_view: LocalUserView,
 ) -> LemmyResult<Json<PrivateMessageResponse>> {
-  let slur_regex = slur_regex(&context).await?;
-  let url_blocklist = get_url_blocklist(&context).await?;
-  let content = process_markdown(&data.content, &slur_regex, &url_blocklist, &context).await?;
-  is_valid_body_field(&content, false)?;
+  let encrypted = data.encrypted.unwrap_or_default();
+  let content = if encrypted {
+    is_valid_encrypted_body_field(&data.content)?;
+    // Clients can only encrypt messages to users who registered a device
+    let device_keys =
+      PersonDeviceKey::list_for_person(&mut context.pool(), data.recipient_id).await?;
+    if device_keys.is_empty() {
+      Err(LemmyErrorType::RecipientHasNoDeviceKeys)?
+    }
+    data.content.clone()
+  } else {
+    let slur_regex = slur_regex(&context).await?;
+    let url_blocklist = get_url_blocklist(&context).await?;
+    let content = process_markdown(&data.content, &slur_regex, &url_blocklist, &context).await?;
+    is_valid_body_field(&content, false)?;
+    content
+  };
 
   PersonActions::read_block(
     &mut context.pool(),
@@ -48,11 +65,14 @@ pub async fn create_private_message(
     check_private_messages_enabled(&recipient_local_user)?;
   }
 
-  let mut form = PrivateMessageInsertForm::new(
-    local_user_view.person.id,
-    data.recipient_id,
-    content.clone(),
-  );
+  let mut form = PrivateMessageInsertForm {
+    encrypted: Some(encrypted),
+    ..PrivateMessageInsertForm::new(
+      local_user_view.person.id,
+      data.recipient_id,
+      content.clone(),
+    )
+  };
 
   form = plugin_hook_before("before_create_local_private_message", form).await?;
   let inserted_private_message = PrivateMessage::create(&mut context.pool(), &form).await?;
@@ -63,14 +83,16 @@ pub async fn create_private_message(
 
   let view = PrivateMessageView::read(&mut context.pool(), inserted_private_message.id).await?;
 
-  // Send email to the local recipient, if one exists
+  // Send email to the local recipient, if one exists. Encrypted content can only be read on the
+  // recipient's devices.
   if view.recipient.local {
     let local_recipient =
       LocalUserView::read_person(&mut context.pool(), data.recipient_id).await?;
+    let email_content = if encrypted { "" } else { &content };
     send_private_message_email(
       &local_user_view,
       &local_recipient,
-      &content,
+      email_content,
       context.settings(),
     )
     .await;
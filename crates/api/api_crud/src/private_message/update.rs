@@ -18,7 +18,7 @@ use lemmy_db_views_private_message::{
 };
 use lemmy_utils::{
   error::{LemmyErrorType, LemmyResult},
-  utils::validation::is_valid_body_field,
+  utils::validation::{is_valid_body_field, is_valid_encrypted_body_field},
 };
 
 pub async fn update_private_message(
@@ -33,11 +33,22 @@ pub async fn update_private_message(
     Err(LemmyErrorType::EditPrivateMessageNotAllowed)?
   }
 
+  // Encrypted messages can't be changed to plain text or the other way round
+  if data.encrypted.unwrap_or_default() != orig_private_message.encrypted {
+    Err(LemmyErrorType::EditPrivateMessageNotAllowed)?
+  }
+
   // Doing the update
-  let slur_regex = slur_regex(&context).await?;
-  let url_blocklist = get_url_blocklist(&context).await?;
-  let content = process_markdown(&data.content, &slur_regex, &url_blocklist, &context).await?;
-  is_valid_body_field(&content, false)?;
+  let content = if orig_private_message.encrypted {
+    is_valid_encrypted_body_field(&data.content)?;
+    data.content.clone()
+  } else {
+    let slur_regex = slur_regex(&context).await?;
+    let url_blocklist = get_url_blocklist(&context).await?;
+    let content = process_markdown(&data.content, &slur_regex, &url_blocklist, &context).await?;
+    is_valid_body_field(&content, false)?;
+    content
+  };
 
   let private_message_id = data.private_message_id;
   let mut form = PrivateMessageUpdateForm {
@@ -1,5 +1,9 @@
 use super::not_zero;
-use crate::site::{application_question_check, site_default_post_listing_type_check};
+use crate::site::{
+  application_question_check,
+  media_quota_check,
+  site_default_post_listing_type_check,
+};
 use activitypub_federation::{config::Data, http_signatures::generate_actor_keypair};
 use actix_web::web::Json;
 use chrono::Utc;
@@ -22,7 +26,7 @@ use lemmy_db_schema::{
     site::{Site, SiteUpdateForm},
   },
   traits::Crud,
-  utils::diesel_string_update,
+  utils::{diesel_opt_number_update, diesel_string_update},
 };
 use lemmy_db_views_local_user::LocalUserView;
 use lemmy_db_views_site::{
@@ -108,6 +112,14 @@ pub async fn create_site(
     disallow_nsfw_content: data.disallow_nsfw_content,
     disable_email_notifications: data.disable_email_notifications,
     suggested_communities: data.suggested_communities,
+    edit_history_public: data.edit_history_public,
+    federation_authorized_fetch: data.federation_authorized_fetch,
+    community_directory_enabled: data.community_directory_enabled,
+    media_quota_mb: diesel_opt_number_update(data.media_quota_mb),
+    new_account_media_quota_mb: diesel_opt_number_update(data.new_account_media_quota_mb),
+    new_account_media_quota_days: data.new_account_media_quota_days,
+    image_proxy_max_age_days: diesel_opt_number_update(data.image_proxy_max_age_days),
+    image_proxy_max_cache_mb: diesel_opt_number_update(data.image_proxy_max_cache_mb),
     ..Default::default()
   };
 
@@ -174,6 +186,18 @@ fn validate_create_payload(local_site: &LocalSite, create_site: &CreateSite) ->
     is_valid_body_field(body, false)?;
   }
 
+  media_quota_check(
+    &[
+      create_site.media_quota_mb,
+      create_site.new_account_media_quota_mb,
+      create_site.image_proxy_max_cache_mb,
+    ],
+    &[
+      create_site.new_account_media_quota_days,
+      create_site.image_proxy_max_age_days,
+    ],
+  )?;
+
   application_question_check(
     &local_site.application_question,
     &create_site.application_question,
@@ -40,6 +40,23 @@ pub fn application_question_check(
   }
 }
 
+/// Longest period in days which can be set for the age of new accounts or of cached images.
+const MAX_MEDIA_QUOTA_DAYS: i32 = 36500;
+
+/// Checks that the media quotas and the image proxy cache limits are not negative, and that the
+/// given numbers of days are within `0..=MAX_MEDIA_QUOTA_DAYS`.
+pub fn media_quota_check(values: &[Option<i32>], days: &[Option<i32>]) -> LemmyResult<()> {
+  if values.iter().flatten().any(|v| *v < 0)
+    || days
+      .iter()
+      .flatten()
+      .any(|d| !(0..=MAX_MEDIA_QUOTA_DAYS).contains(d))
+  {
+    Err(LemmyErrorType::InvalidMediaQuota)?
+  }
+  Ok(())
+}
+
 fn not_zero(val: Option<i32>) -> Option<i32> {
   match val {
     Some(0) => None,
@@ -50,7 +67,12 @@ fn not_zero(val: Option<i32>) -> Option<i32> {
 #[cfg(test)]
 mod tests {
 
-  use crate::site::{application_question_check, not_zero, site_default_post_listing_type_check};
+  use crate::site::{
+    application_question_check,
+    media_quota_check,
+    not_zero,
+    site_default_post_listing_type_check,
+  };
   use lemmy_db_schema_file::enums::{ListingType, RegistrationMode};
 
   #[test]
@@ -61,6 +83,15 @@ mod tests {
     assert!(site_default_post_listing_type_check(&Some(ListingType::Subscribed)).is_err());
   }
 
+  #[test]
+  fn test_media_quota_check() {
+    assert!(media_quota_check(&[None, Some(0), Some(500)], &[Some(30)]).is_ok());
+    assert!(media_quota_check(&[Some(100), Some(-1)], &[]).is_err());
+    assert!(media_quota_check(&[], &[Some(36500)]).is_ok());
+    assert!(media_quota_check(&[], &[Some(-1)]).is_err());
+    assert!(media_quota_check(&[], &[Some(i32::MAX)]).is_err());
+  }
+
   #[test]
   fn test_application_question_check() {
     assert!(
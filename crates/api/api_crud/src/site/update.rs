@@ -1,5 +1,9 @@
 use super::not_zero;
-use crate::site::{application_question_check, site_default_post_listing_type_check};
+use crate::site::{
+  application_question_check,
+  media_quota_check,
+  site_default_post_listing_type_check,
+};
 use activitypub_federation::config::Data;
 use actix_web::web::Json;
 use chrono::Utc;
@@ -117,6 +121,14 @@ pub async fn update_site(
     disallow_nsfw_content: data.disallow_nsfw_content,
     disable_email_notifications: data.disable_email_notifications,
     suggested_communities: data.suggested_communities,
+    edit_history_public: data.edit_history_public,
+    federation_authorized_fetch: data.federation_authorized_fetch,
+    community_directory_enabled: data.community_directory_enabled,
+    media_quota_mb: diesel_opt_number_update(data.media_quota_mb),
+    new_account_media_quota_mb: diesel_opt_number_update(data.new_account_media_quota_mb),
+    new_account_media_quota_days: data.new_account_media_quota_days,
+    image_proxy_max_age_days: diesel_opt_number_update(data.image_proxy_max_age_days),
+    image_proxy_max_cache_mb: diesel_opt_number_update(data.image_proxy_max_cache_mb),
     ..Default::default()
   };
 
@@ -216,6 +228,18 @@ fn validate_update_payload(local_site: &LocalSite, edit_site: &EditSite) -> Lemm
     is_valid_body_field(body, false)?;
   }
 
+  media_quota_check(
+    &[
+      edit_site.media_quota_mb,
+      edit_site.new_account_media_quota_mb,
+      edit_site.image_proxy_max_cache_mb,
+    ],
+    &[
+      edit_site.new_account_media_quota_days,
+      edit_site.image_proxy_max_age_days,
+    ],
+  )?;
+
   application_question_check(
     &local_site.application_question,
     &edit_site.application_question,
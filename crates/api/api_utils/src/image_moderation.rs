@@ -0,0 +1,428 @@
+//! Automated moderation of uploaded and proxied images. Images are compared against a blocklist of
+//! perceptual hashes which is managed by admins, and can be labeled by an external classifier or
+//! by plugins. Depending on the labels, uploads are rejected, quarantined until an admin reviews
+//! them, or marked as NSFW. All actions are logged in the modlog, which only admins can see for
+//! these entries.
+use crate::{
+  context::LemmyContext,
+  media_storage::media_storage,
+  plugins::plugin_hook_before,
+  request::{delete_image_alias, purge_image_from_pictrs},
+};
+use actix_web::web::Bytes;
+use image::imageops::FilterType;
+use lemmy_db_schema::{
+  newtypes::{ImageHash, PersonId},
+  source::{
+    images::{ImageDetails, ImageHashBlock, RemoteImage},
+    mod_log::admin::{AdminImageModeration, AdminImageModerationForm},
+  },
+  traits::Crud,
+};
+use lemmy_db_schema_file::enums::ImageModerationAction;
+use lemmy_db_views_local_image::api::ImageGetParams;
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  settings::structs::Settings,
+  REQWEST_TIMEOUT,
+};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+/// Images whose hashes differ in at most this many bits are considered the same. This tolerates
+/// resizing and recompression, but not cropping.
+pub const MAX_HASH_DISTANCE: i32 = 4;
+
+/// Images are downscaled to this size before hashing and classification.
+const ANALYZE_SIZE: i32 = 256;
+
+/// A label returned by the classifier, with a score between 0 and 1.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ImageLabel {
+  pub name: String,
+  pub score: f64,
+}
+
+/// Data for the `classify_image` plugin hook. Plugins can fetch the image and add labels.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ClassifyImage {
+  image_url: Url,
+  labels: Vec<ImageLabel>,
+}
+
+/// Result of moderating an upload which was not rejected.
+#[derive(Default)]
+pub struct ImageModeration {
+  pub hash: Option<ImageHash>,
+  pub nsfw: bool,
+  /// Only admins can view the upload until one of them approves it.
+  pub quarantined: bool,
+}
+
+/// Difference hash of the image: each bit tells if a pixel is brighter than its right neighbour,
+/// in a 9x8 grayscale version of the image.
+pub fn perceptual_hash(data: &[u8]) -> LemmyResult<ImageHash> {
+  let image = image::load_from_memory(data).with_lemmy_type(LemmyErrorType::NotAnImageType)?;
+  let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+  let mut bits = 0u64;
+  for y in 0..8 {
+    for x in 0..8 {
+      let [left] = small.get_pixel(x, y).0;
+      let [right] = small.get_pixel(x + 1, y).0;
+      bits = (bits << 1) | u64::from(left > right);
+    }
+  }
+  Ok(ImageHash::from_bits(bits))
+}
+
+/// Checks a new upload against the hash blocklist and the classifier. Rejected uploads are purged,
+/// logged and result in an error.
+pub async fn moderate_upload(
+  alias: &str,
+  image_url: &Url,
+  person_id: PersonId,
+  context: &LemmyContext,
+) -> LemmyResult<ImageModeration> {
+  // This fails for files which can't be converted, like videos with the stored backends
+  let data = match media_storage(context.settings())?
+    .get(alias, &analyze_params(), HeaderMap::new(), context)
+    .await
+  {
+    Ok(res) => res.into_bytes(context).await?,
+    Err(e) => {
+      warn!("Failed to read upload {alias} for moderation: {e}");
+      return Ok(ImageModeration::default());
+    }
+  };
+
+  let hash = perceptual_hash(&data)
+    .inspect_err(|e| warn!("Failed to hash upload {alias}: {e}"))
+    .ok();
+  if let Some(hash) = hash {
+    let block = ImageHashBlock::find_match(&mut context.pool(), hash, MAX_HASH_DISTANCE).await?;
+    if let Some(block) = block {
+      return reject_upload(alias, person_id, Some(hash), block.reason, context).await;
+    }
+  }
+
+  let labels = classify_image(image_url, data, context)
+    .await
+    .inspect_err(|e| warn!("Failed to classify upload {alias}: {e}"))
+    .unwrap_or_default();
+  let config = context.settings().pictrs()?.classifier.unwrap_or_default();
+
+  let reject = matching_labels(&labels, &config.reject_labels, config.min_score);
+  if !reject.is_empty() {
+    let reason = Some(format!("Classifier: {}", reject.join(", ")));
+    return reject_upload(alias, person_id, hash, reason, context).await;
+  }
+
+  let quarantine = matching_labels(&labels, &config.quarantine_labels, config.min_score);
+  let nsfw = matching_labels(&labels, &config.nsfw_labels, config.min_score);
+  for (action, labels) in [
+    (ImageModerationAction::QuarantineUpload, &quarantine),
+    (ImageModerationAction::MarkNsfw, &nsfw),
+  ] {
+    if labels.is_empty() {
+      continue;
+    }
+    let form = AdminImageModerationForm {
+      admin_person_id: None,
+      person_id: Some(person_id),
+      action,
+      hash,
+      image_url: Some(image_url.clone().into()),
+      reason: Some(format!("Classifier: {}", labels.join(", "))),
+    };
+    AdminImageModeration::create(&mut context.pool(), &form).await?;
+  }
+
+  Ok(ImageModeration {
+    hash,
+    nsfw: !nsfw.is_empty(),
+    quarantined: !quarantine.is_empty(),
+  })
+}
+
+async fn reject_upload(
+  alias: &str,
+  person_id: PersonId,
+  hash: Option<ImageHash>,
+  reason: Option<String>,
+  context: &LemmyContext,
+) -> LemmyResult<ImageModeration> {
+  // Purging requires the pict-rs api key, otherwise at least the alias is removed
+  if let Err(e) = purge_image_from_pictrs(alias, context).await {
+    warn!("Failed to purge rejected upload {alias}: {e}");
+    delete_image_alias(alias, context).await?;
+  }
+
+  let form = AdminImageModerationForm {
+    admin_person_id: None,
+    person_id: Some(person_id),
+    action: ImageModerationAction::RejectUpload,
+    // The image is gone, but the hash allows admins to block it
+    hash,
+    image_url: None,
+    reason,
+  };
+  AdminImageModeration::create(&mut context.pool(), &form).await?;
+
+  Err(LemmyErrorType::ImageBlocked)?
+}
+
+/// Hashes a newly proxied remote image. If it matches the blocklist, it is purged from the cache
+/// and can't be proxied anymore.
+pub async fn moderate_proxied_image(link: &Url, context: &LemmyContext) -> LemmyResult<ImageHash> {
+  let data = media_storage(context.settings())?
+    .proxy(link, &analyze_params(), HeaderMap::new(), context)
+    .await?
+    .into_bytes(context)
+    .await?;
+  let hash = perceptual_hash(&data)?;
+
+  if let Some(block) =
+    ImageHashBlock::find_match(&mut context.pool(), hash, MAX_HASH_DISTANCE).await?
+  {
+    purge_remote_image(link, context).await?;
+    let form = AdminImageModerationForm {
+      admin_person_id: None,
+      person_id: None,
+      action: ImageModerationAction::PurgeImage,
+      hash: Some(hash),
+      image_url: Some(link.clone().into()),
+      reason: block.reason,
+    };
+    AdminImageModeration::create(&mut context.pool(), &form).await?;
+  }
+  Ok(hash)
+}
+
+/// Purges all local uploads and cached remote images which are similar to the blocked hash.
+/// Returns the number of purged images.
+pub async fn purge_matching_images(
+  block: &ImageHashBlock,
+  admin_person_id: PersonId,
+  context: &LemmyContext,
+) -> LemmyResult<i64> {
+  let images =
+    ImageDetails::list_by_hash(&mut context.pool(), block.hash, MAX_HASH_DISTANCE).await?;
+
+  let mut purged = 0;
+  for image in images {
+    let link = image.link.inner();
+    let res = match image_source(link, context.settings()) {
+      Some(ImageSource::Local(alias)) => purge_image_from_pictrs(&alias, context).await,
+      Some(ImageSource::Proxied(remote)) => purge_remote_image(&remote, context).await,
+      None => continue,
+    };
+    if let Err(e) = res {
+      warn!("Failed to purge blocked image {link}: {e}");
+      continue;
+    }
+    ImageDetails::delete(&mut context.pool(), &image.link).await?;
+
+    let form = AdminImageModerationForm {
+      admin_person_id: Some(admin_person_id),
+      person_id: None,
+      action: ImageModerationAction::PurgeImage,
+      hash: image.perceptual_hash,
+      image_url: Some(image.link.clone()),
+      reason: block.reason.clone(),
+    };
+    AdminImageModeration::create(&mut context.pool(), &form).await?;
+    purged += 1;
+  }
+  Ok(purged)
+}
+
+/// Removes a remote image from the proxy cache, and from the list of images which may be proxied.
+async fn purge_remote_image(link: &Url, context: &LemmyContext) -> LemmyResult<()> {
+  media_storage(context.settings())?
+    .purge_remote(link, context)
+    .await?;
+  RemoteImage::delete(&mut context.pool(), &link.clone().into()).await?;
+  Ok(())
+}
+
+/// Labels the image with the configured classifier and the `classify_image` plugin hook.
+async fn classify_image(
+  image_url: &Url,
+  data: Bytes,
+  context: &LemmyContext,
+) -> LemmyResult<Vec<ImageLabel>> {
+  let mut labels = vec![];
+  if let Some(classifier) = context.settings().pictrs()?.classifier {
+    labels = context
+      .client()
+      .post(classifier.url.as_str())
+      .header(CONTENT_TYPE, "image/png")
+      .body(data)
+      .timeout(REQWEST_TIMEOUT)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?;
+  }
+
+  let data = ClassifyImage {
+    image_url: image_url.clone(),
+    labels,
+  };
+  Ok(plugin_hook_before("classify_image", data).await?.labels)
+}
+
+/// Labels which are contained in `names` and have at least `min_score`, formatted for the modlog.
+fn matching_labels(labels: &[ImageLabel], names: &[String], min_score: f64) -> Vec<String> {
+  labels
+    .iter()
+    .filter(|l| l.score >= min_score && names.contains(&l.name))
+    .map(|l| format!("{} ({:.2})", l.name, l.score))
+    .collect()
+}
+
+fn analyze_params() -> ImageGetParams {
+  ImageGetParams {
+    file_type: Some("png".to_string()),
+    max_size: Some(ANALYZE_SIZE),
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum ImageSource {
+  /// Alias of a local upload
+  Local(String),
+  /// Remote image which is served through the image proxy
+  Proxied(Url),
+}
+
+/// Finds out where an image which is stored in `image_details` comes from.
+fn image_source(link: &Url, settings: &Settings) -> Option<ImageSource> {
+  if link.domain() != Some(&settings.hostname) {
+    return None;
+  }
+  if link.path() == "/api/v4/image/proxy" {
+    link
+      .query_pairs()
+      .find(|(key, _)| key == "url")
+      .and_then(|(_, url)| Url::parse(&url).ok())
+      .map(ImageSource::Proxied)
+  } else {
+    link
+      .path()
+      .strip_prefix("/api/v4/image/")
+      .filter(|alias| !alias.is_empty() && !alias.contains('/'))
+      .map(|alias| ImageSource::Local(alias.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{DynamicImage, GrayImage, ImageFormat, Luma};
+  use pretty_assertions::assert_eq;
+  use std::io::Cursor;
+
+  fn png(image: &DynamicImage) -> LemmyResult<Vec<u8>> {
+    let mut data = Cursor::new(vec![]);
+    image.write_to(&mut data, ImageFormat::Png)?;
+    Ok(data.into_inner())
+  }
+
+  fn gradient(size: u32) -> DynamicImage {
+    let step = 256 / size;
+    DynamicImage::ImageLuma8(GrayImage::from_fn(size, size, |x, _| {
+      Luma([u8::try_from(x * step).unwrap_or(u8::MAX)])
+    }))
+  }
+
+  #[test]
+  fn test_perceptual_hash() -> LemmyResult<()> {
+    // Brightness increases from left to right, so no pixel is brighter than its right neighbour
+    let hash = perceptual_hash(&png(&gradient(64))?)?;
+    assert_eq!(0, hash.bits());
+
+    // Scaled versions of the same image have the same hash
+    let scaled = perceptual_hash(&png(&gradient(32))?)?;
+    assert_eq!(0, hash.distance(&scaled));
+
+    let flipped = perceptual_hash(&png(&gradient(64).fliph())?)?;
+    assert_eq!(u64::MAX, flipped.bits());
+    assert_eq!(64, hash.distance(&flipped));
+
+    assert!(perceptual_hash(b"not an image").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_image_hash_string() -> LemmyResult<()> {
+    let hash = ImageHash::from_bits(u64::MAX);
+    assert_eq!(-1, hash.0);
+    assert_eq!("ffffffffffffffff", hash.to_string());
+    assert_eq!(hash, ImageHash::try_from("ffffffffffffffff".to_string())?);
+    assert_eq!(
+      "\"00000000000000ff\"",
+      serde_json::to_string(&ImageHash(255))?
+    );
+    assert!(ImageHash::try_from("not hex".to_string()).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_matching_labels() {
+    let labels = vec![
+      ImageLabel {
+        name: "nsfw".to_string(),
+        score: 0.95,
+      },
+      ImageLabel {
+        name: "gore".to_string(),
+        score: 0.5,
+      },
+    ];
+    let names = vec!["nsfw".to_string(), "gore".to_string()];
+    assert_eq!(
+      vec!["nsfw (0.95)".to_string()],
+      matching_labels(&labels, &names, 0.8)
+    );
+    assert_eq!(2, matching_labels(&labels, &names, 0.5).len());
+    assert!(matching_labels(&labels, &[], 0.0).is_empty());
+  }
+
+  #[test]
+  fn test_image_source() -> LemmyResult<()> {
+    let settings = Settings::default();
+    let host = &settings.hostname;
+
+    let local = Url::parse(&format!("https://{host}/api/v4/image/abc.jpg"))?;
+    assert_eq!(
+      Some(ImageSource::Local("abc.jpg".to_string())),
+      image_source(&local, &settings)
+    );
+
+    let poster = Url::parse(&format!(
+      "https://{host}/api/v4/image/abc.mp4?file_type=webp"
+    ))?;
+    assert_eq!(
+      Some(ImageSource::Local("abc.mp4".to_string())),
+      image_source(&poster, &settings)
+    );
+
+    let proxied = Url::parse(&format!(
+      "https://{host}/api/v4/image/proxy?url=https%3A%2F%2Fexample.com%2Fa.png&max_size=256"
+    ))?;
+    assert_eq!(
+      Some(ImageSource::Proxied(Url::parse(
+        "https://example.com/a.png"
+      )?)),
+      image_source(&proxied, &settings)
+    );
+
+    let remote = Url::parse("https://example.com/api/v4/image/abc.jpg")?;
+    assert_eq!(None, image_source(&remote, &settings));
+    Ok(())
+  }
+}
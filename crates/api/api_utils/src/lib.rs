@@ -1,6 +1,8 @@
 pub mod build_response;
 pub mod claims;
 pub mod context;
+pub mod image_moderation;
+pub mod media_storage;
 pub mod plugins;
 pub mod request;
 pub mod send_activity;
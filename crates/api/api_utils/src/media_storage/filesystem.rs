@@ -0,0 +1,101 @@
+use super::{stored::BlobStore, MediaStream};
+use actix_web::web::{Bytes, BytesMut};
+use futures::stream;
+use lemmy_utils::error::LemmyResult;
+use reqwest_middleware::ClientWithMiddleware;
+use std::{io::ErrorKind, path::PathBuf};
+use tokio::{
+  fs::{self, File},
+  io::AsyncReadExt,
+};
+use url::Url;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stores files in a local directory.
+pub(super) struct FilesystemStore {
+  path: PathBuf,
+}
+
+impl FilesystemStore {
+  pub(super) fn new(path: &str) -> Self {
+    FilesystemStore { path: path.into() }
+  }
+}
+
+#[async_trait::async_trait]
+impl BlobStore for FilesystemStore {
+  async fn put(
+    &self,
+    _client: &ClientWithMiddleware,
+    key: &str,
+    _content_type: &str,
+    data: Bytes,
+  ) -> LemmyResult<()> {
+    fs::create_dir_all(&self.path).await?;
+    fs::write(self.path.join(key), data).await?;
+    Ok(())
+  }
+
+  async fn get(
+    &self,
+    _client: &ClientWithMiddleware,
+    key: &str,
+  ) -> LemmyResult<Option<MediaStream>> {
+    let file = match File::open(self.path.join(key)).await {
+      Ok(file) => file,
+      Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e.into()),
+    };
+    let body = stream::try_unfold(file, |mut file| async move {
+      let mut chunk = BytesMut::with_capacity(CHUNK_SIZE);
+      let read = file.read_buf(&mut chunk).await?;
+      std::io::Result::Ok((read > 0).then(|| (chunk.freeze(), file)))
+    });
+    Ok(Some(Box::pin(body)))
+  }
+
+  async fn delete(&self, _client: &ClientWithMiddleware, key: &str) -> LemmyResult<()> {
+    match fs::remove_file(self.path.join(key)).await {
+      Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+      _ => Ok(()),
+    }
+  }
+
+  fn signed_url(&self, _key: &str) -> LemmyResult<Option<Url>> {
+    Ok(None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::media_storage::read_stream;
+  use pretty_assertions::assert_eq;
+  use reqwest::Client;
+  use uuid::Uuid;
+
+  #[tokio::test]
+  async fn test_filesystem_store() -> LemmyResult<()> {
+    let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let store = FilesystemStore { path: dir.clone() };
+    let client = ClientWithMiddleware::from(Client::new());
+
+    assert!(store.get(&client, "a.png").await?.is_none());
+    store
+      .put(&client, "a.png", "image/png", Bytes::from_static(b"data"))
+      .await?;
+    let body = store
+      .get(&client, "a.png")
+      .await?
+      .ok_or(std::io::Error::from(ErrorKind::NotFound))?;
+    assert_eq!(Bytes::from_static(b"data"), read_stream(body).await?);
+    store.delete(&client, "a.png").await?;
+    assert!(store.get(&client, "a.png").await?.is_none());
+    // Deleting a missing file is not an error
+    store.delete(&client, "a.png").await?;
+
+    fs::remove_dir_all(dir).await?;
+    Ok(())
+  }
+}
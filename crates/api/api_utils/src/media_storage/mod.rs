@@ -0,0 +1,152 @@
+//! Storage backends for uploaded media. By default everything is handled by pict-rs. Alternatively
+//! originals can be stored in a local directory or in S3-compatible object storage, in which case
+//! Lemmy resizes images itself.
+use crate::{context::LemmyContext, request::PictrsFile};
+use actix_web::{
+  error::PayloadError,
+  web::{Bytes, BytesMut},
+};
+use anyhow::anyhow;
+use filesystem::FilesystemStore;
+use futures::{Stream, StreamExt};
+use lemmy_db_views_local_image::api::ImageGetParams;
+use lemmy_utils::{
+  error::LemmyResult,
+  settings::structs::{MediaStorageBackend, Settings},
+  REQWEST_TIMEOUT,
+};
+use pictrs::Pictrs;
+use reqwest::{header::HeaderMap, Response};
+use s3::S3Store;
+use std::pin::Pin;
+use stored::StoredMedia;
+use url::Url;
+
+mod filesystem;
+mod pictrs;
+mod s3;
+mod stored;
+
+pub type UploadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>> + Send>>;
+
+/// Content of a stored file, which is read in chunks.
+pub type MediaStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>;
+
+/// A file upload, in the multipart format sent by clients.
+pub struct Upload {
+  pub body: UploadStream,
+  /// Headers of the client request, including the multipart content type
+  pub headers: HeaderMap,
+  /// Larger images are downscaled to this width and height
+  pub max_size: Option<u32>,
+  pub allow_video: bool,
+  pub allow_animation: bool,
+}
+
+pub enum MediaResponse {
+  /// Response of pict-rs, which is passed through to the client
+  Pictrs(Response),
+  /// Content of a stored file, which is passed through to the client without buffering it
+  Stream {
+    content_type: String,
+    body: MediaStream,
+  },
+  /// Content of a resized image
+  File { content_type: String, data: Bytes },
+  /// Redirect to a signed url of the stored file
+  Redirect(Url),
+}
+
+impl MediaResponse {
+  /// Reads the whole file, for processing within Lemmy.
+  pub async fn into_bytes(self, context: &LemmyContext) -> LemmyResult<Bytes> {
+    Ok(match self {
+      MediaResponse::Pictrs(res) => res.error_for_status()?.bytes().await?,
+      MediaResponse::Stream { body, .. } => read_stream(body).await?,
+      MediaResponse::File { data, .. } => data,
+      MediaResponse::Redirect(url) => {
+        context
+          .pictrs_client()
+          .get(url.as_str())
+          .timeout(REQWEST_TIMEOUT)
+          .send()
+          .await?
+          .error_for_status()?
+          .bytes()
+          .await?
+      }
+    })
+  }
+}
+
+#[async_trait::async_trait]
+pub trait MediaStorage: Send + Sync {
+  /// Stores the uploaded files.
+  async fn upload(&self, upload: Upload, context: &LemmyContext) -> LemmyResult<Vec<PictrsFile>>;
+
+  /// Downloads a remote image and stores it as thumbnail, downscaled to `max_thumbnail_size`.
+  async fn store_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<PictrsFile>;
+
+  /// Returns a stored file. If `file_type` or `max_size` is given, images are converted and
+  /// downscaled.
+  async fn get(
+    &self,
+    alias: &str,
+    params: &ImageGetParams,
+    headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse>;
+
+  /// Returns a remote image, optionally converted and downscaled like in `get`.
+  async fn proxy(
+    &self,
+    url: &Url,
+    params: &ImageGetParams,
+    headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse>;
+
+  /// Deletes an alias. If the same file was uploaded more than once, the file itself may remain.
+  async fn delete(&self, alias: &str, context: &LemmyContext) -> LemmyResult<()>;
+
+  /// Deletes a file with all its aliases, and returns the deleted aliases.
+  async fn purge(&self, alias: &str, context: &LemmyContext) -> LemmyResult<Vec<String>>;
+
+  /// Removes a remote image from the proxy cache.
+  async fn purge_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<()>;
+}
+
+/// The storage backend which is configured in `media_storage`.
+pub fn media_storage(settings: &Settings) -> LemmyResult<Box<dyn MediaStorage>> {
+  let pictrs = settings.pictrs()?;
+  let config = &settings.media_storage;
+  Ok(match config.backend {
+    MediaStorageBackend::Pictrs => Box::new(Pictrs::new(pictrs)),
+    MediaStorageBackend::Filesystem => {
+      Box::new(StoredMedia::new(FilesystemStore::new(&config.path), pictrs))
+    }
+    MediaStorageBackend::S3 => {
+      let s3 = config
+        .s3
+        .clone()
+        .ok_or_else(|| anyhow!("s3_not_configured"))?;
+      Box::new(StoredMedia::new(S3Store::new(s3), pictrs))
+    }
+  })
+}
+
+/// Reads all chunks of a stored file.
+async fn read_stream(mut body: MediaStream) -> LemmyResult<Bytes> {
+  let mut data = BytesMut::new();
+  while let Some(chunk) = body.next().await {
+    data.extend_from_slice(&chunk?);
+  }
+  Ok(data.freeze())
+}
+
+/// Take file type from param, name, or use jpg if nothing is given
+fn file_type(file_type: Option<String>, name: &str) -> String {
+  file_type
+    .clone()
+    .unwrap_or_else(|| name.split('.').next_back().unwrap_or("jpg").to_string())
+}
@@ -0,0 +1,256 @@
+use super::{file_type, MediaResponse, MediaStorage, Upload};
+use crate::{
+  context::LemmyContext,
+  request::{PictrsFile, PictrsResponse},
+};
+use lemmy_db_views_local_image::api::ImageGetParams;
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  settings::structs::PictrsConfig,
+  REQWEST_TIMEOUT,
+};
+use reqwest::{
+  header::{HeaderMap, CONTENT_LENGTH},
+  Body,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use url::Url;
+use urlencoding::encode;
+
+/// Stores media in pict-rs, which also takes care of resizing and format conversion.
+///
+/// https://git.asonix.dog/asonix/pict-rs/#api
+pub(super) struct Pictrs {
+  config: PictrsConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct PictrsPurgeResponse {
+  msg: String,
+  aliases: Vec<String>,
+}
+
+impl Pictrs {
+  pub(super) fn new(config: PictrsConfig) -> Self {
+    Pictrs { config }
+  }
+
+  /// Url for the processing endpoint if conversion or resizing is requested, otherwise for the
+  /// original.
+  fn processed_url(&self, source: &str, name: &str, params: &ImageGetParams) -> String {
+    if params.file_type.is_none() && params.max_size.is_none() {
+      return format!("{}image/original?{source}", self.config.url);
+    }
+    let file_type = file_type(params.file_type.clone(), name);
+    let mut url = format!("{}image/process.{file_type}?{source}", self.config.url);
+    if let Some(size) = params.max_size {
+      url = format!("{url}&thumbnail={size}");
+    }
+    url
+  }
+
+  async fn get_url(
+    &self,
+    url: String,
+    headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse> {
+    let res = context
+      .pictrs_client()
+      .get(url)
+      .headers(headers)
+      .timeout(REQWEST_TIMEOUT)
+      .send()
+      .await?;
+    Ok(MediaResponse::Pictrs(res))
+  }
+
+  /// Pict-rs doesn't return the size of uploads, so it is read from the headers of the original.
+  /// Without the size media quotas can't be enforced, so a missing header is an error.
+  async fn file_size(&self, alias: &str, context: &LemmyContext) -> LemmyResult<i64> {
+    let res = context
+      .pictrs_client()
+      .head(format!("{}image/original/{alias}", self.config.url))
+      .timeout(REQWEST_TIMEOUT)
+      .send()
+      .await?
+      .error_for_status()?;
+    res
+      .headers()
+      .get(CONTENT_LENGTH)
+      .and_then(|l| l.to_str().ok())
+      .and_then(|l| l.parse().ok())
+      .ok_or(LemmyErrorType::PictrsResponseError("missing content length".to_string()).into())
+  }
+}
+
+#[async_trait::async_trait]
+impl MediaStorage for Pictrs {
+  async fn upload(&self, upload: Upload, context: &LemmyContext) -> LemmyResult<Vec<PictrsFile>> {
+    // Set pictrs parameters to downscale images and restrict file types.
+    let mut query = vec![("allow_video", upload.allow_video.to_string())];
+    if !upload.allow_animation {
+      query.push(("allow_animation", "false".to_string()));
+    }
+    if let Some(max_size) = upload.max_size {
+      query.push(("resize", max_size.to_string()));
+    }
+    let res = context
+      .pictrs_client()
+      .post(format!("{}image", self.config.url))
+      .headers(upload.headers)
+      .query(&query)
+      .timeout(Duration::from_secs(self.config.upload_timeout))
+      .body(Body::wrap_stream(upload.body))
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<PictrsResponse>()
+      .await?;
+
+    let mut files = res.files;
+    let mut size_error = None;
+    for file in &mut files {
+      match self.file_size(&file.file, context).await {
+        Ok(size) => file.details.size = Some(size),
+        Err(e) => size_error = Some(e),
+      }
+    }
+    if let Some(e) = size_error {
+      // Uploads without size can't be counted towards the media quota, so they are removed again
+      for file in &files {
+        self.delete(&file.file, context).await.ok();
+      }
+      return Err(e);
+    }
+    Ok(files)
+  }
+
+  async fn store_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<PictrsFile> {
+    let fetch_url = format!(
+      "{}image/download?url={}&resize={}",
+      self.config.url,
+      encode(url.as_str()),
+      self.config.max_thumbnail_size
+    );
+
+    let mut res = context
+      .pictrs_client()
+      .get(&fetch_url)
+      .timeout(REQWEST_TIMEOUT)
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<PictrsResponse>()
+      .await?;
+
+    let mut file = res
+      .files
+      .pop()
+      .ok_or(LemmyErrorType::PictrsResponseError(res.msg))?;
+    file.details.size = Some(self.file_size(&file.file, context).await?);
+    Ok(file)
+  }
+
+  async fn get(
+    &self,
+    alias: &str,
+    params: &ImageGetParams,
+    headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse> {
+    // Originals are addressed by path, processed images by query parameter
+    let url = if params.file_type.is_none() && params.max_size.is_none() {
+      format!("{}image/original/{alias}", self.config.url)
+    } else {
+      self.processed_url(&format!("src={alias}"), alias, params)
+    };
+    self.get_url(url, headers, context).await
+  }
+
+  async fn proxy(
+    &self,
+    url: &Url,
+    params: &ImageGetParams,
+    headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse> {
+    let source = format!("proxy={}", encode(url.as_str()));
+    let url = self.processed_url(&source, url.path(), params);
+    self.get_url(url, headers, context).await
+  }
+
+  async fn delete(&self, alias: &str, context: &LemmyContext) -> LemmyResult<()> {
+    let url = format!("{}internal/delete?alias={alias}", self.config.url);
+
+    context
+      .pictrs_client()
+      .post(&url)
+      .header(
+        "X-Api-Token",
+        self.config.api_key.clone().unwrap_or_default(),
+      )
+      .timeout(REQWEST_TIMEOUT)
+      .send()
+      .await?
+      .error_for_status()?;
+    Ok(())
+  }
+
+  async fn purge(&self, alias: &str, context: &LemmyContext) -> LemmyResult<Vec<String>> {
+    let purge_url = format!("{}internal/purge?alias={alias}", self.config.url);
+
+    let pictrs_api_key = self
+      .config
+      .api_key
+      .clone()
+      .ok_or(LemmyErrorType::PictrsApiKeyNotProvided)?;
+    let response = context
+      .pictrs_client()
+      .post(&purge_url)
+      .timeout(REQWEST_TIMEOUT)
+      .header("x-api-token", pictrs_api_key)
+      .send()
+      .await?
+      .error_for_status()?;
+
+    let response: PictrsPurgeResponse = response.json().await.map_err(LemmyError::from)?;
+
+    match response.msg.as_str() {
+      // Pictrs purges return all aliases.
+      "ok" => Ok(response.aliases),
+      _ => Err(LemmyErrorType::PictrsPurgeResponseError(response.msg))?,
+    }
+  }
+
+  async fn purge_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<()> {
+    let purge_url = format!(
+      "{}internal/purge?proxy={}",
+      self.config.url,
+      encode(url.as_str())
+    );
+
+    let pictrs_api_key = self
+      .config
+      .api_key
+      .clone()
+      .ok_or(LemmyErrorType::PictrsApiKeyNotProvided)?;
+    let response: PictrsPurgeResponse = context
+      .pictrs_client()
+      .post(&purge_url)
+      .timeout(REQWEST_TIMEOUT)
+      .header("x-api-token", pictrs_api_key)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await
+      .map_err(LemmyError::from)?;
+
+    match response.msg.as_str() {
+      "ok" => Ok(()),
+      _ => Err(LemmyErrorType::PictrsPurgeResponseError(response.msg))?,
+    }
+  }
+}
@@ -0,0 +1,567 @@
+use super::{file_type, read_stream, MediaResponse, MediaStorage, MediaStream, Upload};
+use crate::{
+  context::LemmyContext,
+  request::{PictrsFile, PictrsFileDetails},
+};
+use actix_web::web::Bytes;
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use lemmy_db_views_local_image::api::ImageGetParams;
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  settings::structs::PictrsConfig,
+};
+use mime::Mime;
+use moka::future::Cache;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest_middleware::ClientWithMiddleware;
+use std::{io::Cursor, pin::pin, sync::LazyLock, time::Duration};
+use tokio::{fs, process::Command, task::spawn_blocking, time::timeout};
+use url::Url;
+use uuid::Uuid;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Converted remote images, so that the image proxy doesn't need to fetch and convert them again
+/// for every request. Limited to 100 MB.
+static PROXY_CACHE: LazyLock<Cache<(Url, ImageGetParams), (String, Bytes)>> = LazyLock::new(|| {
+  Cache::builder()
+    .max_capacity(100 * 1024 * 1024)
+    .weigher(|_, (_, data): &(String, Bytes)| u32::try_from(data.len()).unwrap_or(u32::MAX))
+    .time_to_live(Duration::from_secs(60 * 60 * 24))
+    .support_invalidation_closures()
+    .build()
+});
+
+/// Converted and downscaled versions of stored images, keyed by alias. Limited to 100 MB.
+static VARIANT_CACHE: LazyLock<Cache<(String, ImageGetParams), (String, Bytes)>> =
+  LazyLock::new(|| {
+    Cache::builder()
+      .max_capacity(100 * 1024 * 1024)
+      .weigher(|_, (_, data): &(String, Bytes)| u32::try_from(data.len()).unwrap_or(u32::MAX))
+      .time_to_live(Duration::from_secs(60 * 60 * 24))
+      .support_invalidation_closures()
+      .build()
+  });
+
+/// Storage for the original files, in the filesystem or object storage.
+#[async_trait::async_trait]
+pub(super) trait BlobStore: Send + Sync {
+  async fn put(
+    &self,
+    client: &ClientWithMiddleware,
+    key: &str,
+    content_type: &str,
+    data: Bytes,
+  ) -> LemmyResult<()>;
+
+  /// Returns `None` if there is no file with this key.
+  async fn get(&self, client: &ClientWithMiddleware, key: &str)
+    -> LemmyResult<Option<MediaStream>>;
+
+  async fn delete(&self, client: &ClientWithMiddleware, key: &str) -> LemmyResult<()>;
+
+  /// Url under which clients can download the file directly, if the store supports it.
+  fn signed_url(&self, key: &str) -> LemmyResult<Option<Url>>;
+}
+
+/// Stores media in a [BlobStore], and resizes images with the `image` crate.
+pub(super) struct StoredMedia<B> {
+  store: B,
+  config: PictrsConfig,
+}
+
+impl<B: BlobStore> StoredMedia<B> {
+  pub(super) fn new(store: B, config: PictrsConfig) -> Self {
+    StoredMedia { store, config }
+  }
+
+  /// Process and store a single file, under a new random alias.
+  async fn store(
+    &self,
+    data: Bytes,
+    max_size: Option<u32>,
+    allow_video: bool,
+    allow_animation: bool,
+    context: &LemmyContext,
+  ) -> LemmyResult<PictrsFile> {
+    let mut file =
+      spawn_blocking(move || process_file(data, max_size, allow_video, allow_animation)).await??;
+    if file.content_type.starts_with("video/") {
+      let ffprobe_path = self.config.video.clone().unwrap_or_default().ffprobe_path;
+      (file.width, file.height) = video_dimensions(&file.data, &ffprobe_path).await?;
+    }
+    let alias = format!("{}.{}", Uuid::new_v4(), file.extension);
+    let size = i64::try_from(file.data.len()).ok();
+    self
+      .store
+      .put(
+        context.pictrs_client(),
+        &alias,
+        &file.content_type,
+        file.data,
+      )
+      .await?;
+    Ok(PictrsFile {
+      file: alias,
+      details: PictrsFileDetails {
+        width: file.width,
+        height: file.height,
+        content_type: file.content_type,
+        created_at: Utc::now(),
+        blurhash: None,
+        size,
+      },
+    })
+  }
+
+  async fn fetch_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<Bytes> {
+    let res = context
+      .client()
+      .get(url.as_str())
+      .send()
+      .await?
+      .error_for_status()?;
+    read_limited(
+      res.bytes_stream(),
+      self.max_file_size(),
+      self.config.upload_timeout,
+    )
+    .await
+  }
+
+  fn max_file_size(&self) -> usize {
+    usize::try_from(self.config.max_file_size)
+      .unwrap_or(usize::MAX)
+      .saturating_mul(1024 * 1024)
+  }
+}
+
+#[async_trait::async_trait]
+impl<B: BlobStore> MediaStorage for StoredMedia<B> {
+  async fn upload(&self, upload: Upload, context: &LemmyContext) -> LemmyResult<Vec<PictrsFile>> {
+    let boundary = multipart_boundary(&upload.headers).ok_or(LemmyErrorType::InvalidImageUpload)?;
+    let body = read_limited(
+      upload.body,
+      self.max_file_size(),
+      self.config.upload_timeout,
+    )
+    .await?;
+    let mut files = vec![];
+    for data in multipart_files(&body, &boundary) {
+      let file = self
+        .store(
+          data,
+          upload.max_size,
+          upload.allow_video,
+          upload.allow_animation,
+          context,
+        )
+        .await?;
+      files.push(file);
+    }
+    Ok(files)
+  }
+
+  async fn store_remote(&self, url: &Url, context: &LemmyContext) -> LemmyResult<PictrsFile> {
+    let data = self.fetch_remote(url, context).await?;
+    self
+      .store(
+        data,
+        Some(self.config.max_thumbnail_size),
+        false,
+        false,
+        context,
+      )
+      .await
+  }
+
+  async fn get(
+    &self,
+    alias: &str,
+    params: &ImageGetParams,
+    _headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse> {
+    if !is_valid_key(alias) {
+      Err(LemmyErrorType::NotFound)?
+    }
+    let original = params.file_type.is_none() && params.max_size.is_none();
+    if original {
+      if let Some(url) = self.store.signed_url(alias)? {
+        return Ok(MediaResponse::Redirect(url));
+      }
+    }
+    let key = (alias.to_string(), params.clone());
+    if let Some((content_type, data)) = VARIANT_CACHE.get(&key).await {
+      return Ok(MediaResponse::File { content_type, data });
+    }
+    let body = self
+      .store
+      .get(context.pictrs_client(), alias)
+      .await?
+      .ok_or(LemmyErrorType::NotFound)?;
+    if original {
+      let content_type = mime_guess::from_path(alias)
+        .first_or_octet_stream()
+        .to_string();
+      return Ok(MediaResponse::Stream { content_type, body });
+    }
+    let data = read_stream(body).await?;
+    let (alias_, params_) = key.clone();
+    let (content_type, data) =
+      spawn_blocking(move || convert_image(&data, &alias_, &params_)).await??;
+    VARIANT_CACHE
+      .insert(key, (content_type.clone(), data.clone()))
+      .await;
+    Ok(MediaResponse::File { content_type, data })
+  }
+
+  async fn proxy(
+    &self,
+    url: &Url,
+    params: &ImageGetParams,
+    _headers: HeaderMap,
+    context: &LemmyContext,
+  ) -> LemmyResult<MediaResponse> {
+    let key = (url.clone(), params.clone());
+    if let Some((content_type, data)) = PROXY_CACHE.get(&key).await {
+      return Ok(MediaResponse::File { content_type, data });
+    }
+    let data = self.fetch_remote(url, context).await?;
+    // Always decode, so that this can't be used to proxy anything other than images
+    let (url_, params_) = key.clone();
+    let (content_type, data) =
+      spawn_blocking(move || convert_image(&data, url_.path(), &params_)).await??;
+    PROXY_CACHE
+      .insert(key, (content_type.clone(), data.clone()))
+      .await;
+    Ok(MediaResponse::File { content_type, data })
+  }
+
+  async fn delete(&self, alias: &str, context: &LemmyContext) -> LemmyResult<()> {
+    if !is_valid_key(alias) {
+      Err(LemmyErrorType::NotFound)?
+    }
+    self.store.delete(context.pictrs_client(), alias).await?;
+    let alias = alias.to_string();
+    VARIANT_CACHE.invalidate_entries_if(move |(cached_alias, _), _| cached_alias == &alias)?;
+    Ok(())
+  }
+
+  async fn purge(&self, alias: &str, context: &LemmyContext) -> LemmyResult<Vec<String>> {
+    // Each upload gets its own file, so there are no other aliases
+    self.delete(alias, context).await?;
+    Ok(vec![alias.to_string()])
+  }
+
+  async fn purge_remote(&self, url: &Url, _context: &LemmyContext) -> LemmyResult<()> {
+    // Only converted images are cached, in memory
+    let url = url.clone();
+    PROXY_CACHE.invalidate_entries_if(move |(cached_url, _), _| cached_url == &url)?;
+    Ok(())
+  }
+}
+
+/// Aliases are generated by Lemmy, so anything else can be rejected. This also prevents path
+/// traversal in the filesystem store.
+fn is_valid_key(key: &str) -> bool {
+  !key.starts_with('.')
+    && key
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+#[derive(Debug)]
+struct ProcessedFile {
+  data: Bytes,
+  content_type: String,
+  extension: String,
+  width: u16,
+  height: u16,
+}
+
+/// Checks that the file is an image or an allowed video, and downscales images which are larger
+/// than `max_size`. Animations are flattened to their first frame when resizing or when they
+/// aren't allowed.
+fn process_file(
+  data: Bytes,
+  max_size: Option<u32>,
+  allow_video: bool,
+  allow_animation: bool,
+) -> LemmyResult<ProcessedFile> {
+  if let Ok(format) = image::guess_format(&data) {
+    let image = image::load_from_memory_with_format(&data, format)
+      .with_lemmy_type(LemmyErrorType::InvalidImageUpload)?;
+    let too_large = max_size.is_some_and(|m| image.width() > m || image.height() > m);
+    let flatten = !allow_animation && format == ImageFormat::Gif;
+    let (image, format, data) = if too_large || flatten {
+      let image = match max_size {
+        Some(m) if too_large => image.resize(m, m, FilterType::Lanczos3),
+        _ => image,
+      };
+      // Only the first frame of animations is kept
+      let format = if format == ImageFormat::Gif {
+        ImageFormat::Png
+      } else {
+        format
+      };
+      let (format, data) = encode_image(&image, format)?;
+      (image, format, data)
+    } else {
+      (image, format, data)
+    };
+    return Ok(ProcessedFile {
+      data,
+      content_type: format.to_mime_type().to_string(),
+      extension: format
+        .extensions_str()
+        .first()
+        .unwrap_or(&"png")
+        .to_string(),
+      width: u16::try_from(image.width()).unwrap_or(u16::MAX),
+      height: u16::try_from(image.height()).unwrap_or(u16::MAX),
+    });
+  }
+
+  match infer::get(&data) {
+    Some(kind) if kind.mime_type().starts_with("video/") => {
+      if !allow_video {
+        Err(LemmyErrorType::InvalidImageUpload)?
+      }
+      // Dimensions of videos are read afterwards with ffprobe
+      Ok(ProcessedFile {
+        content_type: kind.mime_type().to_string(),
+        extension: kind.extension().to_string(),
+        data,
+        width: 0,
+        height: 0,
+      })
+    }
+    _ => Err(LemmyErrorType::NotAnImageType)?,
+  }
+}
+
+/// Converts an image to the requested file type, and downscales it to `max_size`. Returns the
+/// content type and data.
+fn convert_image(data: &[u8], name: &str, params: &ImageGetParams) -> LemmyResult<(String, Bytes)> {
+  let mut image = image::load_from_memory(data).with_lemmy_type(LemmyErrorType::NotAnImageType)?;
+  if let Some(max_size) = params.max_size.and_then(|m| u32::try_from(m).ok()) {
+    if image.width() > max_size || image.height() > max_size {
+      image = image.thumbnail(max_size, max_size);
+    }
+  }
+  let format = ImageFormat::from_extension(file_type(params.file_type.clone(), name))
+    .unwrap_or(ImageFormat::Png);
+  let (format, data) = encode_image(&image, format)?;
+  Ok((format.to_mime_type().to_string(), data))
+}
+
+/// Reads the width and height of a video with ffprobe. The video is written to a temporary file,
+/// as some containers can't be probed from a pipe.
+async fn video_dimensions(data: &Bytes, ffprobe_path: &str) -> LemmyResult<(u16, u16)> {
+  let path = std::env::temp_dir().join(format!("lemmy-video-{}", Uuid::new_v4()));
+  fs::write(&path, data).await?;
+  let output = timeout(
+    PROBE_TIMEOUT,
+    Command::new(ffprobe_path)
+      .args([
+        "-v",
+        "error",
+        "-protocol_whitelist",
+        "file,pipe",
+        "-select_streams",
+        "v:0",
+        "-show_entries",
+        "stream=width,height",
+        "-of",
+        "csv=p=0:s=x",
+      ])
+      .arg(&path)
+      .kill_on_drop(true)
+      .output(),
+  )
+  .await;
+  fs::remove_file(&path).await.ok();
+
+  let output = output??;
+  if !output.status.success() {
+    Err(LemmyErrorType::CouldntProcessVideo)?
+  }
+  parse_dimensions(&String::from_utf8_lossy(&output.stdout))
+    .ok_or(LemmyErrorType::CouldntProcessVideo.into())
+}
+
+/// Parses ffprobe output like `1280x720`.
+fn parse_dimensions(output: &str) -> Option<(u16, u16)> {
+  let (width, height) = output.trim().split_once('x')?;
+  Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Encodes the image in the given format, or as png if this format can't be written.
+fn encode_image(image: &DynamicImage, format: ImageFormat) -> LemmyResult<(ImageFormat, Bytes)> {
+  // Jpeg has no alpha channel
+  let rgb;
+  let image = if format == ImageFormat::Jpeg {
+    rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+    &rgb
+  } else {
+    image
+  };
+  let mut data = Cursor::new(vec![]);
+  if image.write_to(&mut data, format).is_ok() {
+    return Ok((format, data.into_inner().into()));
+  }
+  let mut data = Cursor::new(vec![]);
+  image
+    .write_to(&mut data, ImageFormat::Png)
+    .with_lemmy_type(LemmyErrorType::InvalidImageUpload)?;
+  Ok((ImageFormat::Png, data.into_inner().into()))
+}
+
+/// Reads the body up to `max_size` bytes, within `timeout_secs`.
+async fn read_limited<S, E>(stream: S, max_size: usize, timeout_secs: u64) -> LemmyResult<Bytes>
+where
+  S: Stream<Item = Result<Bytes, E>>,
+  E: std::error::Error + Send + Sync + 'static,
+{
+  let mut stream = pin!(stream);
+  let read = async {
+    let mut body = vec![];
+    while let Some(chunk) = stream.next().await {
+      body.extend_from_slice(&chunk?);
+      if body.len() > max_size {
+        Err(LemmyErrorType::MediaTooLarge)?
+      }
+    }
+    LemmyResult::Ok(Bytes::from(body))
+  };
+  timeout(Duration::from_secs(timeout_secs), read).await?
+}
+
+fn multipart_boundary(headers: &HeaderMap) -> Option<String> {
+  let content_type: Mime = headers.get(CONTENT_TYPE)?.to_str().ok()?.parse().ok()?;
+  Some(content_type.get_param(mime::BOUNDARY)?.to_string())
+}
+
+/// Returns the contents of all files in a multipart form.
+fn multipart_files(body: &Bytes, boundary: &str) -> Vec<Bytes> {
+  let delimiter = format!("--{boundary}");
+  let delimiter = delimiter.as_bytes();
+  let mut files = vec![];
+  let Some(mut start) = find(body, delimiter) else {
+    return files;
+  };
+  loop {
+    start += delimiter.len();
+    let Some(rest) = body.get(start..) else {
+      break;
+    };
+    // The final delimiter is followed by `--`
+    if rest.starts_with(b"--") {
+      break;
+    }
+    let Some(end) = find(rest, delimiter).map(|e| start + e) else {
+      break;
+    };
+    if let Some(file) = multipart_file(body, start, end) {
+      files.push(file);
+    }
+    start = end;
+  }
+  files
+}
+
+/// Extracts the content of the part between `start` and `end`, if it is a file.
+fn multipart_file(body: &Bytes, start: usize, end: usize) -> Option<Bytes> {
+  let part = body.get(start..end)?;
+  let headers_end = find(part, b"\r\n\r\n")?;
+  let headers = String::from_utf8_lossy(part.get(..headers_end)?);
+  if !headers.contains("filename=") {
+    return None;
+  }
+  // Content is followed by a line break before the next delimiter
+  let content_start = start + headers_end + 4;
+  let content_end = end.checked_sub(2)?.max(content_start);
+  Some(body.slice(content_start..content_end))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::RgbImage;
+  use pretty_assertions::assert_eq;
+
+  fn png(width: u32, height: u32) -> LemmyResult<Bytes> {
+    let (_, data) = encode_image(
+      &DynamicImage::ImageRgb8(RgbImage::new(width, height)),
+      ImageFormat::Png,
+    )?;
+    Ok(data)
+  }
+
+  #[test]
+  fn test_multipart_files() {
+    let body = Bytes::from_static(
+      b"--abc\r\nContent-Disposition: form-data; name=\"images[]\"; filename=\"a.png\"\r\n\
+        Content-Type: image/png\r\n\r\nfirst\r\n\
+        --abc\r\nContent-Disposition: form-data; name=\"other\"\r\n\r\nnot a file\r\n\
+        --abc\r\nContent-Disposition: form-data; name=\"images[]\"; filename=\"b.png\"\r\n\r\n\
+        second\r\n--abc--\r\n",
+    );
+    assert_eq!(
+      vec![Bytes::from_static(b"first"), Bytes::from_static(b"second")],
+      multipart_files(&body, "abc")
+    );
+    assert!(multipart_files(&Bytes::from_static(b"no delimiter"), "abc").is_empty());
+  }
+
+  #[test]
+  fn test_process_file() -> LemmyResult<()> {
+    let file = process_file(png(100, 50)?, Some(20), false, false)?;
+    assert_eq!((20, 10), (file.width, file.height));
+    assert_eq!("image/png", file.content_type);
+    assert_eq!("png", file.extension);
+
+    // Smaller images are stored unchanged
+    let data = png(10, 10)?;
+    let file = process_file(data.clone(), Some(20), false, false)?;
+    assert_eq!(data, file.data);
+
+    assert!(process_file(Bytes::from_static(b"<html></html>"), None, true, true).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_convert_image() -> LemmyResult<()> {
+    let params = ImageGetParams {
+      file_type: Some("jpg".to_string()),
+      max_size: Some(10),
+    };
+    let (content_type, data) = convert_image(&png(40, 20)?, "a.png", &params)?;
+    assert_eq!("image/jpeg", content_type);
+    let image = image::load_from_memory(&data)?;
+    assert_eq!((10, 5), (image.width(), image.height()));
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_dimensions() {
+    assert_eq!(Some((1280, 720)), parse_dimensions("1280x720\n"));
+    assert_eq!(None, parse_dimensions(""));
+    assert_eq!(None, parse_dimensions("100000x720"));
+  }
+
+  #[test]
+  fn test_is_valid_key() {
+    assert!(is_valid_key("3f2c8a4e-6a8e-4bb5-9c1f-0b7b4f2a1d2e.png"));
+    assert!(!is_valid_key("../config.hjson"));
+    assert!(!is_valid_key("a/b.png"));
+    assert!(!is_valid_key(".hidden"));
+  }
+}
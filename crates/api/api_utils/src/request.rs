@@ -1,5 +1,6 @@
 use crate::{
   context::LemmyContext,
+  media_storage::media_storage,
   send_activity::{ActivityChannel, SendActivityData},
   utils::proxy_image_link,
 };
@@ -9,7 +10,7 @@ use encoding_rs::{Encoding, UTF_8};
 use futures::StreamExt;
 use lemmy_db_schema::{
   source::{
-    images::{ImageDetailsInsertForm, LocalImage, LocalImageForm},
+    images::{ImageDetails, ImageDetailsInsertForm, LocalImage, LocalImageForm},
     post::{Post, PostUpdateForm},
     site::Site,
   },
@@ -50,6 +51,35 @@ pub fn client_builder(settings: &Settings) -> ClientBuilder {
     .use_rustls_tls()
 }
 
+/// Resolve the domain and throw an error if it points to any internal IP, using logic from nightly
+/// IpAddr::is_global. This prevents users from making the server send requests to its local
+/// network. The check is skipped in debug builds, to allow testing with local servers.
+pub async fn check_url_is_public(url: &Url) -> LemmyResult<()> {
+  if cfg!(debug_assertions) {
+    return Ok(());
+  }
+  // TODO: Replace with IpAddr::is_global() once stabilized
+  //       https://doc.rust-lang.org/std/net/enum.IpAddr.html#method.is_global
+  let domain = url.domain().ok_or(FederationError::UrlWithoutDomain)?;
+  let invalid_ip = lookup_host((domain.to_owned(), 80))
+    .await?
+    .any(|addr| match addr.ip() {
+      IpAddr::V4(addr) => {
+        addr.is_private() || addr.is_link_local() || addr.is_loopback() || addr.is_multicast()
+      }
+      IpAddr::V6(addr) => {
+        addr.is_loopback()
+                      || addr.is_multicast()
+                      || ((addr.segments()[0] & 0xfe00) == 0xfc00) // is_unique_local
+                      || ((addr.segments()[0] & 0xffc0) == 0xfe80) // is_unicast_link_local
+      }
+    });
+  if invalid_ip {
+    return Err(LemmyErrorType::InvalidUrl.into());
+  }
+  Ok(())
+}
+
 /// Fetches metadata for the given link and optionally generates thumbnail.
 pub async fn fetch_link_metadata(
   url: &Url,
@@ -60,29 +90,7 @@ pub async fn fetch_link_metadata(
     return Err(LemmyErrorType::InvalidUrl.into());
   }
 
-  // Resolve the domain and throw an error if it points to any internal IP,
-  // using logic from nightly IpAddr::is_global.
-  if !cfg!(debug_assertions) {
-    // TODO: Replace with IpAddr::is_global() once stabilized
-    //       https://doc.rust-lang.org/std/net/enum.IpAddr.html#method.is_global
-    let domain = url.domain().ok_or(FederationError::UrlWithoutDomain)?;
-    let invalid_ip = lookup_host((domain.to_owned(), 80))
-      .await?
-      .any(|addr| match addr.ip() {
-        IpAddr::V4(addr) => {
-          addr.is_private() || addr.is_link_local() || addr.is_loopback() || addr.is_multicast()
-        }
-        IpAddr::V6(addr) => {
-          addr.is_loopback()
-                        || addr.is_multicast()
-                        || ((addr.segments()[0] & 0xfe00) == 0xfc00) // is_unique_local
-                        || ((addr.segments()[0] & 0xffc0) == 0xfe80) // is_unicast_link_local
-        }
-      });
-    if invalid_ip {
-      return Err(LemmyErrorType::InvalidUrl.into());
-    }
-  }
+  check_url_is_public(url).await?;
 
   info!("Fetching site metadata for url: {}", url);
   // We only fetch the first MB of data in order to not waste bandwidth especially for large
@@ -226,9 +234,23 @@ pub async fn generate_post_link_metadata(
     metadata.opengraph_data.image.clone()
   };
 
+  let details = match &post.url {
+    Some(url) => ImageDetails::read(&mut context.pool(), url).await?,
+    None => None,
+  };
+  // Uploads which the image classifier labeled as NSFW also mark the post as NSFW
+  let nsfw = details.as_ref().is_some_and(|d| d.nsfw).then_some(true);
+
+  // Videos uploaded to this instance already have a poster frame in pict-rs
+  let video_poster = details
+    .and_then(|details| details.poster_url)
+    .filter(|_| custom_thumbnail.is_none());
+
   // Attempt to generate a thumbnail depending on the instance settings. Either by proxying,
   // storing image persistently in pict-rs or returning the remote url directly as thumbnail.
-  let thumbnail_url = if let (false, Some(url)) = (is_image_post, custom_thumbnail) {
+  let thumbnail_url = if let Some(poster_url) = video_poster {
+    Some(poster_url)
+  } else if let (false, Some(url)) = (is_image_post, custom_thumbnail) {
     proxy_image_link(url.clone(), true, &context)
       .await
       .map_err(|e| warn!("Failed to proxy thumbnail: {e}"))
@@ -252,6 +274,7 @@ pub async fn generate_post_link_metadata(
     embed_video_url: Some(metadata.opengraph_data.embed_video_url),
     thumbnail_url: Some(thumbnail_url),
     url_content_type: Some(metadata.content_type),
+    nsfw,
     ..Default::default()
   };
   let updated_post = Post::update(&mut context.pool(), post.id, &form).await?;
@@ -350,6 +373,8 @@ pub struct PictrsFileDetails {
   pub content_type: String,
   pub created_at: DateTime<Utc>,
   pub blurhash: Option<String>,
+  /// In bytes. Not included in pict-rs responses, so it is filled in afterwards.
+  pub size: Option<i64>,
 }
 
 impl PictrsFileDetails {
@@ -362,16 +387,15 @@ impl PictrsFileDetails {
       height: self.height.into(),
       content_type: self.content_type.clone(),
       blurhash: self.blurhash.clone(),
+      duration: None,
+      poster_url: None,
+      perceptual_hash: None,
+      nsfw: false,
+      quarantined: false,
     }
   }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-struct PictrsPurgeResponse {
-  msg: String,
-  aliases: Vec<String>,
-}
-
 /// Purges an image from pictrs
 /// Note: This should often be coerced from a Result to .ok() in order to fail softly, because:
 /// - It might fail due to image being not local
@@ -393,65 +417,50 @@ pub async fn purge_image_from_pictrs_url(
 }
 
 pub async fn purge_image_from_pictrs(alias: &str, context: &LemmyContext) -> LemmyResult<()> {
-  let pictrs_config = context.settings().pictrs()?;
-  let purge_url = format!("{}internal/purge?alias={}", pictrs_config.url, alias);
-
-  let pictrs_api_key = pictrs_config
-    .api_key
-    .ok_or(LemmyErrorType::PictrsApiKeyNotProvided)?;
-  let response = context
-    .pictrs_client()
-    .post(&purge_url)
-    .timeout(REQWEST_TIMEOUT)
-    .header("x-api-token", pictrs_api_key)
-    .send()
-    .await?
-    .error_for_status()?;
-
-  let response: PictrsPurgeResponse = response.json().await.map_err(LemmyError::from)?;
-
-  // Pictrs purges return all aliases.
-  let aliases = response.aliases;
+  let aliases = media_storage(context.settings())?
+    .purge(alias, context)
+    .await?;
 
   // Delete db rows of aliases.
   LocalImage::delete_by_aliases(&mut context.pool(), &aliases)
     .await
     .ok();
-
-  match response.msg.as_str() {
-    "ok" => Ok(()),
-    _ => Err(LemmyErrorType::PictrsPurgeResponseError(response.msg))?,
+  for alias in &aliases {
+    delete_upload_details(alias, context).await;
   }
+  Ok(())
 }
 
-/// Deletes an alias for an image from the local db and pictrs. If it's not the last / only alias,
-/// the image might remain.
+/// Deletes an alias for an image from the local db and media storage. If it's not the last / only
+/// alias, the image might remain.
 ///
 /// # Security Warning
 /// This is a low-level function that doesn't check if the user is allowed to delete the image
 /// alias. Callers MUST check if the user has permission to delete the alias
 /// before calling this function (the user is an admin or the image belongs to the user).
 pub async fn delete_image_alias(alias: &str, context: &LemmyContext) -> LemmyResult<()> {
-  let pictrs_config = context.settings().pictrs()?;
-  let url = format!("{}internal/delete?alias={}", pictrs_config.url, &alias);
-
-  // Send the delete request to pictrs.
-  context
-    .pictrs_client()
-    .post(&url)
-    .header("X-Api-Token", pictrs_config.api_key.unwrap_or_default())
-    .timeout(REQWEST_TIMEOUT)
-    .send()
-    .await?
-    .error_for_status()?;
+  media_storage(context.settings())?
+    .delete(alias, context)
+    .await?;
 
   // Delete db row if any (old Lemmy versions didn't generate this).
   LocalImage::delete_by_alias(&mut context.pool(), alias)
     .await
     .ok();
+  delete_upload_details(alias, context).await;
   Ok(())
 }
 
+/// Deletes the details of a removed upload, so that it isn't listed as quarantined anymore.
+async fn delete_upload_details(alias: &str, context: &LemmyContext) {
+  let protocol_and_hostname = context.settings().get_protocol_and_hostname();
+  if let Ok(url) = Url::parse(&format!("{protocol_and_hostname}/api/v4/image/{alias}")) {
+    ImageDetails::delete(&mut context.pool(), &url.into())
+      .await
+      .ok();
+  }
+}
+
 /// Retrieves the image with local pict-rs and generates a thumbnail. Returns the thumbnail url.
 async fn generate_pictrs_thumbnail(
   post: &Post,
@@ -473,33 +482,16 @@ async fn generate_pictrs_thumbnail(
   };
 
   // fetch remote non-pictrs images for persistent thumbnail link
-  let fetch_url = format!(
-    "{}image/download?url={}&resize={}",
-    pictrs_config.url,
-    encode(image_url.as_str()),
-    context.settings().pictrs()?.max_thumbnail_size
-  );
-
-  let res = context
-    .pictrs_client()
-    .get(&fetch_url)
-    .timeout(REQWEST_TIMEOUT)
-    .send()
-    .await?
-    .error_for_status()?
-    .json::<PictrsResponse>()
+  let image = media_storage(context.settings())?
+    .store_remote(image_url, context)
     .await?;
 
-  let image = res
-    .files
-    .first()
-    .ok_or(LemmyErrorType::PictrsResponseError(res.msg))?;
-
   let form = LocalImageForm {
     pictrs_alias: image.file.clone(),
     // For thumbnails, the person_id is the post creator
     person_id: post.creator_id,
     thumbnail_for_post_id: Some(Some(post.id)),
+    file_size: image.details.size.unwrap_or_default(),
   };
   let protocol_and_hostname = context.settings().get_protocol_and_hostname();
   let thumbnail_url = image.image_url(&protocol_and_hostname)?;
@@ -524,7 +516,7 @@ pub async fn fetch_pictrs_proxied_image_details(
   // Pictrs needs you to fetch the proxied image before you can fetch the details
   let proxy_url = format!("{pictrs_url}image/original?proxy={encoded_image_url}");
 
-  context
+  let original = context
     .pictrs_client()
     .get(&proxy_url)
     .timeout(REQWEST_TIMEOUT)
@@ -532,10 +524,13 @@ pub async fn fetch_pictrs_proxied_image_details(
     .await?
     .error_for_status()
     .with_lemmy_type(LemmyErrorType::NotAnImageType)?;
+  let size = original
+    .content_length()
+    .and_then(|size| i64::try_from(size).ok());
 
   let details_url = format!("{pictrs_url}image/details/original?proxy={encoded_image_url}");
 
-  let res = context
+  let mut details: PictrsFileDetails = context
     .pictrs_client()
     .get(&details_url)
     .timeout(REQWEST_TIMEOUT)
@@ -544,8 +539,10 @@ pub async fn fetch_pictrs_proxied_image_details(
     .error_for_status()?
     .json()
     .await?;
+  // The details don't include the size, so it is taken from the original
+  details.size = size;
 
-  Ok(res)
+  Ok(details)
 }
 
 // TODO: get rid of this by reading content type from db
@@ -66,6 +66,7 @@ pub enum SendActivityData {
   },
   FollowCommunity(Community, Person, bool),
   FollowMultiCommunity(MultiCommunity, Person, bool),
+  FollowPerson(Person, Person, bool),
   AcceptFollower(CommunityId, PersonId),
   RejectFollower(CommunityId, PersonId),
   UpdateCommunity(Person, Community),
@@ -99,6 +100,7 @@ pub enum SendActivityData {
   CreatePrivateMessage(PrivateMessageView),
   UpdatePrivateMessage(PrivateMessageView),
   DeletePrivateMessage(Person, PrivateMessage, bool),
+  UpdateUser(Person),
   DeleteUser(Person, bool),
   CreateReport {
     object_id: Url,
@@ -1,19 +1,22 @@
 use crate::{
   claims::Claims,
   context::LemmyContext,
+  image_moderation::moderate_proxied_image,
   request::{delete_image_alias, fetch_pictrs_proxied_image_details, purge_image_from_pictrs_url},
 };
 use actix_web::{http::header::Header, HttpRequest};
 use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
-use chrono::{DateTime, Days, Local, TimeZone, Utc};
+use chrono::{DateTime, Days, Local, TimeDelta, TimeZone, Utc};
 use enum_map::{enum_map, EnumMap};
 use lemmy_db_schema::{
   newtypes::{CommentId, CommunityId, DbUrl, InstanceId, PersonId, PostId, PostOrCommentId},
   source::{
     comment::{Comment, CommentActions},
+    comment_revision::CommentRevision,
     community::{Community, CommunityActions, CommunityUpdateForm},
     images::{ImageDetails, RemoteImage},
     instance::{Instance, InstanceActions},
+    instance_policy::InstancePolicy,
     local_site::LocalSite,
     local_site_rate_limit::LocalSiteRateLimit,
     local_site_url_blocklist::LocalSiteUrlBlocklist,
@@ -26,6 +29,7 @@ use lemmy_db_schema::{
     oauth_account::OAuthAccount,
     person::{Person, PersonActions, PersonUpdateForm},
     post::{Post, PostActions, PostReadCommentsForm},
+    post_revision::PostRevision,
     private_message::PrivateMessage,
     registration_application::RegistrationApplication,
     site::Site,
@@ -47,7 +51,10 @@ use lemmy_db_views_site::{
 use lemmy_utils::{
   error::{LemmyError, LemmyErrorExt, LemmyErrorExt2, LemmyErrorType, LemmyResult},
   rate_limit::{ActionType, BucketConfig},
-  settings::{structs::PictrsImageMode, SETTINGS},
+  settings::{
+    structs::{PictrsImageMode, Settings},
+    SETTINGS,
+  },
   spawn_try_task,
   utils::{
     markdown::{image_links::markdown_rewrite_image_links, markdown_check_for_blocked_urls},
@@ -60,8 +67,11 @@ use lemmy_utils::{
 };
 use moka::future::Cache;
 use regex::{escape, Regex, RegexSet};
-use std::sync::LazyLock;
-use tracing::Instrument;
+use std::{
+  collections::HashMap,
+  sync::{Arc, LazyLock},
+};
+use tracing::{warn, Instrument};
 use url::{ParseError, Url};
 use urlencoding::encode;
 use webmention::{Webmention, WebmentionError};
@@ -400,10 +410,11 @@ pub async fn build_federated_instances(
     let mut blocked = Vec::new();
 
     let all = Instance::read_all_with_fed_state(pool).await?;
-    for (instance, federation_state, is_blocked, is_allowed) in all {
+    for (instance, federation_state, is_blocked, is_allowed, policy) in all {
       let i = InstanceWithFederationState {
         instance,
         federation_state: federation_state.map(std::convert::Into::into),
+        policy,
       };
       if is_blocked {
         // blocked instances will only have an entry here if they had been federated with in the
@@ -485,6 +496,43 @@ pub async fn slur_regex(context: &LemmyContext) -> LemmyResult<Regex> {
   )
 }
 
+static INSTANCE_POLICIES: CacheLock<Arc<HashMap<String, InstancePolicy>>> = LazyLock::new(|| {
+  Cache::builder()
+    .max_capacity(1)
+    .time_to_live(CACHE_DURATION_FEDERATION)
+    .build()
+});
+
+/// Returns all instance policies set by admins, keyed by instance domain.
+pub async fn instance_policies(
+  context: &LemmyContext,
+) -> LemmyResult<Arc<HashMap<String, InstancePolicy>>> {
+  let policies = INSTANCE_POLICIES
+    .try_get_with((), async {
+      let policies = InstancePolicy::read_all_with_domain(&mut context.pool()).await?;
+      Ok::<_, LemmyError>(Arc::new(policies.into_iter().collect()))
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("err getting instance policies: {e:?}"))?;
+  Ok(policies)
+}
+
+/// Needs to be called after changing an instance policy, so that it applies immediately.
+pub fn invalidate_instance_policies() {
+  INSTANCE_POLICIES.invalidate_all();
+}
+
+/// Returns true if admins disabled the image proxy cache for the instance which hosts this image.
+pub async fn never_cache_media(url: &Url, context: &LemmyContext) -> LemmyResult<bool> {
+  let policies = instance_policies(context).await?;
+  Ok(
+    url
+      .domain()
+      .and_then(|d| policies.get(d))
+      .is_some_and(|policy| policy.never_cache_media),
+  )
+}
+
 pub async fn get_url_blocklist(context: &LemmyContext) -> LemmyResult<RegexSet> {
   static URL_BLOCKLIST: CacheLock<RegexSet> = LazyLock::new(|| {
     Cache::builder()
@@ -522,6 +570,30 @@ pub fn check_nsfw_allowed(nsfw: Option<bool>, local_site: Option<&LocalSite>) ->
   Ok(())
 }
 
+/// The maximum size of all media uploaded by the user in bytes, or `None` if unlimited. A quota
+/// set for the user takes precedence over the quota for new accounts, which in turn takes
+/// precedence over the quota of the site. Admins can upload without limit. Like for the site, a
+/// quota of 0 set for the user means unlimited.
+pub fn media_quota(local_user_view: &LocalUserView, local_site: &LocalSite) -> Option<i64> {
+  if local_user_view.local_user.admin {
+    return None;
+  }
+  let new_account_quota = local_site.new_account_media_quota_mb.filter(|_| {
+    Utc::now()
+      .checked_sub_signed(TimeDelta::days(
+        local_site.new_account_media_quota_days.into(),
+      ))
+      .is_some_and(|new_account_since| local_user_view.person.published_at > new_account_since)
+  });
+  local_user_view
+    .local_user
+    .media_quota_mb
+    .or(new_account_quota)
+    .or(local_site.media_quota_mb)
+    .filter(|mb| *mb > 0)
+    .map(|mb| i64::from(mb) * 1024 * 1024)
+}
+
 /// Read the site for an ap_id.
 ///
 /// Used for GetCommunityResponse and GetPersonDetails
@@ -763,6 +835,10 @@ pub async fn purge_user_account(
   // No need to update avatar and banner, those are handled in Person::delete_account
   delete_local_user_images(person_id, context).await.ok();
 
+  // Remove the edit history first, so that no old versions of the content are left behind
+  CommentRevision::delete_for_creator(pool, person_id).await?;
+  PostRevision::delete_for_creator(pool, person_id).await?;
+
   // Comments
   Comment::permadelete_for_creator(pool, person_id)
     .await
@@ -862,12 +938,21 @@ pub async fn process_markdown(
 
     // Create images and image detail rows
     for link in links {
+      // These are redirected to the original by the image proxy, so they are never cached
+      if never_cache_media(&link, context).await? {
+        continue;
+      }
       // Insert image details for the remote image
       let details_res = fetch_pictrs_proxied_image_details(&link, context).await;
       if let Ok(details) = details_res {
         let proxied = build_proxied_image_url(&link, false, context)?;
-        let details_form = details.build_image_details_form(&proxied);
+        let mut details_form = details.build_image_details_form(&proxied);
+        details_form.perceptual_hash = moderate_proxied_image(&link, context)
+          .await
+          .inspect_err(|e| warn!("Failed to moderate proxied image {link}: {e}"))
+          .ok();
         ImageDetails::create(&mut context.pool(), &details_form).await?;
+        RemoteImage::set_cached(&mut context.pool(), &link.into(), details.size).await?;
       }
     }
     Ok(text)
@@ -900,8 +985,10 @@ async fn proxy_image_link_internal(
   is_thumbnail: bool,
   context: &LemmyContext,
 ) -> LemmyResult<DbUrl> {
-  // Dont rewrite links pointing to local domain.
-  if link.domain() == Some(&context.settings().hostname) {
+  // Dont rewrite links pointing to local domain, or to instances whose media shouldn't be cached.
+  if link.domain() == Some(&context.settings().hostname)
+    || never_cache_media(&link, context).await?
+  {
     Ok(link.into())
   } else if image_mode == PictrsImageMode::ProxyAllImages {
     RemoteImage::create(&mut context.pool(), vec![link.clone()]).await?;
@@ -911,8 +998,13 @@ async fn proxy_image_link_internal(
     let details_res = fetch_pictrs_proxied_image_details(&link, context).await;
 
     if let Ok(details) = details_res {
-      let details_form = details.build_image_details_form(&proxied);
+      let mut details_form = details.build_image_details_form(&proxied);
+      details_form.perceptual_hash = moderate_proxied_image(&link, context)
+        .await
+        .inspect_err(|e| warn!("Failed to moderate proxied image {link}: {e}"))
+        .ok();
       ImageDetails::create(&mut context.pool(), &details_form).await?;
+      RemoteImage::set_cached(&mut context.pool(), &link.clone().into(), details.size).await?;
     };
 
     Ok(proxied.into())
@@ -967,6 +1059,15 @@ fn build_proxied_image_url(
   Ok(Url::parse(&url)?)
 }
 
+/// Url of the oEmbed endpoint for the given post or comment, for use in discovery links.
+pub fn oembed_link(object: &Url, settings: &Settings) -> String {
+  format!(
+    "{}/api/v4/oembed?url={}",
+    settings.get_protocol_and_hostname(),
+    encode(object.as_str()),
+  )
+}
+
 pub async fn local_user_view_from_jwt(
   jwt: &str,
   context: &LemmyContext,
@@ -1,5 +1,10 @@
 use crate::{
-  activities::{generate_activity_id, generate_announce_activity_id, send_lemmy_activity},
+  activities::{
+    following::relay::read_relay,
+    generate_activity_id,
+    generate_announce_activity_id,
+    send_lemmy_activity,
+  },
   activity_lists::AnnouncableActivities,
   protocol::{
     activities::community::announce::{AnnounceActivity, RawAnnouncableActivities},
@@ -8,20 +13,26 @@ use crate::{
 };
 use activitypub_federation::{
   config::Data,
+  fetch::object_id::ObjectId,
   kinds::activity::AnnounceType,
   traits::{Activity, Object},
 };
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_apub_objects::{
-  objects::community::ApubCommunity,
+  objects::{community::ApubCommunity, post::ApubPost},
   utils::{
     functions::{generate_to, verify_person_in_community, verify_visibility},
     protocol::{Id, InCommunity},
   },
 };
-use lemmy_db_schema::source::{activity::ActivitySendTargets, community::CommunityActions};
+use lemmy_db_schema::source::{
+  activity::ActivitySendTargets,
+  community::CommunityActions,
+  relay::Relay,
+};
 use lemmy_utils::error::{FederationError, LemmyError, LemmyErrorType, LemmyResult};
 use serde_json::Value;
+use tracing::debug;
 use url::Url;
 
 #[async_trait::async_trait]
@@ -152,6 +163,9 @@ impl Activity for AnnounceActivity {
   }
 
   async fn receive(self, context: &Data<Self::DataType>) -> LemmyResult<()> {
+    if let Some(relay) = read_relay(self.actor.inner(), context).await? {
+      return self.receive_relayed(&relay, context).await;
+    }
     let object: AnnouncableActivities = self.object.object(context).await?.try_into()?;
 
     // This is only for sending, not receiving so we reject it.
@@ -169,6 +183,42 @@ impl Activity for AnnounceActivity {
   }
 }
 
+impl AnnounceActivity {
+  /// Relays announce public posts from anywhere in the fediverse, regardless if there are local
+  /// followers. Only the object id is used, and the post is fetched from its origin to ensure it
+  /// is authentic.
+  async fn receive_relayed(&self, relay: &Relay, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    let object_id = match &self.object {
+      IdOrNestedObject::Id(id) => id.clone(),
+      // Some relays wrap the original activity (eg Create/Page) instead of only sending its id
+      IdOrNestedObject::NestedObject(activity) => {
+        let id = activity
+          .other
+          .get("object")
+          .and_then(|o| o.get("id").unwrap_or(o).as_str())
+          .ok_or(LemmyErrorType::NotFound)?;
+        Url::parse(id)?
+      }
+    };
+
+    // Relays also forward content which Lemmy can't handle, like microblog posts. Ignore these
+    // instead of returning an error to the relay.
+    let post = ObjectId::<ApubPost>::from(object_id.clone())
+      .dereference(context)
+      .await;
+    match post {
+      Ok(_) => Relay::mark_received(&mut context.pool(), relay.id).await,
+      Err(e) => {
+        debug!(
+          "Ignoring object {object_id} from relay {}: {e}",
+          relay.ap_id
+        );
+        Ok(())
+      }
+    }
+  }
+}
+
 impl TryFrom<RawAnnouncableActivities> for AnnouncableActivities {
   type Error = serde_json::error::Error;
 
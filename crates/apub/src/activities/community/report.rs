@@ -30,7 +30,7 @@ use lemmy_apub_objects::{
     PostOrComment,
     ReportableObjects,
   },
-  utils::functions::verify_person_in_site_or_community,
+  utils::functions::{instance_policy, verify_person_in_site_or_community},
 };
 use lemmy_db_schema::{
   source::{
@@ -98,6 +98,12 @@ impl Activity for Report {
   }
 
   async fn receive(self, context: &Data<Self::DataType>) -> LemmyResult<()> {
+    if instance_policy(self.actor.inner(), context)
+      .await?
+      .reject_reports
+    {
+      return Ok(());
+    }
     let actor = self.actor.dereference(context).await?;
     let reason = self.reason()?;
     match self.object.dereference(context).await? {
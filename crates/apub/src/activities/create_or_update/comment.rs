@@ -31,6 +31,7 @@ use lemmy_db_schema::{
   source::{
     activity::ActivitySendTargets,
     comment::{Comment, CommentActions, CommentLikeForm},
+    comment_revision::CommentRevision,
     community::Community,
     person::Person,
     post::Post,
@@ -138,7 +139,7 @@ impl Activity for CreateOrUpdateNote {
     // send the activity, not the comment author.
     let existing_comment = self.object.id.dereference_local(context).await.ok();
     if let (Some(distinguished), Some(existing_comment)) =
-      (self.object.distinguished, existing_comment)
+      (self.object.distinguished, &existing_comment)
     {
       if distinguished != existing_comment.distinguished {
         let creator = self.actor.dereference(context).await?;
@@ -166,6 +167,17 @@ impl Activity for CreateOrUpdateNote {
       self.kind == CreateOrUpdateType::Create && !site_view.local_site.disable_email_notifications;
     let actor = self.actor.dereference(context).await?;
 
+    // Keep the previous version around, so that edits can be reviewed later
+    if let Some(existing_comment) = existing_comment {
+      CommentRevision::create_if_changed(
+        &mut context.pool(),
+        &existing_comment,
+        &comment,
+        actor.id,
+      )
+      .await?;
+    }
+
     // Note:
     // Although mentions could be gotten from the post tags (they are included there), or the ccs,
     // Its much easier to scrape them from the comment body, since the API has to do that
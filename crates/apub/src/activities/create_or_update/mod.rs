@@ -1,4 +1,5 @@
 pub mod comment;
 pub(crate) mod note_wrapper;
+pub mod person;
 pub mod post;
 pub mod private_message;
@@ -0,0 +1,60 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity, verify_person},
+  protocol::activities::create_or_update::person::UpdatePerson,
+};
+use activitypub_federation::{
+  config::Data,
+  kinds::{activity::UpdateType, public},
+  protocol::verification::verify_urls_match,
+  traits::{Activity, Object},
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub_objects::objects::person::ApubPerson;
+use lemmy_db_schema::source::{activity::ActivitySendTargets, person::Person};
+use lemmy_utils::error::{LemmyError, LemmyResult};
+use url::Url;
+
+pub(crate) async fn send_update_person(
+  person: Person,
+  context: Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let person: ApubPerson = person.into();
+  let id = generate_activity_id(UpdateType::Update, &context)?;
+  let update = UpdatePerson {
+    actor: person.ap_id.clone().into(),
+    to: vec![public()],
+    object: person.clone().into_json(&context).await?,
+    kind: UpdateType::Update,
+    id,
+  };
+
+  // Any instance may have a copy of the user's device keys
+  let inboxes = ActivitySendTargets::to_all_instances();
+  send_lemmy_activity(&context, update, &person, inboxes, true).await
+}
+
+#[async_trait::async_trait]
+impl Activity for UpdatePerson {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  async fn verify(&self, context: &Data<Self::DataType>) -> LemmyResult<()> {
+    verify_person(&self.actor, context).await?;
+    verify_urls_match(self.actor.inner(), self.object.id.inner())?;
+    ApubPerson::verify(&self.object, self.actor.inner(), context).await?;
+    Ok(())
+  }
+
+  async fn receive(self, context: &Data<Self::DataType>) -> LemmyResult<()> {
+    ApubPerson::from_json(self.object, context).await?;
+    Ok(())
+  }
+}
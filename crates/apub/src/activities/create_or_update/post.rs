@@ -27,6 +27,7 @@ use lemmy_db_schema::{
     community::Community,
     person::Person,
     post::{Post, PostActions, PostLikeForm},
+    post_revision::PostRevision,
   },
   traits::{Crud, Likeable},
 };
@@ -112,6 +113,8 @@ impl Activity for CreateOrUpdatePage {
     let site_view = SiteView::read_local(&mut context.pool()).await?;
     let local_instance_id = site_view.site.instance_id;
 
+    // Keep the previous version around, so that edits can be reviewed later
+    let existing_post = self.object.id.dereference_local(context).await.ok();
     let post = ApubPost::from_json(self.object, context).await?;
 
     // author likes their own post by default
@@ -125,6 +128,10 @@ impl Activity for CreateOrUpdatePage {
       self.kind == CreateOrUpdateType::Create && !site_view.local_site.disable_email_notifications;
     let actor = self.actor.dereference(context).await?;
 
+    if let Some(existing_post) = existing_post {
+      PostRevision::create_if_changed(&mut context.pool(), &existing_post, &post, actor.id).await?;
+    }
+
     // Send the post body mentions
     let mentions = scrape_text_for_mentions(&post.body.clone().unwrap_or_default());
     send_local_notifs(
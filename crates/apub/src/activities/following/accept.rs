@@ -1,5 +1,7 @@
+use super::relay::{read_relay, relay_follow_response, verify_relay_follow};
 use crate::{
   activities::{generate_activity_id, send_lemmy_activity},
+  fetcher::backfill::start_backfill,
   protocol::activities::following::{accept::AcceptFollow, follow::Follow},
 };
 use activitypub_federation::{
@@ -8,12 +10,17 @@ use activitypub_federation::{
   protocol::verification::verify_urls_match,
   traits::{Activity, Actor, Object},
 };
+use either::Either::*;
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_db_schema::{
-  source::{activity::ActivitySendTargets, community::CommunityActions},
-  traits::Followable,
+  source::{
+    activity::ActivitySendTargets,
+    community::{Community, CommunityActions},
+    person::PersonActions,
+  },
+  traits::{Crud, Followable},
 };
-use lemmy_utils::error::{LemmyError, LemmyResult};
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 use url::Url;
 
 impl AcceptFollow {
@@ -47,6 +54,9 @@ impl Activity for AcceptFollow {
   }
 
   async fn verify(&self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    if read_relay(self.actor.inner(), context).await?.is_some() {
+      return verify_relay_follow(&self.object, context).await;
+    }
     verify_urls_match(self.actor.inner(), self.object.object.inner())?;
     self.object.verify(context).await?;
     if let Some(to) = &self.to {
@@ -56,12 +66,27 @@ impl Activity for AcceptFollow {
   }
 
   async fn receive(self, context: &Data<LemmyContext>) -> LemmyResult<()> {
-    let community = self.actor.dereference(context).await?;
+    if let Some(relay) = read_relay(self.actor.inner(), context).await? {
+      return relay_follow_response(&relay, true, context).await;
+    }
+    let target = self.actor.dereference(context).await?;
     let person = self.object.actor.dereference(context).await?;
     // This will throw an error if no follow was requested
-    let community_id = community.id;
     let person_id = person.id;
-    CommunityActions::follow_accepted(&mut context.pool(), community_id, person_id).await?;
+    match target {
+      Left(u) => {
+        PersonActions::follow_accepted(&mut context.pool(), u.id, person_id).await?;
+      }
+      Right(Left(c)) => {
+        CommunityActions::follow_accepted(&mut context.pool(), c.id, person_id).await?;
+        // Fetch older posts and comments when the first local user follows the community
+        let community = Community::read(&mut context.pool(), c.id).await?;
+        if community.subscribers_local <= 1 {
+          start_backfill(&community.into(), context).await?;
+        }
+      }
+      Right(Right(_)) => Err(LemmyErrorType::NotFound)?,
+    }
 
     Ok(())
   }
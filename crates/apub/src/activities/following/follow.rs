@@ -11,7 +11,7 @@ use activitypub_federation::{
 use either::Either::*;
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_apub_objects::{
-  objects::{person::ApubPerson, CommunityOrMulti},
+  objects::{person::ApubPerson, UserOrCommunityOrMulti},
   utils::functions::verify_person_in_community,
 };
 use lemmy_db_schema::{
@@ -31,7 +31,7 @@ use url::Url;
 impl Follow {
   pub(in crate::activities::following) fn new(
     actor: &ApubPerson,
-    target: &CommunityOrMulti,
+    target: &UserOrCommunityOrMulti,
     context: &Data<LemmyContext>,
   ) -> LemmyResult<Follow> {
     Ok(Follow {
@@ -45,7 +45,7 @@ impl Follow {
 
   pub async fn send(
     actor: &ApubPerson,
-    target: &CommunityOrMulti,
+    target: &UserOrCommunityOrMulti,
     context: &Data<LemmyContext>,
   ) -> LemmyResult<()> {
     let follow = Follow::new(actor, target, context)?;
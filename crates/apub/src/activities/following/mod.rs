@@ -8,7 +8,7 @@ use crate::protocol::activities::following::{
 use activitypub_federation::{config::Data, kinds::activity::FollowType, traits::Activity};
 use either::Either::*;
 use lemmy_api_utils::context::LemmyContext;
-use lemmy_apub_objects::objects::{person::ApubPerson, CommunityOrMulti, UserOrCommunityOrMulti};
+use lemmy_apub_objects::objects::{person::ApubPerson, UserOrCommunityOrMulti};
 use lemmy_db_schema::{
   newtypes::{CommunityId, PersonId},
   source::{activity::ActivitySendTargets, community::Community, person::Person},
@@ -20,10 +20,11 @@ use serde::Serialize;
 pub(crate) mod accept;
 pub(crate) mod follow;
 pub(crate) mod reject;
+pub(crate) mod relay;
 pub(crate) mod undo_follow;
 
 pub async fn send_follow(
-  target: CommunityOrMulti,
+  target: UserOrCommunityOrMulti,
   person: Person,
   follow: bool,
   context: &Data<LemmyContext>,
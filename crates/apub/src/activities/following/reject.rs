@@ -1,4 +1,7 @@
-use super::send_activity_from_user_or_community_or_multi;
+use super::{
+  relay::{read_relay, relay_follow_response, verify_relay_follow},
+  send_activity_from_user_or_community_or_multi,
+};
 use crate::{
   activities::generate_activity_id,
   protocol::activities::following::{follow::Follow, reject::RejectFollow},
@@ -9,9 +12,15 @@ use activitypub_federation::{
   protocol::verification::verify_urls_match,
   traits::{Activity, Actor, Object},
 };
+use either::Either::*;
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_db_schema::{
-  source::{activity::ActivitySendTargets, community::CommunityActions},
+  source::{
+    activity::ActivitySendTargets,
+    community::CommunityActions,
+    multi_community::MultiCommunity,
+    person::PersonActions,
+  },
   traits::Followable,
 };
 use lemmy_utils::error::{LemmyError, LemmyResult};
@@ -48,6 +57,9 @@ impl Activity for RejectFollow {
   }
 
   async fn verify(&self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    if read_relay(self.actor.inner(), context).await?.is_some() {
+      return verify_relay_follow(&self.object, context).await;
+    }
     verify_urls_match(self.actor.inner(), self.object.object.inner())?;
     self.object.verify(context).await?;
     if let Some(to) = &self.to {
@@ -57,11 +69,22 @@ impl Activity for RejectFollow {
   }
 
   async fn receive(self, context: &Data<LemmyContext>) -> LemmyResult<()> {
-    let community = self.actor.dereference(context).await?;
+    if let Some(relay) = read_relay(self.actor.inner(), context).await? {
+      return relay_follow_response(&relay, false, context).await;
+    }
+    let target = self.actor.dereference(context).await?;
     let person = self.object.actor.dereference(context).await?;
 
     // remove the follow
-    CommunityActions::unfollow(&mut context.pool(), person.id, community.id).await?;
+    match target {
+      Left(u) => {
+        PersonActions::unfollow(&mut context.pool(), person.id, u.id).await?;
+      }
+      Right(Left(c)) => {
+        CommunityActions::unfollow(&mut context.pool(), person.id, c.id).await?;
+      }
+      Right(Right(m)) => MultiCommunity::unfollow(&mut context.pool(), person.id, m.id).await?,
+    }
 
     Ok(())
   }
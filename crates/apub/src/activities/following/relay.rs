@@ -0,0 +1,142 @@
+use crate::{
+  activities::{generate_activity_id, send_lemmy_activity},
+  protocol::activities::following::{
+    follow::Follow,
+    relay::{RelayFollow, UndoRelayFollow},
+  },
+};
+use activitypub_federation::{
+  config::Data,
+  kinds::{
+    activity::{FollowType, UndoType},
+    public,
+  },
+  protocol::verification::verify_urls_match,
+  traits::{Activity, Object},
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub_objects::objects::instance::ApubSite;
+use lemmy_db_schema::source::{
+  activity::ActivitySendTargets,
+  relay::{Relay, RelayUpdateForm},
+  site::Site,
+};
+use lemmy_db_schema_file::enums::RelayType;
+use lemmy_utils::error::{FederationError, LemmyError, LemmyResult};
+use url::Url;
+
+impl RelayFollow {
+  fn new(site: &ApubSite, relay: &Relay, context: &Data<LemmyContext>) -> LemmyResult<Self> {
+    let object = match relay.relay_type {
+      RelayType::Mastodon => public(),
+      RelayType::LitePub => relay.ap_id.clone().into(),
+    };
+    Ok(RelayFollow {
+      actor: site.id().clone().into(),
+      to: [relay.ap_id.clone().into()],
+      object,
+      kind: FollowType::Follow,
+      id: generate_activity_id(FollowType::Follow, context)?,
+    })
+  }
+}
+
+/// Subscribe the site actor to the relay, or unsubscribe it.
+pub(crate) async fn send_relay_follow(
+  relay: &Relay,
+  follow: bool,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let site: ApubSite = Site::read_local(&mut context.pool()).await?.into();
+  let follow_activity = RelayFollow::new(&site, relay, context)?;
+  let inbox = ActivitySendTargets::to_inbox(relay.inbox_url.clone().into());
+  if follow {
+    send_lemmy_activity(context, follow_activity, &site, inbox, true).await
+  } else {
+    let undo = UndoRelayFollow {
+      actor: site.id().clone().into(),
+      to: [relay.ap_id.clone().into()],
+      object: follow_activity,
+      kind: UndoType::Undo,
+      id: generate_activity_id(UndoType::Undo, context)?,
+    };
+    send_lemmy_activity(context, undo, &site, inbox, true).await
+  }
+}
+
+/// Returns the relay if the given actor is one.
+pub(crate) async fn read_relay(
+  actor: &Url,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Option<Relay>> {
+  Relay::read_from_ap_id(&mut context.pool(), &actor.clone().into()).await
+}
+
+/// Ensures that an accepted or rejected follow was sent by the local site actor.
+pub(crate) async fn verify_relay_follow(
+  follow: &Follow,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let site = Site::read_local(&mut context.pool()).await?;
+  verify_urls_match(follow.actor.inner(), site.ap_id.inner())?;
+  Ok(())
+}
+
+/// Marks the follow of the site actor as accepted or rejected by the relay.
+pub(crate) async fn relay_follow_response(
+  relay: &Relay,
+  accepted: bool,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let form = RelayUpdateForm {
+    follow_accepted: Some(accepted),
+    ..Default::default()
+  };
+  Relay::update(&mut context.pool(), relay.id, &form).await?;
+  Ok(())
+}
+
+/// Relay follows are only sent, Lemmy doesn't act as a relay.
+#[async_trait::async_trait]
+impl Activity for RelayFollow {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  async fn verify(&self, _context: &Data<LemmyContext>) -> LemmyResult<()> {
+    Err(FederationError::Unreachable.into())
+  }
+
+  async fn receive(self, _context: &Data<LemmyContext>) -> LemmyResult<()> {
+    Err(FederationError::Unreachable.into())
+  }
+}
+
+#[async_trait::async_trait]
+impl Activity for UndoRelayFollow {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  async fn verify(&self, _context: &Data<LemmyContext>) -> LemmyResult<()> {
+    Err(FederationError::Unreachable.into())
+  }
+
+  async fn receive(self, _context: &Data<LemmyContext>) -> LemmyResult<()> {
+    Err(FederationError::Unreachable.into())
+  }
+}
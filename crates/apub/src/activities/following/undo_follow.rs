@@ -10,7 +10,7 @@ use activitypub_federation::{
 };
 use either::Either::*;
 use lemmy_api_utils::context::LemmyContext;
-use lemmy_apub_objects::objects::{person::ApubPerson, CommunityOrMulti};
+use lemmy_apub_objects::objects::{person::ApubPerson, UserOrCommunityOrMulti};
 use lemmy_db_schema::{
   source::{
     activity::ActivitySendTargets,
@@ -26,7 +26,7 @@ use url::Url;
 impl UndoFollow {
   pub async fn send(
     actor: &ApubPerson,
-    target: &CommunityOrMulti,
+    target: &UserOrCommunityOrMulti,
     context: &Data<LemmyContext>,
   ) -> LemmyResult<()> {
     let object = Follow::new(actor, target, context)?;
@@ -6,7 +6,7 @@ use crate::{
       lock_page::send_lock_post,
       update::{send_update_community, send_update_multi_community},
     },
-    create_or_update::private_message::send_create_or_update_pm,
+    create_or_update::{person::send_update_person, private_message::send_create_or_update_pm},
     deletion::{
       send_apub_delete_in_community,
       send_apub_delete_private_message,
@@ -270,10 +270,15 @@ pub async fn match_outgoing_activities(
         .await
       }
       FollowCommunity(community, person, follow) => {
-        send_follow(Either::Left(community.into()), person, follow, &context).await
+        let target = Either::Right(Either::Left(community.into()));
+        send_follow(target, person, follow, &context).await
       }
       FollowMultiCommunity(multi, person, follow) => {
-        send_follow(Either::Right(multi.into()), person, follow, &context).await
+        let target = Either::Right(Either::Right(multi.into()));
+        send_follow(target, person, follow, &context).await
+      }
+      FollowPerson(target, person, follow) => {
+        send_follow(Either::Left(target.into()), person, follow, &context).await
       }
       UpdateCommunity(actor, community) => send_update_community(community, actor, context).await,
       DeleteCommunity(actor, community, removed) => {
@@ -337,6 +342,7 @@ pub async fn match_outgoing_activities(
       DeletePrivateMessage(person, pm, deleted) => {
         send_apub_delete_private_message(&person.into(), pm, deleted, context).await
       }
+      UpdateUser(person) => send_update_person(person, context).await,
       DeleteUser(person, remove_data) => send_apub_delete_user(person, remove_data, context).await,
       CreateReport {
         object_id,
@@ -14,7 +14,10 @@ use activitypub_federation::{
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_apub_objects::{
   objects::{person::ApubPerson, PostOrComment},
-  utils::{functions::verify_person_in_community, protocol::InCommunity},
+  utils::{
+    functions::{instance_policy, verify_person_in_community},
+    protocol::InCommunity,
+  },
 };
 use lemmy_utils::error::{LemmyError, LemmyResult};
 use url::Url;
@@ -56,6 +59,13 @@ impl Activity for UndoVote {
   }
 
   async fn receive(self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    // Votes from this instance are ignored, so undoing them is ignored as well
+    if instance_policy(self.actor.inner(), context)
+      .await?
+      .strip_votes
+    {
+      return Ok(());
+    }
     let actor = self.actor.dereference(context).await?;
     let object = self.object.object.dereference(context).await?;
     match object {
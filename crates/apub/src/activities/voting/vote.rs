@@ -13,7 +13,10 @@ use activitypub_federation::{
 use lemmy_api_utils::{context::LemmyContext, utils::check_bot_account};
 use lemmy_apub_objects::{
   objects::{person::ApubPerson, PostOrComment},
-  utils::{functions::verify_person_in_community, protocol::InCommunity},
+  utils::{
+    functions::{instance_policy, verify_person_in_community},
+    protocol::InCommunity,
+  },
 };
 use lemmy_db_schema_file::enums::FederationMode;
 use lemmy_db_views_site::SiteView;
@@ -56,6 +59,12 @@ impl Activity for Vote {
   }
 
   async fn receive(self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    if instance_policy(self.actor.inner(), context)
+      .await?
+      .strip_votes
+    {
+      return Ok(());
+    }
     let actor = self.actor.dereference(context).await?;
     let object = self.object.dereference(context).await?;
 
@@ -9,7 +9,11 @@ use crate::protocol::activities::{
     resolve_report::ResolveReport,
     update::Update,
   },
-  create_or_update::{note_wrapper::CreateOrUpdateNoteWrapper, page::CreateOrUpdatePage},
+  create_or_update::{
+    note_wrapper::CreateOrUpdateNoteWrapper,
+    page::CreateOrUpdatePage,
+    person::UpdatePerson,
+  },
   deletion::{delete::Delete, undo_delete::UndoDelete},
   following::{
     accept::AcceptFollow,
@@ -45,6 +49,7 @@ pub(crate) enum SharedInboxActivities {
   UndoFollow(UndoFollow),
   Report(Report),
   ResolveReport(ResolveReport),
+  UpdatePerson(Box<UpdatePerson>),
   AnnounceActivity(AnnounceActivity),
   /// This is a catch-all and needs to be last
   RawAnnouncableActivities(RawAnnouncableActivities),
@@ -124,6 +129,9 @@ mod tests {
     test_parse_lemmy_item::<SharedInboxActivities>(
       "assets/lemmy/activities/create_or_update/create_comment.json",
     )?;
+    test_parse_lemmy_item::<SharedInboxActivities>(
+      "assets/lemmy/activities/create_or_update/update_person.json",
+    )?;
     test_json::<SharedInboxActivities>("assets/mastodon/activities/follow.json")?;
     Ok(())
   }
@@ -0,0 +1,34 @@
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use actix_web::web::{Json, Query};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub_objects::objects::person::ApubPerson;
+use lemmy_db_schema::{
+  source::{person::Person, person_device_key::PersonDeviceKey},
+  traits::Crud,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person::api::{ListDeviceKeys, ListDeviceKeysResponse};
+use lemmy_utils::error::LemmyResult;
+use tracing::warn;
+
+pub async fn list_device_keys(
+  data: Query<ListDeviceKeys>,
+  context: Data<LemmyContext>,
+  _local_user_view: LocalUserView,
+) -> LemmyResult<Json<ListDeviceKeysResponse>> {
+  let person = Person::read(&mut context.pool(), data.person_id).await?;
+
+  // Keys of remote users are updated when the actor is fetched or sends an update. Refetch it
+  // if it is outdated, to avoid encrypting to old devices.
+  if !person.local {
+    let object_id: ObjectId<ApubPerson> = person.ap_id.clone().into();
+    object_id
+      .dereference(&context)
+      .await
+      .inspect_err(|e| warn!("Failed to refetch {}: {e}", person.ap_id))
+      .ok();
+  }
+
+  let device_keys = PersonDeviceKey::list_for_person(&mut context.pool(), person.id).await?;
+  Ok(Json(ListDeviceKeysResponse { device_keys }))
+}
@@ -89,6 +89,7 @@ pub async fn list_posts(
     time_range_seconds,
     community_id,
     multi_community_id,
+    tag_id: None,
     limit,
     show_hidden,
     show_read,
@@ -11,10 +11,12 @@ use lemmy_db_views_local_user::LocalUserView;
 use lemmy_utils::error::{LemmyErrorType, LemmyResult};
 
 pub mod list_comments;
+pub mod list_device_keys;
 pub mod list_person_content;
 pub mod list_posts;
 pub mod read_community;
 pub mod read_person;
+pub mod relay;
 pub mod resolve_object;
 pub mod search;
 pub mod user_settings_backup;
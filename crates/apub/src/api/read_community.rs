@@ -6,7 +6,11 @@ use lemmy_api_utils::{
   utils::{check_private_instance, is_mod_or_admin_opt, read_site_for_actor},
 };
 use lemmy_apub_objects::objects::community::ApubCommunity;
-use lemmy_db_schema::source::{actor_language::CommunityLanguage, community::Community};
+use lemmy_db_schema::source::{
+  actor_language::CommunityLanguage,
+  community::Community,
+  community_backfill::CommunityBackfill,
+};
 use lemmy_db_views_community::{
   api::{GetCommunity, GetCommunityResponse},
   CommunityView,
@@ -63,11 +67,13 @@ pub async fn get_community(
 
   let community_id = community_view.community.id;
   let discussion_languages = CommunityLanguage::read(&mut context.pool(), community_id).await?;
+  let backfill = CommunityBackfill::read(&mut context.pool(), community_id).await?;
 
   Ok(Json(GetCommunityResponse {
     community_view,
     site,
     moderators,
     discussion_languages,
+    backfill,
   }))
 }
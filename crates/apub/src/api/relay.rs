@@ -0,0 +1,126 @@
+use crate::activities::following::relay::send_relay_follow;
+use activitypub_federation::{config::Data, fetch::fetch_object_http, traits::Object};
+use actix_web::web::Json;
+use chrono::Utc;
+use lemmy_api_utils::{context::LemmyContext, utils::is_admin};
+use lemmy_apub_objects::{objects::relay::ApubRelay, protocol::relay::RelayActor};
+use lemmy_db_schema::source::{
+  federation_queue_state::FederationQueueState,
+  instance::Instance,
+  relay::{Relay, RelayInsertForm, RelayUpdateForm},
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::api::{
+  AddRelay,
+  EditRelay,
+  ListRelaysResponse,
+  RelayResponse,
+  RelayWithFederationState,
+  RemoveRelay,
+  SuccessResponse,
+};
+use lemmy_utils::error::{FederationError, LemmyResult};
+use url::Url;
+
+pub async fn add_relay(
+  data: Json<AddRelay>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<RelayResponse>> {
+  is_admin(&local_user_view)?;
+
+  // Relays are not stored as regular actors, so fetch the actor manually
+  let ap_id = Url::parse(&data.ap_id)?;
+  let actor: RelayActor = fetch_object_http(&ap_id, &context).await?.object;
+  ApubRelay::verify(&actor, &ap_id, &context).await?;
+
+  let domain = actor
+    .id
+    .inner()
+    .domain()
+    .ok_or(FederationError::UrlWithoutDomain)?;
+  let instance = Instance::read_or_create(&mut context.pool(), domain.to_string()).await?;
+  let form = RelayInsertForm {
+    publish: data.publish,
+    ..RelayInsertForm::new(
+      actor.id.clone().into(),
+      actor.shared_inbox_or_inbox().into(),
+      actor.public_key.public_key_pem,
+      instance.id,
+      data.relay_type,
+    )
+  };
+  let relay = Relay::create(&mut context.pool(), &form).await?;
+  send_relay_follow(&relay, true, &context).await?;
+
+  Ok(Json(RelayResponse {
+    relay: with_federation_state(relay, &context).await?,
+  }))
+}
+
+pub async fn edit_relay(
+  data: Json<EditRelay>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<RelayResponse>> {
+  is_admin(&local_user_view)?;
+
+  let form = RelayUpdateForm {
+    publish: Some(data.publish),
+    updated_at: Some(Some(Utc::now())),
+    ..Default::default()
+  };
+  let relay = Relay::update(&mut context.pool(), data.relay_id, &form).await?;
+
+  Ok(Json(RelayResponse {
+    relay: with_federation_state(relay, &context).await?,
+  }))
+}
+
+pub async fn remove_relay(
+  data: Json<RemoveRelay>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<SuccessResponse>> {
+  is_admin(&local_user_view)?;
+
+  let relay = Relay::read(&mut context.pool(), data.relay_id).await?;
+  send_relay_follow(&relay, false, &context).await?;
+  Relay::delete(&mut context.pool(), relay.id).await?;
+
+  Ok(Json(SuccessResponse::default()))
+}
+
+pub async fn list_relays(
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<ListRelaysResponse>> {
+  is_admin(&local_user_view)?;
+
+  let mut relays = vec![];
+  for relay in Relay::list(&mut context.pool()).await? {
+    relays.push(with_federation_state(relay, &context).await?);
+  }
+
+  Ok(Json(ListRelaysResponse { relays }))
+}
+
+/// Statistics for outgoing activities are taken from the federation queue of the relay instance.
+async fn with_federation_state(
+  relay: Relay,
+  context: &LemmyContext,
+) -> LemmyResult<RelayWithFederationState> {
+  let federation_state = if relay.publish {
+    Some(
+      FederationQueueState::load(&mut context.pool(), relay.instance_id)
+        .await?
+        .into(),
+    )
+  } else {
+    None
+  };
+  Ok(RelayWithFederationState {
+    relay,
+    federation_state,
+  })
+}
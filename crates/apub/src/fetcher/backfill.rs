@@ -0,0 +1,405 @@
+use activitypub_federation::{
+  config::Data,
+  fetch::{fetch_object_http, object_id::ObjectId},
+};
+use chrono::{TimeDelta, Utc};
+use futures::{stream, StreamExt};
+use itertools::Itertools;
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub_objects::{
+  objects::{comment::ApubComment, community::ApubCommunity, post::ApubPost},
+  protocol::group::Group,
+};
+use lemmy_db_schema::{
+  source::{community::Community, community_backfill::CommunityBackfill, instance::Instance},
+  traits::Crud,
+};
+use lemmy_utils::{error::LemmyResult, spawn_try_task};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Collection pages which are fetched at most, in case a remote instance returns endless pages.
+const MAX_PAGES: usize = 20;
+/// Backfills which made no progress for this long were interrupted, and are started again.
+const STALLED_AFTER: TimeDelta = TimeDelta::minutes(30);
+
+/// Fetch older posts and comments of a remote community in the background. Only a few posts are
+/// included when the community is first fetched, so this fills the community for local users.
+/// Each community is backfilled only once.
+pub async fn start_backfill(
+  community: &ApubCommunity,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  if community.local || context.settings().federation.backfill_posts == 0 {
+    return Ok(());
+  }
+  if CommunityBackfill::start(&mut context.pool(), community.id).await? {
+    spawn_backfill(community.clone(), context.reset_request_count());
+  }
+  Ok(())
+}
+
+/// Start backfills again which were interrupted, eg by a restart.
+pub async fn restart_stalled_backfills(context: &Data<LemmyContext>) -> LemmyResult<()> {
+  let stalled_since = Utc::now() - STALLED_AFTER;
+  for community_id in CommunityBackfill::restart_stalled(&mut context.pool(), stalled_since).await?
+  {
+    let community = Community::read(&mut context.pool(), community_id).await?;
+    spawn_backfill(community.into(), context.reset_request_count());
+  }
+  Ok(())
+}
+
+fn spawn_backfill(community: ApubCommunity, context: Data<LemmyContext>) {
+  spawn_try_task(async move {
+    info!("Backfilling community {}", community.ap_id);
+    let error = backfill(&community, &context)
+      .await
+      .inspect_err(|e| warn!("Failed to backfill community {}: {e}", community.ap_id))
+      .err()
+      .map(|e| e.to_string());
+    CommunityBackfill::finish(&mut context.pool(), community.id, error).await
+  });
+}
+
+async fn backfill(community: &ApubCommunity, context: &Data<LemmyContext>) -> LemmyResult<()> {
+  let config = &context.settings().federation;
+  let post_limit = usize::try_from(config.backfill_posts)?;
+  let comment_limit = usize::try_from(config.backfill_comments_per_post)?;
+  let concurrency = usize::from(config.backfill_concurrency.max(1));
+
+  let group: Group = fetch_object_http(community.ap_id.inner(), context)
+    .await?
+    .object;
+  // Featured posts first, so that they are included even if the outbox is longer than the limit
+  let mut items = vec![];
+  if let Some(featured) = group.featured {
+    items = collection_items(PageRef::Link(featured), post_limit, context)
+      .await
+      .inspect_err(|e| debug!("Failed to fetch featured posts of {}: {e}", community.ap_id))
+      .unwrap_or_default();
+  }
+  items.extend(collection_items(PageRef::Link(group.outbox), post_limit, context).await?);
+  let posts: Vec<_> = items
+    .into_iter()
+    .filter_map(CollectionObject::from_item)
+    .unique_by(|p| p.id.clone())
+    .take(post_limit)
+    .collect();
+
+  let mut posts_fetched = 0;
+  let mut comments_fetched = 0;
+  let mut results = stream::iter(posts)
+    .map(|post| backfill_post(post, community, comment_limit, context))
+    .buffer_unordered(concurrency);
+  while let Some(result) = results.next().await {
+    let Some(comments) = result else {
+      continue;
+    };
+    posts_fetched += 1;
+    comments_fetched += comments;
+    CommunityBackfill::update_progress(
+      &mut context.pool(),
+      community.id,
+      posts_fetched,
+      comments_fetched,
+    )
+    .await?;
+  }
+  info!(
+    "Backfilled {posts_fetched} posts and {comments_fetched} comments for community {}",
+    community.ap_id
+  );
+  Ok(())
+}
+
+/// Fetch a post with its top comments. Returns the number of fetched comments, or None if the
+/// post couldn't be fetched.
+async fn backfill_post(
+  post: CollectionObject,
+  community: &ApubCommunity,
+  comment_limit: usize,
+  context: &Data<LemmyContext>,
+) -> Option<i32> {
+  let context = context.reset_request_count();
+  let post_id = post.id.clone();
+  let apub_post = ObjectId::<ApubPost>::from(post.id)
+    .dereference(&context)
+    .await
+    .inspect_err(|e| debug!("Failed to backfill post {post_id}: {e}"))
+    .ok()?;
+  // Ignore posts which were moved to another community
+  if apub_post.community_id != community.id {
+    return None;
+  }
+  if comment_limit == 0 {
+    return Some(0);
+  }
+
+  let comment_ids = match post.replies {
+    Some(replies) => collection_items(replies, comment_limit, &context)
+      .await
+      .map(|items| {
+        items
+          .into_iter()
+          .filter_map(CollectionObject::from_item)
+          .map(|c| c.id)
+          .collect()
+      }),
+    None => lemmy_top_comment_ids(&apub_post, community, comment_limit, &context).await,
+  }
+  .inspect_err(|e| debug!("Failed to list comments of post {post_id}: {e}"))
+  .unwrap_or_default();
+
+  let mut comments = 0;
+  for comment_id in comment_ids.into_iter().take(comment_limit) {
+    let comment = ObjectId::<ApubComment>::from(comment_id)
+      .dereference(&context.reset_request_count())
+      .await;
+    if comment.is_ok() {
+      comments += 1;
+    }
+  }
+  Some(comments)
+}
+
+/// Lemmy doesn't include replies in posts, so use its API to find the top comments. This only
+/// works for posts which were made on the instance of the community.
+async fn lemmy_top_comment_ids(
+  post: &ApubPost,
+  community: &ApubCommunity,
+  limit: usize,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Vec<Url>> {
+  let instance = Instance::read(&mut context.pool(), community.instance_id).await?;
+  let remote_post_id = post
+    .ap_id
+    .inner()
+    .path()
+    .strip_prefix("/post/")
+    .filter(|_| post.ap_id.inner().domain() == Some(instance.domain.as_str()))
+    .and_then(|id| id.parse::<i64>().ok());
+  let (Some(remote_post_id), Some("lemmy")) = (remote_post_id, instance.software.as_deref()) else {
+    return Ok(vec![]);
+  };
+
+  let remote_post_id = remote_post_id.to_string();
+  let limit = limit.to_string();
+  let query = [
+    ("post_id", remote_post_id.as_str()),
+    ("type_", "All"),
+    ("sort", "Top"),
+    ("limit", limit.as_str()),
+  ];
+  // Lemmy 1.0 serves the comment list under /api/v4, older versions only under /api/v3. Both
+  // return the comment ids in the same place.
+  let list = match remote_comment_list("v4", &instance.domain, &query, context).await {
+    Ok(list) => list,
+    Err(e) => {
+      debug!(
+        "Falling back to v3 comment list of {}: {e}",
+        instance.domain
+      );
+      remote_comment_list("v3", &instance.domain, &query, context).await?
+    }
+  };
+  Ok(list.comments.into_iter().map(|c| c.comment.ap_id).collect())
+}
+
+async fn remote_comment_list(
+  version: &str,
+  domain: &str,
+  query: &[(&str, &str)],
+  context: &Data<LemmyContext>,
+) -> LemmyResult<RemoteCommentList> {
+  Ok(
+    context
+      .client()
+      .get(format!("https://{domain}/api/{version}/comment/list"))
+      .query(query)
+      .send()
+      .await?
+      .error_for_status()?
+      .json()
+      .await?,
+  )
+}
+
+/// Collect up to `limit` items of an ActivityPub collection, following its pages.
+async fn collection_items(
+  collection: PageRef,
+  limit: usize,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Vec<Value>> {
+  let mut items = vec![];
+  let mut next = Some(collection);
+  for _ in 0..MAX_PAGES {
+    let page: CollectionPage = match next.take() {
+      Some(PageRef::Link(url)) => fetch_object_http(&url, context).await?.object,
+      Some(PageRef::Page(page)) => *page,
+      None => break,
+    };
+    items.extend(page.ordered_items);
+    items.extend(page.items);
+    if items.len() >= limit {
+      break;
+    }
+    next = page.first.or(page.next.map(PageRef::Link));
+  }
+  Ok(items)
+}
+
+/// A collection or collection page, which can be embedded or linked.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum PageRef {
+  Link(Url),
+  Page(Box<CollectionPage>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectionPage {
+  #[serde(default)]
+  ordered_items: Vec<Value>,
+  #[serde(default)]
+  items: Vec<Value>,
+  first: Option<PageRef>,
+  next: Option<Url>,
+}
+
+#[derive(Debug)]
+struct CollectionObject {
+  id: Url,
+  replies: Option<PageRef>,
+}
+
+impl CollectionObject {
+  /// Outbox items are activities like `Announce` or `Create` which wrap the object, while other
+  /// collections contain the objects directly or only their ids.
+  fn from_item(mut item: Value) -> Option<Self> {
+    loop {
+      let kind = item.get("type").and_then(Value::as_str);
+      if !matches!(kind, Some("Announce" | "Create" | "Update")) {
+        break;
+      }
+      item = item.get_mut("object")?.take();
+    }
+    match item {
+      Value::String(id) => Some(CollectionObject {
+        id: Url::parse(&id).ok()?,
+        replies: None,
+      }),
+      Value::Object(mut object) => Some(CollectionObject {
+        id: Url::parse(object.get("id")?.as_str()?).ok()?,
+        replies: object
+          .remove("replies")
+          .and_then(|r| serde_json::from_value(r).ok()),
+      }),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct RemoteCommentList {
+  comments: Vec<RemoteCommentView>,
+}
+
+#[derive(Deserialize)]
+struct RemoteCommentView {
+  comment: RemoteComment,
+}
+
+#[derive(Deserialize)]
+struct RemoteComment {
+  ap_id: Url,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  #[test]
+  fn test_collection_object_from_item() -> LemmyResult<()> {
+    let id = |item: Value| CollectionObject::from_item(item).map(|o| o.id.to_string());
+
+    // Lemmy outbox wraps the post in `Announce` and `Create`
+    let announce = json!({
+      "type": "Announce",
+      "object": {
+        "type": "Create",
+        "object": { "type": "Page", "id": "https://lemmy.ml/post/1" }
+      }
+    });
+    assert_eq!(Some("https://lemmy.ml/post/1".to_string()), id(announce));
+    assert_eq!(
+      Some("https://lemmy.ml/post/2".to_string()),
+      id(json!("https://lemmy.ml/post/2"))
+    );
+    assert_eq!(None, id(json!({ "type": "Announce", "object": 1 })));
+
+    let note = json!({
+      "type": "Note",
+      "id": "https://mastodon.example/notes/1",
+      "replies": {
+        "type": "Collection",
+        "first": {
+          "type": "CollectionPage",
+          "items": ["https://mastodon.example/notes/2"],
+          "next": "https://mastodon.example/notes/1/replies?page=2"
+        }
+      }
+    });
+    let replies = CollectionObject::from_item(note).and_then(|n| n.replies);
+    let Some(PageRef::Page(replies)) = replies else {
+      panic!("Replies should be embedded");
+    };
+    let Some(PageRef::Page(first)) = replies.first else {
+      panic!("First page should be embedded");
+    };
+    assert_eq!(vec![json!("https://mastodon.example/notes/2")], first.items);
+    assert_eq!(
+      Some(Url::parse(
+        "https://mastodon.example/notes/1/replies?page=2"
+      )?),
+      first.next
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_remote_comment_list() -> LemmyResult<()> {
+    // Shape of the Lemmy v4 comment list, v3 differs only in the missing page cursors
+    let list: RemoteCommentList = serde_json::from_value(json!({
+      "comments": [{
+        "comment": {
+          "id": 5,
+          "ap_id": "https://lemmy.ml/comment/5",
+          "content": "hello",
+          "creator_id": 2,
+          "post_id": 1
+        },
+        "creator": { "id": 2, "name": "alice" },
+        "post": { "id": 1 },
+        "community": { "id": 3 },
+        "can_mod": false
+      }],
+      "next_page": "Ta",
+      "prev_page": null
+    }))?;
+    assert_eq!(
+      vec![Url::parse("https://lemmy.ml/comment/5")?],
+      list
+        .comments
+        .into_iter()
+        .map(|c| c.comment.ap_id)
+        .collect::<Vec<_>>()
+    );
+    Ok(())
+  }
+}
@@ -12,6 +12,7 @@ use lemmy_db_schema::{newtypes::InstanceId, traits::ApubActor};
 use lemmy_db_views_local_user::LocalUserView;
 use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 
+pub mod backfill;
 pub mod search;
 
 /// Resolve actor identifier like `!news@example.com` to user or community object.
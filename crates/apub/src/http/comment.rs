@@ -1,4 +1,4 @@
-use super::check_community_content_fetchable;
+use super::{check_community_content_fetchable, with_oembed_link};
 use activitypub_federation::{config::Data, traits::Object};
 use actix_web::{web::Path, HttpRequest, HttpResponse};
 use lemmy_api_utils::context::LemmyContext;
@@ -29,5 +29,7 @@ pub(crate) async fn get_apub_comment(
   let community = Community::read(&mut context.pool(), post.community_id).await?;
   check_community_content_fetchable(&community, &request, &context).await?;
 
-  comment.http_response(&FEDERATION_CONTEXT, &context).await
+  let ap_id = comment.ap_id.clone();
+  let response = comment.http_response(&FEDERATION_CONTEXT, &context).await?;
+  with_oembed_link(response, ap_id.inner(), &context)
 }
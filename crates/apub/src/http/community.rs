@@ -7,10 +7,10 @@ use crate::{
     community_outbox::ApubCommunityOutbox,
   },
   fetcher::get_instance_id,
-  http::check_community_fetchable,
+  http::{check_authorized_fetch, check_community_fetchable, signature::verify_signing_actor},
 };
 use activitypub_federation::{
-  actix_web::{response::create_http_response, signing_actor},
+  actix_web::response::create_http_response,
   config::Data,
   fetch::object_id::ObjectId,
   traits::{Collection, Object},
@@ -53,7 +53,9 @@ pub struct CommunityIsFollowerQuery {
 pub(crate) async fn get_apub_community_http(
   info: Path<CommunityPath>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let community: ApubCommunity =
     Community::read_from_name(&mut context.pool(), &info.community_name, true)
       .await?
@@ -78,6 +80,7 @@ pub(crate) async fn get_apub_community_followers(
   if let Some(is_follower) = &query.is_follower {
     return check_is_follower(community, is_follower, context, request).await;
   }
+  check_authorized_fetch(&request, &context).await?;
   check_community_fetchable(&community)?;
   let followers = ApubCommunityFollower::read_local(&community.into(), &context).await?;
   Ok(create_http_response(followers, &FEDERATION_CONTEXT)?)
@@ -95,7 +98,7 @@ async fn check_is_follower(
   }
   // also check for http sig so that followers are not exposed publicly
   let signing_actor =
-    signing_actor::<SiteOrMultiOrCommunityOrUser>(&request, None, &context).await?;
+    verify_signing_actor::<SiteOrMultiOrCommunityOrUser>(&request, None, &context).await?;
   CommunityFollowerView::check_has_followers_from_instance(
     community.id,
     get_instance_id(&signing_actor),
@@ -137,7 +140,9 @@ pub(crate) async fn get_apub_community_outbox(
 pub(crate) async fn get_apub_community_moderators(
   info: Path<CommunityPath>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let community: ApubCommunity =
     Community::read_from_name(&mut context.pool(), &info.community_name, false)
       .await?
@@ -172,7 +177,9 @@ pub(crate) struct MultiCommunityQuery {
 pub(crate) async fn get_apub_person_multi_community(
   query: Path<MultiCommunityQuery>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let multi: ApubMultiCommunity =
     MultiCommunity::read_from_name(&mut context.pool(), &query.multi_name)
       .await?
@@ -184,7 +191,9 @@ pub(crate) async fn get_apub_person_multi_community(
 pub(crate) async fn get_apub_person_multi_community_follows(
   query: Path<MultiCommunityQuery>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let multi = MultiCommunity::read_from_name(&mut context.pool(), &query.multi_name)
     .await?
     .into();
@@ -256,11 +265,12 @@ pub(crate) mod tests {
     let query = CommunityPath {
       community_name: "asd".to_string(),
     };
-    let res = get_apub_community_http(query.into(), context.clone()).await;
+    let res = get_apub_community_http(query.into(), context.clone(), request.clone()).await;
     assert!(res.is_err());
 
     // fetch valid community
-    let res = get_apub_community_http(path.clone().into(), context.clone()).await?;
+    let res =
+      get_apub_community_http(path.clone().into(), context.clone(), request.clone()).await?;
     assert_eq!(200, res.status());
     let res_group: Group = decode_response(res).await?;
     let community: ApubCommunity = community.into();
@@ -275,7 +285,8 @@ pub(crate) mod tests {
       get_apub_community_followers(path.clone().into(), query, context.clone(), request.clone())
         .await?;
     assert_eq!(200, res.status());
-    let res = get_apub_community_moderators(path.clone().into(), context.clone()).await?;
+    let res =
+      get_apub_community_moderators(path.clone().into(), context.clone(), request.clone()).await?;
     assert_eq!(200, res.status());
     let res = get_apub_community_outbox(path, context.clone(), request).await?;
     assert_eq!(200, res.status());
@@ -292,7 +303,8 @@ pub(crate) mod tests {
     let request = TestRequest::default().to_http_request();
 
     // should return tombstone
-    let res = get_apub_community_http(path.clone().into(), context.clone()).await?;
+    let res =
+      get_apub_community_http(path.clone().into(), context.clone(), request.clone()).await?;
     assert_eq!(410, res.status());
     let res_tombstone = decode_response::<Tombstone>(res).await;
     assert!(res_tombstone.is_ok());
@@ -305,7 +317,8 @@ pub(crate) mod tests {
       get_apub_community_followers(path.clone().into(), query, context.clone(), request.clone())
         .await;
     assert!(res.is_err());
-    let res = get_apub_community_moderators(path.clone().into(), context.clone()).await;
+    let res =
+      get_apub_community_moderators(path.clone().into(), context.clone(), request.clone()).await;
     assert!(res.is_err());
     let res = get_apub_community_outbox(path, context.clone(), request).await;
     assert!(res.is_err());
@@ -321,7 +334,7 @@ pub(crate) mod tests {
     let (data, _, path) = init(false, CommunityVisibility::LocalOnlyPrivate, &context).await?;
     let request = TestRequest::default().to_http_request();
 
-    let res = get_apub_community_http(path.clone().into(), context.clone()).await;
+    let res = get_apub_community_http(path.clone().into(), context.clone(), request.clone()).await;
     assert!(res.is_err());
     let res =
       get_apub_community_featured(path.clone().into(), context.clone(), request.clone()).await;
@@ -331,7 +344,8 @@ pub(crate) mod tests {
       get_apub_community_followers(path.clone().into(), query, context.clone(), request.clone())
         .await;
     assert!(res.is_err());
-    let res = get_apub_community_moderators(path.clone().into(), context.clone()).await;
+    let res =
+      get_apub_community_moderators(path.clone().into(), context.clone(), request.clone()).await;
     assert!(res.is_err());
     let res = get_apub_community_outbox(path, context.clone(), request).await;
     assert!(res.is_err());
@@ -0,0 +1,154 @@
+use crate::activity_lists::SharedInboxActivities;
+use activitypub_federation::{config::Data, traits::Activity};
+use chrono::{TimeDelta, Utc};
+use futures::{stream, StreamExt};
+use lemmy_api_utils::{context::LemmyContext, plugins::plugin_hook_after};
+use lemmy_db_schema::{newtypes::DbUrl, source::activity::ReceivedActivity};
+use lemmy_utils::error::{FederationError, LemmyError, LemmyResult};
+use std::{collections::HashMap, time::Duration};
+use tokio::time::{sleep, timeout};
+use tracing::{debug, warn};
+
+/// Maximum number of queued activities which are read from the database at once
+const QUEUE_BATCH_SIZE: i64 = 1000;
+/// Wait this long before checking again if there are no activities to process
+const QUEUE_EMPTY_RECHECK_DELAY: Duration = Duration::from_secs(1);
+/// Processing happens in the background, so it can take longer than the inbox request
+const PROCESS_ACTIVITY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Process activities which were stored by the shared inbox. Activities by different actors are
+/// processed concurrently, those by the same actor in the order they were received.
+///
+/// If you are running multiple Lemmy server processes, this must only run in one of them.
+pub async fn process_received_activities(context: Data<LemmyContext>) {
+  loop {
+    let processed = process_batch(&context).await.unwrap_or_else(|e| {
+      warn!("Failed to process incoming activities: {e}");
+      0
+    });
+    if processed == 0 {
+      sleep(QUEUE_EMPTY_RECHECK_DELAY).await;
+    }
+  }
+}
+
+/// Returns the number of activities for which processing was attempted.
+async fn process_batch(context: &Data<LemmyContext>) -> LemmyResult<usize> {
+  let queued = ReceivedActivity::read_queued(&mut context.pool(), QUEUE_BATCH_SIZE).await?;
+
+  // group by actor, keeping the order in which they were received
+  let mut by_actor: Vec<Vec<ReceivedActivity>> = vec![];
+  let mut actor_index: HashMap<Option<DbUrl>, usize> = HashMap::new();
+  for activity in queued {
+    match actor_index
+      .get(&activity.actor_ap_id)
+      .and_then(|i| by_actor.get_mut(*i))
+    {
+      Some(activities) => activities.push(activity),
+      None => {
+        actor_index.insert(activity.actor_ap_id.clone(), by_actor.len());
+        by_actor.push(vec![activity]);
+      }
+    }
+  }
+
+  let workers = usize::from(context.settings().federation.inbox_workers.max(1));
+  let processed = stream::iter(by_actor)
+    .map(|activities| process_actor_activities(activities, context))
+    .buffer_unordered(workers)
+    .fold(0, |sum, count| async move { sum + count })
+    .await;
+  Ok(processed)
+}
+
+/// Process the queued activities of a single actor in order. If one of them fails and is retried
+/// later, the following ones have to wait as well.
+async fn process_actor_activities(
+  activities: Vec<ReceivedActivity>,
+  context: &Data<LemmyContext>,
+) -> usize {
+  let mut processed = 0;
+  for activity in activities {
+    processed += 1;
+    let id = activity.id;
+    let attempts = activity.attempts;
+
+    let Some(parsed) = activity
+      .data
+      .and_then(|data| serde_json::from_value::<SharedInboxActivities>(data).ok())
+    else {
+      // parsing will never succeed, so there is no point in retrying
+      let res = ReceivedActivity::mark_failed(
+        &mut context.pool(),
+        id,
+        "Failed to parse activity".to_string(),
+        None,
+      )
+      .await;
+      log_update_error(res);
+      continue;
+    };
+
+    // each activity gets its own limit for fetching remote objects
+    let context = &context.reset_request_count();
+    let res = timeout(PROCESS_ACTIVITY_TIMEOUT, receive(parsed, context))
+      .await
+      .unwrap_or_else(|_| Err(FederationError::InboxTimeout.into()));
+    match res {
+      Ok(()) => {
+        log_update_error(ReceivedActivity::mark_processed(&mut context.pool(), id).await);
+      }
+      Err(e) => {
+        let max_attempts = i32::from(context.settings().federation.inbox_max_attempts);
+        let retry_at = (attempts + 1 < max_attempts).then(|| Utc::now() + retry_delay(attempts));
+        debug!("Failed to process activity {}: {e}", activity.ap_id);
+        let res =
+          ReceivedActivity::mark_failed(&mut context.pool(), id, e.to_string(), retry_at).await;
+        log_update_error(res);
+        if retry_at.is_some() {
+          break;
+        }
+      }
+    }
+  }
+  processed
+}
+
+async fn receive(activity: SharedInboxActivities, context: &Data<LemmyContext>) -> LemmyResult<()> {
+  // This could also take the actor as param, but lifetimes and serde derives are tricky.
+  // It is really a before hook, but doesnt allow modifying the data. It could use a
+  // separate method so that error in plugin causes activity to be rejected.
+  plugin_hook_after("activity_received", &activity)?;
+
+  activity.verify(context).await?;
+  activity.receive(context).await
+}
+
+/// Wait 10 seconds before the first retry, and four times longer for each following one.
+fn retry_delay(previous_attempts: i32) -> TimeDelta {
+  let factor = 4_i32.saturating_pow(u32::try_from(previous_attempts).unwrap_or(0));
+  TimeDelta::seconds(10)
+    .checked_mul(factor)
+    .unwrap_or(TimeDelta::days(1))
+    .min(TimeDelta::days(1))
+}
+
+fn log_update_error(res: Result<(), LemmyError>) {
+  if let Err(e) = res {
+    warn!("Failed to update incoming activity: {e}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_retry_delay() {
+    assert_eq!(TimeDelta::seconds(10), retry_delay(0));
+    assert_eq!(TimeDelta::seconds(40), retry_delay(1));
+    assert_eq!(TimeDelta::seconds(640), retry_delay(3));
+    assert_eq!(TimeDelta::days(1), retry_delay(100));
+  }
+}
@@ -1,31 +1,39 @@
-use crate::{activity_lists::SharedInboxActivities, fetcher::get_instance_id};
+use crate::{
+  activity_lists::SharedInboxActivities,
+  fetcher::get_instance_id,
+  http::signature::verify_signing_actor,
+};
 use activitypub_federation::{
-  actix_web::{
-    inbox::{receive_activity_with_hook, ReceiveActivityHook},
-    response::create_http_response,
-    signing_actor,
-  },
+  actix_web::response::create_http_response,
   config::Data,
+  kinds::public,
+  protocol::verification::verify_domains_match,
   traits::{Activity, Object},
 };
 use actix_web::{
+  http::header::{HeaderValue, LINK},
   web::{self, Bytes},
   HttpRequest,
   HttpResponse,
 };
-use lemmy_api_utils::{context::LemmyContext, plugins::plugin_hook_after};
-use lemmy_apub_objects::objects::{SiteOrMultiOrCommunityOrUser, UserOrCommunity};
+use either::Either;
+use lemmy_api_utils::{context::LemmyContext, utils::oembed_link};
+use lemmy_apub_objects::{
+  objects::{relay::ApubRelay, SiteOrMultiOrCommunityOrUser, UserOrCommunityOrRelay},
+  utils::functions::{check_apub_id_valid, local_site_data_cached},
+};
 use lemmy_db_schema::source::{
-  activity::{ReceivedActivity, SentActivity},
+  activity::{ReceivedActivity, ReceivedActivityForm, SentActivity},
   community::Community,
 };
-use lemmy_db_schema_file::enums::CommunityVisibility;
+use lemmy_db_schema_file::enums::{CommunityVisibility, RelayType};
 use lemmy_db_views_community_follower::CommunityFollowerView;
 use lemmy_utils::{
   error::{FederationError, LemmyErrorExt, LemmyErrorType, LemmyResult},
   FEDERATION_CONTEXT,
 };
 use serde::Deserialize;
+use serde_json::{json, Value};
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::debug;
@@ -33,9 +41,11 @@ use url::Url;
 
 mod comment;
 mod community;
+pub mod inbox_queue;
 mod person;
 mod post;
 pub mod routes;
+mod signature;
 pub mod site;
 
 const INCOMING_ACTIVITY_TIMEOUT: Duration = Duration::from_secs(9);
@@ -45,42 +55,87 @@ pub async fn shared_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> LemmyResult<HttpResponse> {
-  let receive_fut =
-    receive_activity_with_hook::<SharedInboxActivities, UserOrCommunity, LemmyContext>(
-      request, body, Dummy, &data,
-    );
-  // Set a timeout shorter than `REQWEST_TIMEOUT` for processing incoming activities. This is to
-  // avoid taking a long time to process an incoming activity when a required data fetch times out.
-  // In this case our own instance would timeout and be marked as dead by the sender. Better to
-  // consider the activity broken and move on.
-  timeout(INCOMING_ACTIVITY_TIMEOUT, receive_fut)
-    .await
-    .with_lemmy_type(FederationError::InboxTimeout.into())?
+  // Set a timeout shorter than `REQWEST_TIMEOUT` for verifying incoming activities. This is to
+  // avoid taking a long time when fetching the signing actor times out. In this case our own
+  // instance would timeout and be marked as dead by the sender. Better to consider the activity
+  // broken and move on.
+  timeout(
+    INCOMING_ACTIVITY_TIMEOUT,
+    enqueue_activity(request, body, &data),
+  )
+  .await
+  .with_lemmy_type(FederationError::InboxTimeout.into())?
 }
 
-struct Dummy;
-
-impl ReceiveActivityHook<SharedInboxActivities, UserOrCommunity, LemmyContext> for Dummy {
-  async fn hook(
-    self,
-    activity: &SharedInboxActivities,
-    _actor: &UserOrCommunity,
-    context: &Data<LemmyContext>,
-  ) -> LemmyResult<()> {
-    // Store received activities in the database. This ensures that the same activity doesn't get
-    // received and processed more than once, which would be a waste of resources.
-    debug!("Received activity {}", activity.id().to_string());
-    ReceivedActivity::create(&mut context.pool(), &activity.id().clone().into()).await?;
-
-    // This could also take the actor as param, but lifetimes and serde derives are tricky.
-    // It is really a before hook, but doesnt allow modifying the data. It could use a
-    // separate method so that error in plugin causes activity to be rejected.
-    plugin_hook_after("activity_received", activity)?;
-
-    // This method could also be used to check if actor is banned, instead of checking in each
-    // activity handler.
-    Ok(())
+/// Verify the HTTP signature of an incoming activity and store it, so that it gets processed
+/// asynchronously by [inbox_queue::process_received_activities]. This way remote senders don't
+/// have to wait for slow database queries or fetching of remote objects.
+async fn enqueue_activity(
+  request: HttpRequest,
+  body: Bytes,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  // Keep the raw json, so that no fields are lost before processing
+  let data: Value = serde_json::from_slice(&body)?;
+
+  // Verifies the body digest and the signature
+  let actor = verify_signing_actor::<UserOrCommunityOrRelay>(&request, Some(body), context).await?;
+  let data = match &actor {
+    Either::Right(relay) if relay.relay_type == RelayType::Mastodon => {
+      relayed_announce(relay, data)?
+    }
+    _ => data,
+  };
+
+  let activity: SharedInboxActivities = serde_json::from_value(data.clone())?;
+  verify_domains_match(activity.id(), activity.actor())?;
+  check_apub_id_valid(
+    activity.id(),
+    &local_site_data_cached(&mut context.pool()).await?,
+  )?;
+  if actor.id() != activity.actor() {
+    return Err(FederationError::ActivitySignedByOtherActor.into());
   }
+
+  // Storing received activities also ensures that the same activity doesn't get received and
+  // processed more than once, which would be a waste of resources.
+  debug!("Received activity {}", activity.id().to_string());
+  let form = ReceivedActivityForm {
+    ap_id: activity.id().clone().into(),
+    data,
+    actor_ap_id: activity.actor().clone().into(),
+  };
+  ReceivedActivity::enqueue(&mut context.pool(), &form).await?;
+  Ok(HttpResponse::Accepted().finish())
+}
+
+/// Mastodon-style relays forward the activities of other actors unchanged, signed by the relay.
+/// The content can't be verified this way, so it is converted to an Announce of the object by the
+/// relay. Like for LitePub relays, the object is then fetched from its origin.
+fn relayed_announce(relay: &ApubRelay, data: Value) -> LemmyResult<Value> {
+  if data.get("actor").and_then(Value::as_str) == Some(relay.ap_id.as_str()) {
+    return Ok(data);
+  }
+  let forwarded_id = data
+    .get("id")
+    .and_then(Value::as_str)
+    .ok_or(FederationError::InvalidRelayedActivity)?;
+  let object_id = data
+    .get("object")
+    .and_then(|o| o.get("id").unwrap_or(o).as_str())
+    .ok_or(FederationError::InvalidRelayedActivity)?;
+
+  // Derive the id from the forwarded activity, so that it is only received once
+  let mut id = relay.ap_id.inner().clone();
+  id.set_fragment(Some(forwarded_id));
+  Ok(json!({
+    "type": "Announce",
+    "id": id,
+    "actor": relay.ap_id,
+    "to": [public()],
+    "cc": [],
+    "object": object_id,
+  }))
 }
 
 #[derive(Deserialize)]
@@ -93,7 +148,9 @@ pub struct ActivityQuery {
 pub(crate) async fn get_activity(
   info: web::Path<ActivityQuery>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let settings = context.settings();
   let activity_id = Url::parse(&format!(
     "{}/activities/{}/{}",
@@ -120,6 +177,26 @@ fn check_community_fetchable(community: &Community) -> LemmyResult<()> {
   Ok(())
 }
 
+/// If authorized fetch is enabled, ActivityPub GET requests need a valid HTTP signature by an
+/// actor from an instance which is allowed to federate with us. Returns the signing actor in this
+/// case.
+///
+/// The site actor is exempt, because other instances need to fetch it to verify our own signed
+/// fetches.
+pub(crate) async fn check_authorized_fetch(
+  request: &HttpRequest,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Option<SiteOrMultiOrCommunityOrUser>> {
+  let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+  if !local_site_data.authorized_fetch() {
+    return Ok(None);
+  }
+  let signing_actor =
+    verify_signing_actor::<SiteOrMultiOrCommunityOrUser>(request, None, context).await?;
+  check_apub_id_valid(signing_actor.id(), &local_site_data)?;
+  Ok(Some(signing_actor))
+}
+
 /// Check if posts or comments in the community are allowed to be fetched
 async fn check_community_content_fetchable(
   community: &Community,
@@ -127,11 +204,22 @@ async fn check_community_content_fetchable(
   context: &Data<LemmyContext>,
 ) -> LemmyResult<()> {
   use CommunityVisibility::*;
+  let authorized_actor = check_authorized_fetch(request, context).await?;
   match community.visibility {
     Public | Unlisted => Ok(()),
     Private => {
-      let signing_actor =
-        signing_actor::<SiteOrMultiOrCommunityOrUser>(request, None, context).await?;
+      let signing_actor = match authorized_actor {
+        Some(actor) => actor,
+        None => {
+          let actor =
+            verify_signing_actor::<SiteOrMultiOrCommunityOrUser>(request, None, context).await?;
+          check_apub_id_valid(
+            actor.id(),
+            &local_site_data_cached(&mut context.pool()).await?,
+          )?;
+          actor
+        }
+      };
       if community.local {
         Ok(
           CommunityFollowerView::check_has_followers_from_instance(
@@ -157,3 +245,207 @@ async fn check_community_content_fetchable(
     LocalOnlyPublic | LocalOnlyPrivate => Err(LemmyErrorType::NotFound.into()),
   }
 }
+
+/// Add a discovery link for the oEmbed endpoint, so that the post or comment can be embedded by
+/// other websites.
+fn with_oembed_link(
+  mut response: HttpResponse,
+  object: &Url,
+  context: &LemmyContext,
+) -> LemmyResult<HttpResponse> {
+  let link = format!(
+    "<{}>; rel=\"alternate\"; type=\"application/json+oembed\"",
+    oembed_link(object, context.settings())
+  );
+  response
+    .headers_mut()
+    .insert(LINK, HeaderValue::from_str(&link)?);
+  Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::http::site::{get_apub_site_http, get_apub_site_outbox};
+  use activitypub_federation::http_signatures::generate_actor_keypair;
+  use actix_web::test::TestRequest;
+  use chrono::Utc;
+  use lemmy_apub_objects::utils::message_signature::sign_post_request;
+  use lemmy_db_schema::{
+    newtypes::{InstanceId, RelayId},
+    source::{
+      federation_blocklist::{FederationBlockList, FederationBlockListForm},
+      instance::Instance,
+      local_site::{LocalSite, LocalSiteUpdateForm},
+      person::{Person, PersonInsertForm},
+      relay::Relay,
+    },
+    test_data::TestData,
+    traits::Crud,
+  };
+  use lemmy_utils::CACHE_DURATION_FEDERATION;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use tokio::time::sleep;
+
+  #[test]
+  fn test_relayed_announce() -> LemmyResult<()> {
+    let relay_id = Url::parse("https://relay.example/actor")?;
+    let relay = ApubRelay(Relay {
+      id: RelayId(1),
+      ap_id: relay_id.clone().into(),
+      inbox_url: Url::parse("https://relay.example/inbox")?.into(),
+      public_key: String::new(),
+      instance_id: InstanceId(1),
+      relay_type: RelayType::Mastodon,
+      follow_accepted: true,
+      publish: false,
+      received_count: 0,
+      last_received_at: None,
+      published_at: Utc::now(),
+      updated_at: None,
+    });
+
+    let forwarded = json!({
+      "type": "Create",
+      "id": "https://masto.example/users/alice/statuses/1/activity",
+      "actor": "https://masto.example/users/alice",
+      "object": {
+        "type": "Note",
+        "id": "https://masto.example/users/alice/statuses/1",
+        "content": "forged content is ignored"
+      }
+    });
+    let announce = relayed_announce(&relay, forwarded)?;
+    assert_eq!(
+      Some("Announce"),
+      announce.get("type").and_then(Value::as_str)
+    );
+    assert_eq!(
+      Some(relay_id.as_str()),
+      announce.get("actor").and_then(Value::as_str)
+    );
+    assert_eq!(
+      Some("https://masto.example/users/alice/statuses/1"),
+      announce.get("object").and_then(Value::as_str)
+    );
+    let id = Url::parse(
+      announce
+        .get("id")
+        .and_then(Value::as_str)
+        .unwrap_or_default(),
+    )?;
+    assert_eq!(relay_id.domain(), id.domain());
+
+    // Activities of the relay itself are unchanged
+    let own = json!({
+      "type": "Announce",
+      "id": "https://relay.example/activities/1",
+      "actor": "https://relay.example/actor",
+      "object": "https://lemmy.example/post/1"
+    });
+    assert_eq!(own.clone(), relayed_announce(&relay, own)?);
+
+    assert!(relayed_announce(&relay, json!({ "actor": "https://masto.example/u/a" })).is_err());
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_authorized_fetch() -> LemmyResult<()> {
+    let context = LemmyContext::init_test_context().await;
+    let data = TestData::create(&mut context.pool()).await?;
+    let request = TestRequest::default().to_http_request();
+
+    // without authorized fetch, unsigned requests are fine
+    assert!(check_authorized_fetch(&request, &context).await?.is_none());
+
+    set_authorized_fetch(true, &context).await?;
+
+    // unsigned requests are rejected
+    assert!(check_authorized_fetch(&request, &context).await.is_err());
+    let res = get_apub_site_outbox(context.clone(), request.clone()).await;
+    assert!(res.is_err());
+
+    // except for the site actor, which others need to verify our own signed fetches
+    let res = get_apub_site_http(context.clone()).await?;
+    assert_eq!(200, res.status());
+
+    set_authorized_fetch(false, &context).await?;
+    data.delete(&mut context.pool()).await?;
+    Ok(())
+  }
+
+  /// Changes the setting and waits until the cached local site data expires.
+  async fn set_authorized_fetch(enabled: bool, context: &LemmyContext) -> LemmyResult<()> {
+    let form = LocalSiteUpdateForm {
+      federation_authorized_fetch: Some(enabled),
+      ..Default::default()
+    };
+    LocalSite::update(&mut context.pool(), &form).await?;
+    sleep(CACHE_DURATION_FEDERATION).await;
+    Ok(())
+  }
+
+  /// A GET request signed by a remote person, which is stored locally so that it isn't fetched.
+  async fn signed_request(domain: &str, context: &LemmyContext) -> LemmyResult<HttpRequest> {
+    let instance = Instance::read_or_create(&mut context.pool(), domain.to_string()).await?;
+    let keypair = generate_actor_keypair()?;
+    let ap_id = Url::parse(&format!("https://{domain}/u/alice"))?;
+    let form = PersonInsertForm {
+      ap_id: Some(ap_id.clone().into()),
+      local: Some(false),
+      ..PersonInsertForm::new("alice".to_string(), keypair.public_key, instance.id)
+    };
+    Person::create(&mut context.pool(), &form).await?;
+
+    let target_uri = Url::parse(&format!(
+      "{}/site_outbox",
+      context.settings().get_protocol_and_hostname()
+    ))?;
+    let content_type = "application/activity+json";
+    let headers = sign_post_request(
+      &target_uri,
+      content_type,
+      &[],
+      &format!("{ap_id}#main-key"),
+      &keypair.private_key,
+      Utc::now(),
+    )?;
+    let mut request = TestRequest::post()
+      .uri("/site_outbox")
+      .insert_header(("content-type", content_type));
+    for header in headers {
+      request = request.insert_header(header);
+    }
+    Ok(request.to_http_request())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_authorized_fetch_blocked_signer() -> LemmyResult<()> {
+    let context = LemmyContext::init_test_context().await;
+    let data = TestData::create(&mut context.pool()).await?;
+    let blocked = Instance::read_or_create(&mut context.pool(), "blocked.tld".to_string()).await?;
+    let form = FederationBlockListForm {
+      instance_id: blocked.id,
+      updated_at: None,
+      expires_at: None,
+    };
+    FederationBlockList::block(&mut context.pool(), &form).await?;
+    set_authorized_fetch(true, &context).await?;
+
+    // valid signatures by actors from blocked instances are not accepted for fetching
+    let request = signed_request("blocked.tld", &context).await?;
+    assert!(check_authorized_fetch(&request, &context).await.is_err());
+    let request = signed_request("other.tld", &context).await?;
+    assert!(check_authorized_fetch(&request, &context).await?.is_some());
+
+    set_authorized_fetch(false, &context).await?;
+    let other = Instance::read_or_create(&mut context.pool(), "other.tld".to_string()).await?;
+    Instance::delete(&mut context.pool(), other.id).await?;
+    Instance::delete(&mut context.pool(), blocked.id).await?;
+    data.delete(&mut context.pool()).await?;
+    Ok(())
+  }
+}
@@ -1,10 +1,10 @@
-use crate::protocol::collections::empty_outbox::EmptyOutbox;
+use crate::{http::check_authorized_fetch, protocol::collections::empty_outbox::EmptyOutbox};
 use activitypub_federation::{
   actix_web::response::create_http_response,
   config::Data,
   traits::Object,
 };
-use actix_web::{web::Path, HttpResponse};
+use actix_web::{web::Path, HttpRequest, HttpResponse};
 use lemmy_api_utils::{context::LemmyContext, utils::generate_outbox_url};
 use lemmy_apub_objects::objects::person::ApubPerson;
 use lemmy_db_schema::{source::person::Person, traits::ApubActor};
@@ -23,7 +23,9 @@ pub struct PersonQuery {
 pub(crate) async fn get_apub_person_http(
   info: Path<PersonQuery>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let user_name = info.into_inner().user_name;
   // This needs to be able to read deleted persons, so that it can send tombstones
   let person: ApubPerson = Person::read_from_name(&mut context.pool(), &user_name, true)
@@ -37,7 +39,9 @@ pub(crate) async fn get_apub_person_http(
 pub(crate) async fn get_apub_person_outbox(
   info: Path<PersonQuery>,
   context: Data<LemmyContext>,
+  request: HttpRequest,
 ) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let person = Person::read_from_name(&mut context.pool(), &info.user_name, false)
     .await?
     .ok_or(LemmyErrorType::NotFound)?;
@@ -0,0 +1,90 @@
+use activitypub_federation::{
+  actix_web::signing_actor,
+  config::Data,
+  fetch::object_id::ObjectId,
+  traits::{Actor, Object},
+};
+use actix_web::{web::Bytes, HttpRequest};
+use chrono::Utc;
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub_objects::utils::message_signature::{
+  MessageSignature,
+  SIGNATURE_HEADER,
+  SIGNATURE_INPUT_HEADER,
+};
+use lemmy_db_schema::source::instance::Instance;
+use lemmy_utils::error::{FederationError, LemmyError, LemmyResult};
+use moka::future::Cache;
+use serde::Deserialize;
+use std::{sync::LazyLock, time::Duration};
+use url::Url;
+
+/// Domains which sent RFC 9421 signatures recently, so that the instance doesn't need to be
+/// updated on every request.
+static RFC9421_SENDERS: LazyLock<Cache<String, ()>> = LazyLock::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(Duration::from_secs(60 * 60))
+    .build()
+});
+
+/// Verify the HTTP signature of an incoming request and return the signing actor.
+///
+/// Requests with a `Signature-Input` header are signed according to RFC 9421, all others use
+/// draft-cavage signatures which are verified by `activitypub_federation`. Instances which send
+/// RFC 9421 signatures are remembered, so that outgoing activities are signed the same way. This
+/// doesn't apply if the instance recently rejected our RFC 9421 signatures.
+pub(crate) async fn verify_signing_actor<A>(
+  request: &HttpRequest,
+  body: Option<Bytes>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<A>
+where
+  A: Object<DataType = LemmyContext, Error = LemmyError> + Actor + Send + 'static,
+  for<'de2> <A as Object>::Kind: Deserialize<'de2>,
+{
+  let header = |name: &str| {
+    let values = request
+      .headers()
+      .get_all(name)
+      .map(|v| v.to_str().ok())
+      .collect::<Option<Vec<_>>>()?;
+    (!values.is_empty()).then(|| values.join(", "))
+  };
+  let Some(signature_input) = header(SIGNATURE_INPUT_HEADER) else {
+    return signing_actor::<A>(request, body, context).await;
+  };
+  let signature = header(SIGNATURE_HEADER).ok_or(FederationError::InvalidHttpSignature)?;
+  let signature = MessageSignature::parse(&signature_input, &signature)?;
+
+  // The key belongs to the actor, eg `https://example.com/u/alice#main-key`
+  let mut actor_id = signature.key_id.clone();
+  actor_id.set_fragment(None);
+  let actor = ObjectId::<A>::from(actor_id).dereference(context).await?;
+
+  let path = request
+    .uri()
+    .path_and_query()
+    .map(|p| p.as_str())
+    .unwrap_or("/");
+  let target_uri = Url::parse(&format!(
+    "{}{path}",
+    context.settings().get_protocol_and_hostname()
+  ))?;
+  signature.verify(
+    request.method().as_str(),
+    &target_uri,
+    header,
+    body.as_deref(),
+    actor.public_key_pem(),
+    Utc::now(),
+  )?;
+
+  if let Some(domain) = actor.id().domain() {
+    if !RFC9421_SENDERS.contains_key(domain) {
+      Instance::received_rfc9421_signature(&mut context.pool(), domain).await?;
+      RFC9421_SENDERS.insert(domain.to_string(), ()).await;
+    }
+  }
+  Ok(actor)
+}
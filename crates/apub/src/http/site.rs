@@ -1,10 +1,10 @@
-use crate::protocol::collections::empty_outbox::EmptyOutbox;
+use crate::{http::check_authorized_fetch, protocol::collections::empty_outbox::EmptyOutbox};
 use activitypub_federation::{
   actix_web::response::create_http_response,
   config::Data,
   traits::Object,
 };
-use actix_web::HttpResponse;
+use actix_web::{HttpRequest, HttpResponse};
 use lemmy_api_utils::context::LemmyContext;
 use lemmy_apub_objects::objects::instance::ApubSite;
 use lemmy_db_schema::source::site::Site;
@@ -17,7 +17,11 @@ pub(crate) async fn get_apub_site_http(context: Data<LemmyContext>) -> LemmyResu
   site.http_response(&FEDERATION_CONTEXT, &context).await
 }
 
-pub(crate) async fn get_apub_site_outbox(context: Data<LemmyContext>) -> LemmyResult<HttpResponse> {
+pub(crate) async fn get_apub_site_outbox(
+  context: Data<LemmyContext>,
+  request: HttpRequest,
+) -> LemmyResult<HttpResponse> {
+  check_authorized_fetch(&request, &context).await?;
   let outbox_id = format!(
     "{}/site_outbox",
     context.settings().get_protocol_and_hostname()
@@ -1,6 +1,7 @@
 pub mod note;
 pub(crate) mod note_wrapper;
 pub mod page;
+pub mod person;
 pub mod private_message;
 
 #[cfg(test)]
@@ -9,6 +10,7 @@ mod tests {
   use crate::protocol::activities::create_or_update::{
     note::CreateOrUpdateNote,
     page::CreateOrUpdatePage,
+    person::UpdatePerson,
     private_message::CreateOrUpdatePrivateMessage,
   };
   use lemmy_apub_objects::utils::test::test_parse_lemmy_item;
@@ -28,6 +30,9 @@ mod tests {
     test_parse_lemmy_item::<CreateOrUpdatePrivateMessage>(
       "assets/lemmy/activities/create_or_update/create_private_message.json",
     )?;
+    test_parse_lemmy_item::<UpdatePerson>(
+      "assets/lemmy/activities/create_or_update/update_person.json",
+    )?;
     test_parse_lemmy_item::<CreateOrUpdateNoteWrapper>(
       "assets/lemmy/activities/create_or_update/create_comment.json",
     )?;
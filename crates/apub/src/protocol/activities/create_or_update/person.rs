@@ -0,0 +1,22 @@
+use activitypub_federation::{
+  fetch::object_id::ObjectId,
+  kinds::activity::UpdateType,
+  protocol::helpers::deserialize_one_or_many,
+};
+use lemmy_apub_objects::{objects::person::ApubPerson, protocol::person::Person};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Sent when a user changes fields which other instances need to know about, currently their
+/// device keys for encrypted private messages.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePerson {
+  pub(crate) actor: ObjectId<ApubPerson>,
+  #[serde(deserialize_with = "deserialize_one_or_many")]
+  pub(crate) to: Vec<Url>,
+  pub(crate) object: Person,
+  #[serde(rename = "type")]
+  pub(crate) kind: UpdateType,
+  pub(crate) id: Url,
+}
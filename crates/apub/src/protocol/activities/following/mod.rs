@@ -1,6 +1,7 @@
 pub(crate) mod accept;
 pub mod follow;
 pub(crate) mod reject;
+pub(crate) mod relay;
 pub mod undo_follow;
 
 #[cfg(test)]
@@ -0,0 +1,31 @@
+use activitypub_federation::{
+  fetch::object_id::ObjectId,
+  kinds::activity::{FollowType, UndoType},
+};
+use lemmy_apub_objects::objects::instance::ApubSite;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Subscribes the site actor to a relay. Mastodon-style relays expect the public collection as
+/// object, LitePub relays expect the relay actor.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayFollow {
+  pub(crate) actor: ObjectId<ApubSite>,
+  pub(crate) to: [Url; 1],
+  pub(crate) object: Url,
+  #[serde(rename = "type")]
+  pub(crate) kind: FollowType,
+  pub(crate) id: Url,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoRelayFollow {
+  pub(crate) actor: ObjectId<ApubSite>,
+  pub(crate) to: [Url; 1],
+  pub(crate) object: RelayFollow,
+  #[serde(rename = "type")]
+  pub(crate) kind: UndoType,
+  pub(crate) id: Url,
+}
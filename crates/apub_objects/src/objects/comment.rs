@@ -5,6 +5,7 @@ use crate::{
       append_attachments_to_comment,
       check_apub_id_valid_with_strictness,
       generate_to,
+      instance_policy,
       read_from_string_or_source,
       verify_person_in_community,
       verify_visibility,
@@ -208,9 +209,15 @@ impl Object for ApubComment {
 
     let slur_regex = slur_regex(context).await?;
     let url_blocklist = get_url_blocklist(context).await?;
-    let content = append_attachments_to_comment(content, &note.attachment, context).await?;
+    let policy = instance_policy(note.id.inner(), context).await?;
+    let content = if policy.reject_media {
+      content
+    } else {
+      append_attachments_to_comment(content, &note.attachment, context).await?
+    };
     let content = process_markdown(&content, &slur_regex, &url_blocklist, context).await?;
     let content = markdown_rewrite_remote_links(content, context).await;
+    let content = policy.rewrite_links(content);
     let language_id = Some(
       LanguageTag::to_language_id_single(note.language.unwrap_or_default(), &mut context.pool())
         .await?,
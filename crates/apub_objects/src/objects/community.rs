@@ -5,6 +5,7 @@ use crate::{
     functions::{
       check_apub_id_valid_with_strictness,
       community_visibility,
+      instance_policy,
       read_from_string_or_source_opt,
       GetActorType,
     },
@@ -178,20 +179,42 @@ impl Object for ApubCommunity {
     let sidebar = read_from_string_or_source_opt(&group.content, &None, &group.source);
     let sidebar = process_markdown_opt(&sidebar, &slur_regex, &url_blocklist, context).await?;
     let sidebar = markdown_rewrite_remote_links_opt(sidebar, context).await;
-    let icon = proxy_image_link_opt_apub(group.icon.clone().map(|i| i.url), context).await?;
-    let banner = proxy_image_link_opt_apub(group.image.clone().map(|i| i.url), context).await?;
-    let visibility = Some(community_visibility(&group));
+    let policy = instance_policy(group.id.inner(), context).await?;
+    let (icon, banner) = if policy.reject_media {
+      (None, None)
+    } else {
+      (
+        proxy_image_link_opt_apub(group.icon.clone().map(|i| i.url), context).await?,
+        proxy_image_link_opt_apub(group.image.clone().map(|i| i.url), context).await?,
+      )
+    };
+    let mut visibility = community_visibility(&group);
+    // Silenced communities are only visible to subscribers
+    if policy.silence && visibility == CommunityVisibility::Public {
+      visibility = CommunityVisibility::Unlisted;
+    }
+    let nsfw = group.sensitive.unwrap_or(false) || policy.force_nsfw;
 
     // If NSFW is not allowed, then remove NSFW communities
-    let removed = check_nsfw_allowed(group.sensitive, local_site.as_ref())
+    let removed = check_nsfw_allowed(Some(nsfw), local_site.as_ref())
       .err()
       .map(|_| true);
 
+    // Quarantined communities need to be restored by an admin before they are shown
+    let local_removed = if policy.quarantine_communities {
+      Community::read_from_apub_id(&mut context.pool(), &group.id.clone().into())
+        .await?
+        .is_none()
+        .then_some(true)
+    } else {
+      None
+    };
+
     let form = CommunityInsertForm {
       published_at: group.published,
       updated_at: group.updated,
       deleted: Some(false),
-      nsfw: Some(group.sensitive.unwrap_or(false)),
+      nsfw: Some(nsfw),
       ap_id: Some(group.id.clone().into()),
       local: Some(false),
       last_refreshed_at: Some(Utc::now()),
@@ -216,7 +239,8 @@ impl Object for ApubCommunity {
         .and_then(AttributedTo::url),
       posting_restricted_to_mods: group.posting_restricted_to_mods,
       featured_url: group.featured.clone().clone().map(Into::into),
-      visibility,
+      visibility: Some(visibility),
+      local_removed,
       ..CommunityInsertForm::new(
         instance_id,
         group.preferred_username.clone(),
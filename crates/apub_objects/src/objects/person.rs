@@ -1,18 +1,20 @@
 use crate::{
   objects::instance::fetch_instance_actor_for_object,
-  protocol::person::{Person, UserTypes},
+  protocol::person::{KeyPackage, KeyPackageType, KeyPackages, Person, UserTypes},
   utils::{
     functions::{
       check_apub_id_valid_with_strictness,
+      instance_policy,
       read_from_string_or_source_opt,
       GetActorType,
     },
     markdown_links::markdown_rewrite_remote_links_opt,
-    protocol::{ImageObject, Source},
+    protocol::{ContentEncoding, ImageObject, MediaTypeMls, Source},
   },
 };
 use activitypub_federation::{
   config::Data,
+  kinds::collection::CollectionType,
   protocol::verification::{verify_domains_match, verify_is_remote_object},
   traits::{Actor, Object},
 };
@@ -28,8 +30,12 @@ use lemmy_api_utils::{
   },
 };
 use lemmy_db_schema::{
+  impls::person_device_key::MAX_DEVICE_KEYS,
   sensitive::SensitiveString,
-  source::person::{Person as DbPerson, PersonInsertForm, PersonUpdateForm},
+  source::{
+    person::{Person as DbPerson, PersonInsertForm, PersonUpdateForm},
+    person_device_key::{PersonDeviceKey, PersonDeviceKeyInsertForm},
+  },
   traits::{ApubActor, Crud},
 };
 use lemmy_db_schema_file::enums::ActorType;
@@ -38,6 +44,7 @@ use lemmy_utils::{
   utils::{
     markdown::markdown_to_html,
     slurs::{check_slurs, check_slurs_opt},
+    validation::is_valid_device_key,
   },
 };
 use std::ops::Deref;
@@ -97,12 +104,29 @@ impl Object for ApubPerson {
     self.deleted
   }
 
-  async fn into_json(self, _context: &Data<Self::DataType>) -> LemmyResult<Person> {
+  async fn into_json(self, context: &Data<Self::DataType>) -> LemmyResult<Person> {
     let kind = if self.bot_account {
       UserTypes::Service
     } else {
       UserTypes::Person
     };
+    let device_keys = PersonDeviceKey::list_for_person(&mut context.pool(), self.id).await?;
+    let key_packages = (!device_keys.is_empty()).then(|| KeyPackages {
+      kind: CollectionType::Collection,
+      items: device_keys
+        .into_iter()
+        .map(|k| KeyPackage {
+          kind: KeyPackageType::KeyPackage,
+          id: k.ap_id.into(),
+          attributed_to: self.ap_id.clone().into(),
+          media_type: MediaTypeMls::Mls,
+          encoding: ContentEncoding::Base64,
+          content: k.public_key,
+          name: k.device_name,
+          published: Some(k.published_at),
+        })
+        .collect(),
+    });
 
     let person = Person {
       kind,
@@ -120,6 +144,7 @@ impl Object for ApubPerson {
       public_key: self.public_key(),
       updated: self.updated_at,
       inbox: self.inbox_url.clone().into(),
+      key_packages,
     };
     Ok(person)
   }
@@ -150,12 +175,19 @@ impl Object for ApubPerson {
     let bio = read_from_string_or_source_opt(&person.summary, &None, &person.source);
     let bio = process_markdown_opt(&bio, &slur_regex, &url_blocklist, context).await?;
     let bio = markdown_rewrite_remote_links_opt(bio, context).await;
-    let avatar = proxy_image_link_opt_apub(person.icon.map(|i| i.url), context).await?;
-    let banner = proxy_image_link_opt_apub(person.image.map(|i| i.url), context).await?;
+    let policy = instance_policy(person.id.inner(), context).await?;
+    let (icon, image) = if policy.reject_media {
+      (None, None)
+    } else {
+      (person.icon, person.image)
+    };
+    let avatar = proxy_image_link_opt_apub(icon.map(|i| i.url), context).await?;
+    let banner = proxy_image_link_opt_apub(image.map(|i| i.url), context).await?;
 
     // Some Mastodon users have `name: ""` (empty string), need to convert that to `None`
     // https://github.com/mastodon/mastodon/issues/25233
     let display_name = person.name.filter(|n| !n.is_empty());
+    let key_packages = person.key_packages.map(|k| k.items).unwrap_or_default();
 
     let person_form = PersonInsertForm {
       name: person.preferred_username,
@@ -184,6 +216,26 @@ impl Object for ApubPerson {
     };
     let person = DbPerson::upsert(&mut context.pool(), &person_form).await?;
 
+    // Ignore invalid keys, and keys which don't belong to this person
+    let device_keys: Vec<_> = key_packages
+      .into_iter()
+      .filter(|k| {
+        k.attributed_to.inner() == person.ap_id.inner()
+          && k.id.domain() == person.ap_id.inner().domain()
+          && k.id.as_str().len() <= 255
+          && is_valid_device_key(&k.content, k.name.as_deref()).is_ok()
+      })
+      .take(MAX_DEVICE_KEYS)
+      .map(|k| PersonDeviceKeyInsertForm {
+        person_id: person.id,
+        ap_id: k.id.into(),
+        public_key: k.content,
+        device_name: k.name,
+        published_at: k.published,
+      })
+      .collect();
+    PersonDeviceKey::replace_for_person(&mut context.pool(), person.id, &device_keys).await?;
+
     Ok(person.into())
   }
 }
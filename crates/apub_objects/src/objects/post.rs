@@ -10,6 +10,7 @@ use crate::{
     functions::{
       check_apub_id_valid_with_strictness,
       generate_to,
+      instance_policy,
       read_from_string_or_source_opt,
       verify_person_in_community,
       verify_visibility,
@@ -38,6 +39,7 @@ use lemmy_api_utils::{
 use lemmy_db_schema::{
   source::{
     community::Community,
+    images::{ImageDetails, ImageDetailsInsertForm},
     person::Person,
     post::{Post, PostInsertForm, PostUpdateForm},
   },
@@ -121,15 +123,30 @@ impl Object for ApubPost {
     let community = Community::read(&mut context.pool(), community_id).await?;
     let language = Some(LanguageTag::new_single(self.language_id, &mut context.pool()).await?);
 
+    let is_video = self
+      .url_content_type
+      .as_ref()
+      .is_some_and(|t| t.starts_with("video/"));
+    let video_details = match &self.url {
+      Some(url) if is_video => ImageDetails::read(&mut context.pool(), url).await?,
+      _ => None,
+    };
     let attachment = self
       .url
       .clone()
-      .map(|url| {
-        Attachment::new(
+      .map(|url| match video_details {
+        Some(details) => Attachment::new_video(
           url.into(),
           self.url_content_type.clone(),
           self.alt_text.clone(),
-        )
+          details.duration,
+          details.poster_url.map(ImageObject::new),
+        ),
+        None => Attachment::new(
+          url.into(),
+          self.url_content_type.clone(),
+          self.alt_text.clone(),
+        ),
       })
       .into_iter()
       .collect();
@@ -230,8 +247,13 @@ impl Object for ApubPost {
       name = name.chars().take(MAX_TITLE_LENGTH).collect();
     }
 
+    let policy = instance_policy(page.id.inner(), context).await?;
     let first_attachment = page.attachment.first();
-    let url = if let Some(attachment) = first_attachment.cloned() {
+    let url = if policy.reject_media
+      && (page.kind == PageType::Video || first_attachment.is_some_and(Attachment::is_media))
+    {
+      None
+    } else if let Some(attachment) = first_attachment.cloned() {
       Some(attachment.url())
     } else if page.kind == PageType::Video {
       // we cant display videos directly, so insert a link to external video page
@@ -241,7 +263,7 @@ impl Object for ApubPost {
     };
 
     // Ensure that all posts in NSFW communities are marked as NSFW
-    let nsfw = if community.nsfw {
+    let nsfw = if community.nsfw || policy.force_nsfw {
       Some(true)
     } else {
       page.sensitive
@@ -260,6 +282,7 @@ impl Object for ApubPost {
 
     let url_blocklist = get_url_blocklist(context).await?;
 
+    let url = url.map(|u| Url::parse(&policy.rewrite_links(u.to_string())).unwrap_or(u));
     let url = if let Some(url) = url {
       is_url_blocked(&url, &url_blocklist)?;
       is_valid_url(&url)?;
@@ -273,12 +296,15 @@ impl Object for ApubPost {
     };
 
     let alt_text = first_attachment.cloned().and_then(Attachment::alt_text);
+    let poster = first_attachment.and_then(Attachment::poster);
+    let video_duration = first_attachment.and_then(Attachment::video_duration);
 
     let slur_regex = slur_regex(context).await?;
 
     let body = read_from_string_or_source_opt(&page.content, &page.media_type, &page.source);
     let body = process_markdown_opt(&body, &slur_regex, &url_blocklist, context).await?;
     let body = markdown_rewrite_remote_links_opt(body, context).await;
+    let body = body.map(|b| policy.rewrite_links(b));
     let language_id = Some(
       LanguageTag::to_language_id_single(page.language.unwrap_or_default(), &mut context.pool())
         .await?,
@@ -302,14 +328,34 @@ impl Object for ApubPost {
     let timestamp = page.updated.or(page.published).unwrap_or_else(Utc::now);
     let post = Post::insert_apub(&mut context.pool(), timestamp, &form).await?;
     plugin_hook_after("after_receive_federated_post", &post)?;
+
+    // Remote videos aren't fetched, so the duration from the attachment is stored in the details
+    // of the video. Its dimensions are unknown.
+    if let (Some(url), Some((duration, media_type))) = (&post.url, video_duration) {
+      let form = ImageDetailsInsertForm {
+        link: url.clone(),
+        width: 0,
+        height: 0,
+        content_type: media_type.unwrap_or_else(|| "video/mp4".to_string()),
+        blurhash: None,
+        duration: Some(duration),
+        poster_url: None,
+        perceptual_hash: None,
+        nsfw: false,
+        quarantined: false,
+      };
+      ImageDetails::create(&mut context.pool(), &form).await?;
+    }
     let post_ = post.clone();
     let context_ = context.clone();
 
     // Generates a post thumbnail in background task, because some sites can be very slow to
     // respond.
-    spawn_try_task(
-      async move { generate_post_link_metadata(post_, None, |_| None, context_).await },
-    );
+    if !policy.reject_media {
+      spawn_try_task(async move {
+        generate_post_link_metadata(post_, poster, |_| None, context_).await
+      });
+    }
 
     Ok(post.into())
   }
@@ -1,17 +1,14 @@
 use crate::{
-  protocol::private_message::{PrivateMessage, PrivateMessageType},
+  protocol::private_message::{PrivateMessage, PrivateMessageMediaType, PrivateMessageType},
   utils::{
     functions::{check_apub_id_valid_with_strictness, read_from_string_or_source},
     markdown_links::markdown_rewrite_remote_links,
-    protocol::Source,
+    protocol::{ContentEncoding, Source},
   },
 };
 use activitypub_federation::{
   config::Data,
-  protocol::{
-    values::MediaTypeHtml,
-    verification::{verify_domains_match, verify_is_remote_object},
-  },
+  protocol::verification::{verify_domains_match, verify_is_remote_object},
   traits::Object,
 };
 use chrono::Utc;
@@ -31,7 +28,7 @@ use lemmy_db_schema::{
 use lemmy_db_views_local_user::LocalUserView;
 use lemmy_utils::{
   error::{LemmyError, LemmyErrorType, LemmyResult},
-  utils::markdown::markdown_to_html,
+  utils::{markdown::markdown_to_html, validation::is_valid_encrypted_body_field},
 };
 use semver::{Version, VersionReq};
 use std::ops::Deref;
@@ -101,14 +98,31 @@ impl Object for ApubPrivateMessage {
       }
     }
 
+    // Encrypted messages are passed on unchanged, they can't be converted to html
+    let (content, media_type, encoding, source) = if self.encrypted {
+      (
+        self.content.clone(),
+        PrivateMessageMediaType::Mls,
+        Some(ContentEncoding::Base64),
+        None,
+      )
+    } else {
+      (
+        markdown_to_html(&self.content),
+        PrivateMessageMediaType::Html,
+        None,
+        Some(Source::new(self.content.clone())),
+      )
+    };
     let note = PrivateMessage {
       kind,
       id: self.ap_id.clone().into(),
       attributed_to: creator.ap_id.into(),
       to: [recipient.ap_id.into()],
-      content: markdown_to_html(&self.content),
-      media_type: Some(MediaTypeHtml::Html),
-      source: Some(Source::new(self.content.clone())),
+      content,
+      media_type: Some(media_type),
+      encoding,
+      source,
       published: Some(self.published_at),
       updated: self.updated_at,
     };
@@ -144,12 +158,17 @@ impl Object for ApubPrivateMessage {
     {
       check_private_messages_enabled(&recipient_local_user)?;
     }
-    let slur_regex = slur_regex(context).await?;
-    let url_blocklist = get_url_blocklist(context).await?;
-
-    let content = read_from_string_or_source(&note.content, &None, &note.source);
-    let content = process_markdown(&content, &slur_regex, &url_blocklist, context).await?;
-    let content = markdown_rewrite_remote_links(content, context).await;
+    let encrypted = note.media_type == Some(PrivateMessageMediaType::Mls);
+    let content = if encrypted {
+      is_valid_encrypted_body_field(&note.content)?;
+      note.content
+    } else {
+      let slur_regex = slur_regex(context).await?;
+      let url_blocklist = get_url_blocklist(context).await?;
+      let content = read_from_string_or_source(&note.content, &None, &note.source);
+      let content = process_markdown(&content, &slur_regex, &url_blocklist, context).await?;
+      markdown_rewrite_remote_links(content, context).await
+    };
 
     let mut form = PrivateMessageInsertForm {
       creator_id: creator.id,
@@ -161,6 +180,7 @@ impl Object for ApubPrivateMessage {
       read: None,
       ap_id: Some(note.id.into()),
       local: Some(false),
+      encrypted: Some(encrypted),
     };
     form = plugin_hook_before("before_receive_federated_private_message", form).await?;
     let timestamp = note.updated.or(note.published).unwrap_or_else(Utc::now);
@@ -234,6 +254,37 @@ mod tests {
     Ok(())
   }
 
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_encrypted_pm() -> LemmyResult<()> {
+    let context = LemmyContext::init_test_context().await;
+    let test_data = TestData::create(&mut context.pool()).await?;
+    let url = Url::parse("https://enterprise.lemmy.ml/private_message/1621")?;
+    let data = prepare_comment_test(&url, &context).await?;
+    let mut json: PrivateMessage =
+      file_to_json_object("../apub/assets/lemmy/objects/private_message.json")?;
+    json.content = "AAECAwQFBgcICQ==".to_string();
+    json.media_type = Some(PrivateMessageMediaType::Mls);
+    json.encoding = Some(ContentEncoding::Base64);
+    json.source = None;
+    ApubPrivateMessage::verify(&json, &url, &context).await?;
+    let pm = ApubPrivateMessage::from_json(json, &context).await?;
+
+    // The ciphertext is stored and federated unchanged
+    assert!(pm.encrypted);
+    assert_eq!("AAECAwQFBgcICQ==", pm.content);
+    let pm_id = pm.id;
+    let to_apub = pm.into_json(&context).await?;
+    assert_eq!("AAECAwQFBgcICQ==", to_apub.content);
+    assert_eq!(Some(PrivateMessageMediaType::Mls), to_apub.media_type);
+    assert_eq!(None, to_apub.source);
+
+    DbPrivateMessage::delete(&mut context.pool(), pm_id).await?;
+    cleanup(data, &context).await?;
+    test_data.delete(&mut context.pool()).await?;
+    Ok(())
+  }
+
   #[tokio::test]
   #[serial]
   async fn test_parse_pleroma_pm() -> LemmyResult<()> {
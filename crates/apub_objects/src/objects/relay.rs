@@ -0,0 +1,110 @@
+use crate::{
+  protocol::relay::{RelayActor, RelayActorType},
+  utils::functions::check_apub_id_valid_with_strictness,
+};
+use activitypub_federation::{
+  config::Data,
+  protocol::verification::{verify_domains_match, verify_is_remote_object},
+  traits::{Actor, Object},
+};
+use chrono::{DateTime, Utc};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema::source::relay::{Relay, RelayUpdateForm};
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
+use std::ops::Deref;
+use url::Url;
+
+/// An ActivityPub relay. Relays are only created by admins, so fetching an unknown relay actor
+/// fails instead of storing it.
+#[derive(Clone, Debug)]
+pub struct ApubRelay(pub Relay);
+
+impl Deref for ApubRelay {
+  type Target = Relay;
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl From<Relay> for ApubRelay {
+  fn from(r: Relay) -> Self {
+    ApubRelay(r)
+  }
+}
+
+#[async_trait::async_trait]
+impl Object for ApubRelay {
+  type DataType = LemmyContext;
+  type Kind = RelayActor;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    self.ap_id.inner()
+  }
+
+  fn last_refreshed_at(&self) -> Option<DateTime<Utc>> {
+    Some(self.updated_at.unwrap_or(self.published_at))
+  }
+
+  async fn read_from_id(object_id: Url, data: &Data<Self::DataType>) -> LemmyResult<Option<Self>> {
+    Ok(
+      Relay::read_from_ap_id(&mut data.pool(), &object_id.into())
+        .await?
+        .map(Into::into),
+    )
+  }
+
+  async fn delete(self, data: &Data<Self::DataType>) -> LemmyResult<()> {
+    Relay::delete(&mut data.pool(), self.id).await?;
+    Ok(())
+  }
+
+  async fn into_json(self, _data: &Data<Self::DataType>) -> LemmyResult<Self::Kind> {
+    Ok(RelayActor {
+      kind: RelayActorType::Application,
+      id: self.ap_id.clone().into(),
+      inbox: self.inbox_url.clone().into(),
+      public_key: self.public_key(),
+      endpoints: None,
+    })
+  }
+
+  async fn verify(
+    apub: &Self::Kind,
+    expected_domain: &Url,
+    data: &Data<Self::DataType>,
+  ) -> LemmyResult<()> {
+    check_apub_id_valid_with_strictness(apub.id.inner(), true, data).await?;
+    verify_domains_match(expected_domain, apub.id.inner())?;
+    verify_is_remote_object(&apub.id, data)?;
+    Ok(())
+  }
+
+  async fn from_json(apub: Self::Kind, context: &Data<Self::DataType>) -> LemmyResult<Self> {
+    let relay = Relay::read_from_ap_id(&mut context.pool(), &apub.id.clone().into())
+      .await?
+      .ok_or(LemmyErrorType::NotFound)?;
+    let form = RelayUpdateForm {
+      inbox_url: Some(apub.shared_inbox_or_inbox().into()),
+      public_key: Some(apub.public_key.public_key_pem),
+      updated_at: Some(Some(Utc::now())),
+      ..Default::default()
+    };
+    let relay = Relay::update(&mut context.pool(), relay.id, &form).await?;
+    Ok(relay.into())
+  }
+}
+
+impl Actor for ApubRelay {
+  fn public_key_pem(&self) -> &str {
+    &self.public_key
+  }
+
+  fn private_key_pem(&self) -> Option<String> {
+    None
+  }
+
+  fn inbox(&self) -> Url {
+    self.inbox_url.clone().into()
+  }
+}
@@ -5,6 +5,7 @@ pub mod note;
 pub mod page;
 pub mod person;
 pub mod private_message;
+pub mod relay;
 
 #[cfg(test)]
 mod tests {
@@ -15,6 +16,7 @@ mod tests {
     page::Page,
     person::Person,
     private_message::PrivateMessage,
+    relay::RelayActor,
   };
   use crate::utils::test::{test_json, test_parse_lemmy_item};
   use activitypub_federation::protocol::tombstone::Tombstone;
@@ -36,6 +38,7 @@ mod tests {
   fn test_parse_objects_pleroma() -> LemmyResult<()> {
     test_json::<Person>("../apub/assets/pleroma/objects/person.json")?;
     test_json::<Note>("../apub/assets/pleroma/objects/note.json")?;
+    test_json::<RelayActor>("../apub/assets/pleroma/objects/relay.json")?;
     Ok(())
   }
 
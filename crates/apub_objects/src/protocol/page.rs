@@ -91,6 +91,7 @@ pub struct Image {
   name: Option<String>,
 }
 
+#[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Document {
@@ -100,6 +101,10 @@ pub struct Document {
   media_type: Option<String>,
   /// Used for alt_text
   name: Option<String>,
+  /// Duration of videos, as ISO 8601 duration like `PT95S`
+  duration: Option<String>,
+  /// Poster frame of videos
+  icon: Option<ImageObject>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -122,6 +127,44 @@ impl Attachment {
     }
   }
 
+  /// Whether the attachment is an image, video or audio file, as opposed to a link to a website.
+  pub(crate) fn is_media(&self) -> bool {
+    let media_type = match self {
+      Attachment::Image(_) => return true,
+      Attachment::Document(d) => d.media_type.as_deref(),
+      Attachment::Link(l) => l.media_type.as_deref(),
+    };
+    media_type.is_some_and(|t| {
+      t.starts_with("image/") || t.starts_with("video/") || t.starts_with("audio/")
+    })
+  }
+
+  /// Poster frame of a video attachment.
+  pub(crate) fn poster(&self) -> Option<Url> {
+    match self {
+      Attachment::Document(d) => d.icon.as_ref().map(|i| i.url.clone()),
+      _ => None,
+    }
+  }
+
+  /// Duration in seconds and media type of a video attachment. Only durations in whole seconds
+  /// like `PT95S` are supported, as sent by Lemmy and PeerTube.
+  pub(crate) fn video_duration(&self) -> Option<(i32, Option<String>)> {
+    match self {
+      Attachment::Document(d) => {
+        let seconds = d
+          .duration
+          .as_deref()?
+          .strip_prefix("PT")?
+          .strip_suffix('S')?
+          .parse()
+          .ok()?;
+        Some((seconds, d.media_type.clone()))
+      }
+      _ => None,
+    }
+  }
+
   pub(crate) fn alt_text(self) -> Option<String> {
     match self {
       Attachment::Image(i) => i.name,
@@ -196,6 +239,24 @@ impl Attachment {
       })
     }
   }
+
+  /// Creates attachment for a video with known duration in seconds and poster frame.
+  pub(crate) fn new_video(
+    url: Url,
+    media_type: Option<String>,
+    alt_text: Option<String>,
+    duration: Option<i32>,
+    poster: Option<ImageObject>,
+  ) -> Attachment {
+    Attachment::Document(Document {
+      kind: Default::default(),
+      url,
+      media_type,
+      name: alt_text,
+      duration: duration.map(|d| format!("PT{d}S")),
+      icon: poster,
+    })
+  }
 }
 
 // Used for community outbox, so that it can be compatible with Pleroma/Mastodon.
@@ -1,9 +1,10 @@
 use crate::{
   objects::person::ApubPerson,
-  utils::protocol::{Endpoints, ImageObject, Source},
+  utils::protocol::{ContentEncoding, Endpoints, ImageObject, MediaTypeMls, Source},
 };
 use activitypub_federation::{
   fetch::object_id::ObjectId,
+  kinds::collection::CollectionType,
   protocol::{helpers::deserialize_skip_error, public_key::PublicKey},
 };
 use chrono::{DateTime, Utc};
@@ -46,4 +47,39 @@ pub struct Person {
   pub(crate) endpoints: Option<Endpoints>,
   pub(crate) published: Option<DateTime<Utc>>,
   pub(crate) updated: Option<DateTime<Utc>>,
+  /// Device keys for end-to-end encrypted private messages
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) key_packages: Option<KeyPackages>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPackages {
+  #[serde(rename = "type")]
+  pub(crate) kind: CollectionType,
+  pub(crate) items: Vec<KeyPackage>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum KeyPackageType {
+  KeyPackage,
+}
+
+/// The public key of a user device, as defined by the MLS over ActivityPub proposal. Clients use
+/// these to encrypt private messages, the server only passes them on.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPackage {
+  #[serde(rename = "type")]
+  pub(crate) kind: KeyPackageType,
+  pub(crate) id: Url,
+  pub(crate) attributed_to: ObjectId<ApubPerson>,
+  pub(crate) media_type: MediaTypeMls,
+  pub(crate) encoding: ContentEncoding,
+  /// Base64 encoded key package
+  pub(crate) content: String,
+  /// Device name
+  pub(crate) name: Option<String>,
+  pub(crate) published: Option<DateTime<Utc>>,
 }
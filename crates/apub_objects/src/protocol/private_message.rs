@@ -1,13 +1,10 @@
 use crate::{
   objects::{person::ApubPerson, private_message::ApubPrivateMessage},
-  utils::protocol::Source,
+  utils::protocol::{ContentEncoding, Source},
 };
 use activitypub_federation::{
   fetch::object_id::ObjectId,
-  protocol::{
-    helpers::{deserialize_one, deserialize_skip_error},
-    values::MediaTypeHtml,
-  },
+  protocol::helpers::{deserialize_one, deserialize_skip_error},
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -25,7 +22,9 @@ pub struct PrivateMessage {
   pub to: [ObjectId<ApubPerson>; 1],
   pub(crate) content: String,
 
-  pub(crate) media_type: Option<MediaTypeHtml>,
+  pub(crate) media_type: Option<PrivateMessageMediaType>,
+  /// Only set for encrypted messages, whose content is base64 encoded ciphertext
+  pub(crate) encoding: Option<ContentEncoding>,
   #[serde(deserialize_with = "deserialize_skip_error", default)]
   pub(crate) source: Option<Source>,
   pub(crate) published: Option<DateTime<Utc>>,
@@ -39,3 +38,13 @@ pub enum PrivateMessageType {
   ChatMessage,
   Note,
 }
+
+/// Encrypted messages use the media type of the MLS over ActivityPub proposal, so that other
+/// software doesn't treat the ciphertext as html.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum PrivateMessageMediaType {
+  #[serde(rename = "text/html")]
+  Html,
+  #[serde(rename = "message/mls")]
+  Mls,
+}
@@ -0,0 +1,35 @@
+use crate::{objects::relay::ApubRelay, utils::protocol::Endpoints};
+use activitypub_federation::{fetch::object_id::ObjectId, protocol::public_key::PublicKey};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use url::Url;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RelayActorType {
+  Application,
+  Service,
+}
+
+/// The actor of an ActivityPub relay. Only the fields which are needed for federation are parsed.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayActor {
+  #[serde(rename = "type")]
+  pub(crate) kind: RelayActorType,
+  pub id: ObjectId<ApubRelay>,
+  pub inbox: Url,
+  pub public_key: PublicKey,
+  pub(crate) endpoints: Option<Endpoints>,
+}
+
+impl RelayActor {
+  /// Relays usually provide a shared inbox, which is more efficient for sending.
+  pub fn shared_inbox_or_inbox(&self) -> Url {
+    self
+      .endpoints
+      .as_ref()
+      .map(|e| e.shared_inbox.clone())
+      .unwrap_or_else(|| self.inbox.clone())
+  }
+}
@@ -11,11 +11,12 @@ use activitypub_federation::{
 };
 use either::Either;
 use html2md::parse_html;
-use lemmy_api_utils::context::LemmyContext;
+use lemmy_api_utils::{context::LemmyContext, utils::instance_policies};
 use lemmy_db_schema::{
   source::{
     community::{Community, CommunityActions, CommunityModeratorForm},
     instance::{Instance, InstanceActions},
+    instance_policy::InstancePolicy,
     local_site::LocalSite,
   },
   traits::Joinable,
@@ -68,6 +69,16 @@ pub struct LocalSiteData {
   blocked_instances: Vec<Instance>,
 }
 
+impl LocalSiteData {
+  /// Whether ActivityPub GET requests need a valid HTTP signature.
+  pub fn authorized_fetch(&self) -> bool {
+    self
+      .local_site
+      .as_ref()
+      .is_some_and(|l| l.federation_authorized_fetch)
+  }
+}
+
 pub async fn local_site_data_cached(pool: &mut DbPool<'_>) -> LemmyResult<Arc<LocalSiteData>> {
   // All incoming and outgoing federation actions read the blocklist/allowlist and slur filters
   // multiple times. This causes a huge number of database reads if we hit the db directly. So we
@@ -102,6 +113,18 @@ pub async fn local_site_data_cached(pool: &mut DbPool<'_>) -> LemmyResult<Arc<Lo
   )
 }
 
+/// Returns the policy for the instance which the given object or actor belongs to. If the admins
+/// haven't set a policy for this instance, a policy without any rules is returned.
+pub async fn instance_policy(id: &Url, context: &LemmyContext) -> LemmyResult<InstancePolicy> {
+  let policies = instance_policies(context).await?;
+  Ok(
+    id.domain()
+      .and_then(|domain| policies.get(domain))
+      .cloned()
+      .unwrap_or_default(),
+  )
+}
+
 pub async fn check_apub_id_valid_with_strictness(
   apub_id: &Url,
   is_strict: bool,
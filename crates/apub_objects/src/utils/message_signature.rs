@@ -0,0 +1,463 @@
+//! HTTP Message Signatures as defined in [RFC 9421](https://www.rfc-editor.org/rfc/rfc9421),
+//! using the `rsa-v1_5-sha256` algorithm with the existing actor keys. Request bodies are covered
+//! through the `Content-Digest` header from [RFC 9530](https://www.rfc-editor.org/rfc/rfc9530).
+//!
+//! Older draft-cavage signatures are still handled by `activitypub_federation`. They are only
+//! created here to check if an instance which rejects RFC 9421 signatures accepts them.
+
+use base64::{engine::general_purpose::STANDARD as Base64, Engine};
+use chrono::{DateTime, Utc};
+use lemmy_utils::error::{FederationError, LemmyErrorExt, LemmyResult};
+use rsa::{
+  pkcs1::DecodeRsaPublicKey,
+  pkcs1v15::{Signature, SigningKey, VerifyingKey},
+  pkcs8::{DecodePrivateKey, DecodePublicKey},
+  signature::{SignatureEncoding, Signer, Verifier},
+  RsaPrivateKey,
+  RsaPublicKey,
+};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+pub const SIGNATURE_INPUT_HEADER: &str = "signature-input";
+pub const SIGNATURE_HEADER: &str = "signature";
+pub const CONTENT_DIGEST_HEADER: &str = "content-digest";
+
+/// Label used for signatures created by Lemmy
+const SIGNATURE_LABEL: &str = "sig1";
+const ALGORITHM: &str = "rsa-v1_5-sha256";
+/// Signatures which were created longer ago are rejected
+const MAX_SIGNATURE_AGE_SECONDS: i64 = 3600;
+/// Allowed clock difference for signatures which claim to be created in the future
+const MAX_CLOCK_SKEW_SECONDS: i64 = 300;
+
+/// Value of the `Content-Digest` header for the given body.
+pub fn content_digest(body: &[u8]) -> String {
+  format!("sha-256=:{}:", Base64.encode(Sha256::digest(body)))
+}
+
+/// Sign a POST request which delivers an activity. Returns the headers which need to be added to
+/// the request.
+pub fn sign_post_request(
+  target_uri: &Url,
+  content_type: &str,
+  body: &[u8],
+  key_id: &str,
+  private_key_pem: &str,
+  created: DateTime<Utc>,
+) -> LemmyResult<Vec<(&'static str, String)>> {
+  let digest = content_digest(body);
+  let components = [
+    ("@method", "POST".to_string()),
+    ("@target-uri", target_uri.to_string()),
+    ("content-type", content_type.to_string()),
+    (CONTENT_DIGEST_HEADER, digest.clone()),
+  ];
+  let names = components
+    .iter()
+    .map(|(name, _)| format!("\"{name}\""))
+    .collect::<Vec<_>>()
+    .join(" ");
+  let params = format!(
+    "({names});created={};keyid=\"{key_id}\";alg=\"{ALGORITHM}\"",
+    created.timestamp()
+  );
+  let base = signature_base(&components, &params);
+
+  let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+    .with_lemmy_type(FederationError::InvalidHttpSignature.into())?;
+  let signature = SigningKey::<Sha256>::new(private_key).sign(base.as_bytes());
+  Ok(vec![
+    (CONTENT_DIGEST_HEADER, digest),
+    (
+      SIGNATURE_INPUT_HEADER,
+      format!("{SIGNATURE_LABEL}={params}"),
+    ),
+    (
+      SIGNATURE_HEADER,
+      format!(
+        "{SIGNATURE_LABEL}=:{}:",
+        Base64.encode(signature.to_bytes())
+      ),
+    ),
+  ])
+}
+
+/// Sign a POST request with a draft-cavage signature, covering the same headers as
+/// `activitypub_federation`. Returns the headers which need to be added to the request.
+pub fn sign_post_request_cavage(
+  target_uri: &Url,
+  body: &[u8],
+  key_id: &str,
+  private_key_pem: &str,
+  date: DateTime<Utc>,
+) -> LemmyResult<Vec<(&'static str, String)>> {
+  let host = target_uri
+    .host_str()
+    .ok_or(FederationError::UrlWithoutDomain)?;
+  let host = match target_uri.port() {
+    Some(port) => format!("{host}:{port}"),
+    None => host.to_string(),
+  };
+  let path = match target_uri.query() {
+    Some(query) => format!("{}?{query}", target_uri.path()),
+    None => target_uri.path().to_string(),
+  };
+  let date = date.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+  let digest = format!("SHA-256={}", Base64.encode(Sha256::digest(body)));
+  let base = format!("(request-target): post {path}\nhost: {host}\ndate: {date}\ndigest: {digest}");
+
+  let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+    .with_lemmy_type(FederationError::InvalidHttpSignature.into())?;
+  let signature = SigningKey::<Sha256>::new(private_key).sign(base.as_bytes());
+  let signature = format!(
+    "keyId=\"{key_id}\",algorithm=\"hs2019\",headers=\"(request-target) host date digest\",signature=\"{}\"",
+    Base64.encode(signature.to_bytes())
+  );
+  Ok(vec![
+    ("date", date),
+    ("digest", digest),
+    (SIGNATURE_HEADER, signature),
+  ])
+}
+
+/// A signature received in the `Signature-Input` and `Signature` headers.
+#[derive(Debug)]
+pub struct MessageSignature {
+  /// Names of the covered components, in the order in which they are signed
+  pub components: Vec<String>,
+  pub key_id: Url,
+  pub created: Option<i64>,
+  pub expires: Option<i64>,
+  pub alg: Option<String>,
+  /// Signature parameters as they were received, used for the `@signature-params` line
+  params: String,
+  signature: Vec<u8>,
+}
+
+impl MessageSignature {
+  /// Parse the first signature which is present in both headers. Additional signatures are
+  /// ignored.
+  pub fn parse(signature_input: &str, signature: &str) -> LemmyResult<Self> {
+    let signatures = split_dictionary(signature);
+    let (label, params) = split_dictionary(signature_input)
+      .into_iter()
+      .find(|(label, _)| signatures.iter().any(|(l, _)| l == label))
+      .ok_or(FederationError::InvalidHttpSignature)?;
+    let signature = signatures
+      .iter()
+      .find(|(l, _)| *l == label)
+      .and_then(|(_, value)| value.strip_prefix(':')?.strip_suffix(':'))
+      .and_then(|value| Base64.decode(value).ok())
+      .ok_or(FederationError::InvalidHttpSignature)?;
+
+    let (components, parameters) = params
+      .strip_prefix('(')
+      .and_then(|p| p.split_once(')'))
+      .ok_or(FederationError::InvalidHttpSignature)?;
+    let components = components
+      .split_whitespace()
+      .map(|c| {
+        c.strip_prefix('"')
+          .and_then(|c| c.strip_suffix('"'))
+          // component parameters such as `;req` or `;key` are not supported
+          .filter(|c| !c.contains([';', '"']))
+          .map(str::to_lowercase)
+      })
+      .collect::<Option<Vec<_>>>()
+      .ok_or(FederationError::InvalidHttpSignature)?;
+
+    let mut key_id = None;
+    let mut created = None;
+    let mut expires = None;
+    let mut alg = None;
+    for param in parameters.split(';').filter(|p| !p.is_empty()) {
+      let (key, value) = param
+        .split_once('=')
+        .ok_or(FederationError::InvalidHttpSignature)?;
+      let string_value = || value.strip_prefix('"')?.strip_suffix('"');
+      match key.trim() {
+        "keyid" => key_id = string_value().and_then(|v| Url::parse(v).ok()),
+        "created" => created = value.parse().ok(),
+        "expires" => expires = value.parse().ok(),
+        "alg" => alg = string_value().map(str::to_string),
+        _ => {}
+      }
+    }
+
+    Ok(MessageSignature {
+      components,
+      key_id: key_id.ok_or(FederationError::InvalidHttpSignature)?,
+      created,
+      expires,
+      alg,
+      params: params.to_string(),
+      signature,
+    })
+  }
+
+  /// Verify the signature of a request against the public key of the signing actor.
+  ///
+  /// `header` returns the value of a request header by lowercase name. If the request has a body,
+  /// it must be covered by the signature through the `Content-Digest` header.
+  pub fn verify(
+    &self,
+    method: &str,
+    target_uri: &Url,
+    header: impl Fn(&str) -> Option<String>,
+    body: Option<&[u8]>,
+    public_key_pem: &str,
+    now: DateTime<Utc>,
+  ) -> LemmyResult<()> {
+    if self.alg.as_deref().is_some_and(|alg| alg != ALGORITHM) {
+      Err(FederationError::InvalidHttpSignature)?
+    }
+    let now = now.timestamp();
+    let too_old = self
+      .created
+      .map_or(true, |created| created < now - MAX_SIGNATURE_AGE_SECONDS);
+    let from_future = self
+      .created
+      .is_some_and(|created| created > now + MAX_CLOCK_SKEW_SECONDS);
+    let expired = self.expires.is_some_and(|expires| expires < now);
+    if too_old || from_future || expired {
+      Err(FederationError::InvalidHttpSignature)?
+    }
+
+    // the signature must be bound to this specific request
+    let covers = |name: &str| self.components.iter().any(|c| c == name);
+    let covers_target = covers("@target-uri") || covers("@path") || covers("@request-target");
+    if !covers("@method") || !covers_target {
+      Err(FederationError::InvalidHttpSignature)?
+    }
+    if let Some(body) = body {
+      let expected = content_digest(body);
+      let digest_matches = header(CONTENT_DIGEST_HEADER)
+        .is_some_and(|digests| split_list(&digests).any(|d| d == expected));
+      if !covers(CONTENT_DIGEST_HEADER) || !digest_matches {
+        Err(FederationError::InvalidHttpSignature)?
+      }
+    }
+
+    let components = self
+      .components
+      .iter()
+      .map(|name| {
+        let value = component_value(name, method, target_uri, &header)?;
+        Some((name.as_str(), value))
+      })
+      .collect::<Option<Vec<_>>>()
+      .ok_or(FederationError::InvalidHttpSignature)?;
+    let base = signature_base(&components, &self.params);
+
+    let public_key = RsaPublicKey::from_public_key_pem(public_key_pem)
+      .or_else(|_| RsaPublicKey::from_pkcs1_pem(public_key_pem))
+      .with_lemmy_type(FederationError::InvalidHttpSignature.into())?;
+    let signature = Signature::try_from(self.signature.as_slice())
+      .with_lemmy_type(FederationError::InvalidHttpSignature.into())?;
+    VerifyingKey::<Sha256>::new(public_key)
+      .verify(base.as_bytes(), &signature)
+      .with_lemmy_type(FederationError::InvalidHttpSignature.into())?;
+    Ok(())
+  }
+}
+
+/// Value of a single covered component, or None if it is not available for this request.
+fn component_value(
+  name: &str,
+  method: &str,
+  target_uri: &Url,
+  header: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+  let path_and_query = || match target_uri.query() {
+    Some(query) => format!("{}?{query}", target_uri.path()),
+    None => target_uri.path().to_string(),
+  };
+  Some(match name {
+    "@method" => method.to_uppercase(),
+    "@target-uri" => target_uri.to_string(),
+    "@authority" => match target_uri.port() {
+      Some(port) => format!("{}:{port}", target_uri.host_str()?),
+      None => target_uri.host_str()?.to_string(),
+    },
+    "@scheme" => target_uri.scheme().to_string(),
+    "@path" => target_uri.path().to_string(),
+    "@query" => format!("?{}", target_uri.query().unwrap_or_default()),
+    "@request-target" => path_and_query(),
+    n if n.starts_with('@') => return None,
+    n => header(n)?.trim().to_string(),
+  })
+}
+
+/// Build the signature base which is signed, as described in RFC 9421 section 2.5.
+fn signature_base<S: AsRef<str>>(components: &[(&str, S)], params: &str) -> String {
+  let mut base = String::new();
+  for (name, value) in components {
+    base.push_str(&format!("\"{name}\": {}\n", value.as_ref()));
+  }
+  base.push_str(&format!("\"@signature-params\": {params}"));
+  base
+}
+
+/// Split a structured field dictionary into its members. Commas inside quoted strings and inner
+/// lists are ignored.
+fn split_dictionary(value: &str) -> Vec<(&str, &str)> {
+  split_list(value)
+    .filter_map(|member| member.split_once('='))
+    .map(|(label, value)| (label.trim(), value.trim()))
+    .collect()
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+  let mut in_string = false;
+  let mut depth = 0;
+  value
+    .split(move |c| {
+      match c {
+        '"' => in_string = !in_string,
+        '(' if !in_string => depth += 1,
+        ')' if !in_string => depth -= 1,
+        ',' if !in_string && depth == 0 => return true,
+        _ => {}
+      }
+      false
+    })
+    .map(str::trim)
+    .filter(|member| !member.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use activitypub_federation::http_signatures::generate_actor_keypair;
+  use chrono::TimeDelta;
+
+  const CONTENT_TYPE: &str = "application/activity+json";
+
+  #[test]
+  fn test_sign_and_verify() -> LemmyResult<()> {
+    let keypair = generate_actor_keypair()?;
+    let inbox = Url::parse("https://lemmy.ml/inbox")?;
+    let body = br#"{"type":"Follow"}"#;
+    let now = Utc::now();
+    let headers = sign_post_request(
+      &inbox,
+      CONTENT_TYPE,
+      body,
+      "https://example.com/u/alice#main-key",
+      &keypair.private_key,
+      now,
+    )?;
+    let header = |name: &str| {
+      headers
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| v.clone())
+        .or_else(|| (name == "content-type").then(|| CONTENT_TYPE.to_string()))
+    };
+    let signature = MessageSignature::parse(
+      &header(SIGNATURE_INPUT_HEADER).unwrap_or_default(),
+      &header(SIGNATURE_HEADER).unwrap_or_default(),
+    )?;
+    assert_eq!(
+      "https://example.com/u/alice#main-key",
+      signature.key_id.as_str()
+    );
+    assert_eq!(Some(ALGORITHM), signature.alg.as_deref());
+
+    let verify = |body: &[u8], target: &Url, now| {
+      signature.verify("POST", target, header, Some(body), &keypair.public_key, now)
+    };
+    verify(body, &inbox, now)?;
+
+    // modified body, other target or outdated signature are rejected
+    assert!(verify(br#"{"type":"Block"}"#, &inbox, now).is_err());
+    assert!(verify(body, &Url::parse("https://lemmy.ml/u/bob/inbox")?, now).is_err());
+    assert!(verify(body, &inbox, now + TimeDelta::hours(2)).is_err());
+
+    // key of a different actor
+    let other_keypair = generate_actor_keypair()?;
+    assert!(signature
+      .verify(
+        "POST",
+        &inbox,
+        header,
+        Some(body),
+        &other_keypair.public_key,
+        now
+      )
+      .is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_sign_cavage() -> LemmyResult<()> {
+    let keypair = generate_actor_keypair()?;
+    let inbox = Url::parse("https://lemmy.ml:8536/inbox?x=1")?;
+    let body = br#"{"type":"Follow"}"#;
+    let date = DateTime::parse_from_rfc3339("2025-07-01T12:00:00Z")?.with_timezone(&Utc);
+    let headers = sign_post_request_cavage(
+      &inbox,
+      body,
+      "https://example.com/u/alice#main-key",
+      &keypair.private_key,
+      date,
+    )?;
+    let header = |name: &str| {
+      headers
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| v.clone())
+        .unwrap_or_default()
+    };
+    assert_eq!("Tue, 01 Jul 2025 12:00:00 GMT", header("date"));
+    assert_eq!(
+      format!("SHA-256={}", Base64.encode(Sha256::digest(body))),
+      header("digest")
+    );
+
+    let signature = header(SIGNATURE_HEADER);
+    assert!(signature.starts_with(r#"keyId="https://example.com/u/alice#main-key","#));
+    let signature = signature
+      .rsplit_once("signature=\"")
+      .and_then(|(_, s)| s.strip_suffix('"'))
+      .unwrap_or_default();
+    let signature = Signature::try_from(Base64.decode(signature)?.as_slice())?;
+    let base = format!(
+      "(request-target): post /inbox?x=1\nhost: lemmy.ml:8536\ndate: {}\ndigest: {}",
+      header("date"),
+      header("digest")
+    );
+    let public_key = RsaPublicKey::from_public_key_pem(&keypair.public_key)?;
+    VerifyingKey::<Sha256>::new(public_key).verify(base.as_bytes(), &signature)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse() -> LemmyResult<()> {
+    let signature = MessageSignature::parse(
+      r#"sig-b21=();created=1618884473;keyid="https://example.com/key", sig1=("@method" "@path" "Content-Digest");created=1618884473;keyid="https://example.com/actor#main-key";expires=1618884773"#,
+      "sig1=:dGVzdA==:",
+    )?;
+    assert_eq!(
+      vec!["@method", "@path", "content-digest"],
+      signature.components
+    );
+    assert_eq!(
+      "https://example.com/actor#main-key",
+      signature.key_id.as_str()
+    );
+    assert_eq!(Some(1618884473), signature.created);
+    assert_eq!(Some(1618884773), signature.expires);
+    assert_eq!(None, signature.alg);
+    assert_eq!(b"test".to_vec(), signature.signature);
+
+    assert!(MessageSignature::parse(r#"sig1=("@method");created=1"#, "sig1=:dGVzdA==:").is_err());
+    assert!(MessageSignature::parse(
+      r#"sig1=("@method");keyid="https://example.com/key""#,
+      "sig2=:dGVzdA==:"
+    )
+    .is_err());
+    Ok(())
+  }
+}
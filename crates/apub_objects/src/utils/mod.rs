@@ -1,5 +1,6 @@
 pub mod functions;
 pub mod markdown_links;
 pub mod mentions;
+pub mod message_signature;
 pub mod protocol;
 pub mod test;
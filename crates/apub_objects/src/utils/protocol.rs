@@ -33,6 +33,20 @@ impl Source {
   }
 }
 
+/// Media type of end-to-end encrypted content, as defined by the MLS over ActivityPub proposal.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum MediaTypeMls {
+  #[serde(rename = "message/mls")]
+  Mls,
+}
+
+/// Encoding of binary content, which is used for encrypted messages and key packages.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ContentEncoding {
+  #[serde(rename = "base64")]
+  Base64,
+}
+
 pub trait InCommunity {
   fn community(
     &self,
@@ -1,10 +1,17 @@
 use crate::{
   diesel::OptionalExtension,
-  newtypes::{ActivityId, DbUrl},
-  source::activity::{ReceivedActivity, SentActivity, SentActivityForm},
+  newtypes::{ActivityId, DbUrl, ReceivedActivityId},
+  source::activity::{ReceivedActivity, ReceivedActivityForm, SentActivity, SentActivityForm},
   utils::{get_conn, DbPool},
 };
-use diesel::{dsl::insert_into, ExpressionMethods, QueryDsl};
+use chrono::{DateTime, Utc};
+use diesel::{
+  dsl::{insert_into, not},
+  BoolExpressionMethods,
+  ExpressionMethods,
+  PgSortExpressionMethods,
+  QueryDsl,
+};
 use diesel_async::RunQueryDsl;
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 
@@ -37,6 +44,17 @@ impl SentActivity {
       .await
       .with_lemmy_type(LemmyErrorType::NotFound)
   }
+
+  /// Id of the newest sent activity, or 0 if there are none.
+  pub async fn read_latest_id(pool: &mut DbPool<'_>) -> LemmyResult<ActivityId> {
+    use lemmy_db_schema_file::schema::sent_activity::dsl::{id, sent_activity};
+    let conn = &mut get_conn(pool).await?;
+    let latest_id: Option<ActivityId> = sent_activity
+      .select(diesel::dsl::max(id))
+      .get_result(conn)
+      .await?;
+    Ok(latest_id.unwrap_or(ActivityId(0)))
+  }
 }
 
 impl ReceivedActivity {
@@ -56,6 +74,131 @@ impl ReceivedActivity {
       Err(LemmyErrorType::CouldntInsertActivity.into())
     }
   }
+
+  /// Store a received activity so that it gets processed asynchronously. Returns false if the
+  /// activity was received before.
+  pub async fn enqueue(pool: &mut DbPool<'_>, form: &ReceivedActivityForm) -> LemmyResult<bool> {
+    use lemmy_db_schema_file::schema::received_activity::dsl::received_activity;
+    let conn = &mut get_conn(pool).await?;
+    let rows_affected = insert_into(received_activity)
+      .values(form)
+      .on_conflict_do_nothing()
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntInsertActivity)?;
+    Ok(rows_affected == 1)
+  }
+
+  /// Activities which are ready to be processed, with due retries first and then the others in the
+  /// order they were received. Actors who have an activity waiting for a retry are skipped, so
+  /// that their activities are still processed in order.
+  pub async fn read_queued(pool: &mut DbPool<'_>, limit: i64) -> LemmyResult<Vec<Self>> {
+    use lemmy_db_schema_file::schema::received_activity::dsl::{
+      actor_ap_id,
+      data,
+      dead_lettered_at,
+      id,
+      next_attempt_at,
+      processed_at,
+      received_activity,
+    };
+    let conn = &mut get_conn(pool).await?;
+    let now = Utc::now();
+    let waiting_actors = received_activity
+      .filter(processed_at.is_null())
+      .filter(dead_lettered_at.is_null())
+      .filter(next_attempt_at.gt(now))
+      .filter(actor_ap_id.is_not_null())
+      .select(actor_ap_id);
+    received_activity
+      .filter(data.is_not_null())
+      .filter(processed_at.is_null())
+      .filter(dead_lettered_at.is_null())
+      .filter(next_attempt_at.is_null().or(next_attempt_at.le(now)))
+      .filter(
+        actor_ap_id
+          .is_null()
+          .or(not(actor_ap_id.eq_any(waiting_actors))),
+      )
+      .order_by((next_attempt_at.asc().nulls_last(), id.asc()))
+      .limit(limit)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn mark_processed(pool: &mut DbPool<'_>, id_: ReceivedActivityId) -> LemmyResult<()> {
+    use lemmy_db_schema_file::schema::received_activity::dsl::{
+      data,
+      id,
+      processed_at,
+      received_activity,
+    };
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(received_activity.filter(id.eq(id_)))
+      .set((
+        processed_at.eq(Utc::now()),
+        data.eq(None::<serde_json::Value>),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateActivity)?;
+    Ok(())
+  }
+
+  /// Record a failed processing attempt. Without `retry_at` the activity is dead-lettered and not
+  /// processed again.
+  pub async fn mark_failed(
+    pool: &mut DbPool<'_>,
+    id_: ReceivedActivityId,
+    error: String,
+    retry_at: Option<DateTime<Utc>>,
+  ) -> LemmyResult<()> {
+    use lemmy_db_schema_file::schema::received_activity::dsl::{
+      attempts,
+      dead_lettered_at,
+      id,
+      last_error,
+      next_attempt_at,
+      received_activity,
+    };
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(received_activity.filter(id.eq(id_)))
+      .set((
+        attempts.eq(attempts + 1),
+        last_error.eq(error),
+        next_attempt_at.eq(retry_at),
+        dead_lettered_at.eq(retry_at.is_none().then(Utc::now)),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateActivity)?;
+    Ok(())
+  }
+
+  /// Number of queued and of dead-lettered activities.
+  pub async fn count_queue(pool: &mut DbPool<'_>) -> LemmyResult<(i64, i64)> {
+    use lemmy_db_schema_file::schema::received_activity::dsl::{
+      data,
+      dead_lettered_at,
+      processed_at,
+      received_activity,
+    };
+    let conn = &mut get_conn(pool).await?;
+    let queued = received_activity
+      .filter(data.is_not_null())
+      .filter(processed_at.is_null())
+      .filter(dead_lettered_at.is_null())
+      .count()
+      .get_result(conn)
+      .await?;
+    let dead_lettered = received_activity
+      .filter(dead_lettered_at.is_not_null())
+      .count()
+      .get_result(conn)
+      .await?;
+    Ok((queued, dead_lettered))
+  }
 }
 
 #[cfg(test)]
@@ -85,6 +228,42 @@ mod tests {
     Ok(())
   }
 
+  #[tokio::test]
+  #[serial]
+  async fn receive_activity_queue() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let form = ReceivedActivityForm {
+      ap_id: Url::parse("http://example.com/activity/532")?.into(),
+      data: json!({"type": "Like"}),
+      actor_ap_id: Url::parse("http://example.com/u/exampleuser")?.into(),
+    };
+
+    assert!(ReceivedActivity::enqueue(pool, &form).await?);
+    assert!(!ReceivedActivity::enqueue(pool, &form).await?);
+    let queued = ReceivedActivity::read_queued(pool, 10).await?;
+    let activity = queued
+      .iter()
+      .find(|a| a.ap_id == form.ap_id)
+      .ok_or(LemmyErrorType::NotFound)?;
+    assert_eq!(Some(form.data.clone()), activity.data);
+
+    ReceivedActivity::mark_failed(pool, activity.id, "error".to_string(), Some(Utc::now())).await?;
+    let queued = ReceivedActivity::read_queued(pool, 10).await?;
+    let retried = queued
+      .iter()
+      .find(|a| a.ap_id == form.ap_id)
+      .ok_or(LemmyErrorType::NotFound)?;
+    assert_eq!(1, retried.attempts);
+    assert_eq!(Some("error".to_string()), retried.last_error);
+
+    ReceivedActivity::mark_processed(pool, activity.id).await?;
+    let queued = ReceivedActivity::read_queued(pool, 10).await?;
+    assert!(!queued.iter().any(|a| a.ap_id == form.ap_id));
+
+    Ok(())
+  }
+
   #[tokio::test]
   #[serial]
   async fn sent_activity_write_read() -> LemmyResult<()> {
@@ -11,7 +11,7 @@ use crate::{
   },
   traits::{Crud, Likeable, Saveable},
   utils::{
-    functions::{coalesce, hot_rank},
+    functions::{coalesce, hot_rank, replace},
     get_conn,
     uplete,
     validate_like,
@@ -27,6 +27,7 @@ use diesel::{
   ExpressionMethods,
   JoinOnDsl,
   QueryDsl,
+  TextExpressionMethods,
 };
 use diesel_async::RunQueryDsl;
 use diesel_ltree::Ltree;
@@ -38,6 +39,31 @@ use lemmy_utils::{
 use url::Url;
 
 impl Comment {
+  /// Replaces a link in the content of the person's comments.
+  pub async fn replace_url_for_creator(
+    pool: &mut DbPool<'_>,
+    creator_id: PersonId,
+    old_url: &DbUrl,
+    new_url: &DbUrl,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    update(comment::table)
+      .filter(comment::creator_id.eq(creator_id))
+      .filter(comment::content.like(format!("%{old_url}%")))
+      .set((
+        comment::content.eq(replace(
+          comment::content,
+          old_url.as_str(),
+          new_url.as_str(),
+        )),
+        comment::updated_at.eq(Utc::now()),
+      ))
+      .get_results::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateComment)
+  }
+
   pub async fn permadelete_for_creator(
     pool: &mut DbPool<'_>,
     creator_id: PersonId,
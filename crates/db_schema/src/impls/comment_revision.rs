@@ -0,0 +1,196 @@
+use crate::{
+  newtypes::{CommentId, CommentRevisionId, PaginationCursor, PersonId},
+  source::{
+    comment::Comment,
+    comment_revision::{comment_revision_keys as key, CommentRevision, CommentRevisionInsertForm},
+  },
+  utils::{get_conn, limit_fetch, paginate, DbPool},
+};
+use diesel::{insert_into, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use i_love_jesus::SortDirection;
+use lemmy_db_schema_file::schema::{comment, comment_revision};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl CommentRevision {
+  pub async fn create(
+    pool: &mut DbPool<'_>,
+    form: &CommentRevisionInsertForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(comment_revision::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateComment)
+  }
+
+  /// Stores the previous version of a comment, but only if the edit changed its content.
+  pub async fn create_if_changed(
+    pool: &mut DbPool<'_>,
+    orig_comment: &Comment,
+    updated_comment: &Comment,
+    editor_id: PersonId,
+  ) -> LemmyResult<Option<Self>> {
+    if orig_comment.content == updated_comment.content {
+      return Ok(None);
+    }
+    let form =
+      CommentRevisionInsertForm::new(orig_comment.id, editor_id, orig_comment.content.clone());
+    Ok(Some(Self::create(pool, &form).await?))
+  }
+
+  /// Lists the previous versions of a comment, newest first.
+  pub async fn list_for_comment(
+    pool: &mut DbPool<'_>,
+    comment_id: CommentId,
+    cursor_data: Option<CommentRevision>,
+    page_back: Option<bool>,
+    limit: Option<i64>,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    let limit = limit_fetch(limit)?;
+    let query = comment_revision::table
+      .filter(comment_revision::comment_id.eq(comment_id))
+      .limit(limit)
+      .into_boxed();
+    let paginated_query = paginate(query, SortDirection::Desc, cursor_data, None, page_back)
+      .then_order_by(key::published_at)
+      .then_order_by(key::id);
+
+    paginated_query
+      .load::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Removes the edit history of all comments by a given creator. Used when purging their content.
+  pub async fn delete_for_creator(
+    pool: &mut DbPool<'_>,
+    for_creator_id: PersonId,
+  ) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    let creator_comment_ids = comment::table
+      .filter(comment::creator_id.eq(for_creator_id))
+      .select(comment::id);
+
+    diesel::delete(
+      comment_revision::table.filter(comment_revision::comment_id.eq_any(creator_comment_ids)),
+    )
+    .execute(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::Deleted)
+  }
+
+  pub fn to_cursor(&self) -> PaginationCursor {
+    PaginationCursor::new_single('V', self.id.0)
+  }
+
+  pub async fn from_cursor(cursor: &PaginationCursor, pool: &mut DbPool<'_>) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    let id = CommentRevisionId(cursor.first_id()?);
+    comment_revision::table
+      .find(id)
+      .first::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    source::{
+      comment::{Comment, CommentInsertForm, CommentUpdateForm},
+      comment_revision::CommentRevision,
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm},
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_comment_revisions() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let inserted_instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let new_person = PersonInsertForm::test_form(inserted_instance.id, "revision_commenter");
+    let inserted_person = Person::create(pool, &new_person).await?;
+    let new_mod = PersonInsertForm::test_form(inserted_instance.id, "revision_mod");
+    let inserted_mod = Person::create(pool, &new_mod).await?;
+    let new_community = CommunityInsertForm::new(
+      inserted_instance.id,
+      "test community_comment_revisions".to_string(),
+      "nada".to_owned(),
+      "pubkey".to_string(),
+    );
+    let inserted_community = Community::create(pool, &new_community).await?;
+    let new_post = PostInsertForm::new(
+      "A test post".into(),
+      inserted_person.id,
+      inserted_community.id,
+    );
+    let inserted_post = Post::create(pool, &new_post).await?;
+    let comment_form =
+      CommentInsertForm::new(inserted_person.id, inserted_post.id, "first content".into());
+    let inserted_comment = Comment::create(pool, &comment_form, None).await?;
+
+    // An edit which doesn't touch the content shouldn't create a revision
+    let distinguish_form = CommentUpdateForm {
+      distinguished: Some(true),
+      ..Default::default()
+    };
+    let distinguished_comment =
+      Comment::update(pool, inserted_comment.id, &distinguish_form).await?;
+    let unchanged = CommentRevision::create_if_changed(
+      pool,
+      &inserted_comment,
+      &distinguished_comment,
+      inserted_mod.id,
+    )
+    .await?;
+    assert!(unchanged.is_none());
+
+    let edit_form = CommentUpdateForm {
+      content: Some("second content".into()),
+      ..Default::default()
+    };
+    let edited_comment = Comment::update(pool, inserted_comment.id, &edit_form).await?;
+    CommentRevision::create_if_changed(
+      pool,
+      &distinguished_comment,
+      &edited_comment,
+      inserted_mod.id,
+    )
+    .await?;
+
+    let revisions =
+      CommentRevision::list_for_comment(pool, inserted_comment.id, None, None, None).await?;
+    assert_eq!(1, revisions.len());
+    let revision = revisions.first().ok_or(LemmyErrorType::NotFound)?;
+    assert_eq!("first content", revision.content);
+    assert_eq!(Some(inserted_mod.id), revision.editor_id);
+
+    // Deleting the editor keeps the revision of someone else's comment
+    Person::delete(pool, inserted_mod.id).await?;
+    let revisions =
+      CommentRevision::list_for_comment(pool, inserted_comment.id, None, None, None).await?;
+    assert_eq!(1, revisions.len());
+    assert_eq!(None, revisions.first().and_then(|r| r.editor_id));
+
+    let deleted = CommentRevision::delete_for_creator(pool, inserted_person.id).await?;
+    assert_eq!(1, deleted);
+
+    Instance::delete(pool, inserted_instance.id).await?;
+
+    Ok(())
+  }
+}
@@ -22,6 +22,7 @@ use crate::{
     get_conn,
     uplete,
     DbPool,
+    SITEMAP_LIMIT,
   },
 };
 use chrono::{DateTime, Utc};
@@ -291,6 +292,36 @@ impl Community {
       .await
       .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunity)
   }
+
+  /// Local communities which are visible without login, with their last modification.
+  pub async fn list_for_sitemap(
+    pool: &mut DbPool<'_>,
+    include_nsfw: bool,
+  ) -> LemmyResult<Vec<(DbUrl, DateTime<Utc>)>> {
+    let conn = &mut get_conn(pool).await?;
+    let mut query = community::table
+      .select((
+        community::ap_id,
+        coalesce(community::updated_at, community::published_at),
+      ))
+      .filter(community::local.eq(true))
+      .filter(community::deleted.eq(false))
+      .filter(community::removed.eq(false))
+      .filter(community::visibility.eq_any([
+        CommunityVisibility::Public,
+        CommunityVisibility::LocalOnlyPublic,
+      ]))
+      .into_boxed();
+    if !include_nsfw {
+      query = query.filter(community::nsfw.eq(false));
+    }
+    query
+      .order(community::subscribers.desc())
+      .limit(SITEMAP_LIMIT)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
 }
 
 impl CommunityActions {
@@ -507,7 +538,7 @@ impl Followable for CommunityActions {
   }
   async fn follow_accepted(
     pool: &mut DbPool<'_>,
-    community_id: CommunityId,
+    community_id: Self::IdType,
     person_id: PersonId,
   ) -> LemmyResult<Self> {
     let conn = &mut get_conn(pool).await?;
@@ -1002,4 +1033,77 @@ mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_list_for_sitemap() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let community = |name: &str| {
+      CommunityInsertForm::new(
+        instance.id,
+        name.to_string(),
+        name.to_owned(),
+        "pubkey".to_string(),
+      )
+    };
+    let public = Community::create(pool, &community("sitemap_public")).await?;
+    let local_only = CommunityInsertForm {
+      visibility: Some(CommunityVisibility::LocalOnlyPublic),
+      ..community("sitemap_local_only")
+    };
+    let local_only = Community::create(pool, &local_only).await?;
+    let nsfw = CommunityInsertForm {
+      nsfw: Some(true),
+      ..community("sitemap_nsfw")
+    };
+    let nsfw = Community::create(pool, &nsfw).await?;
+    let mut hidden = vec![];
+    for visibility in [
+      CommunityVisibility::Unlisted,
+      CommunityVisibility::LocalOnlyPrivate,
+      CommunityVisibility::Private,
+    ] {
+      let form = CommunityInsertForm {
+        visibility: Some(visibility),
+        ..community(&format!("sitemap_{visibility}").to_lowercase())
+      };
+      hidden.push(Community::create(pool, &form).await?);
+    }
+    let deleted = CommunityInsertForm {
+      deleted: Some(true),
+      ..community("sitemap_deleted")
+    };
+    hidden.push(Community::create(pool, &deleted).await?);
+    let removed = CommunityInsertForm {
+      removed: Some(true),
+      ..community("sitemap_removed")
+    };
+    hidden.push(Community::create(pool, &removed).await?);
+
+    let sfw: Vec<_> = Community::list_for_sitemap(pool, false)
+      .await?
+      .into_iter()
+      .map(|(ap_id, _)| ap_id)
+      .collect();
+    assert!(sfw.contains(&public.ap_id));
+    assert!(sfw.contains(&local_only.ap_id));
+    assert!(!sfw.contains(&nsfw.ap_id));
+    assert!(hidden.iter().all(|c| !sfw.contains(&c.ap_id)));
+
+    let with_nsfw: Vec<_> = Community::list_for_sitemap(pool, true)
+      .await?
+      .into_iter()
+      .map(|(ap_id, _)| ap_id)
+      .collect();
+    assert!(with_nsfw.contains(&public.ap_id));
+    assert!(with_nsfw.contains(&nsfw.ap_id));
+    assert!(hidden.iter().all(|c| !with_nsfw.contains(&c.ap_id)));
+
+    Instance::delete(pool, instance.id).await?;
+
+    Ok(())
+  }
 }
@@ -0,0 +1,156 @@
+use crate::{
+  diesel::OptionalExtension,
+  newtypes::CommunityId,
+  source::community_backfill::CommunityBackfill,
+  utils::{get_conn, now, DbPool},
+};
+use chrono::{DateTime, Utc};
+use diesel::{insert_into, update, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::community_backfill;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl CommunityBackfill {
+  /// Returns true if the backfill was started, or false if the community was already backfilled
+  /// before.
+  pub async fn start(pool: &mut DbPool<'_>, community_id: CommunityId) -> LemmyResult<bool> {
+    let conn = &mut get_conn(pool).await?;
+    let inserted = insert_into(community_backfill::table)
+      .values(community_backfill::community_id.eq(community_id))
+      .on_conflict_do_nothing()
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityBackfill)?;
+    Ok(inserted == 1)
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, community_id: CommunityId) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    community_backfill::table
+      .find(community_id)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn update_progress(
+    pool: &mut DbPool<'_>,
+    community_id: CommunityId,
+    posts_fetched: i32,
+    comments_fetched: i32,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    update(community_backfill::table.find(community_id))
+      .set((
+        community_backfill::posts_fetched.eq(posts_fetched),
+        community_backfill::comments_fetched.eq(comments_fetched),
+        community_backfill::updated_at.eq(now()),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityBackfill)?;
+    Ok(())
+  }
+
+  pub async fn finish(
+    pool: &mut DbPool<'_>,
+    community_id: CommunityId,
+    error: Option<String>,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    update(community_backfill::table.find(community_id))
+      .set((
+        community_backfill::error.eq(error),
+        community_backfill::updated_at.eq(now()),
+        community_backfill::finished_at.eq(now()),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityBackfill)?;
+    Ok(())
+  }
+
+  /// Unfinished backfills which made no progress since `stalled_since` were interrupted, usually
+  /// by a restart. They are reset and their community ids returned, so that they can be started
+  /// again.
+  pub async fn restart_stalled(
+    pool: &mut DbPool<'_>,
+    stalled_since: DateTime<Utc>,
+  ) -> LemmyResult<Vec<CommunityId>> {
+    let conn = &mut get_conn(pool).await?;
+    update(
+      community_backfill::table
+        .filter(community_backfill::finished_at.is_null())
+        .filter(community_backfill::updated_at.lt(stalled_since)),
+    )
+    .set((
+      community_backfill::posts_fetched.eq(0),
+      community_backfill::comments_fetched.eq(0),
+      community_backfill::updated_at.eq(now()),
+    ))
+    .returning(community_backfill::community_id)
+    .get_results(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityBackfill)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::{
+    source::{
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use chrono::TimeDelta;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_backfill() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let instance = Instance::read_or_create(pool, "backfill.xyz".to_string()).await?;
+    let form = CommunityInsertForm::new(
+      instance.id,
+      "backfill".into(),
+      "Backfill".to_owned(),
+      "pubkey".to_string(),
+    );
+    let community = Community::create(pool, &form).await?;
+
+    assert_eq!(None, CommunityBackfill::read(pool, community.id).await?);
+    assert!(CommunityBackfill::start(pool, community.id).await?);
+    assert!(!CommunityBackfill::start(pool, community.id).await?);
+
+    CommunityBackfill::update_progress(pool, community.id, 3, 12).await?;
+    let backfill = CommunityBackfill::read(pool, community.id).await?;
+    assert_eq!(
+      Some((3, 12)),
+      backfill.map(|b| (b.posts_fetched, b.comments_fetched))
+    );
+
+    // running backfills are not restarted
+    let stalled_since = Utc::now() - TimeDelta::minutes(10);
+    let restarted = CommunityBackfill::restart_stalled(pool, stalled_since).await?;
+    assert!(restarted.is_empty());
+    let restarted = CommunityBackfill::restart_stalled(pool, Utc::now()).await?;
+    assert_eq!(vec![community.id], restarted);
+
+    CommunityBackfill::finish(pool, community.id, None).await?;
+    let backfill = CommunityBackfill::read(pool, community.id).await?;
+    assert!(backfill.and_then(|b| b.finished_at).is_some());
+    let restarted = CommunityBackfill::restart_stalled(pool, Utc::now()).await?;
+    assert!(restarted.is_empty());
+
+    Instance::delete_all(pool).await?;
+    Ok(())
+  }
+}
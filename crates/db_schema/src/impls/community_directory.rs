@@ -0,0 +1,275 @@
+use crate::{
+  newtypes::{CommunityDirectoryId, InstanceId, LanguageId, PaginationCursor},
+  source::community_directory::{
+    community_directory_keys as key,
+    CommunityDirectoryEntry,
+    CommunityDirectoryEntryForm,
+  },
+  utils::{fuzzy_search, get_conn, limit_fetch, now, paginate, DbPool},
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+  delete,
+  dsl::{exists, not},
+  insert_into,
+  select,
+  upsert::excluded,
+  BoolExpressionMethods,
+  ExpressionMethods,
+  PgTextExpressionMethods,
+  QueryDsl,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use i_love_jesus::SortDirection;
+use lemmy_db_schema_file::schema::{community_directory, community_directory_language};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl CommunityDirectoryEntry {
+  /// Insert or update directory entries, and set the given languages for all of them.
+  pub async fn upsert(
+    pool: &mut DbPool<'_>,
+    forms: &[CommunityDirectoryEntryForm],
+    language_ids: &[LanguageId],
+  ) -> LemmyResult<Vec<Self>> {
+    use community_directory::dsl::*;
+    if forms.is_empty() {
+      return Ok(vec![]);
+    }
+    let conn = &mut get_conn(pool).await?;
+    conn
+      .run_transaction(|conn| {
+        async move {
+          let entries = insert_into(community_directory)
+            .values(forms)
+            .on_conflict(ap_id)
+            .do_update()
+            .set((
+              instance_id.eq(excluded(instance_id)),
+              name.eq(excluded(name)),
+              title.eq(excluded(title)),
+              description.eq(excluded(description)),
+              icon.eq(excluded(icon)),
+              nsfw.eq(excluded(nsfw)),
+              subscribers.eq(excluded(subscribers)),
+              users_active_month.eq(excluded(users_active_month)),
+              updated_at.eq(now()),
+            ))
+            .get_results::<Self>(conn)
+            .await
+            .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityDirectory)?;
+
+          let entry_ids: Vec<_> = entries.iter().map(|e| e.id).collect();
+          delete(
+            community_directory_language::table
+              .filter(community_directory_language::community_directory_id.eq_any(&entry_ids)),
+          )
+          .execute(conn)
+          .await
+          .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityDirectory)?;
+          let language_forms: Vec<_> = entry_ids
+            .iter()
+            .flat_map(|entry_id| {
+              language_ids.iter().map(move |language_id| {
+                (
+                  community_directory_language::community_directory_id.eq(*entry_id),
+                  community_directory_language::language_id.eq(*language_id),
+                )
+              })
+            })
+            .collect();
+          if language_forms.is_empty() {
+            return Ok(entries);
+          }
+          insert_into(community_directory_language::table)
+            .values(language_forms)
+            .execute(conn)
+            .await
+            .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityDirectory)?;
+          Ok(entries)
+        }
+        .scope_boxed()
+      })
+      .await
+  }
+
+  /// Delete entries of the instance which were not seen by the crawler since `seen_since`, or all
+  /// entries if it is None.
+  pub async fn delete_for_instance(
+    pool: &mut DbPool<'_>,
+    for_instance_id: InstanceId,
+    seen_since: Option<DateTime<Utc>>,
+  ) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    let mut query = delete(community_directory::table)
+      .filter(community_directory::instance_id.eq(for_instance_id))
+      .into_boxed();
+    if let Some(seen_since) = seen_since {
+      query = query.filter(community_directory::updated_at.lt(seen_since));
+    }
+    query
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityDirectory)
+  }
+
+  /// List entries ordered by subscriber count.
+  pub async fn list(
+    pool: &mut DbPool<'_>,
+    search_term: Option<&str>,
+    language_id: Option<LanguageId>,
+    show_nsfw: bool,
+    cursor_data: Option<CommunityDirectoryEntry>,
+    page_back: Option<bool>,
+    limit: Option<i64>,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    let limit = limit_fetch(limit)?;
+    let mut query = community_directory::table.limit(limit).into_boxed();
+
+    if let Some(search_term) = search_term {
+      let searcher = fuzzy_search(search_term);
+      query = query.filter(
+        community_directory::name
+          .ilike(searcher.clone())
+          .or(community_directory::title.ilike(searcher)),
+      );
+    }
+    if let Some(language_id) = language_id {
+      query = query.filter(exists(
+        community_directory_language::table
+          .filter(community_directory_language::community_directory_id.eq(community_directory::id))
+          .filter(community_directory_language::language_id.eq(language_id)),
+      ));
+    }
+    if !show_nsfw {
+      query = query.filter(not(community_directory::nsfw));
+    }
+
+    paginate(query, SortDirection::Desc, cursor_data, None, page_back)
+      .then_order_by(key::subscribers)
+      .then_order_by(key::id)
+      .load::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, id: CommunityDirectoryId) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    community_directory::table
+      .find(id)
+      .first(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Returns true if the directory has any entries for the instance.
+  pub async fn exists_for_instance(
+    pool: &mut DbPool<'_>,
+    for_instance_id: InstanceId,
+  ) -> LemmyResult<bool> {
+    let conn = &mut get_conn(pool).await?;
+    select(exists(
+      community_directory::table.filter(community_directory::instance_id.eq(for_instance_id)),
+    ))
+    .get_result(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub fn to_cursor(&self) -> PaginationCursor {
+    PaginationCursor::new_single('D', self.id.0)
+  }
+
+  pub async fn from_cursor(cursor: &PaginationCursor, pool: &mut DbPool<'_>) -> LemmyResult<Self> {
+    let id = cursor.first_id()?;
+    Self::read(pool, CommunityDirectoryId(id)).await
+  }
+}
+
+#[cfg(test)]
+#[expect(clippy::indexing_slicing)]
+mod tests {
+
+  use super::*;
+  use crate::{
+    source::{instance::Instance, language::Language},
+    utils::build_db_pool_for_tests,
+  };
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use url::Url;
+
+  fn form(
+    instance_id: InstanceId,
+    name: &str,
+    subscribers: i32,
+    nsfw: bool,
+  ) -> LemmyResult<CommunityDirectoryEntryForm> {
+    Ok(CommunityDirectoryEntryForm {
+      instance_id,
+      ap_id: Url::parse(&format!("https://directory.xyz/c/{name}"))?.into(),
+      name: name.to_string(),
+      title: format!("The {name} community"),
+      description: None,
+      icon: None,
+      nsfw,
+      subscribers,
+      users_active_month: 0,
+    })
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_directory() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let instance = Instance::read_or_create(pool, "directory.xyz".to_string()).await?;
+    let english = Language::read_id_from_code(pool, "en").await?;
+    let german = Language::read_id_from_code(pool, "de").await?;
+
+    let forms = vec![
+      form(instance.id, "rust", 10, false)?,
+      form(instance.id, "memes", 20, false)?,
+      form(instance.id, "nsfw", 30, true)?,
+    ];
+    CommunityDirectoryEntry::upsert(pool, &forms, &[english]).await?;
+
+    let names = |entries: Vec<CommunityDirectoryEntry>| -> Vec<String> {
+      entries.into_iter().map(|e| e.name).collect()
+    };
+    let list = CommunityDirectoryEntry::list(pool, None, None, false, None, None, None).await?;
+    assert_eq!(vec!["memes", "rust"], names(list));
+    let list = CommunityDirectoryEntry::list(pool, None, None, true, None, None, None).await?;
+    assert_eq!(vec!["nsfw", "memes", "rust"], names(list));
+    let list =
+      CommunityDirectoryEntry::list(pool, Some("rus"), None, false, None, None, None).await?;
+    assert_eq!(vec!["rust"], names(list));
+    let list =
+      CommunityDirectoryEntry::list(pool, None, Some(german), false, None, None, None).await?;
+    assert!(list.is_empty());
+
+    // pagination continues after the cursor
+    let first =
+      CommunityDirectoryEntry::list(pool, None, Some(english), false, None, None, Some(1)).await?;
+    let cursor = CommunityDirectoryEntry::from_cursor(&first[0].to_cursor(), pool).await?;
+    let list =
+      CommunityDirectoryEntry::list(pool, None, Some(english), false, Some(cursor), None, None)
+        .await?;
+    assert_eq!(vec!["rust"], names(list));
+
+    // entries which were not seen in the last crawl are removed
+    let crawl_start = Utc::now();
+    let updated =
+      CommunityDirectoryEntry::upsert(pool, &[form(instance.id, "rust", 15, false)?], &[german])
+        .await?;
+    assert_eq!(15, updated[0].subscribers);
+    CommunityDirectoryEntry::delete_for_instance(pool, instance.id, Some(crawl_start)).await?;
+    let list =
+      CommunityDirectoryEntry::list(pool, None, Some(german), true, None, None, None).await?;
+    assert_eq!(vec!["rust"], names(list));
+    assert!(CommunityDirectoryEntry::exists_for_instance(pool, instance.id).await?);
+
+    Instance::delete_all(pool).await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,222 @@
+use crate::{
+  newtypes::{CommunityFeedId, CommunityId, DbUrl, PostId},
+  source::community_feed::{CommunityFeed, CommunityFeedInsertForm, CommunityFeedUpdateForm},
+  traits::Crud,
+  utils::{get_conn, now, DbPool},
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+  dsl::{exists, insert_into, select},
+  update,
+  BoolExpressionMethods,
+  ExpressionMethods,
+  QueryDsl,
+};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::{community, community_feed, community_feed_item};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+/// Each community can import this many feeds.
+pub const MAX_COMMUNITY_FEEDS: usize = 10;
+
+impl Crud for CommunityFeed {
+  type InsertForm = CommunityFeedInsertForm;
+  type UpdateForm = CommunityFeedUpdateForm;
+  type IdType = CommunityFeedId;
+
+  async fn create(pool: &mut DbPool<'_>, form: &Self::InsertForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(community_feed::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateCommunityFeed)
+  }
+
+  async fn update(
+    pool: &mut DbPool<'_>,
+    id: CommunityFeedId,
+    form: &Self::UpdateForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    update(community_feed::table.find(id))
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityFeed)
+  }
+}
+
+impl CommunityFeed {
+  pub async fn list_for_community(
+    pool: &mut DbPool<'_>,
+    for_community_id: CommunityId,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    community_feed::table
+      .filter(community_feed::community_id.eq(for_community_id))
+      .order_by(community_feed::id)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Feeds which were not fetched since `fetched_before`, excluding those of deleted or removed
+  /// communities.
+  pub async fn list_due(
+    pool: &mut DbPool<'_>,
+    fetched_before: DateTime<Utc>,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    community_feed::table
+      .inner_join(community::table)
+      .filter(community::deleted.eq(false))
+      .filter(community::removed.eq(false))
+      .filter(
+        community_feed::last_fetched_at
+          .is_null()
+          .or(community_feed::last_fetched_at.lt(fetched_before)),
+      )
+      .select(community_feed::all_columns)
+      .order_by(community_feed::id)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn mark_fetched(
+    pool: &mut DbPool<'_>,
+    id: CommunityFeedId,
+    error: Option<String>,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    update(community_feed::table.find(id))
+      .set((
+        community_feed::last_fetched_at.eq(now()),
+        community_feed::last_error.eq(error),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityFeed)?;
+    Ok(())
+  }
+
+  /// Returns true if an item with the same guid or url was already imported from this feed.
+  pub async fn is_item_imported(
+    pool: &mut DbPool<'_>,
+    id: CommunityFeedId,
+    guid: &str,
+    url: Option<&DbUrl>,
+  ) -> LemmyResult<bool> {
+    let conn = &mut get_conn(pool).await?;
+    let url = url.map(|u| u.as_str());
+    select(exists(
+      community_feed_item::table
+        .filter(community_feed_item::community_feed_id.eq(id))
+        .filter(
+          community_feed_item::guid
+            .eq(guid)
+            .or(community_feed_item::url.eq(url)),
+        ),
+    ))
+    .get_result(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn mark_item_imported(
+    pool: &mut DbPool<'_>,
+    id: CommunityFeedId,
+    guid: &str,
+    url: Option<&DbUrl>,
+    post_id: PostId,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(community_feed_item::table)
+      .values((
+        community_feed_item::community_feed_id.eq(id),
+        community_feed_item::guid.eq(guid),
+        community_feed_item::url.eq(url.map(|u| u.as_str())),
+        community_feed_item::post_id.eq(post_id),
+      ))
+      .on_conflict_do_nothing()
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateCommunityFeed)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::{
+    source::{
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm},
+    },
+    utils::build_db_pool_for_tests,
+  };
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use url::Url;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_feed() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let instance = Instance::read_or_create(pool, "feed.xyz".to_string()).await?;
+    let bot = Person::create(pool, &PersonInsertForm::test_form(instance.id, "feed_bot")).await?;
+    let form = CommunityInsertForm::new(
+      instance.id,
+      "news".into(),
+      "News".to_owned(),
+      "pubkey".to_string(),
+    );
+    let community = Community::create(pool, &form).await?;
+
+    let feed_url: DbUrl = Url::parse("https://news.example/feed.xml")?.into();
+    let form = CommunityFeedInsertForm {
+      title_filter: Some("rust".to_string()),
+      ..CommunityFeedInsertForm::new(community.id, feed_url.clone(), bot.id)
+    };
+    let feed = CommunityFeed::create(pool, &form).await?;
+    assert_eq!(
+      vec![feed.clone()],
+      CommunityFeed::list_for_community(pool, community.id).await?
+    );
+
+    // new feeds are fetched immediately
+    let due = CommunityFeed::list_due(pool, Utc::now()).await?;
+    assert_eq!(vec![feed.id], due.iter().map(|f| f.id).collect::<Vec<_>>());
+    CommunityFeed::mark_fetched(pool, feed.id, Some("timeout".to_string())).await?;
+    let fetched_before = Utc::now() - chrono::TimeDelta::minutes(1);
+    assert!(CommunityFeed::list_due(pool, fetched_before)
+      .await?
+      .is_empty());
+    let feed = CommunityFeed::read(pool, feed.id).await?;
+    assert_eq!(Some("timeout".to_string()), feed.last_error);
+
+    let post_form = PostInsertForm::new("Rust 2.0".to_string(), bot.id, community.id);
+    let post = Post::create(pool, &post_form).await?;
+    let item_url: DbUrl = Url::parse("https://news.example/rust-2")?.into();
+    assert!(!CommunityFeed::is_item_imported(pool, feed.id, "item-1", Some(&item_url)).await?);
+    CommunityFeed::mark_item_imported(pool, feed.id, "item-1", Some(&item_url), post.id).await?;
+    assert!(CommunityFeed::is_item_imported(pool, feed.id, "item-1", None).await?);
+    // same link with a different guid
+    assert!(CommunityFeed::is_item_imported(pool, feed.id, "item-2", Some(&item_url)).await?);
+    assert!(!CommunityFeed::is_item_imported(pool, feed.id, "item-2", None).await?);
+
+    CommunityFeed::delete(pool, feed.id).await?;
+    assert!(CommunityFeed::list_for_community(pool, community.id)
+      .await?
+      .is_empty());
+
+    Instance::delete(pool, instance.id).await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,89 @@
+use crate::{
+  newtypes::{ActivityId, InstanceId},
+  source::federation_queue_control::{FederationQueueControl, FederationQueueControlForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{
+  dsl::{insert_into, update},
+  BoolExpressionMethods,
+  ExpressionMethods,
+  OptionalExtension,
+  QueryDsl,
+  SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::federation_queue_control;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl FederationQueueControl {
+  pub async fn upsert(
+    pool: &mut DbPool<'_>,
+    form: &FederationQueueControlForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(federation_queue_control::table)
+      .values(form)
+      .on_conflict(federation_queue_control::instance_id)
+      .do_update()
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateFederationQueueState)
+  }
+
+  pub async fn read_all(pool: &mut DbPool<'_>) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    federation_queue_control::table
+      .select(Self::as_select())
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Returns the changes which haven't been applied yet for the given instance.
+  pub async fn read_pending(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+  ) -> LemmyResult<Option<Self>> {
+    use federation_queue_control::dsl;
+    let conn = &mut get_conn(pool).await?;
+    federation_queue_control::table
+      .find(instance_id)
+      .filter(
+        dsl::skip_backlog
+          .or(dsl::reset_fail_count)
+          .or(dsl::resend_activity_id.is_not_null()),
+      )
+      .select(Self::as_select())
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Marks the changes as applied, after the federation worker has handled them. If admins made
+  /// new changes in the meantime, these are left pending. The paused state is left unchanged.
+  pub async fn mark_applied(&self, pool: &mut DbPool<'_>) -> LemmyResult<()> {
+    use federation_queue_control::dsl;
+    let conn = &mut get_conn(pool).await?;
+    update(
+      federation_queue_control::table
+        .find(self.instance_id)
+        .filter(dsl::updated_at.eq(self.updated_at)),
+    )
+    .set((
+      dsl::skip_backlog.eq(false),
+      dsl::reset_fail_count.eq(false),
+      dsl::resend_activity_id.eq(None::<ActivityId>),
+    ))
+    .execute(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::CouldntUpdateFederationQueueState)?;
+    Ok(())
+  }
+
+  /// True if there are changes which the federation worker still needs to apply.
+  pub fn has_pending(&self) -> bool {
+    self.skip_backlog || self.reset_fail_count || self.resend_activity_id.is_some()
+  }
+}
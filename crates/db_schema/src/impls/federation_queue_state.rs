@@ -1,11 +1,28 @@
 use crate::{
-  newtypes::InstanceId,
-  source::federation_queue_state::FederationQueueState,
+  newtypes::{ActivityId, InstanceId},
+  source::{
+    federation_queue_control::FederationQueueControl,
+    federation_queue_state::{FederationQueueState, InstanceHealth},
+    instance::Instance,
+  },
   utils::{get_conn, DbPool},
 };
-use diesel::{ExpressionMethods, Insertable, OptionalExtension, QueryDsl, SelectableHelper};
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::{
+  dsl::update,
+  ExpressionMethods,
+  Insertable,
+  OptionalExtension,
+  QueryDsl,
+  SelectableHelper,
+};
 use diesel_async::RunQueryDsl;
-use lemmy_db_schema_file::schema::federation_queue_state;
+use lemmy_db_schema_file::schema::{
+  federation_queue_control,
+  federation_queue_state,
+  instance,
+  sent_activity,
+};
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 
 impl FederationQueueState {
@@ -25,6 +42,9 @@ impl FederationQueueState {
           last_retry_at: None,
           last_successful_id: None, // this value is set to the most current id for new instances
           last_successful_published_time_at: None,
+          last_error: None,
+          last_error_at: None,
+          failing_since: None,
         }),
     )
   }
@@ -40,4 +60,118 @@ impl FederationQueueState {
       .await
       .with_lemmy_type(LemmyErrorType::CouldntUpdateFederationQueueState)
   }
+
+  /// All instances which activities are or were sent to, with their queue state and the changes
+  /// requested by admins.
+  pub async fn list_with_control(
+    pool: &mut DbPool<'_>,
+  ) -> LemmyResult<Vec<(Instance, Self, Option<FederationQueueControl>)>> {
+    let conn = &mut get_conn(pool).await?;
+    federation_queue_state::table
+      .inner_join(instance::table)
+      .left_join(
+        federation_queue_control::table
+          .on(federation_queue_control::instance_id.eq(federation_queue_state::instance_id)),
+      )
+      .select((
+        Instance::as_select(),
+        Self::as_select(),
+        Option::<FederationQueueControl>::as_select(),
+      ))
+      .order_by(instance::domain)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Resets the fail count so that sending is retried immediately, and skips all activities which
+  /// were published before `since`.
+  pub async fn resume(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+    since: DateTime<Utc>,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    let skip_to = sent_activity::table
+      .filter(sent_activity::published_at.lt(since))
+      .select(sent_activity::id)
+      .order_by(sent_activity::id.desc())
+      .first::<ActivityId>(conn)
+      .await
+      .optional()?;
+
+    update(federation_queue_state::table.find(instance_id))
+      .set((
+        federation_queue_state::fail_count.eq(0),
+        federation_queue_state::last_retry_at.eq(None::<DateTime<Utc>>),
+        federation_queue_state::failing_since.eq(None::<DateTime<Utc>>),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateFederationQueueState)?;
+    if let Some(skip_to) = skip_to {
+      update(
+        federation_queue_state::table
+          .find(instance_id)
+          .filter(federation_queue_state::last_successful_id.lt(skip_to)),
+      )
+      .set(federation_queue_state::last_successful_id.eq(skip_to))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateFederationQueueState)?;
+    }
+    Ok(())
+  }
+
+  /// Health of the instance, based on how long sending to it has been failing.
+  pub fn health(&self, dead_after: TimeDelta) -> InstanceHealth {
+    let Some(failing_since) = self.failing_since.filter(|_| self.fail_count > 0) else {
+      return InstanceHealth::Healthy;
+    };
+    let failing_for = Utc::now() - failing_since;
+    if failing_for >= dead_after {
+      InstanceHealth::Dead
+    } else if failing_for >= TimeDelta::hours(1) {
+      InstanceHealth::Unreachable
+    } else {
+      InstanceHealth::Degraded
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_health() {
+    let dead_after = TimeDelta::days(3);
+    let state = |fail_count, failing_for| FederationQueueState {
+      instance_id: InstanceId(1),
+      last_successful_id: Some(ActivityId(1)),
+      last_successful_published_time_at: Some(Utc::now() - TimeDelta::days(30)),
+      fail_count,
+      last_retry_at: None,
+      last_error: None,
+      last_error_at: None,
+      failing_since: Some(Utc::now() - failing_for),
+    };
+    assert_eq!(
+      InstanceHealth::Healthy,
+      state(0, TimeDelta::days(10)).health(dead_after)
+    );
+    assert_eq!(
+      InstanceHealth::Degraded,
+      state(3, TimeDelta::minutes(5)).health(dead_after)
+    );
+    assert_eq!(
+      InstanceHealth::Unreachable,
+      state(20, TimeDelta::hours(5)).health(dead_after)
+    );
+    assert_eq!(
+      InstanceHealth::Dead,
+      state(80, TimeDelta::days(4)).health(dead_after)
+    );
+  }
 }
@@ -1,18 +1,39 @@
 use crate::{
-  newtypes::{DbUrl, PersonId},
-  source::images::{ImageDetails, ImageDetailsInsertForm, LocalImage, LocalImageForm, RemoteImage},
-  utils::{get_conn, DbPool},
+  newtypes::{DbUrl, ImageHash, PersonId},
+  source::images::{
+    ImageDetails,
+    ImageDetailsInsertForm,
+    ImageHashBlock,
+    ImageHashBlockForm,
+    ImageRedirect,
+    ImageRedirectForm,
+    LocalImage,
+    LocalImageForm,
+    RemoteImage,
+    RemoteImageCacheStats,
+  },
+  utils::{get_conn, now, DbPool},
 };
+use chrono::{DateTime, TimeDelta, Utc};
 use diesel::{
-  dsl::exists,
+  dsl::{exists, sql},
   insert_into,
   select,
+  sql_types::{BigInt, Bool, Integer, Nullable, Timestamptz},
   BoolExpressionMethods,
   ExpressionMethods,
+  OptionalExtension,
   QueryDsl,
+  QueryableByName,
 };
 use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
-use lemmy_db_schema_file::schema::{image_details, local_image, remote_image};
+use lemmy_db_schema_file::schema::{
+  image_details,
+  image_hash_block,
+  image_redirect,
+  local_image,
+  remote_image,
+};
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 use url::Url;
 
@@ -61,6 +82,16 @@ impl LocalImage {
     .ok_or(LemmyErrorType::NotFound.into())
   }
 
+  pub async fn read_by_alias(pool: &mut DbPool<'_>, alias: &str) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    local_image::table
+      .find(alias)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
   pub async fn delete_by_alias(pool: &mut DbPool<'_>, alias: &str) -> LemmyResult<Self> {
     let conn = &mut get_conn(pool).await?;
     diesel::delete(local_image::table.filter(local_image::pictrs_alias.eq(alias)))
@@ -69,6 +100,29 @@ impl LocalImage {
       .with_lemmy_type(LemmyErrorType::Deleted)
   }
 
+  /// Total size of the media uploaded by the person, in bytes. Thumbnails which were generated
+  /// for posts are not included.
+  pub async fn get_usage(pool: &mut DbPool<'_>, person_id: PersonId) -> LemmyResult<i64> {
+    #[derive(QueryableByName)]
+    struct Usage {
+      #[diesel(sql_type = diesel::sql_types::BigInt)]
+      total_size: i64,
+    }
+
+    let conn = &mut get_conn(pool).await?;
+    // Diesel returns numeric for sums of bigint
+    let usage = diesel::sql_query(
+      "SELECT coalesce(sum(file_size), 0)::bigint AS total_size
+        FROM local_image
+        WHERE person_id = $1 AND thumbnail_for_post_id IS NULL",
+    )
+    .bind::<diesel::sql_types::Integer, _>(person_id)
+    .get_result::<Usage>(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)?;
+    Ok(usage.total_size)
+  }
+
   /// Delete many aliases. Should be used with a pictrs purge.
   pub async fn delete_by_aliases(pool: &mut DbPool<'_>, aliases: &[String]) -> LemmyResult<usize> {
     let conn = &mut get_conn(pool).await?;
@@ -79,6 +133,30 @@ impl LocalImage {
   }
 }
 
+impl ImageRedirect {
+  pub async fn create(pool: &mut DbPool<'_>, form: &ImageRedirectForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(image_redirect::table)
+      .values(form)
+      .on_conflict(image_redirect::alias)
+      .do_update()
+      .set(form)
+      .get_result(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateImage)
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, alias: &str) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    image_redirect::table
+      .find(alias)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+}
+
 impl RemoteImage {
   pub async fn create(pool: &mut DbPool<'_>, links: Vec<Url>) -> LemmyResult<usize> {
     let conn = &mut get_conn(pool).await?;
@@ -105,6 +183,135 @@ impl RemoteImage {
     .then_some(())
     .ok_or(LemmyErrorType::NotFound.into())
   }
+
+  pub async fn read(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    remote_image::table
+      .find(link_)
+      .first(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(remote_image::table.filter(remote_image::link.eq(link_)))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::Deleted)
+  }
+
+  /// Updates the last access time, which is used to evict images from the proxy cache. To avoid
+  /// a write for every request, this happens at most once per hour.
+  pub async fn mark_accessed(&self, pool: &mut DbPool<'_>) -> LemmyResult<()> {
+    if self.last_accessed_at > Utc::now() - TimeDelta::hours(1) {
+      return Ok(());
+    }
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(remote_image::table.find(&self.link))
+      .set(remote_image::last_accessed_at.eq(now()))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateImage)?;
+    Ok(())
+  }
+
+  /// Marks the image as cached, with the size of the original if it is known.
+  pub async fn set_cached(
+    pool: &mut DbPool<'_>,
+    link_: &DbUrl,
+    file_size: Option<i64>,
+  ) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(remote_image::table.find(link_))
+      .set((
+        remote_image::cached.eq(true),
+        remote_image::file_size.eq(file_size),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateImage)
+  }
+
+  /// Marks the image as removed from the image proxy cache.
+  pub async fn set_evicted(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(remote_image::table.find(link_))
+      .set((
+        remote_image::cached.eq(false),
+        remote_image::file_size.eq(None::<i64>),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateImage)
+  }
+
+  /// Cached images, starting with the least recently requested one. If `accessed_before` is
+  /// given, only images which weren't requested since then are returned.
+  pub async fn list_cached(
+    pool: &mut DbPool<'_>,
+    accessed_before: Option<DateTime<Utc>>,
+    limit: i64,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    let mut query = remote_image::table
+      .filter(remote_image::cached)
+      .into_boxed();
+    if let Some(accessed_before) = accessed_before {
+      query = query.filter(remote_image::last_accessed_at.lt(accessed_before));
+    }
+    query
+      .order_by(remote_image::last_accessed_at.asc())
+      .limit(limit)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Cached images whose size is unknown, because they were cached before sizes were tracked.
+  pub async fn list_unknown_size(pool: &mut DbPool<'_>, limit: i64) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    remote_image::table
+      .filter(remote_image::cached)
+      .filter(remote_image::file_size.is_null())
+      .limit(limit)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn cache_stats(pool: &mut DbPool<'_>) -> LemmyResult<RemoteImageCacheStats> {
+    #[derive(QueryableByName)]
+    struct Stats {
+      #[diesel(sql_type = BigInt)]
+      image_count: i64,
+      #[diesel(sql_type = BigInt)]
+      cached_count: i64,
+      #[diesel(sql_type = BigInt)]
+      total_size: i64,
+      #[diesel(sql_type = Nullable<Timestamptz>)]
+      oldest_access: Option<DateTime<Utc>>,
+    }
+
+    let conn = &mut get_conn(pool).await?;
+    // Diesel returns numeric for sums of bigint
+    let stats = diesel::sql_query(
+      "SELECT count(*) AS image_count,
+          count(*) FILTER (WHERE cached) AS cached_count,
+          coalesce(sum(file_size) FILTER (WHERE cached), 0)::bigint AS total_size,
+          min(last_accessed_at) FILTER (WHERE cached) AS oldest_access
+        FROM remote_image",
+    )
+    .get_result::<Stats>(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)?;
+    Ok(RemoteImageCacheStats {
+      image_count: stats.image_count,
+      cached_count: stats.cached_count,
+      total_size: stats.total_size,
+      oldest_access: stats.oldest_access,
+    })
+  }
 }
 
 impl ImageDetails {
@@ -118,4 +325,192 @@ impl ImageDetails {
       .await
       .with_lemmy_type(LemmyErrorType::CouldntCreateImage)
   }
+
+  pub async fn read(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    image_details::table
+      .find(link_)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// All images whose perceptual hash differs from the given one in at most `max_distance` bits.
+  pub async fn list_by_hash(
+    pool: &mut DbPool<'_>,
+    hash: ImageHash,
+    max_distance: i32,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    image_details::table
+      .filter(
+        sql::<Bool>("bit_count((perceptual_hash # ")
+          .bind::<BigInt, _>(hash)
+          .sql(")::bit(64)) <= ")
+          .bind::<Integer, _>(max_distance),
+      )
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Quarantined uploads, which wait for review by an admin.
+  pub async fn list_quarantined(pool: &mut DbPool<'_>) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    image_details::table
+      .filter(image_details::quarantined)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn approve(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+
+    diesel::update(image_details::table.find(link_))
+      .set(image_details::quarantined.eq(false))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateImage)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, link_: &DbUrl) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+
+    diesel::delete(image_details::table.find(link_))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::Deleted)
+  }
+}
+
+impl ImageHashBlock {
+  pub async fn create(pool: &mut DbPool<'_>, form: &ImageHashBlockForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+
+    insert_into(image_hash_block::table)
+      .values(form)
+      .on_conflict(image_hash_block::hash)
+      .do_update()
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateImage)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, hash: ImageHash) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+
+    diesel::delete(image_hash_block::table.filter(image_hash_block::hash.eq(hash)))
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn list(pool: &mut DbPool<'_>) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    image_hash_block::table
+      .order_by(image_hash_block::published_at.desc())
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// The closest blocked hash which differs from the given one in at most `max_distance` bits.
+  pub async fn find_match(
+    pool: &mut DbPool<'_>,
+    hash: ImageHash,
+    max_distance: i32,
+  ) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    image_hash_block::table
+      .filter(
+        sql::<Bool>("bit_count((hash # ")
+          .bind::<BigInt, _>(hash)
+          .sql(")::bit(64)) <= ")
+          .bind::<Integer, _>(max_distance),
+      )
+      .order_by(
+        sql::<BigInt>("bit_count((hash # ")
+          .bind::<BigInt, _>(hash)
+          .sql(")::bit(64))"),
+      )
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::build_db_pool_for_tests;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_image_proxy_cache() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let stats_before = RemoteImage::cache_stats(pool).await?;
+
+    let a = Url::parse("https://cache.tld/a.png")?;
+    let b = Url::parse("https://cache.tld/b.png")?;
+    let c = Url::parse("https://cache.tld/c.png")?;
+    let d = Url::parse("https://cache.tld/d.png")?;
+    RemoteImage::create(pool, vec![a.clone(), b.clone(), c.clone(), d.clone()]).await?;
+    let (a, b, c, d) = (
+      DbUrl::from(a),
+      DbUrl::from(b),
+      DbUrl::from(c),
+      DbUrl::from(d),
+    );
+    let links = [&a, &b, &c, &d];
+    RemoteImage::set_cached(pool, &a, Some(100)).await?;
+    RemoteImage::set_cached(pool, &b, Some(200)).await?;
+    RemoteImage::set_evicted(pool, &c).await?;
+    // d stays cached with an unknown size, like images which were cached before sizes were tracked
+    let old_access = Utc::now() - TimeDelta::days(2);
+    diesel::update(remote_image::table.find(&a))
+      .set(remote_image::last_accessed_at.eq(old_access))
+      .execute(&mut get_conn(pool).await?)
+      .await?;
+
+    // Ignore images of other tests
+    let ours = |images: Vec<RemoteImage>| -> Vec<DbUrl> {
+      images
+        .into_iter()
+        .map(|i| i.link)
+        .filter(|l| links.contains(&l))
+        .collect()
+    };
+    let cached = ours(RemoteImage::list_cached(pool, None, 100).await?);
+    assert_eq!(3, cached.len());
+    assert_eq!(Some(&a), cached.first());
+    assert!(!cached.contains(&c));
+    let accessed_before = Utc::now() - TimeDelta::days(1);
+    let old = ours(RemoteImage::list_cached(pool, Some(accessed_before), 100).await?);
+    assert_eq!(vec![a.clone()], old);
+    let unknown_size = ours(RemoteImage::list_unknown_size(pool, 100).await?);
+    assert_eq!(vec![d.clone()], unknown_size);
+
+    let stats = RemoteImage::cache_stats(pool).await?;
+    assert_eq!(stats_before.image_count + 4, stats.image_count);
+    assert_eq!(stats_before.cached_count + 3, stats.cached_count);
+    assert_eq!(stats_before.total_size + 300, stats.total_size);
+    assert!(stats.oldest_access.is_some_and(|o| o <= old_access));
+
+    for link in links {
+      RemoteImage::delete(pool, link).await?;
+    }
+    Ok(())
+  }
 }
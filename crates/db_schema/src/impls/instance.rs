@@ -1,9 +1,9 @@
 use crate::{
-  diesel::dsl::IntervalDsl,
   newtypes::{InstanceId, PersonId},
   source::{
-    federation_queue_state::FederationQueueState,
+    federation_queue_state::{FederationQueueState, InstanceHealth},
     instance::{Instance, InstanceActions, InstanceBanForm, InstanceBlockForm, InstanceForm},
+    instance_policy::InstancePolicy,
   },
   traits::{Bannable, Blockable},
   utils::{
@@ -14,9 +14,10 @@ use crate::{
     DbPool,
   },
 };
-use chrono::Utc;
+use chrono::{TimeDelta, Utc};
 use diesel::{
   dsl::{count_star, exists, insert_into, not, select},
+  BoolExpressionMethods,
   ExpressionMethods,
   NullableExpressionMethods,
   OptionalExtension,
@@ -24,17 +25,25 @@ use diesel::{
   SelectableHelper,
 };
 use diesel_async::RunQueryDsl;
-use lemmy_db_schema_file::schema::{
-  federation_allowlist,
-  federation_blocklist,
-  federation_queue_state,
-  instance,
-  instance_actions,
-  local_site,
-  site,
+use lemmy_db_schema_file::{
+  enums::SignatureFormat,
+  schema::{
+    federation_allowlist,
+    federation_blocklist,
+    federation_queue_state,
+    instance,
+    instance_actions,
+    instance_policy,
+    local_site,
+    site,
+  },
 };
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 
+/// After an instance rejected our RFC 9421 signatures, incoming requests with such signatures
+/// only switch outgoing activities back to RFC 9421 after this many days.
+const RFC9421_RETRY_DAYS: i64 = 7;
+
 impl Instance {
   /// Attempt to read Instance column for the given domain. If it doesn't exist, insert a new one.
   /// There is no need for update as the domain of an existing instance cant change.
@@ -93,6 +102,48 @@ impl Instance {
       .with_lemmy_type(LemmyErrorType::CouldntUpdateSite)
   }
 
+  /// Use RFC 9421 signatures for outgoing activities to the given instance, because it sent a
+  /// request signed this way. Does nothing if the instance rejected our RFC 9421 signatures
+  /// recently.
+  pub async fn received_rfc9421_signature(
+    pool: &mut DbPool<'_>,
+    domain_: &str,
+  ) -> LemmyResult<usize> {
+    let mut conn = get_conn(pool).await?;
+    let retry_before = Utc::now() - TimeDelta::days(RFC9421_RETRY_DAYS);
+    diesel::update(
+      instance::table
+        .filter(lower(instance::domain).eq(domain_.to_lowercase()))
+        .filter(instance::signature_format.ne(SignatureFormat::Rfc9421))
+        .filter(
+          instance::rfc9421_rejected_at
+            .is_null()
+            .or(instance::rfc9421_rejected_at.lt(retry_before)),
+        ),
+    )
+    .set(instance::signature_format.eq(SignatureFormat::Rfc9421))
+    .execute(&mut conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::CouldntUpdateSite)
+  }
+
+  /// Use draft-cavage signatures for outgoing activities to the given instance, because it
+  /// rejected our RFC 9421 signature.
+  pub async fn rejected_rfc9421_signature(
+    pool: &mut DbPool<'_>,
+    domain_: &str,
+  ) -> LemmyResult<usize> {
+    let mut conn = get_conn(pool).await?;
+    diesel::update(instance::table.filter(lower(instance::domain).eq(domain_.to_lowercase())))
+      .set((
+        instance::signature_format.eq(SignatureFormat::Cavage),
+        instance::rfc9421_rejected_at.eq(Utc::now()),
+      ))
+      .execute(&mut conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateSite)
+  }
+
   pub async fn delete(pool: &mut DbPool<'_>, instance_id: InstanceId) -> LemmyResult<usize> {
     let conn = &mut get_conn(pool).await?;
     diesel::delete(instance::table.find(instance_id))
@@ -143,49 +194,74 @@ impl Instance {
   }
 
   /// returns a list of all instances, each with a flag of whether the instance is allowed or not
-  /// and dead or not ordered by id
+  /// and dead or not ordered by id. Instances are dead if they haven't been seen alive for
+  /// `dead_after`, or if sending to them has been failing for that long.
   pub async fn read_federated_with_blocked_and_dead(
     pool: &mut DbPool<'_>,
+    dead_after: TimeDelta,
   ) -> LemmyResult<Vec<(Self, bool, bool)>> {
     let conn = &mut get_conn(pool).await?;
-    let is_dead_expr = coalesce(instance::updated_at, instance::published_at).lt(now() - 3.days());
+    let is_dead_expr =
+      coalesce(instance::updated_at, instance::published_at).lt(Utc::now() - dead_after);
     // this needs to be done in two steps because the meaning of the "blocked" column depends on the
     // existence of any value at all in the allowlist. (so a normal join wouldn't work)
     let use_allowlist = federation_allowlist::table
       .select(count_star().gt(0))
       .get_result::<bool>(conn)
       .await?;
-    if use_allowlist {
+    let instances = if use_allowlist {
       instance::table
         .left_join(federation_allowlist::table)
+        .left_join(federation_queue_state::table)
         .select((
           Self::as_select(),
           federation_allowlist::instance_id.nullable().is_not_null(),
           is_dead_expr,
+          Option::<FederationQueueState>::as_select(),
         ))
         .order_by(instance::id)
-        .get_results::<(Self, bool, bool)>(conn)
+        .get_results::<(Self, bool, bool, Option<FederationQueueState>)>(conn)
         .await
-        .with_lemmy_type(LemmyErrorType::NotFound)
     } else {
       instance::table
         .left_join(federation_blocklist::table)
+        .left_join(federation_queue_state::table)
         .select((
           Self::as_select(),
           federation_blocklist::instance_id.nullable().is_null(),
           is_dead_expr,
+          Option::<FederationQueueState>::as_select(),
         ))
         .order_by(instance::id)
-        .get_results::<(Self, bool, bool)>(conn)
+        .get_results::<(Self, bool, bool, Option<FederationQueueState>)>(conn)
         .await
-        .with_lemmy_type(LemmyErrorType::NotFound)
     }
+    .with_lemmy_type(LemmyErrorType::NotFound)?;
+
+    Ok(
+      instances
+        .into_iter()
+        .map(|(instance, allowed, is_dead, state)| {
+          let is_dead =
+            is_dead || state.is_some_and(|s| s.health(dead_after) == InstanceHealth::Dead);
+          (instance, allowed, is_dead)
+        })
+        .collect(),
+    )
   }
 
-  /// returns (instance, blocked, allowed, fed queue state) tuples
+  /// returns (instance, fed queue state, blocked, allowed, policy) tuples
   pub async fn read_all_with_fed_state(
     pool: &mut DbPool<'_>,
-  ) -> LemmyResult<Vec<(Self, Option<FederationQueueState>, bool, bool)>> {
+  ) -> LemmyResult<
+    Vec<(
+      Self,
+      Option<FederationQueueState>,
+      bool,
+      bool,
+      Option<InstancePolicy>,
+    )>,
+  > {
     let conn = &mut get_conn(pool).await?;
     instance::table
       // omit instance representing the local site
@@ -194,11 +270,13 @@ impl Instance {
       .left_join(federation_blocklist::table)
       .left_join(federation_allowlist::table)
       .left_join(federation_queue_state::table)
+      .left_join(instance_policy::table)
       .select((
         Self::as_select(),
         Option::<FederationQueueState>::as_select(),
         federation_blocklist::instance_id.nullable().is_not_null(),
         federation_allowlist::instance_id.nullable().is_not_null(),
+        Option::<InstancePolicy>::as_select(),
       ))
       .get_results(conn)
       .await
@@ -315,3 +393,35 @@ impl Bannable for InstanceActions {
     )
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::build_db_pool_for_tests;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_signature_format() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let domain = "signature.tld";
+    let instance = Instance::read_or_create(pool, domain.to_string()).await?;
+    assert_eq!(SignatureFormat::Cavage, instance.signature_format);
+
+    Instance::received_rfc9421_signature(pool, domain).await?;
+    let instance = Instance::read_or_create(pool, domain.to_string()).await?;
+    assert_eq!(SignatureFormat::Rfc9421, instance.signature_format);
+
+    // after the instance rejected our signature, incoming requests don't switch back
+    Instance::rejected_rfc9421_signature(pool, domain).await?;
+    Instance::received_rfc9421_signature(pool, domain).await?;
+    let instance = Instance::read_or_create(pool, domain.to_string()).await?;
+    assert_eq!(SignatureFormat::Cavage, instance.signature_format);
+    assert!(instance.rfc9421_rejected_at.is_some());
+
+    Instance::delete(pool, instance.id).await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,110 @@
+use crate::{
+  newtypes::InstanceId,
+  source::instance_policy::{InstancePolicy, InstancePolicyForm},
+  utils::{get_conn, now, DbPool},
+};
+use diesel::{
+  dsl::insert_into,
+  upsert::excluded,
+  ExpressionMethods,
+  NullableExpressionMethods,
+  QueryDsl,
+  SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::{instance, instance_policy};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl InstancePolicy {
+  /// Creates the policy or replaces all rules of an existing one.
+  pub async fn upsert(pool: &mut DbPool<'_>, form: &InstancePolicyForm) -> LemmyResult<Self> {
+    use instance_policy::dsl::*;
+    let conn = &mut get_conn(pool).await?;
+    insert_into(instance_policy)
+      .values(form)
+      .on_conflict(instance_id)
+      .do_update()
+      .set((
+        reject_media.eq(excluded(reject_media)),
+        force_nsfw.eq(excluded(force_nsfw)),
+        strip_votes.eq(excluded(strip_votes)),
+        silence.eq(excluded(silence)),
+        reject_reports.eq(excluded(reject_reports)),
+        quarantine_communities.eq(excluded(quarantine_communities)),
+        rewrite_links_from.eq(excluded(rewrite_links_from)),
+        rewrite_links_to.eq(excluded(rewrite_links_to)),
+        reason.eq(excluded(reason)),
+        never_cache_media.eq(excluded(never_cache_media)),
+        updated_at.eq(now().nullable()),
+      ))
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateInstancePolicy)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, instance_id: InstanceId) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(instance_policy::table.find(instance_id))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateInstancePolicy)
+  }
+
+  /// All policies together with the domain of their instance.
+  pub async fn read_all_with_domain(pool: &mut DbPool<'_>) -> LemmyResult<Vec<(String, Self)>> {
+    let conn = &mut get_conn(pool).await?;
+    instance_policy::table
+      .inner_join(instance::table)
+      .select((instance::domain, Self::as_select()))
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Applies the link rewrite rule to the given text, if there is one.
+  pub fn rewrite_links(&self, text: String) -> String {
+    match (&self.rewrite_links_from, &self.rewrite_links_to) {
+      (Some(from), Some(to)) if !from.is_empty() => text.replace(from, to),
+      _ => text,
+    }
+  }
+}
+
+impl InstancePolicyForm {
+  /// A policy without any rule has no effect, so it doesn't need to be stored.
+  pub fn has_rules(&self) -> bool {
+    self.reject_media
+      || self.force_nsfw
+      || self.strip_votes
+      || self.silence
+      || self.reject_reports
+      || self.quarantine_communities
+      || self.rewrite_links_from.is_some()
+      || self.never_cache_media
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_rewrite_links() {
+    let policy = InstancePolicy {
+      rewrite_links_from: Some("https://twitter.com/".to_string()),
+      rewrite_links_to: Some("https://nitter.example/".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      "see [here](https://nitter.example/user/status/1)",
+      policy.rewrite_links("see [here](https://twitter.com/user/status/1)".to_string())
+    );
+
+    let no_rewrite = InstancePolicy::default();
+    assert_eq!(
+      "https://twitter.com/user",
+      no_rewrite.rewrite_links("https://twitter.com/user".to_string())
+    );
+  }
+}
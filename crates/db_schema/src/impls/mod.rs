@@ -4,15 +4,21 @@ pub mod captcha_answer;
 pub mod comment;
 pub mod comment_reply;
 pub mod comment_report;
+pub mod comment_revision;
 pub mod community;
+pub mod community_backfill;
+pub mod community_directory;
+pub mod community_feed;
 pub mod community_report;
 pub mod custom_emoji;
 pub mod email_verification;
 pub mod federation_allowlist;
 pub mod federation_blocklist;
+pub mod federation_queue_control;
 pub mod federation_queue_state;
 pub mod images;
 pub mod instance;
+pub mod instance_policy;
 pub mod keyword_block;
 pub mod language;
 pub mod local_site;
@@ -23,18 +29,23 @@ pub mod login_token;
 pub mod mod_log;
 pub mod multi_community;
 pub mod oauth_account;
+pub mod oauth_client;
 pub mod oauth_provider;
 pub mod password_reset_request;
 pub mod person;
 pub mod person_comment_mention;
+pub mod person_device_key;
 pub mod person_post_mention;
 pub mod post;
 pub mod post_report;
+pub mod post_revision;
 pub mod post_tag;
 pub mod private_message;
 pub mod private_message_report;
 pub mod registration_application;
+pub mod relay;
 pub mod secret;
 pub mod site;
+pub mod sitemap;
 pub mod tag;
 pub mod tagline;
@@ -2,6 +2,7 @@ use crate::{
   newtypes::{
     AdminAllowInstanceId,
     AdminBlockInstanceId,
+    AdminImageModerationId,
     AdminPurgeCommentId,
     AdminPurgeCommunityId,
     AdminPurgePersonId,
@@ -12,6 +13,8 @@ use crate::{
     AdminAllowInstanceForm,
     AdminBlockInstance,
     AdminBlockInstanceForm,
+    AdminImageModeration,
+    AdminImageModerationForm,
     AdminPurgeComment,
     AdminPurgeCommentForm,
     AdminPurgeCommunity,
@@ -29,6 +32,7 @@ use diesel_async::RunQueryDsl;
 use lemmy_db_schema_file::schema::{
   admin_allow_instance,
   admin_block_instance,
+  admin_image_moderation,
   admin_purge_comment,
   admin_purge_community,
   admin_purge_person,
@@ -203,3 +207,31 @@ impl Crud for AdminBlockInstance {
       .with_lemmy_type(LemmyErrorType::CouldntUpdateModlog)
   }
 }
+
+impl Crud for AdminImageModeration {
+  type InsertForm = AdminImageModerationForm;
+  type UpdateForm = AdminImageModerationForm;
+  type IdType = AdminImageModerationId;
+
+  async fn create(pool: &mut DbPool<'_>, form: &Self::InsertForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(admin_image_moderation::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateModlog)
+  }
+
+  async fn update(
+    pool: &mut DbPool<'_>,
+    from_id: Self::IdType,
+    form: &Self::InsertForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(admin_image_moderation::table.find(from_id))
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateModlog)
+  }
+}
@@ -0,0 +1,36 @@
+use crate::{
+  source::oauth_client::{OAuthClient, OAuthClientInsertForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{dsl::insert_into, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::oauth_client;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl OAuthClient {
+  pub async fn create(pool: &mut DbPool<'_>, form: &OAuthClientInsertForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(oauth_client::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateOauthClient)
+  }
+
+  pub async fn read_from_client_id(pool: &mut DbPool<'_>, client_id: &str) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    oauth_client::table
+      .filter(oauth_client::client_id.eq(client_id))
+      .first(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::OauthAuthorizationInvalid)
+  }
+
+  /// Checks if the given redirect uri was registered for this client.
+  pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+    self
+      .redirect_uris
+      .split_whitespace()
+      .any(|uri| uri == redirect_uri)
+  }
+}
@@ -1,6 +1,6 @@
 use crate::{
   diesel::{BoolExpressionMethods, NullableExpressionMethods, OptionalExtension},
-  newtypes::{CommunityId, DbUrl, InstanceId, LocalUserId, PersonId},
+  newtypes::{DbUrl, InstanceId, LocalUserId, PersonId},
   source::person::{
     Person,
     PersonActions,
@@ -11,9 +11,16 @@ use crate::{
     PersonUpdateForm,
   },
   traits::{ApubActor, Blockable, Crud, Followable},
-  utils::{format_actor_url, functions::lower, get_conn, uplete, DbPool},
+  utils::{
+    format_actor_url,
+    functions::{coalesce, lower},
+    get_conn,
+    uplete,
+    DbPool,
+    SITEMAP_LIMIT,
+  },
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use diesel::{
   dsl::{exists, insert_into, not, select},
   expression::SelectableHelper,
@@ -147,6 +154,28 @@ impl Person {
     .then_some(())
     .ok_or(LemmyErrorType::UsernameAlreadyExists.into())
   }
+
+  /// Local users which are not deleted or banned, with their last modification.
+  pub async fn list_for_sitemap(pool: &mut DbPool<'_>) -> LemmyResult<Vec<(DbUrl, DateTime<Utc>)>> {
+    let conn = &mut get_conn(pool).await?;
+    let banned = instance_actions::table
+      .filter(instance_actions::person_id.eq(person::id))
+      .filter(instance_actions::instance_id.eq(person::instance_id))
+      .filter(instance_actions::received_ban_at.is_not_null());
+    person::table
+      .select((
+        person::ap_id,
+        coalesce(person::updated_at, person::published_at),
+      ))
+      .filter(person::local.eq(true))
+      .filter(person::deleted.eq(false))
+      .filter(not(exists(banned)))
+      .order(person::published_at.desc())
+      .limit(SITEMAP_LIMIT)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
 }
 
 impl PersonInsertForm {
@@ -240,9 +269,21 @@ impl Followable for PersonActions {
       .with_lemmy_type(LemmyErrorType::CommunityFollowerAlreadyExists)
   }
 
-  /// Currently no user following
-  async fn follow_accepted(_: &mut DbPool<'_>, _: CommunityId, _: PersonId) -> LemmyResult<Self> {
-    Err(LemmyErrorType::NotFound.into())
+  async fn follow_accepted(
+    pool: &mut DbPool<'_>,
+    target_id: Self::IdType,
+    person_id: PersonId,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    let find_action = person_actions::table
+      .find((person_id, target_id))
+      .filter(person_actions::follow_pending.is_not_null());
+    diesel::update(find_action)
+      .set(person_actions::follow_pending.eq(Some(false)))
+      .returning(Self::as_select())
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CommunityFollowerAlreadyExists)
   }
 
   async fn unfollow(
@@ -457,11 +498,11 @@ mod tests {
     source::{
       comment::{Comment, CommentActions, CommentInsertForm, CommentLikeForm, CommentUpdateForm},
       community::{Community, CommunityInsertForm},
-      instance::Instance,
+      instance::{Instance, InstanceActions, InstanceBanForm},
       person::{Person, PersonActions, PersonFollowerForm, PersonInsertForm, PersonUpdateForm},
       post::{Post, PostActions, PostInsertForm, PostLikeForm},
     },
-    traits::{Crud, Followable, Likeable},
+    traits::{Bannable, Crud, Followable, Likeable},
     utils::{build_db_pool_for_tests, uplete},
   };
   use lemmy_utils::error::LemmyResult;
@@ -691,4 +732,41 @@ mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_list_for_sitemap() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let visible =
+      Person::create(pool, &PersonInsertForm::test_form(instance.id, "visible")).await?;
+    let deleted = PersonInsertForm {
+      deleted: Some(true),
+      ..PersonInsertForm::test_form(instance.id, "deleted")
+    };
+    let deleted = Person::create(pool, &deleted).await?;
+    let banned = Person::create(pool, &PersonInsertForm::test_form(instance.id, "banned")).await?;
+    InstanceActions::ban(pool, &InstanceBanForm::new(banned.id, instance.id, None)).await?;
+    let remote = PersonInsertForm {
+      local: Some(false),
+      ..PersonInsertForm::test_form(instance.id, "remote")
+    };
+    let remote = Person::create(pool, &remote).await?;
+
+    let ap_ids: Vec<_> = Person::list_for_sitemap(pool)
+      .await?
+      .into_iter()
+      .map(|(ap_id, _)| ap_id)
+      .collect();
+    assert!(ap_ids.contains(&visible.ap_id));
+    assert!(!ap_ids.contains(&deleted.ap_id));
+    assert!(!ap_ids.contains(&banned.ap_id));
+    assert!(!ap_ids.contains(&remote.ap_id));
+
+    Instance::delete(pool, instance.id).await?;
+
+    Ok(())
+  }
 }
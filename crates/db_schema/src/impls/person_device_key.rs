@@ -0,0 +1,153 @@
+use crate::{
+  newtypes::{PersonDeviceKeyId, PersonId},
+  source::person_device_key::{PersonDeviceKey, PersonDeviceKeyInsertForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{delete, insert_into, ExpressionMethods, QueryDsl};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use lemmy_db_schema_file::schema::person_device_key;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+/// Each user can register this many devices for encrypted private messages.
+pub const MAX_DEVICE_KEYS: usize = 10;
+
+impl PersonDeviceKey {
+  pub async fn create(
+    pool: &mut DbPool<'_>,
+    form: &PersonDeviceKeyInsertForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(person_device_key::table)
+      .values(form)
+      .get_result(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateDeviceKeys)
+  }
+
+  pub async fn list_for_person(
+    pool: &mut DbPool<'_>,
+    for_person_id: PersonId,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    person_device_key::table
+      .filter(person_device_key::person_id.eq(for_person_id))
+      .order_by(person_device_key::id)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Delete a device key, only if it belongs to the given person.
+  pub async fn delete(
+    pool: &mut DbPool<'_>,
+    device_key_id: PersonDeviceKeyId,
+    for_person_id: PersonId,
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    let deleted = delete(
+      person_device_key::table
+        .find(device_key_id)
+        .filter(person_device_key::person_id.eq(for_person_id)),
+    )
+    .execute(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::CouldntUpdateDeviceKeys)?;
+    if deleted == 0 {
+      Err(LemmyErrorType::NotFound)?
+    }
+    Ok(())
+  }
+
+  /// Replace all device keys of a remote person with the ones from its actor.
+  pub async fn replace_for_person(
+    pool: &mut DbPool<'_>,
+    for_person_id: PersonId,
+    forms: &[PersonDeviceKeyInsertForm],
+  ) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    conn
+      .run_transaction(|conn| {
+        async move {
+          delete(person_device_key::table.filter(person_device_key::person_id.eq(for_person_id)))
+            .execute(conn)
+            .await
+            .with_lemmy_type(LemmyErrorType::CouldntUpdateDeviceKeys)?;
+          if forms.is_empty() {
+            return Ok(());
+          }
+          insert_into(person_device_key::table)
+            .values(forms)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await
+            .with_lemmy_type(LemmyErrorType::CouldntUpdateDeviceKeys)?;
+          Ok(())
+        }
+        .scope_boxed()
+      })
+      .await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::{
+    source::{
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use url::Url;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_person_device_keys() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let person_form = PersonInsertForm::test_form(instance.id, "device_key_person");
+    let person = Person::create(pool, &person_form).await?;
+    let other_form = PersonInsertForm::test_form(instance.id, "device_key_other");
+    let other = Person::create(pool, &other_form).await?;
+
+    let key_form = |name: &str| -> LemmyResult<PersonDeviceKeyInsertForm> {
+      let ap_id = Url::parse(&format!("{}#{name}", person.ap_id))?;
+      Ok(PersonDeviceKeyInsertForm::new(
+        person.id,
+        ap_id.into(),
+        format!("{name}-key"),
+      ))
+    };
+    let phone = PersonDeviceKey::create(pool, &key_form("phone")?).await?;
+    PersonDeviceKey::create(pool, &key_form("laptop")?).await?;
+    let keys = PersonDeviceKey::list_for_person(pool, person.id).await?;
+    assert_eq!(2, keys.len());
+
+    // keys can only be deleted by their owner
+    assert!(PersonDeviceKey::delete(pool, phone.id, other.id)
+      .await
+      .is_err());
+    PersonDeviceKey::delete(pool, phone.id, person.id).await?;
+    let keys = PersonDeviceKey::list_for_person(pool, person.id).await?;
+    assert_eq!(
+      vec!["laptop-key".to_string()],
+      keys.into_iter().map(|k| k.public_key).collect::<Vec<_>>()
+    );
+
+    PersonDeviceKey::replace_for_person(pool, person.id, &[key_form("tablet")?]).await?;
+    let keys = PersonDeviceKey::list_for_person(pool, person.id).await?;
+    assert_eq!(
+      vec!["tablet-key".to_string()],
+      keys.into_iter().map(|k| k.public_key).collect::<Vec<_>>()
+    );
+
+    Instance::delete(pool, instance.id).await?;
+    Ok(())
+  }
+}
@@ -13,7 +13,7 @@ use crate::{
   },
   traits::{Crud, Hideable, Likeable, ReadComments, Readable, Saveable},
   utils::{
-    functions::{coalesce, hot_rank, scaled_rank},
+    functions::{coalesce, hot_rank, replace, scaled_rank},
     get_conn,
     now,
     uplete,
@@ -21,7 +21,6 @@ use crate::{
     DbPool,
     DELETED_REPLACEMENT_TEXT,
     FETCH_LIMIT_MAX,
-    SITEMAP_DAYS,
     SITEMAP_LIMIT,
   },
 };
@@ -37,9 +36,14 @@ use diesel::{
   NullableExpressionMethods,
   OptionalExtension,
   QueryDsl,
+  QueryableByName,
+  TextExpressionMethods,
 };
 use diesel_async::RunQueryDsl;
-use lemmy_db_schema_file::schema::{community, person, post, post_actions};
+use lemmy_db_schema_file::{
+  enums::CommunityVisibility,
+  schema::{community, person, post, post_actions},
+};
 use lemmy_utils::{
   error::{LemmyErrorExt, LemmyErrorExt2, LemmyErrorType, LemmyResult},
   settings::structs::Settings,
@@ -117,23 +121,92 @@ impl Post {
       .with_lemmy_type(LemmyErrorType::NotFound)
   }
 
+  /// Local posts published in the given time range which are visible without login, with their
+  /// last modification.
   pub async fn list_for_sitemap(
     pool: &mut DbPool<'_>,
-  ) -> LemmyResult<Vec<(DbUrl, chrono::DateTime<Utc>)>> {
+    published_since: DateTime<Utc>,
+    published_until: DateTime<Utc>,
+    include_nsfw: bool,
+  ) -> LemmyResult<Vec<(DbUrl, DateTime<Utc>)>> {
     let conn = &mut get_conn(pool).await?;
-    post::table
+    let mut query = post::table
+      .inner_join(community::table)
       .select((post::ap_id, coalesce(post::updated_at, post::published_at)))
       .filter(post::local.eq(true))
       .filter(post::deleted.eq(false))
       .filter(post::removed.eq(false))
-      .filter(post::published_at.ge(Utc::now().naive_utc() - SITEMAP_DAYS))
+      .filter(post::scheduled_publish_time_at.is_null())
+      .filter(post::published_at.ge(published_since))
+      .filter(post::published_at.lt(published_until))
+      .filter(community::deleted.eq(false))
+      .filter(community::removed.eq(false))
+      .filter(community::visibility.eq_any([
+        CommunityVisibility::Public,
+        CommunityVisibility::LocalOnlyPublic,
+      ]))
+      .into_boxed();
+    if !include_nsfw {
+      query = query
+        .filter(post::nsfw.eq(false))
+        .filter(community::nsfw.eq(false));
+    }
+    query
       .order(post::published_at.desc())
       .limit(SITEMAP_LIMIT)
-      .load::<(DbUrl, chrono::DateTime<Utc>)>(conn)
+      .load(conn)
       .await
       .with_lemmy_type(LemmyErrorType::NotFound)
   }
 
+  /// Returns each month with posts for the sitemap, along with the newest modification and the
+  /// number of posts in that month. This uses the same filters as [Post::list_for_sitemap], and is
+  /// used to find the sitemaps which need to be generated again.
+  pub async fn list_sitemap_months(
+    pool: &mut DbPool<'_>,
+    include_nsfw: bool,
+  ) -> LemmyResult<Vec<(DateTime<Utc>, DateTime<Utc>, i64)>> {
+    #[derive(QueryableByName)]
+    struct SitemapMonth {
+      #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+      month: DateTime<Utc>,
+      #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+      last_modified_at: DateTime<Utc>,
+      #[diesel(sql_type = diesel::sql_types::BigInt)]
+      post_count: i64,
+    }
+
+    let conn = &mut get_conn(pool).await?;
+    // Diesel doesn't support grouping by an expression
+    let months = diesel::sql_query(
+      "SELECT date_trunc('month', post.published_at, 'UTC') AS month,
+          max(coalesce(post.updated_at, post.published_at)) AS last_modified_at,
+          count(*) AS post_count
+        FROM post
+        JOIN community ON community.id = post.community_id
+        WHERE post.local
+          AND NOT post.deleted
+          AND NOT post.removed
+          AND post.scheduled_publish_time_at IS NULL
+          AND NOT community.deleted
+          AND NOT community.removed
+          AND community.visibility IN ('Public', 'LocalOnlyPublic')
+          AND ($1 OR NOT (post.nsfw OR community.nsfw))
+        GROUP BY 1
+        ORDER BY 1",
+    )
+    .bind::<diesel::sql_types::Bool, _>(include_nsfw)
+    .load::<SitemapMonth>(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)?;
+    Ok(
+      months
+        .into_iter()
+        .map(|m| (m.month, m.last_modified_at, m.post_count))
+        .collect(),
+    )
+  }
+
   pub async fn permadelete_for_creator(
     pool: &mut DbPool<'_>,
     for_creator_id: PersonId,
@@ -238,6 +311,53 @@ impl Post {
       .with_lemmy_type(LemmyErrorType::CouldntUpdatePost)
   }
 
+  /// Replaces the url and thumbnail of the creator's posts which link to `old_url`, eg after an
+  /// uploaded video was transcoded.
+  pub async fn update_url_for_creator(
+    pool: &mut DbPool<'_>,
+    creator_id: PersonId,
+    old_url: &DbUrl,
+    new_url: &DbUrl,
+    new_thumbnail_url: &DbUrl,
+    new_content_type: &str,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    update(post::table)
+      .filter(post::creator_id.eq(creator_id))
+      .filter(post::url.eq(old_url))
+      .set((
+        post::url.eq(new_url),
+        post::thumbnail_url.eq(new_thumbnail_url),
+        post::url_content_type.eq(new_content_type),
+        post::updated_at.eq(Utc::now()),
+      ))
+      .get_results::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdatePost)
+  }
+
+  /// Replaces a link in the bodies of the person's posts.
+  pub async fn replace_url_in_body_for_creator(
+    pool: &mut DbPool<'_>,
+    creator_id: PersonId,
+    old_url: &DbUrl,
+    new_url: &DbUrl,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+
+    update(post::table)
+      .filter(post::creator_id.eq(creator_id))
+      .filter(post::body.like(format!("%{old_url}%")))
+      .set((
+        post::body.eq(replace(post::body, old_url.as_str(), new_url.as_str())),
+        post::updated_at.eq(Utc::now()),
+      ))
+      .get_results::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdatePost)
+  }
+
   pub fn is_post_creator(person_id: PersonId, post_creator_id: PersonId) -> bool {
     person_id == post_creator_id
   }
@@ -572,6 +692,7 @@ impl PostActions {
 #[cfg(test)]
 mod tests {
   use crate::{
+    newtypes::DbUrl,
     source::{
       comment::{Comment, CommentInsertForm, CommentUpdateForm},
       community::{Community, CommunityInsertForm},
@@ -590,7 +711,8 @@ mod tests {
     traits::{Crud, Likeable, Readable, Saveable},
     utils::{build_db_pool_for_tests, uplete, RANK_DEFAULT},
   };
-  use chrono::DateTime;
+  use chrono::{DateTime, TimeDelta, Utc};
+  use lemmy_db_schema_file::enums::CommunityVisibility;
   use lemmy_utils::error::LemmyResult;
   use pretty_assertions::assert_eq;
   use serial_test::serial;
@@ -934,4 +1056,171 @@ mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_list_for_sitemap() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let person = Person::create(pool, &PersonInsertForm::test_form(instance.id, "sitemap")).await?;
+    let community = |name: &str| {
+      CommunityInsertForm::new(
+        instance.id,
+        name.to_string(),
+        name.to_owned(),
+        "pubkey".to_string(),
+      )
+    };
+    let public = Community::create(pool, &community("sitemap_public")).await?;
+    let nsfw_community = CommunityInsertForm {
+      nsfw: Some(true),
+      ..community("sitemap_nsfw")
+    };
+    let nsfw_community = Community::create(pool, &nsfw_community).await?;
+    let private = CommunityInsertForm {
+      visibility: Some(CommunityVisibility::Private),
+      ..community("sitemap_private")
+    };
+    let private = Community::create(pool, &private).await?;
+    let removed_community = CommunityInsertForm {
+      removed: Some(true),
+      ..community("sitemap_removed")
+    };
+    let removed_community = Community::create(pool, &removed_community).await?;
+
+    // All posts are published in February 2001, one hour apart
+    let month = DateTime::parse_from_rfc3339("2001-02-01T00:00:00Z")?.to_utc();
+    let month_end = DateTime::parse_from_rfc3339("2001-03-01T00:00:00Z")?.to_utc();
+    let mut hours = 0;
+    let mut publish = |form: PostInsertForm| {
+      hours += 1;
+      PostInsertForm {
+        published_at: Some(month + TimeDelta::hours(hours)),
+        ..form
+      }
+    };
+    let new_post = |community_id| PostInsertForm::new("sitemap".into(), person.id, community_id);
+    let visible = Post::create(pool, &publish(new_post(public.id))).await?;
+    let nsfw = PostInsertForm {
+      nsfw: Some(true),
+      ..new_post(public.id)
+    };
+    let nsfw = Post::create(pool, &publish(nsfw)).await?;
+    let in_nsfw_community = Post::create(pool, &publish(new_post(nsfw_community.id))).await?;
+    let deleted = PostInsertForm {
+      deleted: Some(true),
+      ..new_post(public.id)
+    };
+    Post::create(pool, &publish(deleted)).await?;
+    let removed = PostInsertForm {
+      removed: Some(true),
+      ..new_post(public.id)
+    };
+    Post::create(pool, &publish(removed)).await?;
+    let scheduled = PostInsertForm {
+      scheduled_publish_time_at: Some(DateTime::from_timestamp_nanos(i64::MAX)),
+      ..new_post(public.id)
+    };
+    Post::create(pool, &publish(scheduled)).await?;
+    Post::create(pool, &publish(new_post(private.id))).await?;
+    Post::create(pool, &publish(new_post(removed_community.id))).await?;
+
+    let ap_ids = |posts: Vec<(DbUrl, DateTime<Utc>)>| {
+      posts
+        .into_iter()
+        .map(|(ap_id, _)| ap_id)
+        .collect::<Vec<_>>()
+    };
+    let sfw = Post::list_for_sitemap(pool, month, month_end, false).await?;
+    assert_eq!(vec![visible.ap_id.clone()], ap_ids(sfw));
+    // Newest first
+    let with_nsfw = Post::list_for_sitemap(pool, month, month_end, true).await?;
+    assert_eq!(
+      vec![in_nsfw_community.ap_id, nsfw.ap_id, visible.ap_id],
+      ap_ids(with_nsfw)
+    );
+    let next_month =
+      Post::list_for_sitemap(pool, month_end, DateTime::<Utc>::MAX_UTC, true).await?;
+    assert_eq!(0, next_month.len());
+
+    let post_count = |months: Vec<(DateTime<Utc>, DateTime<Utc>, i64)>| {
+      months
+        .into_iter()
+        .find(|(m, _, _)| *m == month)
+        .map(|(_, _, count)| count)
+    };
+    assert_eq!(
+      Some(1),
+      post_count(Post::list_sitemap_months(pool, false).await?)
+    );
+    assert_eq!(
+      Some(3),
+      post_count(Post::list_sitemap_months(pool, true).await?)
+    );
+
+    Instance::delete(pool, instance.id).await?;
+
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_replace_url_for_creator() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let person = Person::create(pool, &PersonInsertForm::test_form(instance.id, "video")).await?;
+    let other = Person::create(pool, &PersonInsertForm::test_form(instance.id, "other")).await?;
+    let community = CommunityInsertForm::new(
+      instance.id,
+      "test community_video".to_string(),
+      "nada".to_owned(),
+      "pubkey".to_string(),
+    );
+    let community = Community::create(pool, &community).await?;
+
+    let old_url: DbUrl = Url::parse("https://lemmy-alpha/api/v4/image/old.mkv")?.into();
+    let new_url: DbUrl = Url::parse("https://lemmy-alpha/api/v4/image/new.mp4")?.into();
+    let body = format!("Watch this: ![video]({old_url})");
+    let form = |creator_id| PostInsertForm {
+      url: Some(old_url.clone()),
+      body: Some(body.clone()),
+      ..PostInsertForm::new("video".into(), creator_id, community.id)
+    };
+    let post = Post::create(pool, &form(person.id)).await?;
+    let other_post = Post::create(pool, &form(other.id)).await?;
+    let comment_form = CommentInsertForm::new(person.id, post.id, format!("Same video: {old_url}"));
+    let comment = Comment::create(pool, &comment_form, None).await?;
+
+    let updated =
+      Post::replace_url_in_body_for_creator(pool, person.id, &old_url, &new_url).await?;
+    assert_eq!(
+      vec![post.id],
+      updated.iter().map(|p| p.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      Some(format!("Watch this: ![video]({new_url})")),
+      Post::read(pool, post.id).await?.body
+    );
+    // The url itself is updated separately
+    assert_eq!(Some(old_url.clone()), Post::read(pool, post.id).await?.url);
+    assert_eq!(Some(body), Post::read(pool, other_post.id).await?.body);
+
+    let updated = Comment::replace_url_for_creator(pool, person.id, &old_url, &new_url).await?;
+    assert_eq!(
+      vec![comment.id],
+      updated.iter().map(|c| c.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      format!("Same video: {new_url}"),
+      Comment::read(pool, comment.id).await?.content
+    );
+
+    Instance::delete(pool, instance.id).await?;
+
+    Ok(())
+  }
 }
@@ -0,0 +1,180 @@
+use crate::{
+  newtypes::{PaginationCursor, PersonId, PostId, PostRevisionId},
+  source::{
+    post::Post,
+    post_revision::{post_revision_keys as key, PostRevision, PostRevisionInsertForm},
+  },
+  utils::{get_conn, limit_fetch, paginate, DbPool},
+};
+use diesel::{insert_into, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use i_love_jesus::SortDirection;
+use lemmy_db_schema_file::schema::{post, post_revision};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl PostRevision {
+  pub async fn create(pool: &mut DbPool<'_>, form: &PostRevisionInsertForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(post_revision::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdatePost)
+  }
+
+  /// Stores the previous version of a post, but only if the edit changed its title, url or body.
+  pub async fn create_if_changed(
+    pool: &mut DbPool<'_>,
+    orig_post: &Post,
+    updated_post: &Post,
+    editor_id: PersonId,
+  ) -> LemmyResult<Option<Self>> {
+    if orig_post.name == updated_post.name
+      && orig_post.url == updated_post.url
+      && orig_post.body == updated_post.body
+    {
+      return Ok(None);
+    }
+    let form = PostRevisionInsertForm {
+      post_id: orig_post.id,
+      editor_id,
+      name: orig_post.name.clone(),
+      url: orig_post.url.clone(),
+      body: orig_post.body.clone(),
+    };
+    Ok(Some(Self::create(pool, &form).await?))
+  }
+
+  /// Lists the previous versions of a post, newest first.
+  pub async fn list_for_post(
+    pool: &mut DbPool<'_>,
+    post_id: PostId,
+    cursor_data: Option<PostRevision>,
+    page_back: Option<bool>,
+    limit: Option<i64>,
+  ) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    let limit = limit_fetch(limit)?;
+    let query = post_revision::table
+      .filter(post_revision::post_id.eq(post_id))
+      .limit(limit)
+      .into_boxed();
+    let paginated_query = paginate(query, SortDirection::Desc, cursor_data, None, page_back)
+      .then_order_by(key::published_at)
+      .then_order_by(key::id);
+
+    paginated_query
+      .load::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Removes the edit history of all posts by a given creator. Used when purging their content.
+  pub async fn delete_for_creator(
+    pool: &mut DbPool<'_>,
+    for_creator_id: PersonId,
+  ) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    let creator_post_ids = post::table
+      .filter(post::creator_id.eq(for_creator_id))
+      .select(post::id);
+
+    diesel::delete(post_revision::table.filter(post_revision::post_id.eq_any(creator_post_ids)))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::Deleted)
+  }
+
+  pub fn to_cursor(&self) -> PaginationCursor {
+    PaginationCursor::new_single('V', self.id.0)
+  }
+
+  pub async fn from_cursor(cursor: &PaginationCursor, pool: &mut DbPool<'_>) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    let id = PostRevisionId(cursor.first_id()?);
+    post_revision::table
+      .find(id)
+      .first::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    source::{
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm, PostUpdateForm},
+      post_revision::PostRevision,
+    },
+    traits::Crud,
+    utils::build_db_pool_for_tests,
+  };
+  use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_post_revisions() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+
+    let inserted_instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let new_person = PersonInsertForm::test_form(inserted_instance.id, "revision_poster");
+    let inserted_person = Person::create(pool, &new_person).await?;
+    let new_community = CommunityInsertForm::new(
+      inserted_instance.id,
+      "test community_revisions".to_string(),
+      "nada".to_owned(),
+      "pubkey".to_string(),
+    );
+    let inserted_community = Community::create(pool, &new_community).await?;
+    let new_post = PostInsertForm {
+      body: Some("first body".into()),
+      ..PostInsertForm::new(
+        "first title".into(),
+        inserted_person.id,
+        inserted_community.id,
+      )
+    };
+    let inserted_post = Post::create(pool, &new_post).await?;
+
+    // An edit which doesn't touch the content shouldn't create a revision
+    let nsfw_form = PostUpdateForm {
+      nsfw: Some(true),
+      ..Default::default()
+    };
+    let nsfw_post = Post::update(pool, inserted_post.id, &nsfw_form).await?;
+    let unchanged =
+      PostRevision::create_if_changed(pool, &inserted_post, &nsfw_post, inserted_person.id).await?;
+    assert!(unchanged.is_none());
+
+    let edit_form = PostUpdateForm {
+      name: Some("second title".into()),
+      body: Some(Some("second body".into())),
+      ..Default::default()
+    };
+    let edited_post = Post::update(pool, inserted_post.id, &edit_form).await?;
+    PostRevision::create_if_changed(pool, &nsfw_post, &edited_post, inserted_person.id).await?;
+
+    let revisions = PostRevision::list_for_post(pool, inserted_post.id, None, None, None).await?;
+    assert_eq!(1, revisions.len());
+    let revision = revisions.first().ok_or(LemmyErrorType::NotFound)?;
+    assert_eq!("first title", revision.name);
+    assert_eq!(Some("first body".to_string()), revision.body);
+    assert_eq!(Some(inserted_person.id), revision.editor_id);
+
+    let deleted = PostRevision::delete_for_creator(pool, inserted_person.id).await?;
+    assert_eq!(1, deleted);
+    let revisions = PostRevision::list_for_post(pool, inserted_post.id, None, None, None).await?;
+    assert!(revisions.is_empty());
+
+    Instance::delete(pool, inserted_instance.id).await?;
+
+    Ok(())
+  }
+}
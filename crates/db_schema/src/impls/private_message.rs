@@ -171,6 +171,7 @@ mod tests {
       .into(),
       local: true,
       removed: false,
+      encrypted: false,
     };
 
     let read_private_message = PrivateMessage::read(pool, inserted_private_message.id).await?;
@@ -0,0 +1,106 @@
+use crate::{
+  newtypes::{DbUrl, InstanceId, RelayId},
+  source::relay::{Relay, RelayInsertForm, RelayUpdateForm},
+  utils::{get_conn, DbPool},
+};
+use diesel::{
+  dsl::{insert_into, now},
+  ExpressionMethods,
+  OptionalExtension,
+  QueryDsl,
+};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::relay;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl Relay {
+  pub async fn create(pool: &mut DbPool<'_>, form: &RelayInsertForm) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(relay::table)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntCreateRelay)
+  }
+
+  pub async fn update(
+    pool: &mut DbPool<'_>,
+    relay_id: RelayId,
+    form: &RelayUpdateForm,
+  ) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(relay::table.find(relay_id))
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateRelay)
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, relay_id: RelayId) -> LemmyResult<Self> {
+    let conn = &mut get_conn(pool).await?;
+    relay::table
+      .find(relay_id)
+      .first(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn read_from_ap_id(pool: &mut DbPool<'_>, ap_id: &DbUrl) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    relay::table
+      .filter(relay::ap_id.eq(ap_id))
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, relay_id: RelayId) -> LemmyResult<usize> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(relay::table.find(relay_id))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn list(pool: &mut DbPool<'_>) -> LemmyResult<Vec<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    relay::table
+      .order_by(relay::published_at.desc())
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Updates the statistics after an activity was received through the relay.
+  pub async fn mark_received(pool: &mut DbPool<'_>, relay_id: RelayId) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(relay::table.find(relay_id))
+      .set((
+        relay::received_count.eq(relay::received_count + 1),
+        relay::last_received_at.eq(now),
+      ))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateRelay)?;
+    Ok(())
+  }
+
+  /// Inbox of a relay on the given instance which local community activities should be sent to,
+  /// if any.
+  pub async fn read_publish_inbox(
+    pool: &mut DbPool<'_>,
+    instance_id: InstanceId,
+  ) -> LemmyResult<Option<DbUrl>> {
+    let conn = &mut get_conn(pool).await?;
+    relay::table
+      .filter(relay::instance_id.eq(instance_id))
+      .filter(relay::follow_accepted)
+      .filter(relay::publish)
+      .select(relay::inbox_url)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+}
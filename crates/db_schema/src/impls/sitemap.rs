@@ -0,0 +1,101 @@
+use crate::{
+  diesel::OptionalExtension,
+  source::sitemap::{Sitemap, SitemapForm},
+  utils::{get_conn, now, DbPool},
+};
+use chrono::{DateTime, Utc};
+use diesel::{delete, insert_into, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use lemmy_db_schema_file::schema::sitemap;
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+
+impl Sitemap {
+  pub async fn upsert(pool: &mut DbPool<'_>, form: &SitemapForm) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    insert_into(sitemap::table)
+      .values(form)
+      .on_conflict(sitemap::name)
+      .do_update()
+      .set((form, sitemap::updated_at.eq(now())))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateSitemap)?;
+    Ok(())
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, name: &str) -> LemmyResult<Option<Self>> {
+    let conn = &mut get_conn(pool).await?;
+    sitemap::table
+      .find(name)
+      .first(conn)
+      .await
+      .optional()
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  /// Returns name, url count and last modification of all sitemaps, without their content.
+  pub async fn list(pool: &mut DbPool<'_>) -> LemmyResult<Vec<(String, i32, DateTime<Utc>)>> {
+    let conn = &mut get_conn(pool).await?;
+    sitemap::table
+      .select((sitemap::name, sitemap::url_count, sitemap::last_modified_at))
+      .order_by(sitemap::name)
+      .load(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::NotFound)
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, names: &[String]) -> LemmyResult<()> {
+    let conn = &mut get_conn(pool).await?;
+    delete(sitemap::table.filter(sitemap::name.eq_any(names)))
+      .execute(conn)
+      .await
+      .with_lemmy_type(LemmyErrorType::CouldntUpdateSitemap)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+
+  use super::*;
+  use crate::utils::build_db_pool_for_tests;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_sitemap() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let last_modified_at = Utc::now();
+
+    let form = SitemapForm {
+      name: "posts-2025-07".to_string(),
+      content: "<urlset></urlset>".to_string(),
+      url_count: 0,
+      last_modified_at,
+    };
+    Sitemap::upsert(pool, &form).await?;
+    let form = SitemapForm {
+      content: "<urlset><url></url></urlset>".to_string(),
+      url_count: 1,
+      ..form
+    };
+    Sitemap::upsert(pool, &form).await?;
+
+    let sitemap = Sitemap::read(pool, "posts-2025-07").await?;
+    assert_eq!(Some(form.content.clone()), sitemap.map(|s| s.content));
+    assert_eq!(None, Sitemap::read(pool, "posts-2025-06").await?);
+
+    let list = Sitemap::list(pool).await?;
+    assert_eq!(1, list.len());
+    assert_eq!(
+      Some((form.name.as_str(), 1)),
+      list.first().map(|s| (s.0.as_str(), s.1))
+    );
+
+    Sitemap::delete(pool, &[form.name]).await?;
+    assert!(Sitemap::list(pool).await?.is_empty());
+    Ok(())
+  }
+}
@@ -111,6 +111,7 @@ pub enum ModlogActionType {
   AdminPurgeComment,
   AdminBlockInstance,
   AdminAllowInstance,
+  AdminImageModeration,
 }
 
 #[derive(EnumString, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
@@ -169,6 +169,11 @@ pub struct InstanceId(pub i32);
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
 pub struct ActivityId(pub i64);
 
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+/// The id of a received activity in the incoming queue.
+pub struct ReceivedActivityId(pub i64);
+
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
 #[cfg_attr(feature = "full", derive(DieselNewType))]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -190,6 +195,27 @@ pub struct CustomEmojiId(i32);
 /// The tagline id.
 pub struct TaglineId(pub i32);
 
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The id of an entry in the community directory.
+pub struct CommunityDirectoryId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The id of a feed which is imported into a community.
+pub struct CommunityFeedId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The id of a device key.
+pub struct PersonDeviceKeyId(pub i32);
+
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
 #[cfg_attr(feature = "full", derive(DieselNewType))]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -204,6 +230,73 @@ pub struct RegistrationApplicationId(pub i32);
 /// The oauth provider id.
 pub struct OAuthProviderId(pub i32);
 
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+/// The id of a client application registered through the Mastodon-compatible API.
+pub struct OAuthClientId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The relay id.
+pub struct RelayId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The id of a blocked image hash.
+pub struct ImageHashBlockId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct AdminImageModerationId(pub i32);
+
+/// A 64-bit perceptual hash of an image. It is stored as bigint, and exposed in the api as a
+/// 16 character hex string because javascript can't represent the full range of i64.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[serde(try_from = "String", into = "String")]
+pub struct ImageHash(pub i64);
+
+impl ImageHash {
+  pub fn from_bits(bits: u64) -> Self {
+    Self(i64::from_be_bytes(bits.to_be_bytes()))
+  }
+
+  pub fn bits(&self) -> u64 {
+    u64::from_be_bytes(self.0.to_be_bytes())
+  }
+
+  /// Number of bits which differ between the two hashes.
+  pub fn distance(&self, other: &ImageHash) -> u32 {
+    (self.bits() ^ other.bits()).count_ones()
+  }
+}
+
+impl Display for ImageHash {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{:016x}", self.bits())
+  }
+}
+
+impl TryFrom<String> for ImageHash {
+  type Error = std::num::ParseIntError;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    u64::from_str_radix(&value, 16).map(Self::from_bits)
+  }
+}
+
+impl From<ImageHash> for String {
+  fn from(value: ImageHash) -> Self {
+    value.to_string()
+  }
+}
+
 #[cfg(feature = "full")]
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "Ltree")]
@@ -362,6 +455,20 @@ pub struct ModAddId(pub i32);
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
 pub struct MultiCommunityId(pub i32);
 
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The post revision id.
+pub struct PostRevisionId(pub i32);
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "full", derive(DieselNewType))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The comment revision id.
+pub struct CommentRevisionId(pub i32);
+
 impl DbUrl {
   pub fn inner(&self) -> &Url {
     &self.0
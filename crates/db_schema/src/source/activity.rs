@@ -1,4 +1,4 @@
-use crate::newtypes::{ActivityId, CommunityId, DbUrl};
+use crate::newtypes::{ActivityId, CommunityId, DbUrl, ReceivedActivityId};
 use chrono::{DateTime, Utc};
 use diesel::Queryable;
 use lemmy_db_schema_file::{
@@ -90,4 +90,24 @@ pub struct SentActivityForm {
 pub struct ReceivedActivity {
   pub ap_id: DbUrl,
   pub published_at: DateTime<Utc>,
+  pub id: ReceivedActivityId,
+  /// The raw activity, only set while it is queued for processing or dead-lettered.
+  pub data: Option<Value>,
+  pub actor_ap_id: Option<DbUrl>,
+  pub attempts: i32,
+  pub next_attempt_at: Option<DateTime<Utc>>,
+  pub last_error: Option<String>,
+  pub processed_at: Option<DateTime<Utc>>,
+  /// Set when processing failed too often, the activity is kept for inspection until
+  /// `dead_letter_retention_days` have passed.
+  pub dead_lettered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = received_activity))]
+pub struct ReceivedActivityForm {
+  pub ap_id: DbUrl,
+  pub data: Value,
+  pub actor_ap_id: DbUrl,
 }
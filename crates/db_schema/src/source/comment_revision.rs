@@ -0,0 +1,35 @@
+use crate::newtypes::{CommentId, CommentRevisionId, PersonId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "full")]
+use {i_love_jesus::CursorKeysModule, lemmy_db_schema_file::schema::comment_revision};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(
+  feature = "full",
+  derive(Queryable, Selectable, Identifiable, CursorKeysModule)
+)]
+#[cfg_attr(feature = "full", diesel(table_name = comment_revision))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "full", cursor_keys_module(name = comment_revision_keys))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// A previous version of a comment, saved whenever the comment is edited.
+pub struct CommentRevision {
+  pub id: CommentRevisionId,
+  pub comment_id: CommentId,
+  /// The person who made the edit which replaced this version. Empty if their account was deleted.
+  pub editor_id: Option<PersonId>,
+  pub content: String,
+  /// When this version was replaced.
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Clone, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = comment_revision))]
+pub struct CommentRevisionInsertForm {
+  pub comment_id: CommentId,
+  pub editor_id: PersonId,
+  pub content: String,
+}
@@ -0,0 +1,27 @@
+use crate::newtypes::CommunityId;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::community_backfill;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = community_backfill))]
+#[cfg_attr(feature = "full", diesel(primary_key(community_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Progress of fetching older posts and comments of a remote community. This starts when the
+/// first local user follows the community.
+pub struct CommunityBackfill {
+  pub community_id: CommunityId,
+  pub posts_fetched: i32,
+  pub comments_fetched: i32,
+  /// Set if the backfill was aborted.
+  pub error: Option<String>,
+  pub published_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+  pub finished_at: Option<DateTime<Utc>>,
+}
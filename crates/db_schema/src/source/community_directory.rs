@@ -0,0 +1,51 @@
+use crate::newtypes::{CommunityDirectoryId, DbUrl, InstanceId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "full")]
+use {i_love_jesus::CursorKeysModule, lemmy_db_schema_file::schema::community_directory};
+
+#[skip_serializing_none]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+  feature = "full",
+  derive(Queryable, Selectable, Identifiable, CursorKeysModule)
+)]
+#[cfg_attr(feature = "full", diesel(table_name = community_directory))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "full", cursor_keys_module(name = community_directory_keys))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// A community on another instance, as listed by the API of that instance. These entries are
+/// collected by a background crawler and are not full community actors. Use `ResolveObject` with
+/// the `ap_id` to fetch the actual community.
+pub struct CommunityDirectoryEntry {
+  pub id: CommunityDirectoryId,
+  pub instance_id: InstanceId,
+  pub ap_id: DbUrl,
+  pub name: String,
+  pub title: String,
+  pub description: Option<String>,
+  pub icon: Option<DbUrl>,
+  pub nsfw: bool,
+  pub subscribers: i32,
+  pub users_active_month: i32,
+  pub published_at: DateTime<Utc>,
+  /// When the community was last seen by the crawler.
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = community_directory))]
+pub struct CommunityDirectoryEntryForm {
+  pub instance_id: InstanceId,
+  pub ap_id: DbUrl,
+  pub name: String,
+  pub title: String,
+  pub description: Option<String>,
+  pub icon: Option<DbUrl>,
+  pub nsfw: bool,
+  pub subscribers: i32,
+  pub users_active_month: i32,
+}
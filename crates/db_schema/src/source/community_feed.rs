@@ -0,0 +1,62 @@
+use crate::newtypes::{CommunityFeedId, CommunityId, DbUrl, LanguageId, PersonId, TagId};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::community_feed;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = community_feed))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// An external RSS or Atom feed, which is registered by the community moderators. New items of
+/// the feed are regularly posted to the community by a bot account.
+pub struct CommunityFeed {
+  pub id: CommunityFeedId,
+  pub community_id: CommunityId,
+  pub url: DbUrl,
+  /// The local bot account which creates the posts. It needs to be a moderator of the community.
+  pub bot_person_id: PersonId,
+  /// Only import items whose title matches this case-insensitive regex.
+  pub title_filter: Option<String>,
+  /// Tag which is added to the imported posts.
+  pub tag_id: Option<TagId>,
+  pub language_id: Option<LanguageId>,
+  pub last_fetched_at: Option<DateTime<Utc>>,
+  /// Set if the last fetch failed.
+  pub last_error: Option<String>,
+  pub published_at: DateTime<Utc>,
+  pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = community_feed))]
+pub struct CommunityFeedInsertForm {
+  pub community_id: CommunityId,
+  pub url: DbUrl,
+  pub bot_person_id: PersonId,
+  #[new(default)]
+  pub title_filter: Option<String>,
+  #[new(default)]
+  pub tag_id: Option<TagId>,
+  #[new(default)]
+  pub language_id: Option<LanguageId>,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "full", derive(AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = community_feed))]
+pub struct CommunityFeedUpdateForm {
+  pub url: Option<DbUrl>,
+  pub bot_person_id: Option<PersonId>,
+  pub title_filter: Option<Option<String>>,
+  pub tag_id: Option<Option<TagId>>,
+  pub language_id: Option<Option<LanguageId>>,
+  pub last_fetched_at: Option<Option<DateTime<Utc>>>,
+  pub last_error: Option<Option<String>>,
+  pub updated_at: Option<Option<DateTime<Utc>>>,
+}
@@ -0,0 +1,40 @@
+use crate::newtypes::{ActivityId, InstanceId};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::federation_queue_control;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = federation_queue_control))]
+#[cfg_attr(feature = "full", diesel(primary_key(instance_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Changes to the outgoing federation queue of an instance, requested by admins.
+pub struct FederationQueueControl {
+  pub instance_id: InstanceId,
+  /// Don't send any activities to this instance until resumed.
+  pub paused: bool,
+  /// Skip all pending activities and continue with the newest one.
+  pub skip_backlog: bool,
+  /// Retry sending immediately instead of waiting for the retry delay.
+  pub reset_fail_count: bool,
+  /// Send this activity to the instance again.
+  pub resend_activity_id: Option<ActivityId>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = federation_queue_control))]
+pub struct FederationQueueControlForm {
+  pub instance_id: InstanceId,
+  pub paused: Option<bool>,
+  pub skip_backlog: Option<bool>,
+  pub reset_fail_count: Option<bool>,
+  pub resend_activity_id: Option<Option<ActivityId>>,
+  pub updated_at: Option<DateTime<Utc>>,
+}
@@ -24,4 +24,25 @@ pub struct FederationQueueState {
   pub fail_count: i32,
   /// timestamp of the last retry attempt (when the last failing activity was resent)
   pub last_retry_at: Option<DateTime<Utc>>,
+  /// the error message of the last failed send attempt
+  pub last_error: Option<String>,
+  pub last_error_at: Option<DateTime<Utc>>,
+  /// when the current streak of failed send attempts started, empty if the last attempt succeeded
+  pub failing_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+/// How well sending activities to an instance works.
+pub enum InstanceHealth {
+  /// The last activity was sent successfully.
+  Healthy,
+  /// Sending has been failing for a short time.
+  Degraded,
+  /// Sending has been failing for more than an hour.
+  Unreachable,
+  /// Sending has been failing for longer than the configured period, so no more activities are
+  /// sent until the instance is reachable again.
+  Dead,
 }
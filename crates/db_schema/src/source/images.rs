@@ -1,4 +1,4 @@
-use crate::newtypes::{DbUrl, PersonId, PostId};
+use crate::newtypes::{DbUrl, ImageHash, ImageHashBlockId, PersonId, PostId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -6,7 +6,13 @@ use std::fmt::Debug;
 #[cfg(feature = "full")]
 use {
   i_love_jesus::CursorKeysModule,
-  lemmy_db_schema_file::schema::{image_details, local_image, remote_image},
+  lemmy_db_schema_file::schema::{
+    image_details,
+    image_hash_block,
+    image_redirect,
+    local_image,
+    remote_image,
+  },
 };
 
 #[skip_serializing_none]
@@ -28,6 +34,8 @@ pub struct LocalImage {
   pub person_id: Option<PersonId>,
   /// This means the image is an auto-generated thumbnail, for a post.
   pub thumbnail_for_post_id: Option<PostId>,
+  /// Size of the stored file in bytes, counted towards the upload quota of the person.
+  pub file_size: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +45,28 @@ pub struct LocalImageForm {
   pub pictrs_alias: String,
   pub person_id: PersonId,
   pub thumbnail_for_post_id: Option<Option<PostId>>,
+  pub file_size: i64,
+}
+
+/// An upload which was replaced by another one, like a video after transcoding. Requests for the
+/// old alias are redirected to the new one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = image_redirect))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "full", diesel(primary_key(alias)))]
+pub struct ImageRedirect {
+  pub alias: String,
+  pub target_alias: String,
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = image_redirect))]
+pub struct ImageRedirectForm {
+  pub alias: String,
+  pub target_alias: String,
 }
 
 /// Stores all images which are hosted on remote domains. When attempting to proxy an image, it
@@ -50,6 +80,29 @@ pub struct LocalImageForm {
 pub struct RemoteImage {
   pub link: DbUrl,
   pub published_at: DateTime<Utc>,
+  /// When the image was last requested through the image proxy.
+  pub last_accessed_at: DateTime<Utc>,
+  /// Whether the original is currently in the image proxy cache.
+  pub cached: bool,
+  /// Size of the cached original, in bytes. Empty if the image is not cached, or if it was cached
+  /// before sizes were tracked.
+  pub file_size: Option<i64>,
+}
+
+/// Statistics about the image proxy cache.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct RemoteImageCacheStats {
+  /// Number of remote images which may be proxied.
+  pub image_count: i64,
+  /// Number of images which are currently cached.
+  pub cached_count: i64,
+  /// Total size of the cached images, in bytes. Images with unknown size aren't counted.
+  pub total_size: i64,
+  /// Last access of the least recently requested image in the cache.
+  pub oldest_access: Option<DateTime<Utc>>,
 }
 
 #[skip_serializing_none]
@@ -66,6 +119,18 @@ pub struct ImageDetails {
   pub height: i32,
   pub content_type: String,
   pub blurhash: Option<String>,
+  /// For videos, the duration in seconds.
+  pub duration: Option<i32>,
+  /// For videos, a still frame which is used as thumbnail.
+  pub poster_url: Option<DbUrl>,
+  /// Perceptual hash which is compared against the image hash blocklist.
+  #[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+  pub perceptual_hash: Option<ImageHash>,
+  /// Set when the image classifier labeled the image as NSFW.
+  pub nsfw: bool,
+  /// Set when the image classifier quarantined the upload. Only admins can view it until one of
+  /// them approves it.
+  pub quarantined: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -77,4 +142,40 @@ pub struct ImageDetailsInsertForm {
   pub height: i32,
   pub content_type: String,
   pub blurhash: Option<String>,
+  /// For videos, the duration in seconds.
+  pub duration: Option<i32>,
+  /// For videos, a still frame which is used as thumbnail.
+  pub poster_url: Option<DbUrl>,
+  /// Perceptual hash which is compared against the image hash blocklist.
+  pub perceptual_hash: Option<ImageHash>,
+  /// Set when the image classifier labeled the image as NSFW.
+  pub nsfw: bool,
+  /// Set when the image classifier quarantined the upload. Only admins can view it until one of
+  /// them approves it.
+  pub quarantined: bool,
+}
+
+/// Perceptual hash of a known-bad image. Uploads and proxied images which are similar to it are
+/// rejected.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = image_hash_block))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ImageHashBlock {
+  pub id: ImageHashBlockId,
+  #[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+  pub hash: ImageHash,
+  pub reason: Option<String>,
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = image_hash_block))]
+pub struct ImageHashBlockForm {
+  pub hash: ImageHash,
+  pub reason: Option<String>,
 }
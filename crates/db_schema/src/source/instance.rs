@@ -1,5 +1,6 @@
 use crate::newtypes::{InstanceId, PersonId};
 use chrono::{DateTime, Utc};
+use lemmy_db_schema_file::enums::SignatureFormat;
 #[cfg(feature = "full")]
 use lemmy_db_schema_file::schema::{instance, instance_actions};
 use serde::{Deserialize, Serialize};
@@ -25,6 +26,13 @@ pub struct Instance {
   pub software: Option<String>,
   /// The version of the instance's software.
   pub version: Option<String>,
+  /// Signature format which is used to send activities to the instance. Switches to RFC 9421 once
+  /// we receive a request signed this way, and back if the instance rejects it.
+  pub signature_format: SignatureFormat,
+  /// When the instance last rejected an activity with RFC 9421 signature which it accepted with
+  /// draft-cavage signature. Until this is some time ago, incoming requests don't switch the
+  /// format back to RFC 9421.
+  pub rfc9421_rejected_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, derive_new::new)]
@@ -38,6 +46,8 @@ pub struct InstanceForm {
   pub version: Option<String>,
   #[new(default)]
   pub updated_at: Option<DateTime<Utc>>,
+  #[new(default)]
+  pub signature_format: Option<SignatureFormat>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -0,0 +1,68 @@
+use crate::newtypes::InstanceId;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::instance_policy;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = instance_policy))]
+#[cfg_attr(feature = "full", diesel(primary_key(instance_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Rules which are applied to content and activities received from an instance.
+pub struct InstancePolicy {
+  pub instance_id: InstanceId,
+  /// Don't store images or videos from this instance.
+  pub reject_media: bool,
+  /// Mark all posts and communities from this instance as NSFW.
+  pub force_nsfw: bool,
+  /// Ignore votes from this instance.
+  pub strip_votes: bool,
+  /// Hide communities, posts and comments from this instance in the All listing. They are still
+  /// shown to those who subscribe to the community or follow the creator.
+  pub silence: bool,
+  /// Ignore reports from this instance.
+  pub reject_reports: bool,
+  /// Newly discovered communities from this instance are removed until an admin restores them.
+  pub quarantine_communities: bool,
+  /// Links starting with this text are rewritten to start with `rewrite_links_to` instead.
+  pub rewrite_links_from: Option<String>,
+  pub rewrite_links_to: Option<String>,
+  pub reason: Option<String>,
+  pub published_at: DateTime<Utc>,
+  pub updated_at: Option<DateTime<Utc>>,
+  /// Images from this instance are never stored in the image proxy cache, clients are redirected
+  /// to the original instead.
+  pub never_cache_media: bool,
+}
+
+#[derive(Clone, Debug, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = instance_policy))]
+pub struct InstancePolicyForm {
+  pub instance_id: InstanceId,
+  #[new(default)]
+  pub reject_media: bool,
+  #[new(default)]
+  pub force_nsfw: bool,
+  #[new(default)]
+  pub strip_votes: bool,
+  #[new(default)]
+  pub silence: bool,
+  #[new(default)]
+  pub reject_reports: bool,
+  #[new(default)]
+  pub quarantine_communities: bool,
+  #[new(default)]
+  pub rewrite_links_from: Option<String>,
+  #[new(default)]
+  pub rewrite_links_to: Option<String>,
+  #[new(default)]
+  pub reason: Option<String>,
+  #[new(default)]
+  pub never_cache_media: bool,
+}
@@ -96,6 +96,27 @@ pub struct LocalSite {
   pub disable_email_notifications: bool,
   pub suggested_communities: Option<MultiCommunityId>,
   pub multi_comm_follower: PersonId,
+  /// Whether post and comment edit history can be viewed by anyone, instead of only the author,
+  /// moderators and admins.
+  pub edit_history_public: bool,
+  /// Whether ActivityPub objects can only be fetched with a valid HTTP signature from an instance
+  /// which is allowed to federate with us.
+  pub federation_authorized_fetch: bool,
+  /// Whether a background task collects communities from other instances for the community
+  /// directory.
+  pub community_directory_enabled: bool,
+  /// Maximum size of all media uploaded by a user, in megabytes. Unlimited if empty.
+  pub media_quota_mb: Option<i32>,
+  /// Upload quota for accounts which are younger than `new_account_media_quota_days`, in
+  /// megabytes.
+  pub new_account_media_quota_mb: Option<i32>,
+  pub new_account_media_quota_days: i32,
+  /// Proxied images which haven't been requested for this many days are removed from the cache.
+  /// Unlimited if empty.
+  pub image_proxy_max_age_days: Option<i32>,
+  /// Maximum size of the image proxy cache, in megabytes. When it is exceeded, the least recently
+  /// requested images are removed. Unlimited if empty.
+  pub image_proxy_max_cache_mb: Option<i32>,
 }
 
 #[derive(Clone, derive_new::new)]
@@ -163,6 +184,22 @@ pub struct LocalSiteInsertForm {
   pub suggested_communities: Option<MultiCommunityId>,
   #[new(default)]
   pub multi_comm_follower: Option<PersonId>,
+  #[new(default)]
+  pub edit_history_public: Option<bool>,
+  #[new(default)]
+  pub federation_authorized_fetch: Option<bool>,
+  #[new(default)]
+  pub community_directory_enabled: Option<bool>,
+  #[new(default)]
+  pub media_quota_mb: Option<i32>,
+  #[new(default)]
+  pub new_account_media_quota_mb: Option<i32>,
+  #[new(default)]
+  pub new_account_media_quota_days: Option<i32>,
+  #[new(default)]
+  pub image_proxy_max_age_days: Option<i32>,
+  #[new(default)]
+  pub image_proxy_max_cache_mb: Option<i32>,
 }
 
 #[derive(Clone, Default)]
@@ -199,4 +236,12 @@ pub struct LocalSiteUpdateForm {
   pub disallow_nsfw_content: Option<bool>,
   pub disable_email_notifications: Option<bool>,
   pub suggested_communities: Option<MultiCommunityId>,
+  pub edit_history_public: Option<bool>,
+  pub federation_authorized_fetch: Option<bool>,
+  pub community_directory_enabled: Option<bool>,
+  pub media_quota_mb: Option<Option<i32>>,
+  pub new_account_media_quota_mb: Option<Option<i32>>,
+  pub new_account_media_quota_days: Option<i32>,
+  pub image_proxy_max_age_days: Option<Option<i32>>,
+  pub image_proxy_max_cache_mb: Option<Option<i32>>,
 }
@@ -84,6 +84,10 @@ pub struct LocalUser {
   pub show_downvotes: VoteShow,
   pub show_upvote_percentage: bool,
   pub show_person_votes: bool,
+  /// Whether to hide the lists of people you follow and who follow you from others.
+  pub hide_follows: bool,
+  /// Overrides the media upload quota of the site for this user, in megabytes. 0 means unlimited.
+  pub media_quota_mb: Option<i32>,
 }
 
 #[derive(Clone, derive_new::new)]
@@ -158,6 +162,10 @@ pub struct LocalUserInsertForm {
   pub show_upvote_percentage: Option<bool>,
   #[new(default)]
   pub show_person_votes: Option<bool>,
+  #[new(default)]
+  pub hide_follows: Option<bool>,
+  #[new(default)]
+  pub media_quota_mb: Option<i32>,
 }
 
 #[derive(Clone, Default)]
@@ -198,4 +206,6 @@ pub struct LocalUserUpdateForm {
   pub show_downvotes: Option<VoteShow>,
   pub show_upvote_percentage: Option<bool>,
   pub show_person_votes: Option<bool>,
+  pub hide_follows: Option<bool>,
+  pub media_quota_mb: Option<Option<i32>>,
 }
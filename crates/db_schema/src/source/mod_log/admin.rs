@@ -1,20 +1,25 @@
 use crate::newtypes::{
   AdminAllowInstanceId,
   AdminBlockInstanceId,
+  AdminImageModerationId,
   AdminPurgeCommentId,
   AdminPurgeCommunityId,
   AdminPurgePersonId,
   AdminPurgePostId,
   CommunityId,
+  DbUrl,
+  ImageHash,
   InstanceId,
   PersonId,
   PostId,
 };
 use chrono::{DateTime, Utc};
+use lemmy_db_schema_file::enums::ImageModerationAction;
 #[cfg(feature = "full")]
 use lemmy_db_schema_file::schema::{
   admin_allow_instance,
   admin_block_instance,
+  admin_image_moderation,
   admin_purge_comment,
   admin_purge_community,
   admin_purge_person,
@@ -181,3 +186,37 @@ pub struct AdminBlockInstanceForm {
   pub blocked: bool,
   pub reason: Option<String>,
 }
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = admin_image_moderation))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// When an image is blocked, rejected, purged or marked as NSFW. The admin is empty for actions
+/// which were taken automatically.
+pub struct AdminImageModeration {
+  pub id: AdminImageModerationId,
+  pub admin_person_id: Option<PersonId>,
+  /// The uploader of the image, if it is a local upload.
+  pub person_id: Option<PersonId>,
+  pub action: ImageModerationAction,
+  #[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+  pub hash: Option<ImageHash>,
+  pub image_url: Option<DbUrl>,
+  pub reason: Option<String>,
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = admin_image_moderation))]
+pub struct AdminImageModerationForm {
+  pub admin_person_id: Option<PersonId>,
+  pub person_id: Option<PersonId>,
+  pub action: ImageModerationAction,
+  pub hash: Option<ImageHash>,
+  pub image_url: Option<DbUrl>,
+  pub reason: Option<String>,
+}
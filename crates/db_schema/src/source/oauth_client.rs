@@ -0,0 +1,41 @@
+use crate::{newtypes::OAuthClientId, sensitive::SensitiveString};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::oauth_client;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = oauth_client))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// A client application (for example a Mastodon app) which can obtain access tokens for users of
+/// this instance.
+pub struct OAuthClient {
+  pub id: OAuthClientId,
+  pub name: String,
+  pub website: Option<String>,
+  /// Whitespace separated list of allowed redirect uris
+  pub redirect_uris: String,
+  /// Whitespace separated list of granted scopes
+  pub scopes: String,
+  pub client_id: String,
+  /// SHA-256 of the client secret, hex encoded
+  #[serde(skip)]
+  pub client_secret_hash: SensitiveString,
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = oauth_client))]
+pub struct OAuthClientInsertForm {
+  pub name: String,
+  pub redirect_uris: String,
+  pub scopes: String,
+  pub client_id: String,
+  pub client_secret_hash: String,
+  #[new(default)]
+  pub website: Option<String>,
+}
@@ -138,9 +138,9 @@ pub struct PersonActions {
   pub target_id: PersonId,
   #[serde(skip)]
   pub person_id: PersonId,
-  #[serde(skip)]
+  /// When the person was followed.
   pub followed_at: Option<DateTime<Utc>>,
-  #[serde(skip)]
+  /// True if the follow of a remote person wasn't accepted yet.
   pub follow_pending: Option<bool>,
   /// When the person was blocked.
   pub blocked_at: Option<DateTime<Utc>>,
@@ -0,0 +1,37 @@
+use crate::newtypes::{DbUrl, PersonDeviceKeyId, PersonId};
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::person_device_key;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = person_device_key))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The public key of a user device, which clients use to encrypt private messages to that user.
+pub struct PersonDeviceKey {
+  pub id: PersonDeviceKeyId,
+  pub person_id: PersonId,
+  pub ap_id: DbUrl,
+  pub device_name: Option<String>,
+  /// Base64 encoded MLS key package
+  pub public_key: String,
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = person_device_key))]
+pub struct PersonDeviceKeyInsertForm {
+  pub person_id: PersonId,
+  pub ap_id: DbUrl,
+  pub public_key: String,
+  #[new(default)]
+  pub device_name: Option<String>,
+  #[new(default)]
+  pub published_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,41 @@
+use crate::newtypes::{DbUrl, PersonId, PostId, PostRevisionId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "full")]
+use {i_love_jesus::CursorKeysModule, lemmy_db_schema_file::schema::post_revision};
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(
+  feature = "full",
+  derive(Queryable, Selectable, Identifiable, CursorKeysModule)
+)]
+#[cfg_attr(feature = "full", diesel(table_name = post_revision))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "full", cursor_keys_module(name = post_revision_keys))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// A previous version of a post, saved whenever the post is edited.
+pub struct PostRevision {
+  pub id: PostRevisionId,
+  pub post_id: PostId,
+  /// The person who made the edit which replaced this version. Empty if their account was deleted.
+  pub editor_id: Option<PersonId>,
+  pub name: String,
+  pub url: Option<DbUrl>,
+  pub body: Option<String>,
+  /// When this version was replaced.
+  pub published_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "full", derive(Insertable))]
+#[cfg_attr(feature = "full", diesel(table_name = post_revision))]
+pub struct PostRevisionInsertForm {
+  pub post_id: PostId,
+  pub editor_id: PersonId,
+  pub name: String,
+  pub url: Option<DbUrl>,
+  pub body: Option<String>,
+}
@@ -32,6 +32,9 @@ pub struct PrivateMessage {
   pub ap_id: DbUrl,
   pub local: bool,
   pub removed: bool,
+  /// The content is encrypted by the client to the device keys of the recipient, and can't be
+  /// read by the server.
+  pub encrypted: bool,
 }
 
 #[derive(Clone, derive_new::new)]
@@ -56,6 +59,8 @@ pub struct PrivateMessageInsertForm {
   pub ap_id: Option<DbUrl>,
   #[new(default)]
   pub local: Option<bool>,
+  #[new(default)]
+  pub encrypted: Option<bool>,
 }
 
 #[derive(Clone, Default)]
@@ -70,4 +75,5 @@ pub struct PrivateMessageUpdateForm {
   pub ap_id: Option<DbUrl>,
   pub local: Option<bool>,
   pub removed: Option<bool>,
+  pub encrypted: Option<bool>,
 }
@@ -0,0 +1,60 @@
+use crate::newtypes::{DbUrl, InstanceId, RelayId};
+use chrono::{DateTime, Utc};
+use lemmy_db_schema_file::enums::RelayType;
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::relay;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = relay))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// An ActivityPub relay which the site actor is subscribed to. Relays forward public posts
+/// between instances which would otherwise not know about each other.
+pub struct Relay {
+  pub id: RelayId,
+  /// The id of the relay actor.
+  pub ap_id: DbUrl,
+  pub inbox_url: DbUrl,
+  #[serde(skip)]
+  pub public_key: String,
+  pub instance_id: InstanceId,
+  pub relay_type: RelayType,
+  /// Whether the relay has accepted the follow of the site actor.
+  pub follow_accepted: bool,
+  /// Whether activities of local public communities are sent to the relay.
+  pub publish: bool,
+  /// The number of activities received through the relay.
+  pub received_count: i64,
+  pub last_received_at: Option<DateTime<Utc>>,
+  pub published_at: DateTime<Utc>,
+  pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, derive_new::new)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = relay))]
+pub struct RelayInsertForm {
+  pub ap_id: DbUrl,
+  pub inbox_url: DbUrl,
+  pub public_key: String,
+  pub instance_id: InstanceId,
+  pub relay_type: RelayType,
+  #[new(default)]
+  pub publish: Option<bool>,
+}
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "full", derive(AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = relay))]
+pub struct RelayUpdateForm {
+  pub inbox_url: Option<DbUrl>,
+  pub public_key: Option<String>,
+  pub follow_accepted: Option<bool>,
+  pub publish: Option<bool>,
+  pub updated_at: Option<Option<DateTime<Utc>>>,
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+#[cfg(feature = "full")]
+use lemmy_db_schema_file::schema::sitemap;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = sitemap))]
+#[cfg_attr(feature = "full", diesel(primary_key(name)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// A generated sitemap file, which is listed in the sitemap index. Posts are split into one
+/// sitemap per month, so that only months with changed posts need to be generated again.
+pub struct Sitemap {
+  pub name: String,
+  pub content: String,
+  pub url_count: i32,
+  /// Newest modification date of the contained urls
+  pub last_modified_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = sitemap))]
+pub struct SitemapForm {
+  pub name: String,
+  pub content: String,
+  pub url_count: i32,
+  pub last_modified_at: DateTime<Utc>,
+}
@@ -97,7 +97,7 @@ pub trait Followable {
     Self: Sized;
   fn follow_accepted(
     pool: &mut DbPool<'_>,
-    community_id: CommunityId,
+    item_id: Self::IdType,
     person_id: PersonId,
   ) -> impl Future<Output = LemmyResult<Self>> + Send
   where
@@ -2,7 +2,6 @@ pub mod queries;
 pub mod uplete;
 
 use crate::newtypes::DbUrl;
-use chrono::TimeDelta;
 use deadpool::Runtime;
 use diesel::{
   dsl,
@@ -62,7 +61,6 @@ use url::Url;
 const FETCH_LIMIT_DEFAULT: i64 = 20;
 pub const FETCH_LIMIT_MAX: usize = 50;
 pub const SITEMAP_LIMIT: i64 = 50000;
-pub const SITEMAP_DAYS: TimeDelta = TimeDelta::days(31);
 pub const RANK_DEFAULT: f64 = 0.0001;
 
 /// Some connection options to speed up queries
@@ -549,6 +547,9 @@ pub mod functions {
 
   define_sql_function!(fn lower(x: Text) -> Text);
 
+  // Also used for nullable columns, the result is null if the string is null
+  define_sql_function!(fn replace<T: diesel::sql_types::SqlType + diesel::sql_types::SingleValue>(string: T, from: Text, to: Text) -> T);
+
   define_sql_function!(fn random() -> Text);
 
   define_sql_function!(fn random_smallint() -> SmallInt);
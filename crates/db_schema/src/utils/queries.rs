@@ -15,7 +15,7 @@ use crate::{
 use diesel::{
   dsl::{case_when, exists, not},
   expression::SqlLiteral,
-  helper_types::{Eq, NotEq},
+  helper_types::{Eq, Filter, NotEq, Select},
   sql_types::Json,
   BoolExpressionMethods,
   ExpressionMethods,
@@ -33,6 +33,7 @@ use lemmy_db_schema_file::{
     community_actions,
     image_details,
     instance_actions,
+    instance_policy,
     local_site,
     local_user,
     multi_community,
@@ -247,6 +248,16 @@ pub fn post_tags_fragment() -> _ {
     .single_value()
 }
 
+#[diesel::dsl::auto_type]
+/// Gets the duration of the post's video, which is stored in the details of the video itself and
+/// not in those of its poster frame
+pub fn post_video_duration() -> _ {
+  image_details::table
+    .filter(image_details::link.nullable().eq(post::url))
+    .select(image_details::duration)
+    .single_value()
+}
+
 #[diesel::dsl::auto_type]
 /// Gets the post tags available within a specific community
 pub fn community_post_tags_fragment() -> _ {
@@ -285,6 +296,32 @@ pub fn filter_not_unlisted_or_is_subscribed() -> _ {
   not_unlisted.or(is_subscribed)
 }
 
+/// Content whose creator is followed by the user, and the follow was accepted. `follow_pending` is
+/// only set while following.
+#[diesel::dsl::auto_type]
+pub fn filter_is_followed_person() -> _ {
+  person_actions::follow_pending.eq(false)
+}
+
+type SilencedInstancesType = Select<
+  Filter<instance_policy::table, Eq<instance_policy::silence, bool>>,
+  instance_policy::instance_id,
+>;
+
+/// Content by users of silenced instances is only shown to those who are subscribed to the
+/// community, or follow the creator.
+#[diesel::dsl::auto_type]
+pub fn filter_not_silenced_or_is_followed() -> _ {
+  let silenced_instances: SilencedInstancesType = instance_policy::table
+    .filter(instance_policy::silence.eq(true))
+    .select(instance_policy::instance_id);
+  let not_silenced = not(person::instance_id.eq_any(silenced_instances));
+  let is_subscribed: IsSubscribedType = filter_is_subscribed();
+  not_silenced
+    .or(is_subscribed)
+    .or(filter_is_followed_person())
+}
+
 #[diesel::dsl::auto_type]
 pub fn community_join() -> _ {
   community::table.on(post::community_id.eq(community::id))
@@ -74,6 +74,8 @@ pub enum ListingType {
   ModeratorView,
   /// Communities which are recommended by local instance admins
   Suggested,
+  /// Content only from people you follow.
+  FollowedPeople,
 }
 
 #[derive(
@@ -180,6 +182,26 @@ pub enum FederationMode {
   Disable,
 }
 
+#[derive(
+  EnumString, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Hash,
+)]
+#[cfg_attr(feature = "full", derive(DbEnum))]
+#[cfg_attr(
+  feature = "full",
+  ExistingTypePath = "crate::schema::sql_types::SignatureFormatEnum"
+)]
+#[cfg_attr(feature = "full", DbValueStyle = "verbatim")]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+/// The HTTP signature format which is used for activities sent to an instance
+pub enum SignatureFormat {
+  #[default]
+  /// draft-cavage-http-signatures, supported by all Fediverse software
+  Cavage,
+  /// RFC 9421 HTTP Message Signatures
+  Rfc9421,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "full", derive(DbEnum))]
 #[cfg_attr(
@@ -226,3 +248,49 @@ pub enum VoteShow {
   ShowForOthers,
   Hide,
 }
+
+#[derive(EnumString, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "full", derive(DbEnum))]
+#[cfg_attr(
+  feature = "full",
+  ExistingTypePath = "crate::schema::sql_types::RelayTypeEnum"
+)]
+#[cfg_attr(feature = "full", DbValueStyle = "verbatim")]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+/// The protocol flavor spoken by an ActivityPub relay.
+pub enum RelayType {
+  /// Mastodon-style relays are subscribed to by following the public collection. They forward
+  /// the original activities of other actors, signed by the relay.
+  Mastodon,
+  /// LitePub relays (used by Pleroma) are subscribed to by following the relay actor. They wrap
+  /// forwarded objects in an Announce by the relay.
+  LitePub,
+}
+
+#[derive(EnumString, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "full", derive(DbEnum))]
+#[cfg_attr(
+  feature = "full",
+  ExistingTypePath = "crate::schema::sql_types::ImageModerationActionEnum"
+)]
+#[cfg_attr(feature = "full", DbValueStyle = "verbatim")]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+/// An action taken against an image, by an admin or automatically.
+pub enum ImageModerationAction {
+  /// A perceptual hash was added to the blocklist.
+  BlockHash,
+  /// A perceptual hash was removed from the blocklist.
+  UnblockHash,
+  /// An upload was rejected because it matched the blocklist or the classifier.
+  RejectUpload,
+  /// A cached or stored image was purged because it matched the blocklist.
+  PurgeImage,
+  /// The classifier marked an image as NSFW.
+  MarkNsfw,
+  /// The classifier quarantined an upload, which is hidden until an admin reviews it.
+  QuarantineUpload,
+  /// An admin approved a quarantined upload.
+  ApproveUpload,
+}
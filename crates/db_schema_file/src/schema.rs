@@ -21,6 +21,10 @@ pub mod sql_types {
   #[diesel(postgres_type(name = "federation_mode_enum"))]
   pub struct FederationModeEnum;
 
+  #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+  #[diesel(postgres_type(name = "image_moderation_action_enum"))]
+  pub struct ImageModerationActionEnum;
+
   #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
   #[diesel(postgres_type(name = "listing_type_enum"))]
   pub struct ListingTypeEnum;
@@ -41,6 +45,14 @@ pub mod sql_types {
   #[diesel(postgres_type(name = "registration_mode_enum"))]
   pub struct RegistrationModeEnum;
 
+  #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+  #[diesel(postgres_type(name = "relay_type_enum"))]
+  pub struct RelayTypeEnum;
+
+  #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+  #[diesel(postgres_type(name = "signature_format_enum"))]
+  pub struct SignatureFormatEnum;
+
   #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
   #[diesel(postgres_type(name = "vote_show_enum"))]
   pub struct VoteShowEnum;
@@ -69,6 +81,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ImageModerationActionEnum;
+
+    admin_image_moderation (id) {
+        id -> Int4,
+        admin_person_id -> Nullable<Int4>,
+        person_id -> Nullable<Int4>,
+        action -> ImageModerationActionEnum,
+        hash -> Nullable<Int8>,
+        image_url -> Nullable<Text>,
+        reason -> Nullable<Text>,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     admin_purge_comment (id) {
         id -> Int4,
@@ -181,6 +209,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    comment_revision (id) {
+        id -> Int4,
+        comment_id -> Int4,
+        editor_id -> Nullable<Int4>,
+        content -> Text,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::CommunityVisibility;
@@ -252,6 +290,89 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    instance_policy (instance_id) {
+        instance_id -> Int4,
+        reject_media -> Bool,
+        force_nsfw -> Bool,
+        strip_votes -> Bool,
+        silence -> Bool,
+        reject_reports -> Bool,
+        quarantine_communities -> Bool,
+        rewrite_links_from -> Nullable<Text>,
+        rewrite_links_to -> Nullable<Text>,
+        reason -> Nullable<Text>,
+        published_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
+        never_cache_media -> Bool,
+    }
+}
+
+diesel::table! {
+    community_backfill (community_id) {
+        community_id -> Int4,
+        posts_fetched -> Int4,
+        comments_fetched -> Int4,
+        error -> Nullable<Text>,
+        published_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    community_directory (id) {
+        id -> Int4,
+        instance_id -> Int4,
+        #[max_length = 255]
+        ap_id -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        #[max_length = 255]
+        title -> Varchar,
+        description -> Nullable<Text>,
+        icon -> Nullable<Text>,
+        nsfw -> Bool,
+        subscribers -> Int4,
+        users_active_month -> Int4,
+        published_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    community_directory_language (community_directory_id, language_id) {
+        community_directory_id -> Int4,
+        language_id -> Int4,
+    }
+}
+
+diesel::table! {
+    community_feed (id) {
+        id -> Int4,
+        community_id -> Int4,
+        url -> Text,
+        bot_person_id -> Int4,
+        title_filter -> Nullable<Text>,
+        tag_id -> Nullable<Int4>,
+        language_id -> Nullable<Int4>,
+        last_fetched_at -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+        published_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    community_feed_item (community_feed_id, guid) {
+        community_feed_id -> Int4,
+        guid -> Text,
+        url -> Nullable<Text>,
+        post_id -> Nullable<Int4>,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     community_language (community_id, language_id) {
         community_id -> Int4,
@@ -278,6 +399,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    community_shared_inbox (community_id, instance_id, shared_inbox_url) {
+        community_id -> Int4,
+        instance_id -> Int4,
+        #[max_length = 255]
+        shared_inbox_url -> Varchar,
+        follower_count -> Int4,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     custom_emoji (id) {
         id -> Int4,
@@ -326,6 +458,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    federation_queue_control (instance_id) {
+        instance_id -> Int4,
+        paused -> Bool,
+        skip_backlog -> Bool,
+        reset_fail_count -> Bool,
+        resend_activity_id -> Nullable<Int8>,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     federation_queue_state (instance_id) {
         instance_id -> Int4,
@@ -333,6 +476,9 @@ diesel::table! {
         fail_count -> Int4,
         last_retry_at -> Nullable<Timestamptz>,
         last_successful_published_time_at -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+        last_error_at -> Nullable<Timestamptz>,
+        failing_since -> Nullable<Timestamptz>,
     }
 }
 
@@ -344,6 +490,28 @@ diesel::table! {
         content_type -> Text,
         #[max_length = 50]
         blurhash -> Nullable<Varchar>,
+        duration -> Nullable<Int4>,
+        poster_url -> Nullable<Text>,
+        perceptual_hash -> Nullable<Int8>,
+        nsfw -> Bool,
+        quarantined -> Bool,
+    }
+}
+
+diesel::table! {
+    image_hash_block (id) {
+        id -> Int4,
+        hash -> Int8,
+        reason -> Nullable<Text>,
+        published_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    image_redirect (alias) {
+        alias -> Text,
+        target_alias -> Text,
+        published_at -> Timestamptz,
     }
 }
 
@@ -359,6 +527,9 @@ diesel::table! {
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SignatureFormatEnum;
+
     instance (id) {
         id -> Int4,
         #[max_length = 255]
@@ -369,6 +540,8 @@ diesel::table! {
         software -> Nullable<Varchar>,
         #[max_length = 255]
         version -> Nullable<Varchar>,
+        signature_format -> SignatureFormatEnum,
+        rfc9421_rejected_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -397,6 +570,7 @@ diesel::table! {
         published_at -> Timestamptz,
         person_id -> Nullable<Int4>,
         thumbnail_for_post_id -> Nullable<Int4>,
+        file_size -> Int8,
     }
 }
 
@@ -453,6 +627,14 @@ diesel::table! {
         disable_email_notifications -> Bool,
         suggested_communities -> Nullable<Int4>,
         multi_comm_follower -> Int4,
+        edit_history_public -> Bool,
+        federation_authorized_fetch -> Bool,
+        community_directory_enabled -> Bool,
+        media_quota_mb -> Nullable<Int4>,
+        new_account_media_quota_mb -> Nullable<Int4>,
+        new_account_media_quota_days -> Int4,
+        image_proxy_max_age_days -> Nullable<Int4>,
+        image_proxy_max_cache_mb -> Nullable<Int4>,
     }
 }
 
@@ -533,6 +715,8 @@ diesel::table! {
         show_downvotes -> VoteShowEnum,
         show_upvote_percentage -> Bool,
         show_person_votes -> Bool,
+        hide_follows -> Bool,
+        media_quota_mb -> Nullable<Int4>,
     }
 }
 
@@ -707,6 +891,7 @@ diesel::table! {
         mod_remove_post_id -> Nullable<Int4>,
         mod_transfer_community_id -> Nullable<Int4>,
         mod_change_community_visibility_id -> Nullable<Int4>,
+        admin_image_moderation_id -> Nullable<Int4>,
     }
 }
 
@@ -762,6 +947,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    oauth_client (id) {
+        id -> Int4,
+        name -> Text,
+        website -> Nullable<Text>,
+        redirect_uris -> Text,
+        scopes -> Text,
+        client_id -> Text,
+        client_secret_hash -> Text,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     oauth_provider (id) {
         id -> Int4,
@@ -857,6 +1055,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    person_device_key (id) {
+        id -> Int4,
+        person_id -> Int4,
+        #[max_length = 255]
+        ap_id -> Varchar,
+        #[max_length = 255]
+        device_name -> Nullable<Varchar>,
+        public_key -> Text,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     person_liked_combined (id) {
         id -> Int4,
@@ -965,6 +1176,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_revision (id) {
+        id -> Int4,
+        post_id -> Int4,
+        editor_id -> Nullable<Int4>,
+        #[max_length = 200]
+        name -> Varchar,
+        #[max_length = 2000]
+        url -> Nullable<Varchar>,
+        body -> Nullable<Text>,
+        published_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     post_tag (post_id, tag_id) {
         post_id -> Int4,
@@ -987,6 +1212,7 @@ diesel::table! {
         ap_id -> Varchar,
         local -> Bool,
         removed -> Bool,
+        encrypted -> Bool,
     }
 }
 
@@ -1008,6 +1234,14 @@ diesel::table! {
     received_activity (ap_id) {
         ap_id -> Text,
         published_at -> Timestamptz,
+        id -> Int8,
+        data -> Nullable<Jsonb>,
+        actor_ap_id -> Nullable<Text>,
+        attempts -> Int4,
+        next_attempt_at -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+        processed_at -> Nullable<Timestamptz>,
+        dead_lettered_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -1022,10 +1256,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::RelayTypeEnum;
+
+    relay (id) {
+        id -> Int4,
+        ap_id -> Text,
+        inbox_url -> Text,
+        public_key -> Text,
+        instance_id -> Int4,
+        relay_type -> RelayTypeEnum,
+        follow_accepted -> Bool,
+        publish -> Bool,
+        received_count -> Int8,
+        last_received_at -> Nullable<Timestamptz>,
+        published_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     remote_image (link) {
         link -> Text,
         published_at -> Timestamptz,
+        last_accessed_at -> Timestamptz,
+        cached -> Bool,
+        file_size -> Nullable<Int8>,
     }
 }
 
@@ -1109,6 +1366,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    sitemap (name) {
+        #[max_length = 50]
+        name -> Varchar,
+        content -> Text,
+        url_count -> Int4,
+        last_modified_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     tag (id) {
         id -> Int4,
@@ -1148,15 +1416,30 @@ diesel::joinable!(comment_actions -> person (person_id));
 diesel::joinable!(comment_reply -> comment (comment_id));
 diesel::joinable!(comment_reply -> person (recipient_id));
 diesel::joinable!(comment_report -> comment (comment_id));
+diesel::joinable!(comment_revision -> comment (comment_id));
+diesel::joinable!(comment_revision -> person (editor_id));
 diesel::joinable!(community -> instance (instance_id));
 diesel::joinable!(community_actions -> community (community_id));
+diesel::joinable!(community_backfill -> community (community_id));
+diesel::joinable!(community_directory -> instance (instance_id));
+diesel::joinable!(community_directory_language -> community_directory (community_directory_id));
+diesel::joinable!(community_directory_language -> language (language_id));
+diesel::joinable!(community_feed -> community (community_id));
+diesel::joinable!(community_feed -> language (language_id));
+diesel::joinable!(community_feed -> person (bot_person_id));
+diesel::joinable!(community_feed -> tag (tag_id));
+diesel::joinable!(community_feed_item -> community_feed (community_feed_id));
+diesel::joinable!(community_feed_item -> post (post_id));
 diesel::joinable!(community_language -> community (community_id));
 diesel::joinable!(community_language -> language (language_id));
 diesel::joinable!(community_report -> community (community_id));
+diesel::joinable!(community_shared_inbox -> community (community_id));
+diesel::joinable!(community_shared_inbox -> instance (instance_id));
 diesel::joinable!(custom_emoji_keyword -> custom_emoji (custom_emoji_id));
 diesel::joinable!(email_verification -> local_user (local_user_id));
 diesel::joinable!(federation_allowlist -> instance (instance_id));
 diesel::joinable!(federation_blocklist -> instance (instance_id));
+diesel::joinable!(federation_queue_control -> instance (instance_id));
 diesel::joinable!(federation_queue_state -> instance (instance_id));
 diesel::joinable!(inbox_combined -> comment_reply (comment_reply_id));
 diesel::joinable!(inbox_combined -> person_comment_mention (person_comment_mention_id));
@@ -1164,6 +1447,8 @@ diesel::joinable!(inbox_combined -> person_post_mention (person_post_mention_id)
 diesel::joinable!(inbox_combined -> private_message (private_message_id));
 diesel::joinable!(instance_actions -> instance (instance_id));
 diesel::joinable!(instance_actions -> person (person_id));
+diesel::joinable!(instance_policy -> instance (instance_id));
+diesel::joinable!(image_redirect -> local_image (target_alias));
 diesel::joinable!(local_image -> person (person_id));
 diesel::joinable!(local_image -> post (thumbnail_for_post_id));
 diesel::joinable!(local_site -> multi_community (suggested_communities));
@@ -1193,6 +1478,7 @@ diesel::joinable!(mod_remove_post -> post (post_id));
 diesel::joinable!(mod_transfer_community -> community (community_id));
 diesel::joinable!(modlog_combined -> admin_allow_instance (admin_allow_instance_id));
 diesel::joinable!(modlog_combined -> admin_block_instance (admin_block_instance_id));
+diesel::joinable!(modlog_combined -> admin_image_moderation (admin_image_moderation_id));
 diesel::joinable!(modlog_combined -> admin_purge_comment (admin_purge_comment_id));
 diesel::joinable!(modlog_combined -> admin_purge_community (admin_purge_community_id));
 diesel::joinable!(modlog_combined -> admin_purge_person (admin_purge_person_id));
@@ -1222,6 +1508,7 @@ diesel::joinable!(person_comment_mention -> comment (comment_id));
 diesel::joinable!(person_comment_mention -> person (recipient_id));
 diesel::joinable!(person_content_combined -> comment (comment_id));
 diesel::joinable!(person_content_combined -> post (post_id));
+diesel::joinable!(person_device_key -> person (person_id));
 diesel::joinable!(person_liked_combined -> comment (comment_id));
 diesel::joinable!(person_liked_combined -> person (person_id));
 diesel::joinable!(person_liked_combined -> post (post_id));
@@ -1236,11 +1523,14 @@ diesel::joinable!(post -> person (creator_id));
 diesel::joinable!(post_actions -> person (person_id));
 diesel::joinable!(post_actions -> post (post_id));
 diesel::joinable!(post_report -> post (post_id));
+diesel::joinable!(post_revision -> person (editor_id));
+diesel::joinable!(post_revision -> post (post_id));
 diesel::joinable!(post_tag -> post (post_id));
 diesel::joinable!(post_tag -> tag (tag_id));
 diesel::joinable!(private_message_report -> private_message (private_message_id));
 diesel::joinable!(registration_application -> local_user (local_user_id));
 diesel::joinable!(registration_application -> person (admin_id));
+diesel::joinable!(relay -> instance (instance_id));
 diesel::joinable!(report_combined -> comment_report (comment_report_id));
 diesel::joinable!(report_combined -> community_report (community_report_id));
 diesel::joinable!(report_combined -> post_report (post_report_id));
@@ -1258,6 +1548,7 @@ diesel::joinable!(tag -> community (community_id));
 diesel::allow_tables_to_appear_in_same_query!(
   admin_allow_instance,
   admin_block_instance,
+  admin_image_moderation,
   admin_purge_comment,
   admin_purge_community,
   admin_purge_person,
@@ -1267,20 +1558,31 @@ diesel::allow_tables_to_appear_in_same_query!(
   comment_actions,
   comment_reply,
   comment_report,
+  comment_revision,
   community,
   community_actions,
+  community_backfill,
+  community_directory,
+  community_directory_language,
+  community_feed,
+  community_feed_item,
   community_language,
   community_report,
+  community_shared_inbox,
   custom_emoji,
   custom_emoji_keyword,
   email_verification,
   federation_allowlist,
   federation_blocklist,
+  federation_queue_control,
   federation_queue_state,
   image_details,
+  image_hash_block,
+  image_redirect,
   inbox_combined,
   instance,
   instance_actions,
+  instance_policy,
   language,
   local_image,
   local_site,
@@ -1306,23 +1608,27 @@ diesel::allow_tables_to_appear_in_same_query!(
   multi_community_entry,
   multi_community_follow,
   oauth_account,
+  oauth_client,
   oauth_provider,
   password_reset_request,
   person,
   person_actions,
   person_comment_mention,
   person_content_combined,
+  person_device_key,
   person_liked_combined,
   person_post_mention,
   person_saved_combined,
   post,
   post_actions,
   post_report,
+  post_revision,
   post_tag,
   private_message,
   private_message_report,
   received_activity,
   registration_application,
+  relay,
   remote_image,
   report_combined,
   search_combined,
@@ -1330,6 +1636,7 @@ diesel::allow_tables_to_appear_in_same_query!(
   sent_activity,
   site,
   site_language,
+  sitemap,
   tag,
   tagline,
 );
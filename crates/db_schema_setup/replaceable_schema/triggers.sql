@@ -273,9 +273,85 @@ BEGIN
 WHERE
     a.id = diff.community_id
         AND (diff.subscribers, diff.subscribers_local) != (0, 0);
+    -- Count followers of local communities per remote shared inbox, so that federation workers
+    -- don't need to scan all follows. This needs to read the person, which is why
+    -- delete_follow_before_person removes the follows of a deleted person before the person itself.
+    INSERT INTO community_shared_inbox AS a (community_id, instance_id, shared_inbox_url, follower_count)
+    SELECT
+        (community_actions).community_id, person.instance_id, person.inbox_url, sum(count_diff)
+    FROM select_old_and_new_rows AS old_and_new_rows
+    INNER JOIN community ON community.id = (community_actions).community_id
+    INNER JOIN person ON person.id = (community_actions).person_id
+WHERE (community_actions).followed_at IS NOT NULL
+    AND community.local
+    AND NOT person.local
+GROUP BY (community_actions).community_id, person.instance_id, person.inbox_url
+HAVING
+    sum(count_diff) != 0
+ON CONFLICT (community_id, instance_id, shared_inbox_url)
+    DO UPDATE SET
+        follower_count = a.follower_count + excluded.follower_count, updated_at = now();
+    DELETE FROM community_shared_inbox AS a
+    WHERE a.follower_count <= 0
+        AND a.community_id IN (
+            SELECT
+                (community_actions).community_id
+            FROM select_old_and_new_rows AS old_and_new_rows);
 RETURN NULL;
 END;
 $$);
+-- Move follows of local communities to the new shared inbox when the inbox of a remote person changes
+CREATE FUNCTION r.community_shared_inbox_person_update ()
+    RETURNS TRIGGER
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    IF NOT NEW.local AND (NEW.inbox_url, NEW.instance_id) != (OLD.inbox_url, OLD.instance_id) THEN
+        UPDATE
+            community_shared_inbox AS a
+        SET
+            follower_count = a.follower_count - 1,
+            updated_at = now()
+        FROM
+            community_actions
+            INNER JOIN community ON community.id = community_actions.community_id
+        WHERE
+            community_actions.person_id = NEW.id
+            AND community_actions.followed_at IS NOT NULL
+            AND community.local
+            AND a.community_id = community_actions.community_id
+            AND a.instance_id = OLD.instance_id
+            AND a.shared_inbox_url = OLD.inbox_url;
+        INSERT INTO community_shared_inbox AS a (community_id, instance_id, shared_inbox_url, follower_count)
+        SELECT
+            community_actions.community_id,
+            NEW.instance_id,
+            NEW.inbox_url,
+            1
+        FROM
+            community_actions
+            INNER JOIN community ON community.id = community_actions.community_id
+        WHERE
+            community_actions.person_id = NEW.id
+            AND community_actions.followed_at IS NOT NULL
+            AND community.local
+        ON CONFLICT (community_id,
+            instance_id,
+            shared_inbox_url)
+            DO UPDATE SET
+                follower_count = a.follower_count + 1, updated_at = now();
+        DELETE FROM community_shared_inbox AS a
+        WHERE a.instance_id = OLD.instance_id
+            AND a.shared_inbox_url = OLD.inbox_url
+            AND a.follower_count <= 0;
+    END IF;
+    RETURN NULL;
+END
+$$;
+CREATE TRIGGER community_shared_inbox
+    AFTER UPDATE OF inbox_url, instance_id ON person
+    FOR EACH ROW
+    EXECUTE FUNCTION r.community_shared_inbox_person_update ();
 CALL r.create_triggers ('post_report', $$
 BEGIN
     UPDATE
@@ -323,7 +399,8 @@ WHERE (diff.report_count, diff.unresolved_report_count) != (0, 0)
 RETURN NULL;
 END;
 $$);
--- Change the order of some cascading deletions to make deletion triggers run before the deletion of rows that the triggers need to read
+-- Change the order of some cascading deletions to make deletion triggers run before the deletion of rows that the triggers need to read.
+-- The community_actions trigger reads the person to update subscriber counts and community_shared_inbox.
 CREATE FUNCTION r.delete_follow_before_person ()
     RETURNS TRIGGER
     LANGUAGE plpgsql
@@ -556,9 +633,10 @@ END;
 $a$;
 CALL r.create_person_liked_combined_trigger ('post');
 CALL r.create_person_liked_combined_trigger ('comment');
--- modlog: (17 tables)
+-- modlog: (18 tables)
 -- admin_allow_instance
 -- admin_block_instance
+-- admin_image_moderation
 -- admin_purge_comment
 -- admin_purge_community
 -- admin_purge_person
@@ -598,6 +676,7 @@ END;
 $a$;
 CALL r.create_modlog_combined_trigger ('admin_allow_instance');
 CALL r.create_modlog_combined_trigger ('admin_block_instance');
+CALL r.create_modlog_combined_trigger ('admin_image_moderation');
 CALL r.create_modlog_combined_trigger ('admin_purge_comment');
 CALL r.create_modlog_combined_trigger ('admin_purge_community');
 CALL r.create_modlog_combined_trigger ('admin_purge_person');
@@ -1,11 +1,7 @@
 use crate::{CommentSlimView, CommentView};
-use lemmy_db_schema::newtypes::{
-  CommentId,
-  CommunityId,
-  LanguageId,
-  LocalUserId,
-  PaginationCursor,
-  PostId,
+use lemmy_db_schema::{
+  newtypes::{CommentId, CommunityId, LanguageId, LocalUserId, PaginationCursor, PostId},
+  source::comment_revision::CommentRevision,
 };
 use lemmy_db_schema_file::enums::{CommentSortType, ListingType};
 use lemmy_db_views_vote::VoteView;
@@ -137,6 +133,30 @@ pub struct ListCommentLikesResponse {
   pub prev_page: Option<PaginationCursor>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// List the edit history of a comment. Only visible to the author, mods and admins, unless the
+/// site has made edit history public.
+pub struct ListCommentRevisions {
+  pub comment_id: CommentId,
+  pub page_cursor: Option<PaginationCursor>,
+  pub page_back: Option<bool>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The comment edit history response
+pub struct ListCommentRevisionsResponse {
+  pub revisions: Vec<CommentRevision>,
+  /// the pagination cursor to use to fetch the next page
+  pub next_page: Option<PaginationCursor>,
+  pub prev_page: Option<PaginationCursor>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -31,6 +31,9 @@ use lemmy_db_schema::{
       creator_home_instance_actions_join,
       creator_local_instance_actions_join,
       filter_blocked,
+      filter_is_followed_person,
+      filter_not_silenced_or_is_followed,
+      filter_not_unlisted_or_is_subscribed,
       my_comment_actions_join,
       my_community_actions_join,
       my_instance_actions_community_join,
@@ -195,11 +198,20 @@ impl CommentQuery<'_> {
     query = match o.listing_type.unwrap_or_default() {
       ListingType::Subscribed => query.filter(is_subscribed),
       ListingType::Local => query.filter(community::local.eq(true)),
+      // Comments by silenced users are still shown in posts and communities
+      ListingType::All
+        if o.post_id.is_none() && o.parent_path.is_none() && o.community_id.is_none() =>
+      {
+        query.filter(filter_not_silenced_or_is_followed())
+      }
       ListingType::All => query,
       ListingType::ModeratorView => {
         query.filter(community_actions::became_moderator_at.is_not_null())
       }
       ListingType::Suggested => query.filter(suggested_communities()),
+      ListingType::FollowedPeople => query
+        .filter(filter_is_followed_person())
+        .filter(filter_not_unlisted_or_is_subscribed()),
     };
 
     if !o.local_user.show_bot_accounts() {
@@ -1,7 +1,20 @@
 use crate::{CommunityView, MultiCommunityView};
 use lemmy_db_schema::{
-  newtypes::{CommunityId, LanguageId, MultiCommunityId, PaginationCursor, PersonId, TagId},
-  source::site::Site,
+  newtypes::{
+    CommunityFeedId,
+    CommunityId,
+    LanguageId,
+    MultiCommunityId,
+    PaginationCursor,
+    PersonId,
+    TagId,
+  },
+  source::{
+    community_backfill::CommunityBackfill,
+    community_directory::CommunityDirectoryEntry,
+    community_feed::CommunityFeed,
+    site::Site,
+  },
   CommunitySortType,
 };
 use lemmy_db_schema_file::enums::{CommunityVisibility, ListingType};
@@ -200,6 +213,8 @@ pub struct GetCommunityResponse {
   pub site: Option<Site>,
   pub moderators: Vec<CommunityModeratorView>,
   pub discussion_languages: Vec<LanguageId>,
+  /// Progress of fetching older posts and comments, for remote communities.
+  pub backfill: Option<CommunityBackfill>,
 }
 
 #[skip_serializing_none]
@@ -251,6 +266,32 @@ pub struct ListCommunitiesResponse {
   pub prev_page: Option<PaginationCursor>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Search the directory of communities on other instances. Results are ordered by subscriber
+/// count.
+pub struct ListCommunityDirectory {
+  pub search_term: Option<String>,
+  pub language_id: Option<LanguageId>,
+  pub show_nsfw: Option<bool>,
+  pub page_cursor: Option<PaginationCursor>,
+  pub page_back: Option<bool>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The response for the community directory.
+pub struct ListCommunityDirectoryResponse {
+  pub communities: Vec<CommunityDirectoryEntry>,
+  /// the pagination cursor to use to fetch the next page
+  pub next_page: Option<PaginationCursor>,
+  pub prev_page: Option<PaginationCursor>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -300,6 +341,61 @@ pub struct DeleteCommunityTag {
   pub tag_id: TagId,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Import the items of an RSS or Atom feed into a community. The bot needs to be a local bot
+/// account which moderates the community. Only the bot itself or an admin can register feeds for
+/// it.
+pub struct CreateCommunityFeed {
+  pub community_id: CommunityId,
+  pub url: String,
+  pub bot_person_id: PersonId,
+  /// Only import items whose title matches this case-insensitive regex.
+  pub title_filter: Option<String>,
+  pub tag_id: Option<TagId>,
+  pub language_id: Option<LanguageId>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Edit a community feed. Optional fields which are left empty are cleared.
+pub struct EditCommunityFeed {
+  pub community_feed_id: CommunityFeedId,
+  pub url: String,
+  pub bot_person_id: PersonId,
+  pub title_filter: Option<String>,
+  pub tag_id: Option<TagId>,
+  pub language_id: Option<LanguageId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Stop importing a feed into a community.
+pub struct DeleteCommunityFeed {
+  pub community_feed_id: CommunityFeedId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// List the feeds which are imported into a community (only doable by moderators).
+pub struct ListCommunityFeeds {
+  pub community_id: CommunityId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The feeds of a community.
+pub struct ListCommunityFeedsResponse {
+  pub feeds: Vec<CommunityFeed>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
@@ -136,7 +136,10 @@ impl CommunityQuery<'_> {
 
     if let Some(listing_type) = o.listing_type {
       query = match listing_type {
-        ListingType::All => query.filter(filter_not_unlisted_or_is_subscribed()),
+        // Communities don't have a creator, so there is nothing to filter by followed people
+        ListingType::All | ListingType::FollowedPeople => {
+          query.filter(filter_not_unlisted_or_is_subscribed())
+        }
         ListingType::Subscribed => query.filter(filter_is_subscribed()),
         ListingType::Local => query
           .filter(community::local.eq(true))
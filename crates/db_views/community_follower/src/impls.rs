@@ -23,7 +23,7 @@ use lemmy_db_schema::{
 };
 use lemmy_db_schema_file::{
   enums::{CommunityFollowerState, CommunityVisibility},
-  schema::{community, community_actions, person},
+  schema::{community, community_actions, community_shared_inbox, person},
 };
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 
@@ -35,29 +35,23 @@ impl CommunityFollowerView {
       .inner_join(community::table)
       .inner_join(person::table.on(community_actions::person_id.eq(person::id)))
   }
-  /// return a list of local community ids and remote inboxes that at least one user of the given
-  /// instance has followed
+  /// return a list of local community ids and remote shared inboxes that at least one user of the
+  /// given instance has followed. This reads from `community_shared_inbox` which is maintained by
+  /// triggers, so it is an indexed lookup instead of a scan through all follows.
   pub async fn get_instance_followed_community_inboxes(
     pool: &mut DbPool<'_>,
     instance_id: InstanceId,
-    published_since: chrono::DateTime<Utc>,
+    updated_since: chrono::DateTime<Utc>,
   ) -> LemmyResult<Vec<(CommunityId, DbUrl)>> {
     let conn = &mut get_conn(pool).await?;
-    // In most cases this will fetch the same url many times (the shared inbox url)
-    // PG will only send a single copy to rust, but it has to scan through all follower rows (same
-    // as it was before). So on the PG side it would be possible to optimize this further by
-    // adding e.g. a new table community_followed_instances (community_id, instance_id)
-    // that would work for all instances that support fully shared inboxes.
-    // It would be a bit more complicated though to keep it in sync.
-
-    Self::joins()
-      .filter(person::instance_id.eq(instance_id))
-      .filter(community::local) // this should be a no-op since community_followers table only has
-      // local-person+remote-community or remote-person+local-community
-      .filter(not(person::local))
-      .filter(community_actions::followed_at.gt(published_since.naive_utc()))
-      .select((community::id, person::inbox_url))
-      .distinct() // only need each community_id, inbox combination once
+    community_shared_inbox::table
+      .filter(community_shared_inbox::instance_id.eq(instance_id))
+      .filter(community_shared_inbox::updated_at.gt(updated_since))
+      .filter(community_shared_inbox::follower_count.gt(0))
+      .select((
+        community_shared_inbox::community_id,
+        community_shared_inbox::shared_inbox_url,
+      ))
       .load::<(CommunityId, DbUrl)>(conn)
       .await
       .with_lemmy_type(LemmyErrorType::NotFound)
@@ -301,6 +295,7 @@ impl PendingFollow {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use diesel::OptionalExtension;
   use lemmy_db_schema::{
     source::{
       community::{CommunityActions, CommunityFollowerForm, CommunityInsertForm},
@@ -377,4 +372,96 @@ mod tests {
     Instance::delete(pool, remote_instance.id).await?;
     Ok(())
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_instance_followed_community_inboxes() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let since = Utc::now() - chrono::TimeDelta::days(1);
+
+    let local_instance = Instance::read_or_create(pool, "my_domain.tld".to_string()).await?;
+    let community_form = CommunityInsertForm::new(
+      local_instance.id,
+      "test_community_4".to_string(),
+      "nada".to_owned(),
+      "pubkey".to_string(),
+    );
+    let community = Community::create(pool, &community_form).await?;
+
+    // two remote users with the same shared inbox
+    let remote_instance = Instance::read_or_create(pool, "other_domain.tld".to_string()).await?;
+    let person_form = PersonInsertForm {
+      local: Some(false),
+      ..PersonInsertForm::new(
+        "alice".to_string(),
+        "pubkey".to_string(),
+        remote_instance.id,
+      )
+    };
+    let alice = Person::create(pool, &person_form).await?;
+    let shared_inbox = alice.inbox_url.clone();
+    let person_form = PersonInsertForm {
+      local: Some(false),
+      inbox_url: Some(shared_inbox.clone()),
+      ..PersonInsertForm::new("bob".to_string(), "pubkey".to_string(), remote_instance.id)
+    };
+    let bob = Person::create(pool, &person_form).await?;
+    let persons = [alice, bob];
+
+    let inboxes = CommunityFollowerView::get_instance_followed_community_inboxes(
+      pool,
+      remote_instance.id,
+      since,
+    )
+    .await?;
+    assert!(inboxes.is_empty());
+
+    for person in &persons {
+      let form =
+        CommunityFollowerForm::new(community.id, person.id, CommunityFollowerState::Accepted);
+      CommunityActions::follow(pool, &form).await?;
+    }
+    let inboxes = CommunityFollowerView::get_instance_followed_community_inboxes(
+      pool,
+      remote_instance.id,
+      since,
+    )
+    .await?;
+    assert_eq!(vec![(community.id, shared_inbox.clone())], inboxes);
+
+    // the inbox is still used as long as one person follows
+    for (i, person) in persons.iter().enumerate() {
+      CommunityActions::unfollow(pool, person.id, community.id).await?;
+      let inboxes = CommunityFollowerView::get_instance_followed_community_inboxes(
+        pool,
+        remote_instance.id,
+        since,
+      )
+      .await?;
+      assert_eq!(i == 0, !inboxes.is_empty());
+    }
+
+    // deleting a follower also updates the shared inbox
+    for person in &persons {
+      let form =
+        CommunityFollowerForm::new(community.id, person.id, CommunityFollowerState::Accepted);
+      CommunityActions::follow(pool, &form).await?;
+    }
+    for (i, person) in persons.iter().enumerate() {
+      Person::delete(pool, person.id).await?;
+      let follower_count = community_shared_inbox::table
+        .filter(community_shared_inbox::community_id.eq(community.id))
+        .select(community_shared_inbox::follower_count)
+        .first::<i32>(&mut get_conn(pool).await?)
+        .await
+        .optional()?;
+      let expected = if i == 0 { Some(1) } else { None };
+      assert_eq!(expected, follower_count);
+    }
+
+    Instance::delete(pool, local_instance.id).await?;
+    Instance::delete(pool, remote_instance.id).await?;
+    Ok(())
+  }
 }
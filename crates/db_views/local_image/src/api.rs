@@ -1,5 +1,8 @@
-use crate::LocalImageView;
-use lemmy_db_schema::newtypes::PaginationCursor;
+use crate::{LocalImageView, MediaUploaderSortType, MediaUploaderView};
+use lemmy_db_schema::{
+  newtypes::{ImageHash, PaginationCursor, PersonId},
+  source::images::{ImageDetails, ImageHashBlock, RemoteImageCacheStats},
+};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
@@ -49,6 +52,115 @@ pub struct ListMediaResponse {
   /// the pagination cursor to use to fetch the next page
   pub next_page: Option<PaginationCursor>,
   pub prev_page: Option<PaginationCursor>,
+  /// Storage used by your uploads. Not included when listing the media of all users.
+  pub usage: Option<MediaUsage>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct MediaUsage {
+  /// Total size of your uploads in bytes.
+  pub used_bytes: i64,
+  /// Maximum size of all your uploads in bytes, or empty if unlimited.
+  pub quota_bytes: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Get the local users with the largest uploads. Only for admins.
+pub struct ListMediaUploaders {
+  pub sort: Option<MediaUploaderSortType>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ListMediaUploadersResponse {
+  pub uploaders: Vec<MediaUploaderView>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Set the media upload quota for a single user, overriding the quota of the site. Only for
+/// admins.
+pub struct AdminSetMediaQuota {
+  pub person_id: PersonId,
+  /// In megabytes, 0 means unlimited. If empty, the quota of the site applies again.
+  pub media_quota_mb: Option<i32>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Add a perceptual hash to the image blocklist. Either the hash or the url of an image which
+/// is known to Lemmy needs to be given. Matching local uploads and cached remote images are
+/// purged. Only for admins.
+pub struct BlockImageHash {
+  #[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+  pub hash: Option<ImageHash>,
+  pub image_url: Option<Url>,
+  pub reason: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Remove a perceptual hash from the image blocklist. Only for admins.
+pub struct UnblockImageHash {
+  #[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+  pub hash: ImageHash,
+  pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ImageHashBlockResponse {
+  pub image_hash_block: ImageHashBlock,
+  /// Number of images which were purged because they matched the hash.
+  pub purged: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ListImageHashBlocksResponse {
+  pub image_hash_blocks: Vec<ImageHashBlock>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Approve an upload which was quarantined by the image classifier, so that everyone can view it.
+/// To reject it, purge the image instead. Only for admins.
+pub struct ApproveQuarantinedImage {
+  pub image_url: Url,
+  pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ListQuarantinedImagesResponse {
+  pub images: Vec<ImageDetails>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Size of the image proxy cache. Only for admins.
+pub struct ImageProxyStatsResponse {
+  pub stats: RemoteImageCacheStats,
 }
 
 #[skip_serializing_none]
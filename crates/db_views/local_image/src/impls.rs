@@ -1,15 +1,19 @@
-use crate::LocalImageView;
-use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
+use crate::{LocalImageView, MediaUploaderSortType, MediaUploaderView};
+use diesel::{ExpressionMethods, QueryDsl, QueryableByName, SelectableHelper};
 use diesel_async::RunQueryDsl;
 use i_love_jesus::SortDirection;
 use lemmy_db_schema::{
   newtypes::{PaginationCursor, PersonId},
-  source::images::{local_image_keys as key, LocalImage},
+  source::{
+    images::{local_image_keys as key, LocalImage},
+    person::Person,
+  },
   traits::PaginationCursorBuilder,
   utils::{get_conn, limit_fetch, paginate, DbPool},
 };
 use lemmy_db_schema_file::schema::{local_image, person, post};
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+use std::collections::HashMap;
 
 impl LocalImageView {
   #[diesel::dsl::auto_type(no_type_alias)]
@@ -79,6 +83,68 @@ impl LocalImageView {
   }
 }
 
+impl MediaUploaderView {
+  /// The people with the largest uploads. Thumbnails which were generated for posts are not
+  /// included, same as for the upload quota.
+  pub async fn list(
+    pool: &mut DbPool<'_>,
+    sort: MediaUploaderSortType,
+    limit: Option<i64>,
+  ) -> LemmyResult<Vec<Self>> {
+    #[derive(QueryableByName)]
+    struct Usage {
+      #[diesel(sql_type = diesel::sql_types::Integer)]
+      person_id: PersonId,
+      #[diesel(sql_type = diesel::sql_types::BigInt)]
+      file_count: i64,
+      #[diesel(sql_type = diesel::sql_types::BigInt)]
+      total_size: i64,
+    }
+
+    let conn = &mut get_conn(pool).await?;
+    let limit = limit_fetch(limit)?;
+    let order = match sort {
+      MediaUploaderSortType::TotalSize => "total_size DESC, file_count DESC",
+      MediaUploaderSortType::FileCount => "file_count DESC, total_size DESC",
+    };
+    // Diesel returns numeric for sums of bigint
+    let usages = diesel::sql_query(format!(
+      "SELECT person_id, count(*) AS file_count, coalesce(sum(file_size), 0)::bigint AS total_size
+        FROM local_image
+        WHERE person_id IS NOT NULL AND thumbnail_for_post_id IS NULL
+        GROUP BY person_id
+        ORDER BY {order}, person_id
+        LIMIT $1"
+    ))
+    .bind::<diesel::sql_types::BigInt, _>(limit)
+    .load::<Usage>(conn)
+    .await
+    .with_lemmy_type(LemmyErrorType::NotFound)?;
+
+    let person_ids: Vec<_> = usages.iter().map(|u| u.person_id).collect();
+    let mut persons: HashMap<_, _> = person::table
+      .filter(person::id.eq_any(person_ids))
+      .select(Person::as_select())
+      .load::<Person>(conn)
+      .await?
+      .into_iter()
+      .map(|p| (p.id, p))
+      .collect();
+    Ok(
+      usages
+        .into_iter()
+        .filter_map(|u| {
+          Some(MediaUploaderView {
+            person: persons.remove(&u.person_id)?,
+            file_count: u.file_count,
+            total_size: u.total_size,
+          })
+        })
+        .collect(),
+    )
+  }
+}
+
 impl PaginationCursorBuilder for LocalImageView {
   type CursorData = LocalImage;
   fn to_cursor(&self) -> PaginationCursor {
@@ -23,3 +23,24 @@ pub struct LocalImageView {
   #[cfg_attr(feature = "full", diesel(embed))]
   pub post: Option<Post>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// A local user with the total size and number of their uploads.
+pub struct MediaUploaderView {
+  pub person: Person,
+  pub file_count: i64,
+  /// In bytes.
+  pub total_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+/// The sort types for the list of uploaders, always largest first.
+pub enum MediaUploaderSortType {
+  #[default]
+  TotalSize,
+  FileCount,
+}
@@ -1,6 +1,7 @@
 use crate::{
   AdminAllowInstanceView,
   AdminBlockInstanceView,
+  AdminImageModerationView,
   AdminPurgeCommentView,
   AdminPurgeCommunityView,
   AdminPurgePersonView,
@@ -52,6 +53,7 @@ use lemmy_db_schema_file::{
   schema::{
     admin_allow_instance,
     admin_block_instance,
+    admin_image_moderation,
     admin_purge_comment,
     admin_purge_community,
     admin_purge_person,
@@ -91,6 +93,7 @@ impl ModlogCombinedViewInternal {
       admin_allow_instance::admin_person_id
         .eq(person::id)
         .or(admin_block_instance::admin_person_id.eq(person::id))
+        .or(admin_image_moderation::admin_person_id.eq(person::id.nullable()))
         .or(admin_purge_comment::admin_person_id.eq(person::id))
         .or(admin_purge_community::admin_person_id.eq(person::id))
         .or(admin_purge_person::admin_person_id.eq(person::id))
@@ -135,7 +138,8 @@ impl ModlogCombinedViewInternal {
             .is_not_null()
             .and(post::creator_id.eq(other_person)),
         )
-        .or(mod_transfer_community::other_person_id.eq(other_person)),
+        .or(mod_transfer_community::other_person_id.eq(other_person))
+        .or(admin_image_moderation::person_id.eq(other_person.nullable())),
     );
 
     let comment_join = comment::table.on(mod_remove_comment::comment_id.eq(comment::id));
@@ -198,6 +202,7 @@ impl ModlogCombinedViewInternal {
     modlog_combined::table
       .left_join(admin_allow_instance::table)
       .left_join(admin_block_instance::table)
+      .left_join(admin_image_moderation::table)
       .left_join(admin_purge_comment::table)
       .left_join(admin_purge_community::table)
       .left_join(admin_purge_person::table)
@@ -245,6 +250,7 @@ impl PaginationCursorBuilder for ModlogCombinedView {
       ModRemoveCommunity(v) => ('O', v.mod_remove_community.id.0),
       ModRemovePost(v) => ('P', v.mod_remove_post.id.0),
       ModTransferCommunity(v) => ('Q', v.mod_transfer_community.id.0),
+      AdminImageModeration(v) => ('R', v.admin_image_moderation.id.0),
     };
     PaginationCursor::new_single(prefix, id)
   }
@@ -282,6 +288,7 @@ impl PaginationCursorBuilder for ModlogCombinedView {
       'O' => query.filter(modlog_combined::mod_remove_community_id.eq(id)),
       'P' => query.filter(modlog_combined::mod_remove_post_id.eq(id)),
       'Q' => query.filter(modlog_combined::mod_transfer_community_id.eq(id)),
+      'R' => query.filter(modlog_combined::admin_image_moderation_id.eq(id)),
       _ => return Err(LemmyErrorType::CouldntParsePaginationToken.into()),
     };
 
@@ -370,11 +377,19 @@ impl ModlogCombinedQuery<'_> {
         AdminPurgeComment => query.filter(modlog_combined::admin_purge_comment_id.is_not_null()),
         AdminBlockInstance => query.filter(modlog_combined::admin_block_instance_id.is_not_null()),
         AdminAllowInstance => query.filter(modlog_combined::admin_allow_instance_id.is_not_null()),
+        AdminImageModeration => {
+          query.filter(modlog_combined::admin_image_moderation_id.is_not_null())
+        }
       }
     }
 
+    // Image moderation entries may reference illegal content, so they are only shown to admins
+    if !self.local_user.is_admin() {
+      query = query.filter(modlog_combined::admin_image_moderation_id.is_null());
+    }
+
     query = match self.listing_type.unwrap_or(ListingType::All) {
-      ListingType::All => query,
+      ListingType::All | ListingType::FollowedPeople => query,
       ListingType::Subscribed => query.filter(filter_is_subscribed()),
       ListingType::Local => query
         .filter(community::local.eq(true))
@@ -455,6 +470,14 @@ impl InternalToCombinedView for ModlogCombinedViewInternal {
           admin: v.moderator,
         },
       ))
+    } else if let Some(admin_image_moderation) = v.admin_image_moderation {
+      Some(ModlogCombinedView::AdminImageModeration(
+        AdminImageModerationView {
+          admin_image_moderation,
+          admin: v.moderator,
+          other_person: v.other_person.clone(),
+        },
+      ))
     } else if let (Some(admin_purge_comment), Some(post)) = (v.admin_purge_comment, v.post.clone())
     {
       Some(ModlogCombinedView::AdminPurgeComment(
@@ -630,12 +653,15 @@ mod tests {
       comment::{Comment, CommentInsertForm},
       community::{Community, CommunityInsertForm},
       instance::Instance,
+      local_user::{LocalUser, LocalUserInsertForm},
       mod_log::{
         admin::{
           AdminAllowInstance,
           AdminAllowInstanceForm,
           AdminBlockInstance,
           AdminBlockInstanceForm,
+          AdminImageModeration,
+          AdminImageModerationForm,
           AdminPurgeComment,
           AdminPurgeCommentForm,
           AdminPurgeCommunity,
@@ -677,7 +703,7 @@ mod tests {
     utils::{build_db_pool_for_tests, DbPool},
     ModlogActionType,
   };
-  use lemmy_db_schema_file::enums::CommunityVisibility;
+  use lemmy_db_schema_file::enums::{CommunityVisibility, ImageModerationAction};
   use lemmy_utils::error::LemmyResult;
   use pretty_assertions::assert_eq;
   use serial_test::serial;
@@ -1429,4 +1455,52 @@ mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn image_moderation_only_for_admins() -> LemmyResult<()> {
+    let pool = &build_db_pool_for_tests();
+    let pool = &mut pool.into();
+    let data = init_data(pool).await?;
+
+    let form = AdminImageModerationForm {
+      admin_person_id: Some(data.timmy.id),
+      person_id: Some(data.sara.id),
+      action: ImageModerationAction::RejectUpload,
+      hash: None,
+      image_url: None,
+      reason: None,
+    };
+    AdminImageModeration::create(pool, &form).await?;
+
+    // Not visible when logged out
+    let modlog = ModlogCombinedQuery::default().list(pool).await?;
+    assert_eq!(0, modlog.len());
+
+    let admin_form = LocalUserInsertForm::test_form_admin(data.timmy.id);
+    let admin = LocalUser::create(pool, &admin_form, vec![]).await?;
+
+    let modlog = ModlogCombinedQuery {
+      local_user: Some(&admin),
+      ..Default::default()
+    }
+    .list(pool)
+    .await?;
+    assert_eq!(1, modlog.len());
+
+    if let ModlogCombinedView::AdminImageModeration(v) = &modlog[0] {
+      assert_eq!(
+        ImageModerationAction::RejectUpload,
+        v.admin_image_moderation.action
+      );
+      assert_eq!(Some(data.timmy.id), v.admin.as_ref().map(|a| a.id));
+      assert_eq!(Some(data.sara.id), v.other_person.as_ref().map(|p| p.id));
+    } else {
+      panic!("wrong type");
+    }
+
+    cleanup(data, pool).await?;
+
+    Ok(())
+  }
 }
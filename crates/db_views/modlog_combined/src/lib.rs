@@ -6,6 +6,7 @@ use lemmy_db_schema::source::{
     admin::{
       AdminAllowInstance,
       AdminBlockInstance,
+      AdminImageModeration,
       AdminPurgeComment,
       AdminPurgeCommunity,
       AdminPurgePerson,
@@ -271,6 +272,19 @@ pub struct AdminAllowInstanceView {
   pub admin: Option<Person>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "full", derive(Queryable))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// When an image is blocked, rejected, purged or marked as NSFW. Only visible to admins.
+pub struct AdminImageModerationView {
+  pub admin_image_moderation: AdminImageModeration,
+  pub admin: Option<Person>,
+  pub other_person: Option<Person>,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "full", derive(Queryable, Selectable))]
 #[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
@@ -282,6 +296,8 @@ pub(crate) struct ModlogCombinedViewInternal {
   #[cfg_attr(feature = "full", diesel(embed))]
   pub admin_block_instance: Option<AdminBlockInstance>,
   #[cfg_attr(feature = "full", diesel(embed))]
+  pub admin_image_moderation: Option<AdminImageModeration>,
+  #[cfg_attr(feature = "full", diesel(embed))]
   pub admin_purge_comment: Option<AdminPurgeComment>,
   #[cfg_attr(feature = "full", diesel(embed))]
   pub admin_purge_community: Option<AdminPurgeCommunity>,
@@ -341,6 +357,7 @@ pub(crate) struct ModlogCombinedViewInternal {
 pub enum ModlogCombinedView {
   AdminAllowInstance(AdminAllowInstanceView),
   AdminBlockInstance(AdminBlockInstanceView),
+  AdminImageModeration(AdminImageModerationView),
   AdminPurgeComment(AdminPurgeCommentView),
   AdminPurgeCommunity(AdminPurgeCommunityView),
   AdminPurgePerson(AdminPurgePersonView),
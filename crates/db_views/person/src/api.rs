@@ -1,5 +1,8 @@
 use crate::PersonView;
-use lemmy_db_schema::{newtypes::PersonId, source::site::Site};
+use lemmy_db_schema::{
+  newtypes::{PaginationCursor, PersonDeviceKeyId, PersonId},
+  source::{person_device_key::PersonDeviceKey, site::Site},
+};
 use lemmy_db_views_community_moderator::CommunityModeratorView;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -66,6 +69,46 @@ pub struct BlockPersonResponse {
   pub blocked: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Follow a person, to see their posts and comments with listing type FollowedPeople.
+pub struct FollowPerson {
+  pub person_id: PersonId,
+  pub follow: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The response for following a person.
+pub struct FollowPersonResponse {
+  pub person_view: PersonView,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Lists the followers of a person, or the people they follow.
+pub struct ListPersonFollows {
+  pub person_id: PersonId,
+  pub page_cursor: Option<PaginationCursor>,
+  pub page_back: Option<bool>,
+  pub limit: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ListPersonFollowsResponse {
+  pub persons: Vec<PersonView>,
+  /// the pagination cursor to use to fetch the next page
+  pub next_page: Option<PaginationCursor>,
+  pub prev_page: Option<PaginationCursor>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -110,3 +153,44 @@ pub struct NotePerson {
   pub person_id: PersonId,
   pub note: String,
 }
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Register a device key for end-to-end encrypted private messages.
+pub struct AddDeviceKey {
+  /// Base64 encoded MLS key package. The private key must stay on the device.
+  pub public_key: String,
+  pub device_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct DeviceKeyResponse {
+  pub device_key: PersonDeviceKey,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Remove one of your device keys.
+pub struct DeleteDeviceKey {
+  pub device_key_id: PersonDeviceKeyId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// List the device keys of a person, to encrypt a private message to them.
+pub struct ListDeviceKeys {
+  pub person_id: PersonId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct ListDeviceKeysResponse {
+  pub device_keys: Vec<PersonDeviceKey>,
+}
@@ -18,7 +18,7 @@ use lemmy_db_schema::{
     DbPool,
   },
 };
-use lemmy_db_schema_file::schema::{local_user, person};
+use lemmy_db_schema_file::schema::{local_user, person, person_actions};
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
 
 impl PaginationCursorBuilder for PersonView {
@@ -78,6 +78,10 @@ impl PersonView {
 #[derive(Default)]
 pub struct PersonQuery {
   pub admins_only: Option<bool>,
+  /// Only return the followers of this person.
+  pub followers_of: Option<PersonId>,
+  /// Only return the people followed by this person.
+  pub followed_by: Option<PersonId>,
   pub cursor_data: Option<Person>,
   pub page_back: Option<bool>,
   pub limit: Option<i64>,
@@ -97,6 +101,21 @@ impl PersonQuery {
       .into_boxed();
 
     // Filters
+    let follows = diesel::alias!(person_actions as follows);
+    if let Some(target_id) = self.followers_of {
+      let followers = follows
+        .filter(follows.field(person_actions::target_id).eq(target_id))
+        .filter(follows.field(person_actions::followed_at).is_not_null())
+        .select(follows.field(person_actions::person_id));
+      query = query.filter(person::id.eq_any(followers));
+    }
+    if let Some(person_id) = self.followed_by {
+      let followed = follows
+        .filter(follows.field(person_actions::person_id).eq(person_id))
+        .filter(follows.field(person_actions::followed_at).is_not_null())
+        .select(follows.field(person_actions::target_id));
+      query = query.filter(person::id.eq_any(followed));
+    }
 
     if self.admins_only.unwrap_or_default() {
       query = query.filter(local_user::admin);
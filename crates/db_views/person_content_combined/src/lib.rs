@@ -24,6 +24,7 @@ use {
     creator_is_admin,
     local_user_can_mod,
     post_tags_fragment,
+    post_video_duration,
   },
   lemmy_db_schema::utils::queries::{creator_banned_from_community, creator_is_moderator},
   lemmy_db_views_local_user::LocalUserView,
@@ -71,6 +72,12 @@ pub(crate) struct PersonContentCombinedViewInternal {
     )
   )]
   pub post_tags: TagsView,
+  #[cfg_attr(feature = "full",
+    diesel(
+      select_expression = post_video_duration()
+    )
+  )]
+  pub post_video_duration: Option<i32>,
   #[cfg_attr(feature = "full",
     diesel(
       select_expression = local_user_can_mod()
@@ -25,7 +25,12 @@ use {
     creator_banned_within_community,
     creator_is_moderator,
   },
-  lemmy_db_schema::utils::queries::{creator_is_admin, local_user_can_mod, post_tags_fragment},
+  lemmy_db_schema::utils::queries::{
+    creator_is_admin,
+    local_user_can_mod,
+    post_tags_fragment,
+    post_video_duration,
+  },
   lemmy_db_views_local_user::LocalUserView,
 };
 
@@ -71,6 +76,12 @@ pub(crate) struct PersonLikedCombinedViewInternal {
     )
   )]
   pub post_tags: TagsView,
+  #[cfg_attr(feature = "full",
+    diesel(
+      select_expression = post_video_duration()
+    )
+  )]
+  pub post_video_duration: Option<i32>,
   #[cfg_attr(feature = "full",
     diesel(
       select_expression = local_user_can_mod()
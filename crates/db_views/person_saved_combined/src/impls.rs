@@ -247,6 +247,7 @@ impl InternalToCombinedView for PersonSavedCombinedViewInternal {
         creator_banned: v.creator_banned,
         creator_is_moderator: v.creator_is_moderator,
         creator_banned_from_community: v.creator_banned_from_community,
+        video_duration: v.post_video_duration,
       }))
     }
   }
@@ -10,6 +10,7 @@ use lemmy_db_schema::{
     PostId,
     TagId,
   },
+  source::post_revision::PostRevision,
   PostFeatureType,
 };
 use lemmy_db_schema_file::enums::{ListingType, PostSortType};
@@ -219,6 +220,30 @@ pub struct ListPostLikesResponse {
   pub prev_page: Option<PaginationCursor>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// List the edit history of a post. Only visible to the author, mods and admins, unless the site
+/// has made edit history public.
+pub struct ListPostRevisions {
+  pub post_id: PostId,
+  pub page_cursor: Option<PaginationCursor>,
+  pub page_back: Option<bool>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The post edit history response
+pub struct ListPostRevisionsResponse {
+  pub revisions: Vec<PostRevision>,
+  /// the pagination cursor to use to fetch the next page
+  pub next_page: Option<PaginationCursor>,
+  pub prev_page: Option<PaginationCursor>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
@@ -17,7 +17,15 @@ use diesel_async::RunQueryDsl;
 use i_love_jesus::{asc_if, SortDirection};
 use lemmy_db_schema::{
   impls::local_user::LocalUserOptionHelper,
-  newtypes::{CommunityId, InstanceId, MultiCommunityId, PaginationCursor, PersonId, PostId},
+  newtypes::{
+    CommunityId,
+    InstanceId,
+    MultiCommunityId,
+    PaginationCursor,
+    PersonId,
+    PostId,
+    TagId,
+  },
   source::{
     community::CommunityActions,
     local_user::LocalUser,
@@ -37,7 +45,9 @@ use lemmy_db_schema::{
       creator_home_instance_actions_join,
       creator_local_instance_actions_join,
       filter_blocked,
+      filter_is_followed_person,
       filter_is_subscribed,
+      filter_not_silenced_or_is_followed,
       filter_not_unlisted_or_is_subscribed,
       image_details_join,
       my_community_actions_join,
@@ -67,6 +77,7 @@ use lemmy_db_schema_file::{
     person,
     post,
     post_actions,
+    post_tag,
   },
 };
 use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
@@ -260,6 +271,8 @@ pub struct PostQuery<'a> {
   pub time_range_seconds: Option<i32>,
   pub community_id: Option<CommunityId>,
   pub multi_community_id: Option<MultiCommunityId>,
+  /// Only list posts which have this tag
+  pub tag_id: Option<TagId>,
   pub local_user: Option<&'a LocalUser>,
   pub show_hidden: Option<bool>,
   pub show_read: Option<bool>,
@@ -392,11 +405,22 @@ impl PostQuery<'_> {
           .filter(community::local.eq(true))
           .filter(filter_not_unlisted_or_is_subscribed());
       }
-      ListingType::All => query = query.filter(filter_not_unlisted_or_is_subscribed()),
+      ListingType::All => {
+        query = query.filter(filter_not_unlisted_or_is_subscribed());
+        // Posts by silenced users are still shown in communities
+        if o.community_id.is_none() && o.multi_community_id.is_none() {
+          query = query.filter(filter_not_silenced_or_is_followed());
+        }
+      }
       ListingType::ModeratorView => {
         query = query.filter(community_actions::became_moderator_at.is_not_null());
       }
       ListingType::Suggested => query = query.filter(suggested_communities()),
+      ListingType::FollowedPeople => {
+        query = query
+          .filter(filter_is_followed_person())
+          .filter(filter_not_unlisted_or_is_subscribed());
+      }
     }
 
     if !o.show_nsfw.unwrap_or(o.local_user.show_nsfw(site)) {
@@ -490,6 +514,14 @@ impl PostQuery<'_> {
         query.filter(post::published_at.gt(now() - seconds_to_pg_interval(time_range_seconds)));
     }
 
+    if let Some(tag_id) = o.tag_id {
+      query = query.filter(exists(
+        post_tag::table
+          .filter(post_tag::post_id.eq(post::id))
+          .filter(post_tag::tag_id.eq(tag_id)),
+      ));
+    }
+
     // Only sort by ascending for Old
     let sort = o.sort.unwrap_or(Hot);
     let sort_direction = asc_if(sort == Old);
@@ -583,12 +615,20 @@ mod tests {
         CommunityUpdateForm,
       },
       instance::{Instance, InstanceActions, InstanceBanForm, InstanceBlockForm},
+      instance_policy::{InstancePolicy, InstancePolicyForm},
       keyword_block::LocalUserKeywordBlock,
       language::Language,
       local_site::{LocalSite, LocalSiteUpdateForm},
       local_user::{LocalUser, LocalUserInsertForm, LocalUserUpdateForm},
       multi_community::{MultiCommunity, MultiCommunityInsertForm},
-      person::{Person, PersonActions, PersonBlockForm, PersonInsertForm, PersonNoteForm},
+      person::{
+        Person,
+        PersonActions,
+        PersonBlockForm,
+        PersonFollowerForm,
+        PersonInsertForm,
+        PersonNoteForm,
+      },
       post::{
         Post,
         PostActions,
@@ -1615,6 +1655,75 @@ mod tests {
     Ok(())
   }
 
+  #[test_context(Data)]
+  #[tokio::test]
+  #[serial]
+  async fn post_listing_silenced_instance(data: &mut Data) -> LemmyResult<()> {
+    const POST_BY_SILENCED_PERSON: &str = "post by silenced person";
+    const POST_LISTING_WITH_SILENCED: [&str; 4] =
+      [POST_BY_SILENCED_PERSON, POST_WITH_TAGS, POST_BY_BOT, POST];
+
+    let pool = &data.pool();
+    let pool = &mut pool.into();
+
+    let silenced_instance =
+      Instance::read_or_create(pool, "silenced_domain.tld".to_string()).await?;
+    let person_form = PersonInsertForm::test_form(silenced_instance.id, "silenced_person");
+    let silenced_person = Person::create(pool, &person_form).await?;
+
+    let post_form = PostInsertForm {
+      language_id: Some(LanguageId(1)),
+      ..PostInsertForm::new(
+        POST_BY_SILENCED_PERSON.to_string(),
+        silenced_person.id,
+        data.community.id,
+      )
+    };
+    Post::create(pool, &post_form).await?;
+
+    let all_query = || PostQuery {
+      listing_type: Some(ListingType::All),
+      ..data.default_post_query()
+    };
+
+    // no policy, should return all posts
+    let post_listings_all = all_query().list(&data.site, pool).await?;
+    assert_eq!(POST_LISTING_WITH_SILENCED, *names(&post_listings_all));
+
+    // silence the instance
+    let policy_form = InstancePolicyForm {
+      silence: true,
+      ..InstancePolicyForm::new(silenced_instance.id)
+    };
+    InstancePolicy::upsert(pool, &policy_form).await?;
+
+    // now posts by users of that instance should be hidden from All
+    let post_listings_silenced = all_query().list(&data.site, pool).await?;
+    assert_eq!(
+      vec![POST_WITH_TAGS, POST_BY_BOT, POST],
+      names(&post_listings_silenced)
+    );
+
+    // but still shown in the community
+    let post_listings_community = PostQuery {
+      community_id: Some(data.community.id),
+      ..all_query()
+    }
+    .list(&data.site, pool)
+    .await?;
+    assert_eq!(POST_LISTING_WITH_SILENCED, *names(&post_listings_community));
+
+    // Follow the silenced person to see their posts anyway
+    let follow_form = PersonFollowerForm::new(silenced_person.id, data.tegan.person.id, false);
+    PersonActions::follow(pool, &follow_form).await?;
+    let post_listings_bypass = all_query().list(&data.site, pool).await?;
+    assert_eq!(POST_LISTING_WITH_SILENCED, *names(&post_listings_bypass));
+    PersonActions::unfollow(pool, data.tegan.person.id, silenced_person.id).await?;
+
+    Instance::delete(pool, silenced_instance.id).await?;
+    Ok(())
+  }
+
   #[test_context(Data)]
   #[tokio::test]
   #[serial]
@@ -2363,6 +2472,47 @@ mod tests {
     assert_eq!(0, all_posts[1].tags.0.len()); // bot post
     assert_eq!(0, all_posts[2].tags.0.len()); // normal post
 
+    let tagged_posts = PostQuery {
+      tag_id: Some(data.tag_2.id),
+      ..data.default_post_query()
+    }
+    .list(&data.site, pool)
+    .await?;
+    assert_eq!(vec![POST_WITH_TAGS], names(&tagged_posts));
+
+    Ok(())
+  }
+
+  #[test_context(Data)]
+  #[tokio::test]
+  #[serial]
+  async fn post_listing_followed_people(data: &mut Data) -> LemmyResult<()> {
+    let pool = &data.pool();
+    let pool = &mut pool.into();
+
+    let followed_people_query = PostQuery {
+      listing_type: Some(ListingType::FollowedPeople),
+      ..data.default_post_query()
+    };
+    let listing = followed_people_query.clone().list(&data.site, pool).await?;
+    assert!(listing.is_empty());
+
+    // Pending follows aren't included
+    let form = PersonFollowerForm::new(data.bot.person.id, data.tegan.person.id, true);
+    PersonActions::follow(pool, &form).await?;
+    let listing = followed_people_query.clone().list(&data.site, pool).await?;
+    assert!(listing.is_empty());
+
+    let form = PersonFollowerForm::new(data.bot.person.id, data.tegan.person.id, false);
+    PersonActions::follow(pool, &form).await?;
+
+    let listing = followed_people_query.clone().list(&data.site, pool).await?;
+    assert_eq!(vec![POST_BY_BOT], names(&listing));
+
+    PersonActions::unfollow(pool, data.tegan.person.id, data.bot.person.id).await?;
+    let listing = followed_people_query.list(&data.site, pool).await?;
+    assert!(listing.is_empty());
+
     Ok(())
   }
 
@@ -22,6 +22,7 @@ use {
     local_user_can_mod_post,
     post_creator_is_admin,
     post_tags_fragment,
+    post_video_duration,
   },
 };
 
@@ -88,4 +89,11 @@ pub struct PostView {
     )
   )]
   pub creator_banned_from_community: bool,
+  /// For video posts, the duration of the video in seconds.
+  #[cfg_attr(feature = "full",
+    diesel(
+      select_expression = post_video_duration()
+    )
+  )]
+  pub video_duration: Option<i32>,
 }
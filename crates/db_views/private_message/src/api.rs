@@ -1,7 +1,9 @@
 use crate::PrivateMessageView;
 use lemmy_db_schema::newtypes::{PersonId, PrivateMessageId};
 use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
@@ -9,6 +11,9 @@ use serde::{Deserialize, Serialize};
 pub struct CreatePrivateMessage {
   pub content: String,
   pub recipient_id: PersonId,
+  /// The content is base64 encoded ciphertext, encrypted by the client to the device keys of the
+  /// recipient.
+  pub encrypted: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
@@ -20,6 +25,7 @@ pub struct DeletePrivateMessage {
   pub deleted: bool,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
 #[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
@@ -27,6 +33,8 @@ pub struct DeletePrivateMessage {
 pub struct EditPrivateMessage {
   pub private_message_id: PrivateMessageId,
   pub content: String,
+  /// Must match the original message.
+  pub encrypted: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
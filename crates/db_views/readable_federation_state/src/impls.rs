@@ -1,6 +1,7 @@
 use crate::ReadableFederationState;
+use chrono::TimeDelta;
 use lemmy_db_schema::source::federation_queue_state::FederationQueueState;
-use lemmy_utils::federate_retry_sleep_duration;
+use lemmy_utils::{federate_retry_sleep_duration, settings::SETTINGS};
 
 #[allow(clippy::expect_used)]
 impl From<FederationQueueState> for ReadableFederationState {
@@ -10,6 +11,9 @@ impl From<FederationQueueState> for ReadableFederationState {
         r + chrono::Duration::from_std(federate_retry_sleep_duration(internal_state.fail_count))
           .expect("sleep duration longer than 2**63 ms (262 million years)")
       }),
+      health: internal_state.health(TimeDelta::days(
+        SETTINGS.federation.dead_instance_days.into(),
+      )),
       internal_state,
     }
   }
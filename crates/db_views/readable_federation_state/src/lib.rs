@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use lemmy_db_schema::source::federation_queue_state::FederationQueueState;
+use lemmy_db_schema::source::federation_queue_state::{FederationQueueState, InstanceHealth};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -14,4 +14,5 @@ pub struct ReadableFederationState {
   internal_state: FederationQueueState,
   /// timestamp of the next retry attempt (null if fail count is 0)
   next_retry: Option<DateTime<Utc>>,
+  health: InstanceHealth,
 }
@@ -249,6 +249,8 @@ mod tests {
         show_score: sara_local_user.show_score,
         show_upvote_percentage: sara_local_user.show_upvote_percentage,
         show_person_votes: sara_local_user.show_person_votes,
+        hide_follows: sara_local_user.hide_follows,
+        media_quota_mb: sara_local_user.media_quota_mb,
       },
       creator: Person {
         id: sara_person.id,
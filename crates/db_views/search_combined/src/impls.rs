@@ -38,6 +38,7 @@ use lemmy_db_schema::{
       creator_home_instance_actions_join,
       creator_local_instance_actions_join,
       creator_local_user_admin_join,
+      filter_is_followed_person,
       filter_is_subscribed,
       filter_not_unlisted_or_is_subscribed,
       image_details_join,
@@ -347,6 +348,10 @@ impl SearchCombinedQuery {
         query.filter(community_actions::became_moderator_at.is_not_null())
       }
       ListingType::Suggested => query.filter(suggested_communities()),
+      ListingType::FollowedPeople => query
+        .filter(filter_is_followed_person().and(
+          filter_not_unlisted_or_is_subscribed().or(search_combined::person_id.is_not_null()),
+        )),
     };
     // Filter by the time range
     if let Some(time_range_seconds) = self.time_range_seconds {
@@ -460,6 +465,7 @@ impl InternalToCombinedView for SearchCombinedViewInternal {
         creator_banned: v.creator_banned,
         creator_is_moderator: v.creator_is_moderator,
         creator_banned_from_community: v.creator_banned_from_community,
+        video_duration: v.post_video_duration,
       }))
     } else if let Some(community) = v.community {
       Some(SearchCombinedView::Community(CommunityView {
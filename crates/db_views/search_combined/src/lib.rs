@@ -30,6 +30,7 @@ use {
     creator_is_admin,
     local_user_can_mod,
     post_tags_fragment,
+    post_video_duration,
   },
   lemmy_db_schema::utils::queries::{creator_banned_from_community, creator_is_moderator},
   lemmy_db_views_local_user::LocalUserView,
@@ -80,6 +81,12 @@ pub(crate) struct SearchCombinedViewInternal {
   )]
   /// tags of this post
   pub post_tags: TagsView,
+  #[cfg_attr(feature = "full",
+    diesel(
+      select_expression = post_video_duration()
+    )
+  )]
+  pub post_video_duration: Option<i32>,
   #[cfg_attr(feature = "full",
     diesel(
       select_expression = community_post_tags_fragment()
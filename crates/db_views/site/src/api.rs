@@ -2,18 +2,22 @@ use crate::SiteView;
 use chrono::{DateTime, Utc};
 use lemmy_db_schema::{
   newtypes::{
+    ActivityId,
     InstanceId,
     LanguageId,
     MultiCommunityId,
     OAuthProviderId,
     PaginationCursor,
+    RelayId,
     TaglineId,
   },
   sensitive::SensitiveString,
   source::{
     comment::Comment,
     community::Community,
+    federation_queue_control::FederationQueueControl,
     instance::Instance,
+    instance_policy::InstancePolicy,
     language::Language,
     local_site_url_blocklist::LocalSiteUrlBlocklist,
     local_user::LocalUser,
@@ -22,6 +26,7 @@ use lemmy_db_schema::{
     person::Person,
     post::Post,
     private_message::PrivateMessage,
+    relay::Relay,
     tagline::Tagline,
   },
 };
@@ -32,6 +37,7 @@ use lemmy_db_schema_file::enums::{
   PostListingMode,
   PostSortType,
   RegistrationMode,
+  RelayType,
   VoteShow,
 };
 use lemmy_db_views_community_follower::CommunityFollowerView;
@@ -68,6 +74,45 @@ pub struct AdminBlockInstanceParams {
   pub expires_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Set the rules which are applied to content and activities received from an instance. Rules
+/// which are not given are disabled, so a policy without any rules removes it.
+pub struct AdminSetInstancePolicy {
+  pub instance: String,
+  pub reject_media: Option<bool>,
+  pub force_nsfw: Option<bool>,
+  pub strip_votes: Option<bool>,
+  pub silence: Option<bool>,
+  pub reject_reports: Option<bool>,
+  pub quarantine_communities: Option<bool>,
+  /// Rewrite links starting with this text, for example `https://twitter.com/`.
+  pub rewrite_links_from: Option<String>,
+  pub rewrite_links_to: Option<String>,
+  /// Don't store images from this instance in the image proxy cache.
+  pub never_cache_media: Option<bool>,
+  pub reason: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Control the outgoing federation queue of an instance. Changes are applied by the federation
+/// worker within a minute.
+pub struct AdminEditFederationQueue {
+  pub instance_id: InstanceId,
+  /// Stop or resume sending activities to this instance.
+  pub paused: Option<bool>,
+  /// Skip all pending activities and continue with the newest one.
+  pub skip_backlog: Option<bool>,
+  /// Retry sending immediately instead of waiting for the retry delay.
+  pub reset_fail_count: Option<bool>,
+  /// Send this activity to the instance again.
+  pub resend_activity_id: Option<ActivityId>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -157,6 +202,14 @@ pub struct CreateSite {
   pub disallow_nsfw_content: Option<bool>,
   pub disable_email_notifications: Option<bool>,
   pub suggested_communities: Option<MultiCommunityId>,
+  pub edit_history_public: Option<bool>,
+  pub federation_authorized_fetch: Option<bool>,
+  pub community_directory_enabled: Option<bool>,
+  pub media_quota_mb: Option<i32>,
+  pub new_account_media_quota_mb: Option<i32>,
+  pub new_account_media_quota_days: Option<i32>,
+  pub image_proxy_max_age_days: Option<i32>,
+  pub image_proxy_max_cache_mb: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -279,6 +332,27 @@ pub struct EditSite {
   pub disable_email_notifications: Option<bool>,
   /// A multicommunity with suggested communities which is shown on the homepage
   pub suggested_communities: Option<MultiCommunityId>,
+  /// Whether post and comment edit history is visible to everyone, instead of only the author,
+  /// moderators and admins.
+  pub edit_history_public: Option<bool>,
+  /// Require valid HTTP signatures from allowed instances to fetch ActivityPub objects. This
+  /// prevents blocked instances from reading content.
+  pub federation_authorized_fetch: Option<bool>,
+  /// Collect public communities from other Lemmy instances once per day, so that users can find
+  /// them in the community directory.
+  pub community_directory_enabled: Option<bool>,
+  /// Maximum size of all media uploaded by a user, in megabytes. 0 means unlimited.
+  pub media_quota_mb: Option<i32>,
+  /// Upload quota for new accounts, in megabytes. 0 means the regular quota applies.
+  pub new_account_media_quota_mb: Option<i32>,
+  /// Accounts younger than this number of days use the upload quota for new accounts.
+  pub new_account_media_quota_days: Option<i32>,
+  /// Remove proxied images from the cache if they weren't requested for this many days. 0 means
+  /// unlimited.
+  pub image_proxy_max_age_days: Option<i32>,
+  /// Maximum size of the image proxy cache in megabytes, the least recently requested images are
+  /// removed first. 0 means unlimited.
+  pub image_proxy_max_cache_mb: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -334,6 +408,8 @@ pub struct InstanceWithFederationState {
   /// if federation to this instance is or was active, show state of outgoing federation to this
   /// instance
   pub federation_state: Option<ReadableFederationState>,
+  /// rules applied to content and activities received from this instance
+  pub policy: Option<InstancePolicy>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -541,6 +617,8 @@ pub struct SaveUserSettings {
   pub hide_media: Option<bool>,
   /// Whether to show vote totals given to others.
   pub show_person_votes: Option<bool>,
+  /// Whether to hide the lists of people you follow and who follow you from others.
+  pub hide_follows: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -739,6 +817,63 @@ pub struct UserSettingsBackup {
   pub blocked_instances: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Subscribe the site actor to an ActivityPub relay.
+pub struct AddRelay {
+  /// The id of the relay actor, for example `https://relay.example.com/actor`.
+  pub ap_id: String,
+  /// Mastodon-style relays forward the original activities, LitePub relays wrap them in an
+  /// Announce. Both are supported.
+  pub relay_type: RelayType,
+  /// Also send activities of local public communities to the relay.
+  pub publish: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Change whether local community activities are published to a relay.
+pub struct EditRelay {
+  pub relay_id: RelayId,
+  pub publish: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// Unsubscribe from a relay.
+pub struct RemoveRelay {
+  pub relay_id: RelayId,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct RelayWithFederationState {
+  #[serde(flatten)]
+  pub relay: Relay,
+  /// if publishing to the relay is enabled, show state of outgoing federation to it
+  pub federation_state: Option<ReadableFederationState>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct RelayResponse {
+  pub relay: RelayWithFederationState,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The relays which the site actor is subscribed to, with statistics.
+pub struct ListRelaysResponse {
+  pub relays: Vec<RelayWithFederationState>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
@@ -766,3 +901,25 @@ impl Default for SuccessResponse {
     SuccessResponse { success: true }
   }
 }
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+pub struct FederationQueueView {
+  pub instance: Instance,
+  pub federation_state: ReadableFederationState,
+  /// Number of activities which haven't been processed for this instance yet. Not all of them
+  /// necessarily need to be sent there.
+  pub queue_depth: i64,
+  /// Changes requested by admins
+  pub control: Option<FederationQueueControl>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts-rs", ts(optional_fields, export))]
+/// The outgoing federation queues for all instances.
+pub struct ListFederationQueuesResponse {
+  pub queues: Vec<FederationQueueView>,
+}
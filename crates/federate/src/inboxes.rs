@@ -1,13 +1,16 @@
 use crate::util::LEMMY_TEST_FAST_FEDERATION;
+use activitypub_federation::kinds::public;
 use chrono::{DateTime, TimeZone, Utc};
 use lemmy_db_schema::{
   newtypes::{CommunityId, DbUrl, InstanceId},
-  source::{activity::SentActivity, site::Site},
+  source::{activity::SentActivity, relay::Relay, site::Site},
   utils::{ActualDbPool, DbPool},
 };
+use lemmy_db_schema_file::enums::ActorType;
 use lemmy_db_views_community_follower::CommunityFollowerView;
 use lemmy_utils::error::LemmyResult;
 use reqwest::Url;
+use serde_json::Value;
 use std::{
   collections::{HashMap, HashSet},
   sync::LazyLock,
@@ -18,16 +21,15 @@ use std::{
 /// The first time some user on an instance follows a specific remote community (or, more precisely:
 /// the first time a (followed_community_id, follower_inbox_url) tuple appears), this delay limits
 /// the maximum time until the follow actually results in activities from that community id being
-/// sent to that inbox url. This delay currently needs to not be too small because the DB load is
-/// currently fairly high because of the current structure of storing inboxes for every person, not
-/// having a separate list of shared_inboxes, and the architecture of having every instance queue be
-/// fully separate. (see https://github.com/LemmyNet/lemmy/issues/3958)
+/// sent to that inbox url. So a new follow doesn't take effect immediately, but only after up to
+/// this delay. Shared inboxes are aggregated per instance in `community_shared_inbox`, so that the
+/// query is a cheap indexed lookup and can run often.
 #[allow(clippy::expect_used)]
 static FOLLOW_ADDITIONS_RECHECK_DELAY: LazyLock<chrono::TimeDelta> = LazyLock::new(|| {
   if *LEMMY_TEST_FAST_FEDERATION {
     chrono::TimeDelta::try_seconds(1).expect("TimeDelta out of bounds")
   } else {
-    chrono::TimeDelta::try_minutes(2).expect("TimeDelta out of bounds")
+    chrono::TimeDelta::try_seconds(10).expect("TimeDelta out of bounds")
   }
 });
 /// The same as FOLLOW_ADDITIONS_RECHECK_DELAY, but triggering when the last person on an instance
@@ -44,6 +46,7 @@ pub trait DataSource: Send + Sync {
     instance_id: InstanceId,
     last_fetch: DateTime<Utc>,
   ) -> LemmyResult<Vec<(CommunityId, DbUrl)>>;
+  async fn read_relay_publish_inbox(&self, instance_id: InstanceId) -> LemmyResult<Option<DbUrl>>;
 }
 pub struct DbDataSource {
   pool: ActualDbPool,
@@ -72,6 +75,10 @@ impl DataSource for DbDataSource {
     )
     .await
   }
+
+  async fn read_relay_publish_inbox(&self, instance_id: InstanceId) -> LemmyResult<Option<DbUrl>> {
+    Relay::read_publish_inbox(&mut DbPool::Pool(&self.pool), instance_id).await
+  }
 }
 
 pub(crate) struct CommunityInboxCollector<T: DataSource> {
@@ -84,6 +91,9 @@ pub(crate) struct CommunityInboxCollector<T: DataSource> {
   followed_communities: HashMap<CommunityId, HashSet<Url>>,
   last_full_communities_fetch: DateTime<Utc>,
   last_incremental_communities_fetch: DateTime<Utc>,
+  /// inbox of a relay on this instance which local community activities are published to
+  relay_inbox: Option<Url>,
+  last_relay_fetch: DateTime<Utc>,
   instance_id: InstanceId,
   domain: String,
   pub(crate) data_source: T,
@@ -111,6 +121,8 @@ impl<T: DataSource> CommunityInboxCollector<T> {
       followed_communities: HashMap::new(),
       last_full_communities_fetch: Utc.timestamp_nanos(0),
       last_incremental_communities_fetch: Utc.timestamp_nanos(0),
+      relay_inbox: None,
+      last_relay_fetch: Utc.timestamp_nanos(0),
       instance_id,
       domain,
     }
@@ -142,6 +154,20 @@ impl<T: DataSource> CommunityInboxCollector<T> {
         inbox_urls.extend(urls.iter().cloned());
       }
     }
+    if is_public_community_announce(activity) {
+      // relays are loaded lazily because most instances don't have any
+      if (Utc::now() - self.last_relay_fetch) > *FOLLOW_ADDITIONS_RECHECK_DELAY {
+        self.relay_inbox = self
+          .data_source
+          .read_relay_publish_inbox(self.instance_id)
+          .await?
+          .map(Into::into);
+        self.last_relay_fetch = Utc::now();
+      }
+      if let Some(inbox) = &self.relay_inbox {
+        inbox_urls.insert(inbox.clone());
+      }
+    }
     inbox_urls.extend(
       activity
         .send_inboxes
@@ -215,6 +241,19 @@ impl<T: DataSource> CommunityInboxCollector<T> {
   }
 }
 
+/// Relays only receive announcements of activities in local public communities.
+fn is_public_community_announce(activity: &SentActivity) -> bool {
+  let is_public = activity
+    .data
+    .get("to")
+    .and_then(Value::as_array)
+    .is_some_and(|to| to.iter().any(|t| t.as_str() == Some(public().as_str())));
+  activity.actor_type == ActorType::Community
+    && activity.send_community_followers_of.is_some()
+    && activity.data.get("type").and_then(Value::as_str) == Some("Announce")
+    && is_public
+}
+
 #[cfg(test)]
 #[expect(clippy::indexing_slicing)]
 mod tests {
@@ -223,7 +262,6 @@ mod tests {
     newtypes::{ActivityId, CommunityId, InstanceId, SiteId},
     source::activity::SentActivity,
   };
-  use lemmy_db_schema_file::enums::ActorType;
   use lemmy_utils::error::LemmyResult;
   use mockall::{mock, predicate::*};
   use serde_json::json;
@@ -236,6 +274,7 @@ mod tests {
               instance_id: InstanceId,
               last_fetch: DateTime<Utc>,
           ) -> LemmyResult<Vec<(CommunityId, DbUrl)>>;
+          async fn read_relay_publish_inbox(&self, instance_id: InstanceId) -> LemmyResult<Option<DbUrl>>;
       }
   }
 
@@ -561,4 +600,46 @@ mod tests {
 
     Ok(())
   }
+
+  #[tokio::test]
+  async fn test_get_inbox_urls_relay() -> LemmyResult<()> {
+    let mut collector = setup_collector();
+    let community_id = CommunityId(1);
+    let relay_inbox = Url::parse("https://example.com/inbox")?;
+    let relay_inbox_clone = relay_inbox.clone();
+
+    collector
+      .data_source
+      .expect_read_relay_publish_inbox()
+      .return_once(move |_| Ok(Some(relay_inbox_clone.into())));
+
+    let mut activity = SentActivity {
+      id: ActivityId(1),
+      ap_id: Url::parse("https://example.com/activities/1")?.into(),
+      data: json!({
+        "type": "Announce",
+        "to": ["https://lemmy.example/c/test", "https://www.w3.org/ns/activitystreams#Public"]
+      }),
+      sensitive: false,
+      published_at: Utc::now(),
+      send_inboxes: vec![],
+      send_community_followers_of: Some(community_id),
+      send_all_instances: false,
+      actor_type: ActorType::Community,
+      actor_apub_id: None,
+    };
+
+    let result = collector.get_inbox_urls(&activity).await?;
+    assert_eq!(result, vec![relay_inbox]);
+
+    // activities in non-public communities are not published
+    activity.data = json!({
+      "type": "Announce",
+      "to": ["https://lemmy.example/c/test", "https://lemmy.example/c/test/followers"]
+    });
+    let result = collector.get_inbox_urls(&activity).await?;
+    assert!(result.is_empty());
+
+    Ok(())
+  }
 }
@@ -1,7 +1,11 @@
 use crate::{util::CancellableTask, worker::InstanceWorker};
 use activitypub_federation::config::FederationConfig;
+use chrono::{DateTime, TimeDelta, Utc};
 use lemmy_api_utils::context::LemmyContext;
-use lemmy_db_schema::{newtypes::InstanceId, source::instance::Instance};
+use lemmy_db_schema::{
+  newtypes::InstanceId,
+  source::{federation_queue_control::FederationQueueControl, instance::Instance},
+};
 use lemmy_utils::{error::LemmyResult, settings::structs::FederationWorkerConfig};
 use stats::receive_print_stats;
 use std::{collections::HashMap, time::Duration};
@@ -15,6 +19,7 @@ use tracing::info;
 use util::FederationQueueStateWithDomain;
 
 mod inboxes;
+mod priority;
 mod send;
 mod stats;
 mod util;
@@ -37,6 +42,9 @@ pub struct Opts {
 pub struct SendManager {
   opts: Opts,
   workers: HashMap<InstanceId, CancellableTask>,
+  /// Time of the admin changes which each worker was last restarted for, so that it isn't
+  /// restarted again while it is still applying them.
+  restarted_for_control: HashMap<InstanceId, DateTime<Utc>>,
   context: FederationConfig<LemmyContext>,
   stats_sender: UnboundedSender<FederationQueueStateWithDomain>,
   exit_print: JoinHandle<()>,
@@ -57,6 +65,7 @@ impl SendManager {
     Self {
       opts,
       workers: HashMap::new(),
+      restarted_for_control: HashMap::new(),
       stats_sender,
       exit_print: tokio::spawn(receive_print_stats(
         context.inner_pool().clone(),
@@ -104,12 +113,19 @@ impl SendManager {
     );
     let local_domain = self.context.settings().get_hostname_without_port()?;
     let mut pool = self.context.pool();
+    let dead_after = TimeDelta::days(self.federation_worker_config.dead_instance_days.into());
     loop {
       let mut total_count = 0;
       let mut dead_count = 0;
       let mut disallowed_count = 0;
+      let mut paused_count = 0;
+      let controls: HashMap<_, _> = FederationQueueControl::read_all(&mut pool)
+        .await?
+        .into_iter()
+        .map(|c| (c.instance_id, c))
+        .collect();
       for (instance, allowed, is_dead) in
-        Instance::read_federated_with_blocked_and_dead(&mut pool).await?
+        Instance::read_federated_with_blocked_and_dead(&mut pool, dead_after).await?
       {
         if instance.domain == local_domain {
           continue;
@@ -124,8 +140,27 @@ impl SendManager {
         if is_dead {
           dead_count += 1;
         }
-        let should_federate = allowed && !is_dead;
+        let control = controls.get(&instance.id);
+        let paused = control.is_some_and(|c| c.paused);
+        if paused {
+          paused_count += 1;
+        }
+        let should_federate = allowed && !is_dead && !paused;
         if should_federate {
+          let pending_control = control
+            .filter(|c| c.has_pending())
+            .filter(|c| self.restarted_for_control.get(&instance.id) != Some(&c.updated_at));
+          if let Some(control) = pending_control {
+            // restart the worker so that it applies the changes requested by admins
+            self
+              .restarted_for_control
+              .insert(instance.id, control.updated_at);
+            if let Some(worker) = self.workers.remove(&instance.id) {
+              if let Err(e) = worker.cancel().await {
+                tracing::error!("error stopping worker: {e}");
+              }
+            }
+          }
           if self.workers.contains_key(&instance.id) {
             // worker already running
             continue;
@@ -159,7 +194,7 @@ impl SendManager {
         }
       }
       let worker_count = self.workers.len();
-      tracing::info!("Federating to {worker_count}/{total_count} instances ({dead_count} dead, {disallowed_count} disallowed)");
+      tracing::info!("Federating to {worker_count}/{total_count} instances ({dead_count} dead, {disallowed_count} disallowed, {paused_count} paused)");
       tokio::select! {
         () = sleep(INSTANCES_RECHECK_DELAY) => {},
         _ = cancel.cancelled() => { return Ok(()) }
@@ -195,11 +230,13 @@ mod test {
 
   use super::*;
   use activitypub_federation::config::Data;
-  use chrono::DateTime;
+  use chrono::{DateTime, Utc};
   use lemmy_db_schema::{
     source::{
       federation_allowlist::{FederationAllowList, FederationAllowListForm},
       federation_blocklist::{FederationBlockList, FederationBlockListForm},
+      federation_queue_control::FederationQueueControlForm,
+      federation_queue_state::FederationQueueState,
       instance::InstanceForm,
       person::{Person, PersonInsertForm},
     },
@@ -237,6 +274,7 @@ mod test {
 
       let federation_worker_config = FederationWorkerConfig {
         concurrent_sends_per_instance,
+        ..Default::default()
       };
       let pool = &mut context.pool();
       let instances = vec![
@@ -385,4 +423,55 @@ mod test {
     data.cleanup().await?;
     Ok(())
   }
+
+  /// Sending to instance has been failing for a long time, there should be no worker created for it
+  #[tokio::test]
+  #[serial]
+  async fn test_send_manager_failing() -> LemmyResult<()> {
+    let mut data = TestData::init(1, 1).await?;
+
+    let state = FederationQueueState {
+      instance_id: data.instances[0].id,
+      last_successful_id: None,
+      last_successful_published_time_at: Some(Utc::now() - TimeDelta::days(10)),
+      fail_count: 100,
+      last_retry_at: Some(Utc::now()),
+      last_error: None,
+      last_error_at: None,
+      failing_since: Some(Utc::now() - TimeDelta::days(10)),
+    };
+    FederationQueueState::upsert(&mut data.context.pool(), &state).await?;
+
+    data.run().await?;
+    let workers = &data.send_manager.workers;
+    assert_eq!(2, workers.len());
+    assert!(workers.contains_key(&data.instances[1].id));
+    assert!(workers.contains_key(&data.instances[2].id));
+
+    data.cleanup().await?;
+    Ok(())
+  }
+
+  /// Pause sending to an instance, there should be no worker created for it
+  #[tokio::test]
+  #[serial]
+  async fn test_send_manager_paused() -> LemmyResult<()> {
+    let mut data = TestData::init(1, 1).await?;
+
+    let form = FederationQueueControlForm {
+      instance_id: data.instances[0].id,
+      paused: Some(true),
+      ..Default::default()
+    };
+    FederationQueueControl::upsert(&mut data.context.pool(), &form).await?;
+
+    data.run().await?;
+    let workers = &data.send_manager.workers;
+    assert_eq!(2, workers.len());
+    assert!(workers.contains_key(&data.instances[1].id));
+    assert!(workers.contains_key(&data.instances[2].id));
+
+    data.cleanup().await?;
+    Ok(())
+  }
 }
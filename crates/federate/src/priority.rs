@@ -0,0 +1,188 @@
+use serde_json::Value;
+
+/// Send lane of an outgoing activity. Each lane has its own limit of concurrent sends, and
+/// activities in a higher lane may overtake older activities in a lower lane. This way moderation
+/// actions are not delayed by a flood of votes from a busy community.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Priority {
+  Votes,
+  Content,
+  Moderation,
+}
+
+/// Information about an outgoing activity which decides when it can be sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ActivityClass {
+  pub priority: Priority,
+  /// The object which the (innermost) activity acts on. Activities about the same object are
+  /// always sent in order. If this is None the activity is never overtaken.
+  pub object_id: Option<String>,
+  /// Actor of the innermost activity, used to coalesce votes.
+  pub actor_id: Option<String>,
+}
+
+impl ActivityClass {
+  pub(crate) fn classify(data: &Value) -> Self {
+    // announced activities are sent for the inner activity
+    let inner = unwrap_activity(data, "Announce");
+    let priority = match activity_type(inner) {
+      Some("Like" | "Dislike") => Priority::Votes,
+      Some("Delete" | "Remove" | "Block" | "Lock" | "Flag") => Priority::Moderation,
+      Some("Undo") => match inner.get("object").and_then(activity_type) {
+        Some("Like" | "Dislike") => Priority::Votes,
+        Some("Delete" | "Remove" | "Block" | "Lock") => Priority::Moderation,
+        _ => Priority::Content,
+      },
+      _ => Priority::Content,
+    };
+    let inner = unwrap_activity(inner, "Undo");
+    ActivityClass {
+      priority,
+      object_id: inner.get("object").and_then(object_id),
+      actor_id: inner.get("actor").and_then(object_id),
+    }
+  }
+
+  /// Returns true if this activity can be sent before the older activity `other`. Activities
+  /// about the same object or by the same actor keep their order. This also applies if one acts on
+  /// the actor of the other, so that for example deleting a user doesn't overtake their posts.
+  pub(crate) fn may_overtake(&self, other: &ActivityClass) -> bool {
+    let (Some(object), Some(actor), Some(other_object), Some(other_actor)) = (
+      &self.object_id,
+      &self.actor_id,
+      &other.object_id,
+      &other.actor_id,
+    ) else {
+      return false;
+    };
+    object != other_object && actor != other_actor && object != other_actor && actor != other_object
+  }
+
+  /// Returns true if this is a vote which makes the older vote `other` obsolete, because both are
+  /// by the same actor on the same object.
+  pub(crate) fn supersedes_vote(&self, other: &ActivityClass) -> bool {
+    self.priority == Priority::Votes
+      && other.priority == Priority::Votes
+      && self.object_id.is_some()
+      && self.actor_id.is_some()
+      && self.object_id == other.object_id
+      && self.actor_id == other.actor_id
+  }
+}
+
+fn activity_type(data: &Value) -> Option<&str> {
+  data.get("type").and_then(Value::as_str)
+}
+
+/// If `data` is an activity of type `kind` which embeds another activity, return the embedded one.
+fn unwrap_activity<'a>(data: &'a Value, kind: &str) -> &'a Value {
+  match data.get("object") {
+    Some(object) if activity_type(data) == Some(kind) && object.is_object() => object,
+    _ => data,
+  }
+}
+
+/// Objects are either given as plain id, or embedded with an `id` field.
+fn object_id(value: &Value) -> Option<String> {
+  match value {
+    Value::String(id) => Some(id.clone()),
+    Value::Object(_) => value.get("id").and_then(Value::as_str).map(str::to_string),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use serde_json::json;
+
+  fn announce(activity: Value) -> Value {
+    json!({
+      "type": "Announce",
+      "actor": "https://lemmy.ml/c/main",
+      "object": activity,
+    })
+  }
+
+  fn vote(kind: &str, object: &str) -> Value {
+    json!({
+      "type": kind,
+      "actor": "https://lemmy.ml/u/alice",
+      "object": object,
+    })
+  }
+
+  #[test]
+  fn test_classify() {
+    let like = vote("Like", "https://lemmy.ml/post/1");
+    let class = ActivityClass::classify(&announce(like.clone()));
+    assert_eq!(Priority::Votes, class.priority);
+    assert_eq!(Some("https://lemmy.ml/post/1".to_string()), class.object_id);
+    assert_eq!(Some("https://lemmy.ml/u/alice".to_string()), class.actor_id);
+
+    let undo = json!({
+      "type": "Undo",
+      "actor": "https://lemmy.ml/u/alice",
+      "object": like,
+    });
+    assert_eq!(ActivityClass::classify(&undo), class);
+
+    let delete = json!({
+      "type": "Delete",
+      "actor": "https://lemmy.ml/u/alice",
+      "object": {"id": "https://lemmy.ml/post/1", "type": "Tombstone"},
+    });
+    let delete_class = ActivityClass::classify(&announce(delete));
+    assert_eq!(Priority::Moderation, delete_class.priority);
+    assert_eq!(class.object_id, delete_class.object_id);
+
+    let create = json!({
+      "type": "Create",
+      "actor": "https://lemmy.ml/u/alice",
+      "object": {"id": "https://lemmy.ml/comment/1", "type": "Note"},
+    });
+    let create_class = ActivityClass::classify(&create);
+    assert_eq!(Priority::Content, create_class.priority);
+    assert_eq!(
+      Some("https://lemmy.ml/comment/1".to_string()),
+      create_class.object_id
+    );
+
+    let unknown = ActivityClass::classify(&json!({"type": "Create"}));
+    assert_eq!(Priority::Content, unknown.priority);
+    assert_eq!(None, unknown.object_id);
+  }
+
+  #[test]
+  fn test_ordering() {
+    let like = ActivityClass::classify(&vote("Like", "https://lemmy.ml/post/1"));
+    let dislike = ActivityClass::classify(&vote("Dislike", "https://lemmy.ml/post/1"));
+    let same_actor = ActivityClass::classify(&vote("Like", "https://lemmy.ml/post/2"));
+    let other = ActivityClass::classify(&json!({
+      "type": "Like",
+      "actor": "https://lemmy.ml/u/bob",
+      "object": "https://lemmy.ml/post/2",
+    }));
+    let unknown = ActivityClass::classify(&json!({"type": "Create"}));
+
+    assert!(!dislike.may_overtake(&like));
+    assert!(!same_actor.may_overtake(&like));
+    assert!(other.may_overtake(&like));
+    assert!(!other.may_overtake(&unknown));
+    assert!(!unknown.may_overtake(&other));
+
+    // a user can't be deleted or banned before their older activities are sent
+    let delete_user = ActivityClass::classify(&json!({
+      "type": "Delete",
+      "actor": "https://lemmy.ml/u/admin",
+      "object": "https://lemmy.ml/u/alice",
+    }));
+    assert_eq!(Priority::Moderation, delete_user.priority);
+    assert!(!delete_user.may_overtake(&like));
+    assert!(delete_user.may_overtake(&other));
+
+    assert!(dislike.supersedes_vote(&like));
+    assert!(!other.supersedes_vote(&like));
+    assert!(!unknown.supersedes_vote(&unknown));
+  }
+}
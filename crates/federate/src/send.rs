@@ -3,18 +3,27 @@ use activitypub_federation::{
   activity_sending::SendActivityTask,
   config::Data,
   protocol::context::WithContext,
-  traits::Activity,
+  traits::{Activity, Actor, Object},
+  FEDERATION_CONTENT_TYPE,
 };
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use lemmy_api_utils::context::LemmyContext;
-use lemmy_db_schema::{newtypes::ActivityId, source::activity::SentActivity};
+use lemmy_apub_objects::{
+  objects::SiteOrMultiOrCommunityOrUser,
+  utils::message_signature::{sign_post_request, sign_post_request_cavage},
+};
+use lemmy_db_schema::{
+  newtypes::ActivityId,
+  source::{activity::SentActivity, instance::Instance},
+};
+use lemmy_db_schema_file::enums::SignatureFormat;
 use lemmy_utils::{
   error::{LemmyError, LemmyResult},
   federate_retry_sleep_duration,
   FEDERATION_CONTEXT,
 };
-use reqwest::Url;
+use reqwest::{header::CONTENT_TYPE, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::ops::Deref;
@@ -65,7 +74,7 @@ impl Ord for SendSuccessInfo {
 /// 5. It simplifies concurrency management and makes the flow of data more predictable.
 pub(crate) enum SendActivityResult {
   Success(SendSuccessInfo),
-  Failure { fail_count: i32 },
+  Failure { fail_count: i32, error: String },
 }
 /// Represents a task for retrying to send an activity.
 ///
@@ -87,8 +96,10 @@ pub(crate) struct SendRetryTask<'a> {
   /// thread to each send task. It allows the task to determine how long to sleep initially
   /// if the request fails.
   pub initial_fail_count: i32,
-  /// For logging purposes
+  /// For logging purposes, and to store the signature format
   pub domain: String,
+  /// Signatures which the target instance was last known to accept
+  pub signature_format: SignatureFormat,
   pub context: Data<LemmyContext>,
   pub stop: CancellationToken,
 }
@@ -104,6 +115,7 @@ impl SendRetryTask<'_> {
       report,
       initial_fail_count,
       domain,
+      mut signature_format,
       context,
       stop,
     } = self;
@@ -119,15 +131,62 @@ impl SendRetryTask<'_> {
 
     let object: DummyActivity = serde_json::from_value(object.clone())?;
     let object = WithContext::new(object, FEDERATION_CONTEXT.deref().clone());
-    let requests = SendActivityTask::prepare(&object, actor.as_ref(), inbox_urls, &context).await?;
-    for task in requests {
-      // usually only one due to shared inbox
+    for inbox in inbox_urls {
+      // usually only one due to shared inbox. This also skips inboxes which are not valid.
+      let Some(task) =
+        SendActivityTask::prepare(&object, actor.as_ref(), vec![inbox.clone()], &context)
+          .await?
+          .pop()
+      else {
+        continue;
+      };
       tracing::debug!("sending out {}", task);
       let mut fail_count = initial_fail_count;
-      while let Err(e) = task.sign_and_send(&context).await {
+      loop {
+        let res = match signature_format {
+          SignatureFormat::Rfc9421 => {
+            let res = send_signed(&object, actor.as_ref(), &inbox, signature_format, &context);
+            match res.await {
+              Ok(SignedResponse::SignatureRejected) => {
+                // Only switch to draft-cavage if the instance accepts it. Otherwise the signature
+                // was rejected for a different reason, eg because the actor is blocked.
+                let cavage = send_signed(
+                  &object,
+                  actor.as_ref(),
+                  &inbox,
+                  SignatureFormat::Cavage,
+                  &context,
+                );
+                match cavage.await {
+                  Ok(SignedResponse::Accepted) => {
+                    tracing::info!(
+                      "{domain}: RFC 9421 signature rejected, using draft-cavage instead"
+                    );
+                    signature_format = SignatureFormat::Cavage;
+                    Instance::rejected_rfc9421_signature(pool, &domain)
+                      .await
+                      .map_err(|e| anyhow::anyhow!(e))?;
+                    Ok(())
+                  }
+                  Ok(SignedResponse::SignatureRejected) => {
+                    tracing::debug!("activity was rejected by {inbox} with both signature formats");
+                    Ok(())
+                  }
+                  Err(e) => Err(e),
+                }
+              }
+              res => res.map(|_| ()),
+            }
+          }
+          SignatureFormat::Cavage => task.sign_and_send(&context).await.map_err(Into::into),
+        };
+        let Err(e) = res else {
+          break;
+        };
         fail_count += 1;
         report.send(SendActivityResult::Failure {
           fail_count,
+          error: e.to_string(),
           // activity_id: activity.id,
         })?;
         let retry_delay = federate_retry_sleep_duration(fail_count);
@@ -157,6 +216,60 @@ impl SendRetryTask<'_> {
   }
 }
 
+enum SignedResponse {
+  Accepted,
+  /// The instance doesn't accept signatures in this format
+  SignatureRejected,
+}
+
+/// Send the activity with a signature in the given format. Like in `activitypub_federation`,
+/// activities which are rejected for other reasons are not retried.
+async fn send_signed(
+  object: &WithContext<DummyActivity>,
+  actor: &SiteOrMultiOrCommunityOrUser,
+  inbox: &Url,
+  signature_format: SignatureFormat,
+  context: &Data<LemmyContext>,
+) -> Result<SignedResponse> {
+  let body = serde_json::to_vec(object)?;
+  let private_key = actor
+    .private_key_pem()
+    .context("actor has no private key")?;
+  let key_id = format!("{}#main-key", actor.id());
+  let headers = match signature_format {
+    SignatureFormat::Rfc9421 => sign_post_request(
+      inbox,
+      FEDERATION_CONTENT_TYPE,
+      &body,
+      &key_id,
+      &private_key,
+      Utc::now(),
+    ),
+    SignatureFormat::Cavage => {
+      sign_post_request_cavage(inbox, &body, &key_id, &private_key, Utc::now())
+    }
+  }
+  .map_err(|e| anyhow::anyhow!(e))?;
+  let mut request = context
+    .client()
+    .post(inbox.as_str())
+    .header(CONTENT_TYPE, FEDERATION_CONTENT_TYPE);
+  for (name, value) in headers {
+    request = request.header(name, value);
+  }
+  let status = request.body(body).send().await?.status();
+  if status.is_success() {
+    Ok(SignedResponse::Accepted)
+  } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+    Ok(SignedResponse::SignatureRejected)
+  } else if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+    tracing::debug!("activity was rejected by {inbox}: {status}");
+    Ok(SignedResponse::Accepted)
+  } else {
+    Err(anyhow::anyhow!("sending to {inbox} failed: {status}"))
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct DummyActivity {
   id: Url,
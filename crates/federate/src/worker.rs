@@ -1,5 +1,6 @@
 use crate::{
   inboxes::RealCommunityInboxCollector,
+  priority::ActivityClass,
   send::{SendActivityResult, SendRetryTask, SendSuccessInfo},
   util::{
     get_activity_cached,
@@ -15,6 +16,8 @@ use lemmy_api_utils::context::LemmyContext;
 use lemmy_db_schema::{
   newtypes::ActivityId,
   source::{
+    activity::SentActivity,
+    federation_queue_control::FederationQueueControl,
     federation_queue_state::FederationQueueState,
     instance::{Instance, InstanceForm},
   },
@@ -25,7 +28,14 @@ use lemmy_utils::{
   federate_retry_sleep_duration,
   settings::structs::FederationWorkerConfig,
 };
-use std::{cmp::max, collections::BinaryHeap, ops::Add, time::Duration};
+use reqwest::Url;
+use std::{
+  cmp::max,
+  collections::{BinaryHeap, HashMap, VecDeque},
+  ops::Add,
+  sync::Arc,
+  time::Duration,
+};
 use tokio::{
   sync::mpsc::{self, UnboundedSender},
   time::sleep,
@@ -41,6 +51,9 @@ static SAVE_STATE_EVERY_TIME: Duration = Duration::from_secs(60);
 static SAVE_STATE_EVERY_TIME: Duration = Duration::from_secs(0);
 /// Maximum number of successful sends to allow out of order
 const MAX_SUCCESSFULS: usize = 1000;
+/// Maximum number of activities to read ahead, so that activities with higher priority can be sent
+/// before older ones
+const MAX_PENDING: usize = 64;
 
 /// in prod mode, try to collect multiple send results at the same time to reduce load
 #[cfg(not(test))]
@@ -76,8 +89,17 @@ pub(crate) struct InstanceWorker {
   // activities that have been successfully sent but
   // that are not the lowest number and thus can't be written to the database yet
   successfuls: BinaryHeap<SendSuccessInfo>,
-  // number of activities that currently have a task spawned to send it
-  in_flight: i8,
+  // activities that were read ahead and need to be sent to this instance, ordered by id
+  pending: VecDeque<PendingActivity>,
+  // activities that currently have a task spawned to send it
+  in_flight: HashMap<ActivityId, ActivityClass>,
+}
+
+/// An activity which needs to be sent to this instance, but which wasn't sent yet.
+struct PendingActivity {
+  activity: Arc<SentActivity>,
+  inbox_urls: Vec<Url>,
+  class: ActivityClass,
 }
 
 impl InstanceWorker {
@@ -90,6 +112,8 @@ impl InstanceWorker {
   ) -> LemmyResult<()> {
     let pool = config.to_request_data().inner_pool().clone();
     let state = FederationQueueState::load(&mut DbPool::Pool(&pool), instance.id).await?;
+    let control =
+      FederationQueueControl::read_pending(&mut DbPool::Pool(&pool), instance.id).await?;
     let (report_send_result, receive_send_result) =
       tokio::sync::mpsc::unbounded_channel::<SendActivityResult>();
     let mut worker = InstanceWorker {
@@ -109,85 +133,106 @@ impl InstanceWorker {
       receive_send_result,
       report_send_result,
       successfuls: BinaryHeap::<SendSuccessInfo>::new(),
-      in_flight: 0,
+      pending: VecDeque::new(),
+      in_flight: HashMap::new(),
     };
+    if let Some(control) = control {
+      worker.apply_control(control).await?;
+    }
 
     worker.loop_until_stopped().await
   }
+
+  /// Apply changes to the queue which were requested by admins. The SendManager restarts the
+  /// worker when there are pending changes, so this only needs to happen on startup.
+  async fn apply_control(&mut self, control: FederationQueueControl) -> LemmyResult<()> {
+    if control.skip_backlog {
+      let latest_id = get_latest_activity_id(&mut self.pool()).await?;
+      tracing::info!(
+        "{}: skipping backlog from {:?} to {:?}",
+        self.instance.domain,
+        self.state.last_successful_id,
+        latest_id
+      );
+      self.state.last_successful_id = Some(latest_id);
+    }
+    if control.skip_backlog || control.reset_fail_count {
+      self.state.fail_count = 0;
+      self.state.last_retry_at = None;
+      self.state.failing_since = None;
+    }
+    self.save_and_send_state().await?;
+    if let Some(activity_id) = control.resend_activity_id {
+      self.spawn_resend(activity_id).await?;
+    }
+    control.mark_applied(&mut self.pool()).await?;
+    Ok(())
+  }
   /// loop fetch new activities from db and send them to the inboxes of the given instances
   /// this worker only returns if (a) there is an internal error or (b) the cancellation token is
   /// cancelled (graceful exit)
   async fn loop_until_stopped(&mut self) -> LemmyResult<()> {
     self.initial_fail_sleep().await?;
-    let (mut last_sent_id, mut newest_id) = self.get_latest_ids().await?;
+    let (mut last_read_id, mut newest_id) = self.get_latest_ids().await?;
 
     while !self.stop.is_cancelled() {
-      // check if we need to wait for a send to finish before sending the next one
-      // we wait if (a) the last request failed, only if a request is already in flight (not at the
-      // start of the loop) or (b) if we have too many successfuls in memory or (c) if we have
-      // too many in flight
-      let need_wait_for_event = (self.in_flight != 0 && self.state.fail_count > 0)
-        || self.successfuls.len() >= MAX_SUCCESSFULS
-        || self.in_flight >= self.federation_worker_config.concurrent_sends_per_instance;
-      if need_wait_for_event || self.receive_send_result.len() > MIN_ACTIVITY_SEND_RESULTS_TO_HANDLE
-      {
-        // if len() > 0 then this does not block and allows us to write to db more often
-        // if len is 0 then this means we wait for something to change our above conditions,
-        // which can only happen by an event sent into the channel
+      if self.receive_send_result.len() > MIN_ACTIVITY_SEND_RESULTS_TO_HANDLE {
+        // this does not block and allows us to write to db more often
         self.handle_send_results().await?;
-        // handle_send_results does not guarantee that we are now in a condition where we want to
-        // send a new one, so repeat this check until the if no longer applies
         continue;
       }
 
-      // send a new activity if there is one
+      // read ahead new activities, so that they can be sent by priority
       self.inbox_collector.update_communities().await?;
-      let next_id_to_send = ActivityId(last_sent_id.0 + 1);
-      let successfuls_len: i64 = self.successfuls.len().try_into()?;
-      {
-        // sanity check: calculate next id to send based on the last id and the in flight requests
-        let expected_next_id = self.state.last_successful_id.map(|last_successful_id| {
-          last_successful_id.0 + successfuls_len + i64::from(self.in_flight) + 1
-        });
-        // compare to next id based on incrementing
-        if expected_next_id != Some(next_id_to_send.0) {
-          return Err(
-            anyhow::anyhow!(
-              "{}: next id to send is not as expected: {:?} != {:?}",
-              self.instance.domain,
-              expected_next_id,
-              next_id_to_send
-            )
-            .into(),
+      if last_read_id >= newest_id {
+        // lazily fetch latest id only if we have cought up
+        newest_id = self.get_latest_ids().await?.1;
+        if last_read_id > newest_id {
+          tracing::error!(
+            "{}: last read id {} is higher than latest id {} in database (did the db get cleared?)",
+            self.instance.domain,
+            last_read_id.0,
+            newest_id.0
           );
         }
       }
+      let mut skipped = false;
+      while self.pending.len() < MAX_PENDING && last_read_id < newest_id {
+        last_read_id = ActivityId(last_read_id.0 + 1);
+        skipped |= self.read_pending(last_read_id).await?;
+      }
+      if skipped {
+        self.pop_successfuls_and_write(false).await?;
+      }
+      self.check_last_read_id(last_read_id)?;
+
+      // send a new activity if possible
+      if let Some(index) = self.next_to_send()? {
+        let pending = self
+          .pending
+          .remove(index)
+          .context("next_to_send returns valid index")?;
+        self
+          .in_flight
+          .insert(pending.activity.id, pending.class.clone());
+        self.spawn_send(pending);
+        continue;
+      }
 
-      if next_id_to_send > newest_id {
-        // lazily fetch latest id only if we have cought up
-        newest_id = self.get_latest_ids().await?.1;
-        if next_id_to_send > newest_id {
-          if next_id_to_send > ActivityId(newest_id.0 + 1) {
-            tracing::error!(
-                "{}: next send id {} is higher than latest id {}+1 in database (did the db get cleared?)",
-                self.instance.domain,
-                next_id_to_send.0,
-                newest_id.0
-              );
-          }
-          // no more work to be done, wait before rechecking
-          tokio::select! {
-            () = sleep(*WORK_FINISHED_RECHECK_DELAY) => {},
-            () = self.stop.cancelled() => {
-              tracing::debug!("cancelled worker loop while waiting for new work")
-            }
-          }
-          continue;
+      if !self.in_flight.is_empty() {
+        // wait for a send to finish, this is the only event which allows us to send the next
+        // activity
+        self.handle_send_results().await?;
+        continue;
+      }
+
+      // no more work to be done, wait before rechecking
+      tokio::select! {
+        () = sleep(*WORK_FINISHED_RECHECK_DELAY) => {},
+        () = self.stop.cancelled() => {
+          tracing::debug!("cancelled worker loop while waiting for new work")
         }
       }
-      self.in_flight += 1;
-      last_sent_id = next_id_to_send;
-      self.spawn_send_if_needed(next_id_to_send).await?;
     }
     tracing::debug!("cancelled worker loop after send");
 
@@ -196,6 +241,73 @@ impl InstanceWorker {
     Ok(())
   }
 
+  /// Sanity check: every activity up to the last read id must be either sent successfully, in
+  /// flight or pending.
+  fn check_last_read_id(&self, last_read_id: ActivityId) -> LemmyResult<()> {
+    let successfuls_len: i64 = self.successfuls.len().try_into()?;
+    let in_flight_len: i64 = self.in_flight.len().try_into()?;
+    let pending_len: i64 = self.pending.len().try_into()?;
+    let expected_last_read_id = self.state.last_successful_id.map(|last_successful_id| {
+      last_successful_id.0 + successfuls_len + in_flight_len + pending_len
+    });
+    if expected_last_read_id != Some(last_read_id.0) {
+      return Err(
+        anyhow::anyhow!(
+          "{}: last read id is not as expected: {:?} != {:?}",
+          self.instance.domain,
+          expected_last_read_id,
+          last_read_id
+        )
+        .into(),
+      );
+    }
+    Ok(())
+  }
+
+  /// Returns the index of the pending activity which should be sent next. This is the oldest
+  /// activity with the highest priority which has a free send slot in its lane, and which may
+  /// overtake all older activities that weren't sent yet (see [ActivityClass::may_overtake]).
+  fn next_to_send(&self) -> LemmyResult<Option<usize>> {
+    // if the last request failed, only send one activity at a time
+    if !self.in_flight.is_empty() && self.state.fail_count > 0 {
+      return Ok(None);
+    }
+    // with too many successfuls in memory, only the oldest activity may be sent to fill the gap
+    let candidates = if self.successfuls.len() >= MAX_SUCCESSFULS {
+      1
+    } else {
+      self.pending.len()
+    };
+    let limit = usize::try_from(self.federation_worker_config.concurrent_sends_per_instance)?;
+    let mut next: Option<(usize, &ActivityClass)> = None;
+    for (index, pending) in self.pending.iter().enumerate().take(candidates) {
+      let class = &pending.class;
+      if next.is_some_and(|(_, n)| n.priority >= class.priority) {
+        continue;
+      }
+      let lane_in_flight = self
+        .in_flight
+        .values()
+        .filter(|c| c.priority == class.priority)
+        .count();
+      // activities in other lanes are sent concurrently, so they must not need a specific order
+      let blocked = lane_in_flight >= limit
+        || self
+          .in_flight
+          .values()
+          .any(|c| c.priority != class.priority && !class.may_overtake(c))
+        || self
+          .pending
+          .iter()
+          .take(index)
+          .any(|older| !class.may_overtake(&older.class));
+      if !blocked {
+        next = Some((index, class));
+      }
+    }
+    Ok(next.map(|(index, _)| index))
+  }
+
   async fn initial_fail_sleep(&mut self) -> Result<()> {
     // before starting queue, sleep remaining duration if last request failed
     if self.state.fail_count > 0 {
@@ -263,14 +375,18 @@ impl InstanceWorker {
     for event in events {
       match event {
         SendActivityResult::Success(s) => {
-          self.in_flight -= 1;
+          self.in_flight.remove(&s.activity_id);
           if !s.was_skipped {
             self.state.fail_count = max(0, self.state.fail_count - 1);
+            self.state.failing_since = None;
             self.mark_instance_alive().await?;
           }
           self.successfuls.push(s);
         }
-        SendActivityResult::Failure { fail_count, .. } => {
+        SendActivityResult::Failure { fail_count, error } => {
+          self.state.last_error = Some(error);
+          self.state.last_error_at = Some(Utc::now());
+          self.state.failing_since.get_or_insert_with(Utc::now);
           if fail_count > self.state.fail_count {
             // override fail count - if multiple activities are currently sending this value may get
             // conflicting info but that's fine.
@@ -308,6 +424,15 @@ impl InstanceWorker {
     }
     Ok(())
   }
+  /// The signature format changes when the instance sends us RFC 9421 signatures, or when it
+  /// rejects them. Send tasks which are spawned from now on use the new format.
+  async fn refresh_signature_format(&mut self) -> Result<()> {
+    self.instance.signature_format = Instance::read(&mut self.pool(), self.instance.id)
+      .await
+      .map_err(|e| anyhow::anyhow!(e))?
+      .signature_format;
+    Ok(())
+  }
   /// Checks that sequential activities `last_successful_id + 1`, `last_successful_id + 2` etc have
   /// been sent successfully. In that case updates `last_successful_id` and saves the state to the
   /// database if the time since the last save is greater than `SAVE_STATE_EVERY_TIME`.
@@ -343,54 +468,97 @@ impl InstanceWorker {
 
     let save_state_every = chrono::Duration::from_std(SAVE_STATE_EVERY_TIME)?;
     if force_write || (Utc::now() - self.last_state_insert) > save_state_every {
+      self.refresh_signature_format().await?;
       self.save_and_send_state().await?;
     }
     Ok(())
   }
 
-  /// we collect the relevant inboxes in the main instance worker task, and only spawn the send task
-  /// if we have inboxes to send to this limits CPU usage and reduces overhead for the (many)
-  /// cases where we don't have any inboxes
-  async fn spawn_send_if_needed(&mut self, activity_id: ActivityId) -> LemmyResult<()> {
-    let Some(ele) = get_activity_cached(&mut self.pool(), activity_id)
+  /// Read an activity and add it to the pending activities. We collect the relevant inboxes in
+  /// the main instance worker task, and only keep the activity if we have inboxes to send to. This
+  /// limits CPU usage and reduces overhead for the (many) cases where we don't have any inboxes.
+  /// Returns true if any activity was skipped.
+  async fn read_pending(&mut self, activity_id: ActivityId) -> LemmyResult<bool> {
+    let Some(activity) = get_activity_cached(&mut self.pool(), activity_id)
       .await
       .context("failed reading activity from db")?
     else {
       tracing::debug!("{}: {:?} does not exist", self.instance.domain, activity_id);
-      self
-        .report_send_result
-        .send(SendActivityResult::Success(SendSuccessInfo {
-          activity_id,
-          published_at: None,
-          was_skipped: true,
-        }))?;
-      return Ok(());
+      self.successfuls.push(SendSuccessInfo {
+        activity_id,
+        published_at: None,
+        was_skipped: true,
+      });
+      return Ok(true);
     };
-    let activity = &ele;
-    let inbox_urls = self.inbox_collector.get_inbox_urls(activity).await?;
+    let inbox_urls = self.inbox_collector.get_inbox_urls(&activity).await?;
     if inbox_urls.is_empty() {
       // this is the case when the activity is not relevant to this receiving instance (e.g. no user
       // subscribed to the relevant community)
       tracing::debug!("{}: {:?} no inboxes", self.instance.domain, activity.id);
-      self
-        .report_send_result
-        .send(SendActivityResult::Success(SendSuccessInfo {
-          activity_id,
-          // it would be valid here to either return None or Some(activity.published). The published
-          // time is only used for stats pages that track federation delay. None can be a bit
-          // misleading because if you look at / chart the published time for federation from a
-          // large to a small instance that's only subscribed to a few small communities,
-          // then it will show the last published time as a days ago even though
-          // federation is up to date.
-          published_at: Some(activity.published_at),
+      self.successfuls.push(SendSuccessInfo {
+        activity_id,
+        // it would be valid here to either return None or Some(activity.published). The published
+        // time is only used for stats pages that track federation delay. None can be a bit
+        // misleading because if you look at / chart the published time for federation from a
+        // large to a small instance that's only subscribed to a few small communities,
+        // then it will show the last published time as a days ago even though
+        // federation is up to date.
+        published_at: Some(activity.published_at),
+        was_skipped: true,
+      });
+      return Ok(true);
+    }
+    let class = ActivityClass::classify(&activity.data);
+    Ok(self.push_pending(PendingActivity {
+      activity,
+      inbox_urls,
+      class,
+    }))
+  }
+
+  /// Add an activity to the pending activities. Returns true if this skips an older vote which
+  /// is superseded by the new activity.
+  fn push_pending(&mut self, pending: PendingActivity) -> bool {
+    let mut skipped = false;
+    if self.federation_worker_config.coalesce_votes {
+      // the older vote wasn't sent yet, so only the new one needs to be sent
+      let superseded = self
+        .pending
+        .iter()
+        .position(|older| pending.class.supersedes_vote(&older.class))
+        .and_then(|index| self.pending.remove(index));
+      if let Some(superseded) = superseded {
+        tracing::debug!(
+          "{}: {:?} superseded by {:?}",
+          self.instance.domain,
+          superseded.activity.id,
+          pending.activity.id
+        );
+        self.successfuls.push(SendSuccessInfo {
+          activity_id: superseded.activity.id,
+          published_at: Some(superseded.activity.published_at),
           was_skipped: true,
-        }))?;
-      return Ok(());
+        });
+        skipped = true;
+      }
     }
+    self.pending.push_back(pending);
+    skipped
+  }
+
+  fn spawn_send(&self, pending: PendingActivity) {
+    let PendingActivity {
+      activity: ele,
+      inbox_urls,
+      ..
+    } = pending;
+    let activity_id = ele.id;
     let initial_fail_count = self.state.fail_count;
     let data = self.federation_lib_config.to_request_data();
     let stop = self.stop.clone();
     let domain = self.instance.domain.clone();
+    let signature_format = self.instance.signature_format;
     let mut report = self.report_send_result.clone();
     tokio::spawn(async move {
       let res = SendRetryTask {
@@ -400,6 +568,7 @@ impl InstanceWorker {
         report: &mut report,
         initial_fail_count,
         domain,
+        signature_format,
         context: data,
         stop,
       }
@@ -425,6 +594,56 @@ impl InstanceWorker {
           .ok();
       }
     });
+  }
+
+  /// Send a single activity again, independently of the queue. The result is not reported to the
+  /// worker, so that it doesn't interfere with `last_successful_id` and the fail count.
+  async fn spawn_resend(&mut self, activity_id: ActivityId) -> LemmyResult<()> {
+    let Some(activity) = get_activity_cached(&mut self.pool(), activity_id)
+      .await
+      .context("failed reading activity from db")?
+    else {
+      tracing::warn!(
+        "{}: can't resend {:?}, it does not exist",
+        self.instance.domain,
+        activity_id
+      );
+      return Ok(());
+    };
+    self.inbox_collector.update_communities().await?;
+    let inbox_urls = self.inbox_collector.get_inbox_urls(&activity).await?;
+    if inbox_urls.is_empty() {
+      tracing::info!(
+        "{}: no inboxes to resend {:?} to",
+        self.instance.domain,
+        activity_id
+      );
+      return Ok(());
+    }
+    let data = self.federation_lib_config.to_request_data();
+    let stop = self.stop.clone();
+    let domain = self.instance.domain.clone();
+    let signature_format = self.instance.signature_format;
+    tokio::spawn(async move {
+      // results are ignored, but the receiver needs to stay alive so that retries still happen
+      let (mut report, _receive) = mpsc::unbounded_channel();
+      let res = SendRetryTask {
+        activity: &activity,
+        object: &activity.data,
+        inbox_urls,
+        report: &mut report,
+        initial_fail_count: 0,
+        domain,
+        signature_format,
+        context: data,
+        stop,
+      }
+      .send_retry_loop()
+      .await;
+      if let Err(e) = res {
+        tracing::warn!("resending {} errored internally: {:?}", activity.ap_id, e);
+      }
+    });
     Ok(())
   }
 
@@ -523,6 +742,9 @@ mod test {
 
       let fed_config = FederationWorkerConfig {
         concurrent_sends_per_instance,
+        // the test activities are all votes on the same object
+        coalesce_votes: false,
+        ..Default::default()
       };
       spawn(InstanceWorker::init_and_loop(
         instance.clone(),
@@ -718,6 +940,134 @@ mod test {
     Ok(())
   }
 
+  /// A worker which isn't running, so that activities can be queued manually.
+  async fn idle_worker(coalesce_votes: bool) -> LemmyResult<InstanceWorker> {
+    let config = LemmyContext::init_test_federation_config().await;
+    let pool = config.to_request_data().inner_pool().clone();
+    let instance =
+      Instance::read_or_create(&mut DbPool::Pool(&pool), "lanes.tld".to_string()).await?;
+    let state = FederationQueueState::load(&mut DbPool::Pool(&pool), instance.id).await?;
+    let (stats_sender, _) = unbounded_channel();
+    let (report_send_result, receive_send_result) = unbounded_channel();
+    Ok(InstanceWorker {
+      inbox_collector: RealCommunityInboxCollector::new_real(
+        pool.clone(),
+        instance.id,
+        instance.domain.clone(),
+      ),
+      federation_worker_config: FederationWorkerConfig {
+        concurrent_sends_per_instance: 1,
+        coalesce_votes,
+        ..Default::default()
+      },
+      instance,
+      stop: CancellationToken::new(),
+      federation_lib_config: config,
+      stats_sender,
+      state,
+      last_state_insert: Utc.timestamp_nanos(0),
+      pool,
+      receive_send_result,
+      report_send_result,
+      successfuls: BinaryHeap::new(),
+      pending: VecDeque::new(),
+      in_flight: HashMap::new(),
+    })
+  }
+
+  fn pending(id: i64, kind: &str, actor: &str, object: &str) -> LemmyResult<PendingActivity> {
+    let data = json!({
+      "type": kind,
+      "actor": format!("http://ds9.lemmy.ml/u/{actor}"),
+      "object": format!("http://ds9.lemmy.ml/{object}"),
+      "id": format!("http://ds9.lemmy.ml/activities/{id}"),
+    });
+    let class = ActivityClass::classify(&data);
+    let activity = SentActivity {
+      id: ActivityId(id),
+      ap_id: Url::parse(&format!("http://ds9.lemmy.ml/activities/{id}"))?.into(),
+      data,
+      sensitive: false,
+      published_at: Utc::now(),
+      send_inboxes: vec![],
+      send_community_followers_of: None,
+      send_all_instances: false,
+      actor_type: ActorType::Person,
+      actor_apub_id: None,
+    };
+    Ok(PendingActivity {
+      activity: Arc::new(activity),
+      inbox_urls: vec![],
+      class,
+    })
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_lanes() -> LemmyResult<()> {
+    let mut worker = idle_worker(false).await?;
+    worker.push_pending(pending(1, "Like", "bob", "post/1")?);
+    worker.push_pending(pending(2, "Create", "carol", "comment/2")?);
+    worker.push_pending(pending(3, "Delete", "mod", "post/3")?);
+
+    // moderation overtakes older content and votes
+    assert_eq!(Some(2), worker.next_to_send()?);
+    let moderation = worker.pending.remove(2).unwrap();
+    worker
+      .in_flight
+      .insert(moderation.activity.id, moderation.class);
+
+    // content is sent next, while the moderation lane is busy
+    assert_eq!(Some(1), worker.next_to_send()?);
+    let content = worker.pending.remove(1).unwrap();
+    worker.in_flight.insert(content.activity.id, content.class);
+
+    // votes have their own lane
+    assert_eq!(Some(0), worker.next_to_send()?);
+    let vote = worker.pending.remove(0).unwrap();
+    worker.in_flight.insert(vote.activity.id, vote.class);
+
+    // the vote lane is full now
+    worker.push_pending(pending(4, "Like", "dave", "post/4")?);
+    assert_eq!(None, worker.next_to_send()?);
+
+    Instance::delete(&mut worker.pool(), worker.instance.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_lanes_keep_order_of_actor() -> LemmyResult<()> {
+    let mut worker = idle_worker(false).await?;
+    worker.push_pending(pending(1, "Create", "alice", "post/1")?);
+    // deleting or banning the user must not overtake their post
+    worker.push_pending(pending(2, "Delete", "alice", "u/alice")?);
+    worker.push_pending(pending(3, "Block", "admin", "u/alice")?);
+    assert_eq!(Some(0), worker.next_to_send()?);
+
+    Instance::delete(&mut worker.pool(), worker.instance.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_coalesce_votes() -> LemmyResult<()> {
+    let mut worker = idle_worker(true).await?;
+    assert!(!worker.push_pending(pending(1, "Like", "bob", "post/1")?));
+    assert!(!worker.push_pending(pending(2, "Like", "carol", "post/1")?));
+    assert!(worker.push_pending(pending(3, "Dislike", "bob", "post/1")?));
+
+    // only the latest vote by bob is sent, the older one counts as successful
+    let pending_ids: Vec<_> = worker.pending.iter().map(|p| p.activity.id).collect();
+    assert_eq!(vec![ActivityId(2), ActivityId(3)], pending_ids);
+    let skipped = worker.successfuls.pop().unwrap();
+    assert_eq!(ActivityId(1), skipped.activity_id);
+    assert!(skipped.was_skipped);
+
+    Instance::delete(&mut worker.pool(), worker.instance.id).await?;
+    Ok(())
+  }
+
   async fn wait_receive(
     expected_fail_count: i32,
     rec: &mut UnboundedReceiver<FederationQueueStateWithDomain>,
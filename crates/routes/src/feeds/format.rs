@@ -0,0 +1,450 @@
+use actix_web::{
+  http::header::{Accept, ETag, EntityTag, Header, IfNoneMatch, VARY},
+  HttpMessage,
+  HttpRequest,
+  HttpResponse,
+};
+use atom_syndication::{Content, Entry, Feed, Link, Text};
+use chrono::{DateTime, FixedOffset, Utc};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_utils::error::LemmyResult;
+use rss::{Channel, Item};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// Feeds are built as RSS channels, and converted to the other formats when requested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum FeedFormat {
+  Rss,
+  Atom,
+  Json,
+}
+
+impl FeedFormat {
+  /// Feeds ending with `.atom` or `.json` always use that format. For `.xml`, which was used for
+  /// all feeds before, the format can be chosen with the `Accept` header.
+  fn from_request(req: &HttpRequest) -> Self {
+    match req.match_info().get("format") {
+      Some("atom") => FeedFormat::Atom,
+      Some("json") => FeedFormat::Json,
+      _ => Accept::parse(req)
+        .ok()
+        .and_then(|accept| {
+          accept
+            .ranked()
+            .iter()
+            .find_map(|mime| Self::from_mime(mime.essence_str()))
+        })
+        .unwrap_or(FeedFormat::Rss),
+    }
+  }
+
+  fn from_mime(mime: &str) -> Option<Self> {
+    match mime {
+      "application/rss+xml" => Some(FeedFormat::Rss),
+      "application/atom+xml" => Some(FeedFormat::Atom),
+      "application/feed+json" | "application/json" => Some(FeedFormat::Json),
+      _ => None,
+    }
+  }
+
+  fn content_type(self) -> &'static str {
+    match self {
+      FeedFormat::Rss => "application/rss+xml",
+      FeedFormat::Atom => "application/atom+xml",
+      FeedFormat::Json => "application/feed+json",
+    }
+  }
+
+  fn render(self, channel: Channel, feed_url: &str) -> LemmyResult<String> {
+    Ok(match self {
+      FeedFormat::Rss => channel.to_string(),
+      FeedFormat::Atom => to_atom(channel, feed_url).to_string(),
+      FeedFormat::Json => serde_json::to_string(&to_json_feed(channel, feed_url))?,
+    })
+  }
+}
+
+/// Render the feed in the requested format. Responds with `304 Not Modified` if the feed reader
+/// already has the current version, based on the `If-None-Match` header.
+///
+/// There is no `Last-Modified` header, because feeds which aren't sorted by date can change
+/// without getting any newer items.
+pub(super) fn feed_response(
+  req: &HttpRequest,
+  channel: Channel,
+  context: &LemmyContext,
+) -> LemmyResult<HttpResponse> {
+  let format = FeedFormat::from_request(req);
+  let feed_url = format!(
+    "{}{}",
+    context.settings().get_protocol_and_hostname(),
+    req.uri().path()
+  );
+  let body = format.render(channel, &feed_url)?;
+  let etag = EntityTag::new_strong(format!("{:x}", Sha256::digest(&body)));
+
+  if is_not_modified(req, &etag) {
+    return Ok(
+      HttpResponse::NotModified()
+        .insert_header(ETag(etag))
+        .insert_header((VARY, "Accept"))
+        .finish(),
+    );
+  }
+  Ok(
+    HttpResponse::Ok()
+      .content_type(format.content_type())
+      .insert_header(ETag(etag))
+      // the format of `.xml` feeds depends on the Accept header
+      .insert_header((VARY, "Accept"))
+      .body(body),
+  )
+}
+
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag) -> bool {
+  match req.get_header::<IfNoneMatch>() {
+    Some(IfNoneMatch::Any) => true,
+    Some(IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(etag)),
+    None => false,
+  }
+}
+
+fn item_date(item: &Item) -> Option<DateTime<FixedOffset>> {
+  DateTime::parse_from_rfc2822(item.pub_date.as_deref()?).ok()
+}
+
+/// Creators are stored as urls in the Dublin Core extension.
+fn item_creators(item: &Item) -> Vec<String> {
+  item
+    .dublin_core_ext
+    .as_ref()
+    .map(|dc| dc.creators.clone())
+    .unwrap_or_default()
+}
+
+/// Url of the thumbnail from the `media:content` extension.
+fn item_thumbnail(item: &Item) -> Option<String> {
+  item
+    .extensions
+    .get("media")?
+    .get("content")?
+    .first()?
+    .attrs
+    .get("url")
+    .cloned()
+}
+
+fn item_id(item: &Item) -> String {
+  item
+    .guid
+    .as_ref()
+    .map(|g| g.value.clone())
+    .or_else(|| item.link.clone())
+    .unwrap_or_default()
+}
+
+fn to_atom(channel: Channel, feed_url: &str) -> Feed {
+  let updated = channel
+    .items
+    .iter()
+    .filter_map(item_date)
+    .max()
+    .unwrap_or_else(|| Utc::now().fixed_offset());
+  let entries = channel
+    .items
+    .into_iter()
+    .map(|item| {
+      let date = item_date(&item);
+      let mut links: Vec<_> = item
+        .link
+        .iter()
+        .map(|href| Link {
+          href: href.clone(),
+          ..Default::default()
+        })
+        .collect();
+      if let Some(enclosure) = &item.enclosure {
+        links.push(Link {
+          href: enclosure.url.clone(),
+          rel: "enclosure".to_string(),
+          mime_type: Some(enclosure.mime_type.clone()),
+          ..Default::default()
+        });
+      }
+      Entry {
+        id: item_id(&item),
+        title: Text::plain(item.title.clone().unwrap_or_default()),
+        updated: date.unwrap_or(updated),
+        published: date,
+        authors: item_creators(&item)
+          .into_iter()
+          .map(|url| atom_syndication::Person {
+            name: url.clone(),
+            uri: Some(url),
+            ..Default::default()
+          })
+          .collect(),
+        categories: item
+          .categories
+          .iter()
+          .map(|c| atom_syndication::Category {
+            term: c.name.clone(),
+            scheme: c.domain.clone(),
+            ..Default::default()
+          })
+          .collect(),
+        links,
+        content: item.description.map(|d| Content {
+          value: Some(d),
+          content_type: Some("html".to_string()),
+          ..Default::default()
+        }),
+        ..Default::default()
+      }
+    })
+    .collect();
+
+  Feed {
+    id: feed_url.to_string(),
+    title: Text::plain(channel.title),
+    subtitle: (!channel.description.is_empty()).then(|| Text::html(channel.description)),
+    updated,
+    links: vec![
+      Link {
+        href: channel.link,
+        ..Default::default()
+      },
+      Link {
+        href: feed_url.to_string(),
+        rel: "self".to_string(),
+        mime_type: Some(FeedFormat::Atom.content_type().to_string()),
+        ..Default::default()
+      },
+    ],
+    entries,
+    ..Default::default()
+  }
+}
+
+/// See https://www.jsonfeed.org/version/1.1/
+#[derive(Serialize)]
+struct JsonFeed {
+  version: &'static str,
+  title: String,
+  home_page_url: String,
+  feed_url: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  description: Option<String>,
+  items: Vec<JsonFeedItem>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem {
+  id: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  content_html: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  image: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  date_published: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  authors: Vec<JsonFeedAuthor>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  tags: Vec<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  attachments: Vec<JsonFeedAttachment>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor {
+  url: String,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAttachment {
+  url: String,
+  mime_type: String,
+}
+
+fn to_json_feed(channel: Channel, feed_url: &str) -> JsonFeed {
+  let items = channel
+    .items
+    .into_iter()
+    .map(|item| JsonFeedItem {
+      id: item_id(&item),
+      image: item_thumbnail(&item),
+      date_published: item_date(&item).map(|d| d.to_rfc3339()),
+      authors: item_creators(&item)
+        .into_iter()
+        .map(|url| JsonFeedAuthor { url })
+        .collect(),
+      tags: item.categories.iter().map(|c| c.name.clone()).collect(),
+      attachments: item
+        .enclosure
+        .iter()
+        .map(|e| JsonFeedAttachment {
+          url: e.url.clone(),
+          mime_type: e.mime_type.clone(),
+        })
+        .collect(),
+      url: item.link,
+      title: item.title,
+      content_html: item.description,
+    })
+    .collect();
+
+  JsonFeed {
+    version: JSON_FEED_VERSION,
+    title: channel.title,
+    home_page_url: channel.link,
+    feed_url: feed_url.to_string(),
+    description: (!channel.description.is_empty()).then_some(channel.description),
+    items,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::test::TestRequest;
+  use pretty_assertions::assert_eq;
+  use rss::{extension::dublincore::DublinCoreExtension, Category, Guid};
+  use serde_json::Value;
+
+  fn channel() -> Channel {
+    Channel {
+      title: "Lemmy - rust".to_string(),
+      link: "https://lemmy.example/c/rust".to_string(),
+      items: vec![Item {
+        title: Some("First post".to_string()),
+        link: Some("https://lemmy.example/post/1".to_string()),
+        guid: Some(Guid {
+          permalink: true,
+          value: "https://lemmy.example/post/1".to_string(),
+        }),
+        pub_date: Some("Tue, 01 Jul 2025 10:00:00 +0000".to_string()),
+        description: Some("<p>Hello</p>".to_string()),
+        dublin_core_ext: Some(DublinCoreExtension {
+          creators: vec!["https://lemmy.example/u/alice".to_string()],
+          ..Default::default()
+        }),
+        categories: vec![Category {
+          name: "Rust".to_string(),
+          domain: Some("https://lemmy.example/c/rust".to_string()),
+        }],
+        ..Default::default()
+      }],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_feed_format_from_request() {
+    let format = |uri: &str, accept: &str, ext: &str| {
+      let req = TestRequest::with_uri(uri)
+        .insert_header(("Accept", accept))
+        .param("format", ext)
+        .to_http_request();
+      FeedFormat::from_request(&req)
+    };
+    assert_eq!(FeedFormat::Rss, format("/feeds/all.xml", "*/*", "xml"));
+    assert_eq!(
+      FeedFormat::Atom,
+      format("/feeds/all.xml", "application/atom+xml", "xml")
+    );
+    assert_eq!(
+      FeedFormat::Json,
+      format(
+        "/feeds/all.xml",
+        "application/rss+xml;q=0.5, application/feed+json",
+        "xml"
+      )
+    );
+    assert_eq!(
+      FeedFormat::Json,
+      format("/feeds/all.json", "application/rss+xml", "json")
+    );
+    assert_eq!(FeedFormat::Atom, format("/feeds/all.atom", "*/*", "atom"));
+  }
+
+  #[test]
+  fn test_atom_feed() -> LemmyResult<()> {
+    let atom = to_atom(channel(), "https://lemmy.example/feeds/c/rust.atom");
+    assert_eq!("https://lemmy.example/feeds/c/rust.atom", atom.id);
+    assert_eq!(
+      DateTime::parse_from_rfc3339("2025-07-01T10:00:00Z")?,
+      atom.updated
+    );
+    let Some(entry) = atom.entries.first() else {
+      panic!("Feed should have an entry");
+    };
+    assert_eq!("https://lemmy.example/post/1", entry.id);
+    assert_eq!("First post", entry.title.value);
+    assert_eq!(
+      vec!["https://lemmy.example/u/alice"],
+      entry
+        .authors
+        .iter()
+        .map(|a| a.name.as_str())
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(
+      Some("<p>Hello</p>"),
+      entry.content.as_ref().and_then(|c| c.value.as_deref())
+    );
+
+    // must be valid Atom which can be read again
+    let parsed: Feed = atom.to_string().parse()?;
+    assert_eq!(1, parsed.entries.len());
+    Ok(())
+  }
+
+  #[test]
+  fn test_json_feed() -> LemmyResult<()> {
+    let feed = to_json_feed(channel(), "https://lemmy.example/feeds/c/rust.json");
+    let json = serde_json::to_value(feed)?;
+    let get = |pointer: &str| json.pointer(pointer).and_then(Value::as_str);
+    assert_eq!(Some(JSON_FEED_VERSION), get("/version"));
+    assert_eq!(
+      Some("https://lemmy.example/feeds/c/rust.json"),
+      get("/feed_url")
+    );
+    assert_eq!(Some("https://lemmy.example/post/1"), get("/items/0/id"));
+    assert_eq!(
+      Some("2025-07-01T10:00:00+00:00"),
+      get("/items/0/date_published")
+    );
+    assert_eq!(
+      Some("https://lemmy.example/u/alice"),
+      get("/items/0/authors/0/url")
+    );
+    assert_eq!(Some("Rust"), get("/items/0/tags/0"));
+    assert!(json.pointer("/items/0/attachments").is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn test_conditional_get() {
+    let etag = EntityTag::new_strong("abc".to_string());
+    let not_modified = |name: &str, value: &str| {
+      let req = TestRequest::default()
+        .insert_header((name, value))
+        .to_http_request();
+      is_not_modified(&req, &etag)
+    };
+    assert!(not_modified("If-None-Match", "\"abc\""));
+    assert!(not_modified("If-None-Match", "W/\"abc\""));
+    assert!(!not_modified("If-None-Match", "\"def\""));
+    assert!(!not_modified(
+      "If-Modified-Since",
+      "Tue, 01 Jul 2025 10:00:00 GMT"
+    ));
+  }
+}
@@ -1,16 +1,19 @@
 use actix_web::{error::ErrorBadRequest, web, Error, HttpRequest, HttpResponse, Result};
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
+use format::feed_response;
 use lemmy_api_utils::{
   context::LemmyContext,
   utils::{check_private_instance, local_user_view_from_jwt},
 };
 use lemmy_db_schema::{
-  source::{community::Community, person::Person},
+  newtypes::{PostId, TagId},
+  source::{community::Community, multi_community::MultiCommunity, person::Person},
   traits::ApubActor,
   PersonContentType,
 };
-use lemmy_db_schema_file::enums::{ListingType, PostSortType};
+use lemmy_db_schema_file::enums::{CommentSortType, ListingType, PostSortType};
+use lemmy_db_views_comment::{impls::CommentQuery, CommentView};
 use lemmy_db_views_inbox_combined::{impls::InboxCombinedQuery, InboxCombinedView};
 use lemmy_db_views_modlog_combined::{impls::ModlogCombinedQuery, ModlogCombinedView};
 use lemmy_db_views_person_content_combined::impls::PersonContentCombinedQuery;
@@ -18,7 +21,7 @@ use lemmy_db_views_post::{impls::PostQuery, PostView};
 use lemmy_db_views_site::SiteView;
 use lemmy_utils::{
   cache_header::cache_1hour,
-  error::{LemmyError, LemmyErrorType, LemmyResult},
+  error::{LemmyError, LemmyErrorExt, LemmyErrorType, LemmyResult},
   settings::structs::Settings,
   utils::markdown::markdown_to_html,
 };
@@ -33,12 +36,18 @@ use rss::{
 use serde::Deserialize;
 use std::{collections::BTreeMap, str::FromStr, sync::LazyLock};
 
+mod format;
+
 const RSS_FETCH_LIMIT: i64 = 20;
 
 #[derive(Deserialize)]
 struct Params {
   sort: Option<String>,
   limit: Option<i64>,
+  /// Listing type of the front feed, defaults to subscribed
+  type_: Option<ListingType>,
+  time_range_seconds: Option<i32>,
+  tag_id: Option<TagId>,
 }
 
 impl Params {
@@ -52,23 +61,57 @@ impl Params {
   fn get_limit(&self) -> i64 {
     self.limit.unwrap_or(RSS_FETCH_LIMIT)
   }
+  fn post_query(&self) -> Result<PostQuery<'static>, Error> {
+    Ok(PostQuery {
+      listing_type: self.type_,
+      sort: Some(self.sort_type()?),
+      time_range_seconds: self.time_range_seconds,
+      tag_id: self.tag_id,
+      limit: Some(self.get_limit()),
+      ..Default::default()
+    })
+  }
+  /// Comment feeds show the newest comments by default.
+  fn comment_query(&self) -> Result<CommentQuery<'static>, Error> {
+    let sort = self
+      .sort
+      .as_deref()
+      .map(CommentSortType::from_str)
+      .transpose()
+      .map_err(ErrorBadRequest)?;
+    Ok(CommentQuery {
+      sort: Some(sort.unwrap_or(CommentSortType::New)),
+      time_range_seconds: self.time_range_seconds,
+      limit: Some(self.get_limit()),
+      ..Default::default()
+    })
+  }
 }
 
 enum RequestType {
   Community,
+  MultiCommunity,
+  Post,
   User,
   Front,
   Inbox,
   Modlog,
 }
 
+/// All feeds are available as RSS (`.xml`), Atom (`.atom`) and JSON Feed (`.json`).
 pub fn config(cfg: &mut web::ServiceConfig) {
   cfg.service(
     web::scope("/feeds")
-      .route("/{type}/{name}.xml", web::get().to(get_feed))
-      .route("/all.xml", web::get().to(get_all_feed).wrap(cache_1hour()))
       .route(
-        "/local.xml",
+        "/{type}/{name}.{format:xml|atom|json}",
+        web::get().to(get_feed),
+      )
+      .route(
+        "/all.{format:xml|atom|json}",
+        web::get().to(get_all_feed).wrap(cache_1hour()),
+      )
+      .route(
+        "/local.{format:xml|atom|json}",
         web::get().to(get_local_feed).wrap(cache_1hour()),
       ),
   );
@@ -88,40 +131,26 @@ static RSS_NAMESPACE: LazyLock<BTreeMap<String, String>> = LazyLock::new(|| {
 });
 
 async fn get_all_feed(
+  req: HttpRequest,
   info: web::Query<Params>,
   context: web::Data<LemmyContext>,
 ) -> Result<HttpResponse, Error> {
-  Ok(
-    get_feed_data(
-      &context,
-      ListingType::All,
-      info.sort_type()?,
-      info.get_limit(),
-    )
-    .await?,
-  )
+  Ok(get_feed_data(&req, &context, ListingType::All, info.post_query()?).await?)
 }
 
 async fn get_local_feed(
+  req: HttpRequest,
   info: web::Query<Params>,
   context: web::Data<LemmyContext>,
 ) -> Result<HttpResponse, Error> {
-  Ok(
-    get_feed_data(
-      &context,
-      ListingType::Local,
-      info.sort_type()?,
-      info.get_limit(),
-    )
-    .await?,
-  )
+  Ok(get_feed_data(&req, &context, ListingType::Local, info.post_query()?).await?)
 }
 
 async fn get_feed_data(
+  req: &HttpRequest,
   context: &LemmyContext,
   listing_type: ListingType,
-  sort_type: PostSortType,
-  limit: i64,
+  query: PostQuery<'_>,
 ) -> LemmyResult<HttpResponse> {
   let site_view = SiteView::read_local(&mut context.pool()).await?;
 
@@ -129,9 +158,7 @@ async fn get_feed_data(
 
   let posts = PostQuery {
     listing_type: (Some(listing_type)),
-    sort: (Some(sort_type)),
-    limit: (Some(limit)),
-    ..Default::default()
+    ..query
   }
   .list(&site_view.site, &mut context.pool())
   .await?;
@@ -150,12 +177,7 @@ async fn get_feed_data(
     channel.set_description(&site_desc);
   }
 
-  let rss = channel.to_string();
-  Ok(
-    HttpResponse::Ok()
-      .content_type("application/rss+xml")
-      .body(rss),
-  )
+  feed_response(req, channel, context)
 }
 
 async fn get_feed(
@@ -169,6 +191,8 @@ async fn get_feed(
   let request_type = match req_type.as_str() {
     "u" => RequestType::User,
     "c" => RequestType::Community,
+    "m" => RequestType::MultiCommunity,
+    "post" => RequestType::Post,
     "front" => RequestType::Front,
     "inbox" => RequestType::Inbox,
     "modlog" => RequestType::Modlog,
@@ -177,24 +201,18 @@ async fn get_feed(
 
   let builder = match request_type {
     RequestType::User => get_feed_user(&context, &info.get_limit(), &param).await,
-    RequestType::Community => {
-      get_feed_community(&context, &info.sort_type()?, &info.get_limit(), &param).await
-    }
-    RequestType::Front => {
-      get_feed_front(&context, &info.sort_type()?, &info.get_limit(), &param).await
+    RequestType::Community => get_feed_community(&context, info.post_query()?, &param).await,
+    RequestType::MultiCommunity => {
+      get_feed_multi_community(&context, info.post_query()?, &param).await
     }
+    RequestType::Post => get_feed_post(&context, info.comment_query()?, &param).await,
+    RequestType::Front => get_feed_front(&context, info.post_query()?, &param).await,
     RequestType::Inbox => get_feed_inbox(&context, &param).await,
     RequestType::Modlog => get_feed_modlog(&context, &param).await,
   }
   .map_err(ErrorBadRequest)?;
 
-  let rss = builder.to_string();
-
-  Ok(
-    HttpResponse::Ok()
-      .content_type("application/rss+xml")
-      .body(rss),
-  )
+  Ok(feed_response(&req, builder, &context)?)
 }
 
 async fn get_feed_user(
@@ -241,8 +259,7 @@ async fn get_feed_user(
 
 async fn get_feed_community(
   context: &LemmyContext,
-  sort_type: &PostSortType,
-  limit: &i64,
+  query: PostQuery<'_>,
   community_name: &str,
 ) -> LemmyResult<Channel> {
   let site_view = SiteView::read_local(&mut context.pool()).await?;
@@ -256,10 +273,8 @@ async fn get_feed_community(
   check_private_instance(&None, &site_view.local_site)?;
 
   let posts = PostQuery {
-    sort: (Some(*sort_type)),
     community_id: (Some(community.id)),
-    limit: (Some(*limit)),
-    ..Default::default()
+    ..query
   }
   .list(&site_view.site, &mut context.pool())
   .await?;
@@ -281,10 +296,93 @@ async fn get_feed_community(
   Ok(channel)
 }
 
+async fn get_feed_multi_community(
+  context: &LemmyContext,
+  query: PostQuery<'_>,
+  multi_name: &str,
+) -> LemmyResult<Channel> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  let multi = MultiCommunity::read_from_name(&mut context.pool(), multi_name).await?;
+
+  check_private_instance(&None, &site_view.local_site)?;
+
+  let posts = PostQuery {
+    multi_community_id: (Some(multi.id)),
+    ..query
+  }
+  .list(&site_view.site, &mut context.pool())
+  .await?;
+
+  let items = create_post_items(posts, context.settings())?;
+
+  let mut channel = Channel {
+    namespaces: RSS_NAMESPACE.clone(),
+    title: format!(
+      "{} - {}",
+      site_view.site.name,
+      multi.title.unwrap_or(multi.name)
+    ),
+    link: multi.ap_id.to_string(),
+    items,
+    ..Default::default()
+  };
+
+  if let Some(multi_desc) = multi.description {
+    channel.set_description(markdown_to_html(&multi_desc));
+  }
+
+  Ok(channel)
+}
+
+/// Gets the comments of a single post
+async fn get_feed_post(
+  context: &LemmyContext,
+  query: CommentQuery<'_>,
+  post_id: &str,
+) -> LemmyResult<Channel> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  let post_id = PostId(post_id.parse().with_lemmy_type(LemmyErrorType::NotFound)?);
+  let post_view = PostView::read(
+    &mut context.pool(),
+    post_id,
+    None,
+    site_view.site.instance_id,
+    false,
+  )
+  .await?;
+  if !post_view.community.visibility.can_view_without_login() {
+    return Err(LemmyErrorType::NotFound.into());
+  }
+
+  check_private_instance(&None, &site_view.local_site)?;
+
+  let comments = CommentQuery {
+    post_id: (Some(post_id)),
+    ..query
+  }
+  .list(&site_view.site, &mut context.pool())
+  .await?;
+
+  let items = create_comment_items(comments, context.settings())?;
+
+  let mut channel = Channel {
+    namespaces: RSS_NAMESPACE.clone(),
+    title: format!("{} - {}", site_view.site.name, post_view.post.name),
+    link: post_view.post.local_url(context.settings())?.to_string(),
+    items,
+    ..Default::default()
+  };
+
+  if let Some(post_body) = post_view.post.body {
+    channel.set_description(markdown_to_html(&post_body));
+  }
+
+  Ok(channel)
+}
+
 async fn get_feed_front(
   context: &LemmyContext,
-  sort_type: &PostSortType,
-  limit: &i64,
+  query: PostQuery<'_>,
   jwt: &str,
 ) -> LemmyResult<Channel> {
   let site_view = SiteView::read_local(&mut context.pool()).await?;
@@ -292,12 +390,11 @@ async fn get_feed_front(
 
   check_private_instance(&Some(local_user.clone()), &site_view.local_site)?;
 
+  let listing_type = query.listing_type.unwrap_or(ListingType::Subscribed);
   let posts = PostQuery {
-    listing_type: (Some(ListingType::Subscribed)),
+    listing_type: (Some(listing_type)),
     local_user: (Some(&local_user.local_user)),
-    sort: (Some(*sort_type)),
-    limit: (Some(*limit)),
-    ..Default::default()
+    ..query
   }
   .list(&site_view.site, &mut context.pool())
   .await?;
@@ -306,7 +403,7 @@ async fn get_feed_front(
   let items = create_post_items(posts, context.settings())?;
   let mut channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
-    title: format!("{} - Subscribed", site_view.site.name),
+    title: format!("{} - {listing_type}", site_view.site.name),
     link: protocol_and_hostname,
     items,
     ..Default::default()
@@ -439,6 +536,23 @@ fn create_reply_and_mention_items(
   Ok(reply_items)
 }
 
+fn create_comment_items(comments: Vec<CommentView>, settings: &Settings) -> LemmyResult<Vec<Item>> {
+  comments
+    .iter()
+    .filter(|c| !c.comment.deleted && !c.comment.removed)
+    .map(|c| {
+      let comment_url = c.comment.local_url(settings)?;
+      build_item(
+        &c.creator,
+        &c.comment.published_at,
+        comment_url.as_str(),
+        &c.comment.content,
+        settings,
+      )
+    })
+    .collect()
+}
+
 fn create_modlog_items(
   modlog: Vec<ModlogCombinedView>,
   settings: &Settings,
@@ -484,6 +598,14 @@ fn create_modlog_items(
         &v.admin_block_instance.reason,
         settings,
       ),
+      ModlogCombinedView::AdminImageModeration(v) => build_modlog_item(
+        &v.admin,
+        &v.admin_image_moderation.published_at,
+        &modlog_url,
+        &format!("Image moderation - {}", v.admin_image_moderation.action),
+        &v.admin_image_moderation.reason,
+        settings,
+      ),
       ModlogCombinedView::AdminPurgeComment(v) => build_modlog_item(
         &v.admin,
         &v.admin_purge_comment.published_at,
@@ -707,6 +829,10 @@ fn build_modlog_item(
   } else {
     None
   };
+  let dublin_core_ext = mod_.as_ref().map(|mod_| DublinCoreExtension {
+    creators: vec![mod_.ap_id.to_string()],
+    ..DublinCoreExtension::default()
+  });
 
   Ok(Item {
     title: Some(action.to_string()),
@@ -715,6 +841,7 @@ fn build_modlog_item(
     link: Some(url.to_owned()),
     guid,
     description: reason.clone(),
+    dublin_core_ext,
     ..Default::default()
   })
 }
@@ -732,6 +859,10 @@ fn build_item(
     value: url.to_owned(),
   });
   let description = Some(markdown_to_html(content));
+  let dublin_core_ext = Some(DublinCoreExtension {
+    creators: vec![creator.ap_id.to_string()],
+    ..DublinCoreExtension::default()
+  });
 
   Ok(Item {
     title: Some(format!("Reply from {}", creator.name)),
@@ -745,6 +876,7 @@ fn build_item(
     link: Some(url.to_owned()),
     guid,
     description,
+    dublin_core_ext,
     ..Default::default()
   })
 }
@@ -0,0 +1,208 @@
+//! Minimal html pages for posts, communities and users, rendered on the server. If enabled with
+//! `html_fallback`, these are served to browsers so that the instance stays readable without
+//! lemmy-ui. They also include OpenGraph tags for link previews in chat apps.
+use activitypub_federation::config::Data;
+use actix_web::{
+  guard::{Guard, GuardContext},
+  http::header::ACCEPT,
+  web::{self, get, resource, Path, Query, ServiceConfig},
+  HttpResponse,
+};
+use lemmy_api_crud::post::read::get_post;
+use lemmy_api_utils::{context::LemmyContext, utils::oembed_link};
+use lemmy_apub::api::{
+  list_comments::list_comments,
+  list_person_content::list_person_content,
+  list_posts::list_posts,
+  read_community::get_community,
+  read_person::read_person,
+};
+use lemmy_db_schema::newtypes::PostId;
+use lemmy_db_views_comment::api::GetComments;
+use lemmy_db_views_community::api::GetCommunity;
+use lemmy_db_views_person::api::GetPersonDetails;
+use lemmy_db_views_person_content_combined::ListPersonContent;
+use lemmy_db_views_post::api::{GetPost, GetPosts};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  rate_limit::RateLimit,
+};
+use render::{community_body, description, page, person_body, post_body, PageMeta};
+
+mod render;
+
+/// Comments below this depth are not shown.
+const MAX_COMMENT_DEPTH: i32 = 8;
+/// Number of posts shown for communities, and of posts and comments for users.
+const LIST_LIMIT: i64 = 20;
+
+pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
+  cfg
+    .service(
+      resource("/post/{post_id}")
+        .guard(HtmlRequestGuard)
+        .wrap(rate_limit.message())
+        .route(get().to(post_page)),
+    )
+    .service(
+      resource("/c/{community_name}")
+        .guard(HtmlRequestGuard)
+        .wrap(rate_limit.message())
+        .route(get().to(community_page)),
+    )
+    .service(
+      resource("/u/{user_name}")
+        .guard(HtmlRequestGuard)
+        .wrap(rate_limit.message())
+        .route(get().to(person_page)),
+    );
+}
+
+/// Only requests which accept html are handled here. ActivityPub fetches for the same paths fall
+/// through to the federation routes.
+struct HtmlRequestGuard;
+
+impl Guard for HtmlRequestGuard {
+  fn check(&self, ctx: &GuardContext) -> bool {
+    ctx
+      .head()
+      .headers
+      .get(ACCEPT)
+      .and_then(|accept| accept.to_str().ok())
+      .is_some_and(|accept| accept.contains("text/html"))
+  }
+}
+
+async fn post_page(
+  post_id: Path<String>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let post_id = PostId(post_id.parse().with_lemmy_type(LemmyErrorType::NotFound)?);
+  let data = GetPost {
+    id: Some(post_id),
+    comment_id: None,
+  };
+  // Handlers in the api crates take actix data instead of federation data
+  let post_view = get_post(
+    Query(data),
+    web::Data::new(LemmyContext::clone(&context)),
+    None,
+  )
+  .await?
+  .into_inner()
+  .post_view;
+  let data = GetComments {
+    post_id: Some(post_id),
+    max_depth: Some(MAX_COMMENT_DEPTH),
+    ..Default::default()
+  };
+  let comments = list_comments(Query(data), context.reset_request_count(), None)
+    .await?
+    .into_inner()
+    .comments;
+
+  let post = &post_view.post;
+  let meta = PageMeta {
+    title: post.name.clone(),
+    description: post
+      .body
+      .as_deref()
+      .or(post.embed_description.as_deref())
+      .map(description),
+    url: post.ap_id.clone(),
+    image: post.thumbnail_url.clone().filter(|_| !post.nsfw),
+    type_: "article",
+    oembed: Some(oembed_link(post.ap_id.inner(), context.settings())),
+  };
+  html_response(&context, &meta, &post_body(&post_view, &comments)).await
+}
+
+async fn community_page(
+  name: Path<String>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let data = GetCommunity {
+    id: None,
+    name: Some(name.into_inner()),
+  };
+  let community = get_community(Query(data), context.reset_request_count(), None)
+    .await?
+    .into_inner()
+    .community_view
+    .community;
+  let data = GetPosts {
+    community_id: Some(community.id),
+    limit: Some(LIST_LIMIT),
+    ..Default::default()
+  };
+  let posts = list_posts(Query(data), context.reset_request_count(), None)
+    .await?
+    .into_inner()
+    .posts;
+
+  let meta = PageMeta {
+    title: community.title.clone(),
+    description: community
+      .description
+      .as_deref()
+      .or(community.sidebar.as_deref())
+      .map(description),
+    url: community.ap_id.clone(),
+    image: community
+      .icon
+      .clone()
+      .or(community.banner.clone())
+      .filter(|_| !community.nsfw),
+    type_: "website",
+    oembed: None,
+  };
+  html_response(&context, &meta, &community_body(&community, &posts)).await
+}
+
+async fn person_page(name: Path<String>, context: Data<LemmyContext>) -> LemmyResult<HttpResponse> {
+  let data = GetPersonDetails {
+    person_id: None,
+    username: Some(name.into_inner()),
+  };
+  let person = read_person(Query(data), context.reset_request_count(), None)
+    .await?
+    .into_inner()
+    .person_view
+    .person;
+  let data = ListPersonContent {
+    person_id: Some(person.id),
+    limit: Some(LIST_LIMIT),
+    ..Default::default()
+  };
+  let content = list_person_content(Query(data), context.reset_request_count(), None)
+    .await?
+    .into_inner()
+    .content;
+
+  let meta = PageMeta {
+    title: person
+      .display_name
+      .clone()
+      .unwrap_or_else(|| person.name.clone()),
+    description: person.bio.as_deref().map(description),
+    url: person.ap_id.clone(),
+    image: person.avatar.clone(),
+    type_: "profile",
+    oembed: None,
+  };
+  html_response(&context, &meta, &person_body(&person, &content)).await
+}
+
+async fn html_response(
+  context: &LemmyContext,
+  meta: &PageMeta,
+  body: &str,
+) -> LemmyResult<HttpResponse> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/html; charset=utf-8")
+      .body(page(&site_view.site.name, meta, body)),
+  )
+}
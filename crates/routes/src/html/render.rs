@@ -0,0 +1,349 @@
+use crate::utils::escape_html;
+use chrono::{DateTime, Utc};
+use lemmy_db_schema::{
+  newtypes::{CommentId, DbUrl},
+  source::{community::Community, person::Person},
+};
+use lemmy_db_views_comment::CommentView;
+use lemmy_db_views_person_content_combined::PersonContentCombinedView;
+use lemmy_db_views_post::PostView;
+use lemmy_utils::utils::markdown::markdown_to_html;
+use std::collections::HashMap;
+
+/// Maximum length of the OpenGraph description.
+const DESCRIPTION_LENGTH: usize = 300;
+
+const STYLE: &str = "body{max-width:50rem;margin:auto;padding:1rem;font-family:sans-serif;\
+line-height:1.5}img{max-width:100%}ul.comments{list-style:none;padding-left:1rem;\
+border-left:1px solid #ccc}.meta{color:#666;font-size:.9em}";
+
+/// OpenGraph metadata of a page, used for link previews.
+pub(super) struct PageMeta {
+  pub(super) title: String,
+  pub(super) description: Option<String>,
+  /// Canonical url of the object.
+  pub(super) url: DbUrl,
+  pub(super) image: Option<DbUrl>,
+  /// OpenGraph type, eg `article` or `profile`.
+  pub(super) type_: &'static str,
+  /// Discovery link for the oEmbed endpoint.
+  pub(super) oembed: Option<String>,
+}
+
+/// A complete html document with the given body.
+pub(super) fn page(site_name: &str, meta: &PageMeta, body: &str) -> String {
+  let mut head = vec![
+    og("og:site_name", site_name),
+    og("og:title", &meta.title),
+    og("og:url", meta.url.as_str()),
+    og("og:type", meta.type_),
+  ];
+  if let Some(description) = &meta.description {
+    head.push(format!(
+      "<meta name=\"description\" content=\"{}\">",
+      escape_html(description)
+    ));
+    head.push(og("og:description", description));
+  }
+  if let Some(oembed) = &meta.oembed {
+    head.push(format!(
+      "<link rel=\"alternate\" type=\"application/json+oembed\" href=\"{}\">",
+      escape_html(oembed)
+    ));
+  }
+  if let Some(image) = &meta.image {
+    head.push(og("og:image", image.as_str()));
+    head.push(og("twitter:card", "summary_large_image"));
+  } else {
+    head.push(og("twitter:card", "summary"));
+  }
+  format!(
+    "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title} - {site}</title>
+<link rel=\"canonical\" href=\"{url}\">
+<link rel=\"alternate\" type=\"application/activity+json\" href=\"{url}\">
+{head}
+<style>{STYLE}</style>
+</head>
+<body>
+<header><a href=\"/\">{site}</a></header>
+<main>
+{body}
+</main>
+</body>
+</html>",
+    title = escape_html(&meta.title),
+    site = escape_html(site_name),
+    url = escape_html(meta.url.as_str()),
+    head = head.join("\n"),
+  )
+}
+
+fn og(property: &str, content: &str) -> String {
+  format!(
+    "<meta property=\"{property}\" content=\"{}\">",
+    escape_html(content)
+  )
+}
+
+/// A post with its comment tree.
+pub(super) fn post_body(post_view: &PostView, comments: &[CommentView]) -> String {
+  let post = &post_view.post;
+  let mut html = format!(
+    "<article>\n<h1>{}</h1>\n<p class=\"meta\">{} in {} · {} · {} points · {} comments</p>\n",
+    escape_html(&post.name),
+    person_link(&post_view.creator),
+    community_link(&post_view.community),
+    time(post.published_at),
+    post.score,
+    post.comments,
+  );
+  if let Some(url) = &post.url {
+    html.push_str(&format!(
+      "<p><a href=\"{url}\" rel=\"nofollow noopener\">{url}</a></p>\n",
+      url = escape_html(url.as_str())
+    ));
+  }
+  if let Some(thumbnail) = post.thumbnail_url.as_ref().filter(|_| !post.nsfw) {
+    html.push_str(&format!(
+      "<p><img src=\"{}\" alt=\"{}\"></p>\n",
+      escape_html(thumbnail.as_str()),
+      escape_html(post.alt_text.as_deref().unwrap_or_default())
+    ));
+  }
+  if let Some(body) = &post.body {
+    html.push_str(&markdown_to_html(body));
+  }
+  html.push_str("</article>\n<section>\n<h2>Comments</h2>\n");
+  html.push_str(&comment_tree(comments));
+  html.push_str("</section>");
+  html
+}
+
+/// Comments are nested below their parents. Comments whose parent is missing are left out.
+pub(super) fn comment_tree(comments: &[CommentView]) -> String {
+  let mut children: HashMap<Option<CommentId>, Vec<&CommentView>> = HashMap::new();
+  for comment_view in comments {
+    children
+      .entry(parent_id(comment_view))
+      .or_default()
+      .push(comment_view);
+  }
+  comment_list(None, &children)
+}
+
+fn comment_list(
+  parent: Option<CommentId>,
+  children: &HashMap<Option<CommentId>, Vec<&CommentView>>,
+) -> String {
+  let Some(comments) = children.get(&parent) else {
+    return String::new();
+  };
+  let items: Vec<_> = comments
+    .iter()
+    .map(|comment_view| {
+      let comment = &comment_view.comment;
+      let content = if comment.removed {
+        "<p><em>removed by mod</em></p>".to_string()
+      } else if comment.deleted {
+        "<p><em>deleted by creator</em></p>".to_string()
+      } else {
+        markdown_to_html(&comment.content)
+      };
+      format!(
+        "<li id=\"comment-{id}\">\n<p class=\"meta\">{creator} · {time} · {score} points</p>\n\
+         {content}{replies}</li>",
+        id = comment.id.0,
+        creator = person_link(&comment_view.creator),
+        time = time(comment.published_at),
+        score = comment.score,
+        replies = comment_list(Some(comment.id), children),
+      )
+    })
+    .collect();
+  format!("<ul class=\"comments\">\n{}\n</ul>\n", items.join("\n"))
+}
+
+/// The parent comment id, based on the comment path like `0.12.34`. Top-level comments have no
+/// parent.
+fn parent_id(comment_view: &CommentView) -> Option<CommentId> {
+  comment_view
+    .comment
+    .path
+    .0
+    .split('.')
+    .rev()
+    .nth(1)
+    .and_then(|id| id.parse().ok())
+    .filter(|id| *id != 0)
+    .map(CommentId)
+}
+
+/// A community with its newest posts.
+pub(super) fn community_body(community: &Community, posts: &[PostView]) -> String {
+  let mut html = format!(
+    "<h1>{}</h1>\n<p class=\"meta\">!{} · {} subscribers · {} posts</p>\n",
+    escape_html(&community.title),
+    escape_html(&actor_name(
+      &community.name,
+      &community.ap_id,
+      community.local
+    )),
+    community.subscribers,
+    community.posts,
+  );
+  if let Some(sidebar) = &community.sidebar {
+    html.push_str(&markdown_to_html(sidebar));
+  }
+  html.push_str("<h2>Posts</h2>\n");
+  html.push_str(&post_list(posts));
+  html
+}
+
+/// A user profile with the newest posts and comments.
+pub(super) fn person_body(person: &Person, content: &[PersonContentCombinedView]) -> String {
+  let mut html = format!(
+    "<h1>{}</h1>\n<p class=\"meta\">@{} · joined {}</p>\n",
+    escape_html(person.display_name.as_deref().unwrap_or(&person.name)),
+    escape_html(&actor_name(&person.name, &person.ap_id, person.local)),
+    time(person.published_at),
+  );
+  if let Some(bio) = &person.bio {
+    html.push_str(&markdown_to_html(bio));
+  }
+  html.push_str("<h2>Posts and comments</h2>\n<ul>\n");
+  for item in content {
+    let item = match item {
+      PersonContentCombinedView::Post(post_view) => post_item(post_view),
+      PersonContentCombinedView::Comment(comment_view) => format!(
+        "<li><p class=\"meta\">comment on <a href=\"/post/{}#comment-{}\">{}</a> · {}</p>\n{}</li>",
+        comment_view.post.id.0,
+        comment_view.comment.id.0,
+        escape_html(&comment_view.post.name),
+        time(comment_view.comment.published_at),
+        markdown_to_html(&comment_view.comment.content),
+      ),
+    };
+    html.push_str(&item);
+    html.push('\n');
+  }
+  html.push_str("</ul>");
+  html
+}
+
+fn post_list(posts: &[PostView]) -> String {
+  let items: Vec<_> = posts.iter().map(post_item).collect();
+  format!("<ul>\n{}\n</ul>", items.join("\n"))
+}
+
+fn post_item(post_view: &PostView) -> String {
+  format!(
+    "<li><a href=\"/post/{}\">{}</a> <span class=\"meta\">{} · {} · {} comments</span></li>",
+    post_view.post.id.0,
+    escape_html(&post_view.post.name),
+    community_link(&post_view.community),
+    time(post_view.post.published_at),
+    post_view.post.comments,
+  )
+}
+
+fn person_link(person: &Person) -> String {
+  format!(
+    "<a href=\"/u/{}\">{}</a>",
+    escape_html(&actor_name(&person.name, &person.ap_id, person.local)),
+    escape_html(person.display_name.as_deref().unwrap_or(&person.name)),
+  )
+}
+
+fn community_link(community: &Community) -> String {
+  format!(
+    "<a href=\"/c/{}\">{}</a>",
+    escape_html(&actor_name(
+      &community.name,
+      &community.ap_id,
+      community.local
+    )),
+    escape_html(&community.title),
+  )
+}
+
+/// Name of a local actor, or `name@domain` for remote actors.
+fn actor_name(name: &str, ap_id: &DbUrl, local: bool) -> String {
+  match ap_id.inner().domain() {
+    Some(domain) if !local => format!("{name}@{domain}"),
+    _ => name.to_string(),
+  }
+}
+
+fn time(time: DateTime<Utc>) -> String {
+  format!(
+    "<time datetime=\"{}\">{}</time>",
+    time.to_rfc3339(),
+    time.format("%Y-%m-%d %H:%M UTC")
+  )
+}
+
+/// A plain text excerpt of markdown content, for the page description.
+pub(super) fn description(markdown: &str) -> String {
+  let text = markdown.split_whitespace().collect::<Vec<_>>().join(" ");
+  if text.chars().count() > DESCRIPTION_LENGTH {
+    let excerpt: String = text.chars().take(DESCRIPTION_LENGTH - 1).collect();
+    format!("{}…", excerpt.trim_end())
+  } else {
+    text
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lemmy_utils::error::LemmyResult;
+  use pretty_assertions::assert_eq;
+  use url::Url;
+
+  #[test]
+  fn test_page() -> LemmyResult<()> {
+    let meta = PageMeta {
+      title: "<script>alert(1)</script>".to_string(),
+      description: Some("Tom & Jerry".to_string()),
+      url: Url::parse("https://lemmy.example/post/1")?.into(),
+      image: Some(Url::parse("https://lemmy.example/pictrs/image/a.png")?.into()),
+      type_: "article",
+      oembed: Some("https://lemmy.example/api/v4/oembed?url=a&b".to_string()),
+    };
+    let html = page("Lemmy", &meta, "<p>body</p>");
+    assert!(html.contains("<title>&lt;script&gt;alert(1)&lt;/script&gt; - Lemmy</title>"));
+    assert!(html.contains("<meta property=\"og:description\" content=\"Tom &amp; Jerry\">"));
+    assert!(html.contains(
+      "<meta property=\"og:image\" content=\"https://lemmy.example/pictrs/image/a.png\">"
+    ));
+    assert!(html.contains("<link rel=\"canonical\" href=\"https://lemmy.example/post/1\">"));
+    assert!(html.contains(
+      "<link rel=\"alternate\" type=\"application/json+oembed\" \
+       href=\"https://lemmy.example/api/v4/oembed?url=a&amp;b\">"
+    ));
+    assert!(!html.contains("<script>"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_actor_name() -> LemmyResult<()> {
+    let ap_id: DbUrl = Url::parse("https://lemmy.ml/c/memes")?.into();
+    assert_eq!("memes", actor_name("memes", &ap_id, true));
+    assert_eq!("memes@lemmy.ml", actor_name("memes", &ap_id, false));
+    Ok(())
+  }
+
+  #[test]
+  fn test_description() {
+    assert_eq!("Line one line two", description("Line one\n\nline   two"));
+    let long = "word ".repeat(100);
+    let excerpt = description(&long);
+    assert_eq!(DESCRIPTION_LENGTH, excerpt.chars().count());
+    assert!(excerpt.ends_with("word…"));
+  }
+}
@@ -1,42 +1,61 @@
-use super::utils::{adapt_request, convert_header};
+use super::utils::{forwarded_headers, media_response};
 use actix_web::{
-  body::{BodyStream, BoxBody},
-  http::StatusCode,
+  body::BoxBody,
+  http::header::LOCATION,
   web::{Data, *},
   HttpRequest,
   HttpResponse,
   Responder,
 };
-use lemmy_api_utils::context::LemmyContext;
-use lemmy_db_schema::source::images::RemoteImage;
+use lemmy_api_utils::{
+  context::LemmyContext,
+  media_storage::{media_storage, MediaResponse},
+  request::fetch_pictrs_proxied_image_details,
+  utils::never_cache_media,
+};
+use lemmy_db_schema::source::images::{ImageDetails, ImageRedirect, RemoteImage};
 use lemmy_db_views_local_image::api::{ImageGetParams, ImageProxyParams};
-use lemmy_utils::error::LemmyResult;
-use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
 use url::Url;
 
 pub async fn get_image(
   filename: Path<String>,
   Query(params): Query<ImageGetParams>,
   req: HttpRequest,
+  local_user_view: Option<LocalUserView>,
   context: Data<LemmyContext>,
 ) -> LemmyResult<HttpResponse> {
   let name = &filename.into_inner();
+  let protocol_and_hostname = context.settings().get_protocol_and_hostname();
+
+  // Replaced uploads like transcoded videos are deleted, but existing links keep working
+  if let Some(redirect) = ImageRedirect::read(&mut context.pool(), name).await? {
+    let mut target = Url::parse(&format!(
+      "{protocol_and_hostname}/api/v4/image/{}",
+      redirect.target_alias
+    ))?;
+    target.set_query(req.uri().query());
+    return Ok(
+      HttpResponse::MovedPermanently()
+        .insert_header((LOCATION, target.as_str()))
+        .finish(),
+    );
+  }
 
-  // If there are no query params, the URL is original
-  let pictrs_url = context.settings().pictrs()?.url;
-  let processed_url = if params.file_type.is_none() && params.max_size.is_none() {
-    format!("{}image/original/{}", pictrs_url, name)
-  } else {
-    let file_type = file_type(params.file_type, name);
-    let mut url = format!("{}image/process.{}?src={}", pictrs_url, file_type, name);
-
-    if let Some(size) = params.max_size {
-      url = format!("{url}&thumbnail={size}",);
-    }
-    url
-  };
+  // Quarantined uploads can only be viewed by admins until they are approved
+  let image_url = Url::parse(&format!("{protocol_and_hostname}/api/v4/image/{name}"))?;
+  let quarantined = ImageDetails::read(&mut context.pool(), &image_url.into())
+    .await?
+    .is_some_and(|details| details.quarantined);
+  if quarantined && !local_user_view.is_some_and(|view| view.local_user.admin) {
+    Err(LemmyErrorType::NotFound)?
+  }
 
-  do_get_image(processed_url, req, &context).await
+  let res = media_storage(context.settings())?
+    .get(name, &params, forwarded_headers(&req), &context)
+    .await?;
+  media_response(res)
 }
 
 pub async fn image_proxy(
@@ -45,76 +64,42 @@ pub async fn image_proxy(
   context: Data<LemmyContext>,
 ) -> LemmyResult<Either<HttpResponse<()>, HttpResponse<BoxBody>>> {
   let url = Url::parse(&params.url)?;
-  let encoded_url = utf8_percent_encode(&params.url, NON_ALPHANUMERIC).to_string();
 
   // Check that url corresponds to a federated image so that this can't be abused as a proxy
   // for arbitrary purposes.
-  RemoteImage::validate(&mut context.pool(), url.clone().into()).await?;
+  let remote_image = RemoteImage::read(&mut context.pool(), &url.clone().into()).await?;
 
-  let pictrs_config = context.settings().pictrs()?;
-  let processed_url = if params.file_type.is_none() && params.max_size.is_none() {
-    format!("{}image/original?proxy={}", pictrs_config.url, encoded_url)
-  } else {
-    let file_type = file_type(params.file_type, url.path());
-    let mut url = format!(
-      "{}image/process.{}?proxy={}",
-      pictrs_config.url, file_type, encoded_url
-    );
-
-    if let Some(size) = params.max_size {
-      url = format!("{url}&thumbnail={size}",);
-    }
-    url
-  };
-
-  let bypass_proxy = pictrs_config
+  let bypass_proxy = context
+    .settings()
+    .pictrs()?
     .proxy_bypass_domains
     .iter()
     .any(|s| url.domain().is_some_and(|d| d == s));
-  if bypass_proxy {
+  if bypass_proxy || never_cache_media(&url, &context).await? {
     // Bypass proxy and redirect user to original image
     Ok(Either::Left(Redirect::to(url.to_string()).respond_to(&req)))
   } else {
     // Proxy the image data through Lemmy
-    Ok(Either::Right(
-      do_get_image(processed_url, req, &context).await?,
-    ))
-  }
-}
-
-pub(super) async fn do_get_image(
-  url: String,
-  req: HttpRequest,
-  context: &LemmyContext,
-) -> LemmyResult<HttpResponse> {
-  let mut client_req = adapt_request(&req, url, context);
-
-  if let Some(addr) = req.head().peer_addr {
-    client_req = client_req.header("X-Forwarded-For", addr.to_string());
-  }
-
-  if let Some(addr) = req.head().peer_addr {
-    client_req = client_req.header("X-Forwarded-For", addr.to_string());
-  }
-
-  let res = client_req.send().await?;
-
-  if res.status() == http::StatusCode::NOT_FOUND {
-    return Ok(HttpResponse::NotFound().finish());
-  }
-
-  let mut client_res = HttpResponse::build(StatusCode::from_u16(res.status().as_u16())?);
-
-  for (name, value) in res.headers().iter().filter(|(h, _)| *h != "connection") {
-    client_res.insert_header(convert_header(name, value));
+    let params = ImageGetParams {
+      file_type: params.file_type,
+      max_size: params.max_size,
+    };
+    let res = media_storage(context.settings())?
+      .proxy(&url, &params, forwarded_headers(&req), &context)
+      .await?;
+
+    remote_image.mark_accessed(&mut context.pool()).await?;
+    // Pict-rs caches the image again after it was evicted, so its size needs to be counted again.
+    // The response may be a resized variant, so the size of the original is read from pict-rs.
+    if let (false, MediaResponse::Pictrs(res)) = (remote_image.cached, &res) {
+      if res.status().is_success() {
+        let size = fetch_pictrs_proxied_image_details(&url, &context)
+          .await
+          .ok()
+          .and_then(|details| details.size);
+        RemoteImage::set_cached(&mut context.pool(), &remote_image.link, size).await?;
+      }
+    }
+    Ok(Either::Right(media_response(res)?))
   }
-
-  Ok(client_res.body(BodyStream::new(res.bytes_stream())))
-}
-
-/// Take file type from param, name, or use jpg if nothing is given
-pub(super) fn file_type(file_type: Option<String>, name: &str) -> String {
-  file_type
-    .clone()
-    .unwrap_or_else(|| name.split('.').next_back().unwrap_or("jpg").to_string())
 }
@@ -7,6 +7,7 @@ pub mod delete;
 pub mod download;
 pub mod upload;
 mod utils;
+mod video;
 
 pub async fn pictrs_health(context: Data<LemmyContext>) -> LemmyResult<Json<SuccessResponse>> {
   let pictrs_config = context.settings().pictrs()?;
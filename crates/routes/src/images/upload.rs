@@ -1,9 +1,14 @@
-use super::utils::{adapt_request, delete_old_image, make_send};
+use super::{
+  utils::{delete_old_image, forwarded_headers, make_send},
+  video::{create_poster_details, probe_video, spawn_transcode},
+};
 use actix_web::{self, web::*, HttpRequest};
 use lemmy_api_utils::{
   context::LemmyContext,
-  request::PictrsResponse,
-  utils::{is_admin, is_mod_or_admin},
+  image_moderation::moderate_upload,
+  media_storage::{media_storage, Upload},
+  request::delete_image_alias,
+  utils::{is_admin, is_mod_or_admin, media_quota},
 };
 use lemmy_db_schema::{
   source::{
@@ -17,9 +22,12 @@ use lemmy_db_schema::{
 use lemmy_db_views_community::api::CommunityIdQuery;
 use lemmy_db_views_local_image::api::UploadImageResponse;
 use lemmy_db_views_local_user::LocalUserView;
-use lemmy_utils::error::{LemmyErrorType, LemmyResult};
-use reqwest::Body;
-use std::time::Duration;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{
+  error::{LemmyErrorType, LemmyResult},
+  settings::structs::MediaStorageBackend,
+};
+use tracing::warn;
 use UploadType::*;
 
 pub enum UploadType {
@@ -172,51 +180,85 @@ pub async fn do_upload_image(
   local_user_view: &LocalUserView,
   context: &Data<LemmyContext>,
 ) -> LemmyResult<UploadImageResponse> {
-  let pictrs = context.settings().pictrs()?;
-  let max_upload_size = pictrs.max_upload_size.map(|m| m.to_string());
-  let image_url = format!("{}image", pictrs.url);
-
-  let mut client_req = adapt_request(&req, image_url, context);
-
-  // Set pictrs parameters to downscale images and restrict file types.
-  // https://git.asonix.dog/asonix/pict-rs/#api
-  client_req = match upload_type {
-    Avatar => {
-      let max_size = pictrs.max_avatar_size.to_string();
-      client_req.query(&[
-        ("resize", max_size.as_ref()),
-        ("allow_animation", "false"),
-        ("allow_video", "false"),
-      ])
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  let quota = media_quota(local_user_view, &local_site);
+  // Rejects uploads early when the quota is already used up. Concurrent uploads of the same user
+  // all pass this check, so the quota is only enforced after uploading.
+  if let Some(quota) = quota {
+    let usage = LocalImage::get_usage(&mut context.pool(), local_user_view.person.id).await?;
+    if usage >= quota {
+      Err(LemmyErrorType::MediaQuotaExceeded)?
     }
-    Banner => {
-      let max_size = pictrs.max_banner_size.to_string();
-      client_req.query(&[
-        ("resize", max_size.as_ref()),
-        ("allow_animation", "false"),
-        ("allow_video", "false"),
-      ])
+  }
+
+  let pictrs = context.settings().pictrs()?;
+  let (max_size, allow_video, allow_animation) = match upload_type {
+    Avatar => (Some(pictrs.max_avatar_size), false, false),
+    Banner => (Some(pictrs.max_banner_size), false, false),
+    Other => (pictrs.max_upload_size, pictrs.allow_video_uploads, true),
+  };
+  let upload = Upload {
+    body: Box::pin(make_send(body)),
+    headers: forwarded_headers(&req),
+    max_size,
+    allow_video,
+    allow_animation,
+  };
+  let mut images = media_storage(context.settings())?
+    .upload(upload, context)
+    .await?;
+
+  // The size is only known after uploading, so files which exceed the quota are deleted again.
+  // The usage is read again to include uploads which finished in the meantime. Uploads which
+  // finish at the same time can still exceed the quota together, as neither is stored yet.
+  if let Some(quota) = quota {
+    let usage = LocalImage::get_usage(&mut context.pool(), local_user_view.person.id).await?;
+    let upload_size: i64 = images
+      .iter()
+      .map(|i| i.details.size.unwrap_or_default())
+      .sum();
+    if usage + upload_size > quota {
+      for image in &images {
+        delete_image_alias(&image.file, context).await?;
+      }
+      Err(LemmyErrorType::MediaQuotaExceeded)?
     }
-    Other => {
-      let mut query = vec![("allow_video", pictrs.allow_video_uploads.to_string())];
-      if let Some(max_upload_size) = max_upload_size {
-        query.push(("resize", max_upload_size));
+  }
+
+  // Poster frames and transcoding need pict-rs
+  let uses_pictrs = context.settings().media_storage.backend == MediaStorageBackend::Pictrs;
+  let mut videos = vec![];
+  for image in &images {
+    let is_video = uses_pictrs && image.details.content_type.starts_with("video/");
+    let info = if is_video {
+      probe_video(image, context)
+        .await
+        .inspect_err(|e| warn!("Failed to process video {}: {e}", image.file))
+        .ok()
+        .flatten()
+    } else {
+      None
+    };
+    videos.push((is_video, info));
+  }
+
+  let protocol_and_hostname = context.settings().get_protocol_and_hostname();
+  let mut moderations = vec![];
+  for image in &images {
+    let image_url = image.image_url(&protocol_and_hostname)?;
+    match moderate_upload(&image.file, &image_url, local_user_view.person.id, context).await {
+      Ok(moderation) => moderations.push(moderation),
+      Err(e) => {
+        // The rejected file is already purged, remove the others from the same request
+        for image in &images {
+          delete_image_alias(&image.file, context).await.ok();
+        }
+        return Err(e);
       }
-      client_req.query(&query)
     }
-  };
-  if let Some(addr) = req.head().peer_addr {
-    client_req = client_req.header("X-Forwarded-For", addr.to_string())
-  };
-  let res = client_req
-    .timeout(Duration::from_secs(pictrs.upload_timeout))
-    .body(Body::wrap_stream(make_send(body)))
-    .send()
-    .await?
-    .error_for_status()?;
-
-  let mut images = res.json::<PictrsResponse>().await?;
-  for image in &images.files {
+  }
+
+  for ((image, (is_video, info)), moderation) in images.iter().zip(videos).zip(moderations) {
     // Pictrs allows uploading multiple images in a single request. Lemmy doesnt need this,
     // but still a user may upload multiple and so we need to store all links in db for
     // to allow deletion via web ui.
@@ -224,21 +266,34 @@ pub async fn do_upload_image(
       pictrs_alias: image.file.to_string(),
       person_id: local_user_view.person.id,
       thumbnail_for_post_id: None,
+      file_size: image.details.size.unwrap_or_default(),
     };
 
-    let protocol_and_hostname = context.settings().get_protocol_and_hostname();
     let thumbnail_url = image.image_url(&protocol_and_hostname)?;
 
     // Also store the details for the image
-    let details_form = image.details.build_image_details_form(&thumbnail_url);
+    let mut details_form = image.details.build_image_details_form(&thumbnail_url);
+    details_form.perceptual_hash = moderation.hash;
+    details_form.nsfw = moderation.nsfw;
+    details_form.quarantined = moderation.quarantined;
+    if is_video {
+      let duration = info.as_ref().and_then(|i| i.duration);
+      create_poster_details(&thumbnail_url, &mut details_form, duration, context).await?;
+    }
     LocalImage::create(&mut context.pool(), &form, &details_form).await?;
+
+    if let Some(info) = info.filter(|i| i.needs_transcode) {
+      spawn_transcode(
+        image.file.clone(),
+        local_user_view.person.id,
+        info.duration,
+        context,
+      )?;
+    }
   }
-  let image = images
-    .files
-    .pop()
-    .ok_or(LemmyErrorType::InvalidImageUpload)?;
+  let image = images.pop().ok_or(LemmyErrorType::InvalidImageUpload)?;
 
-  let url = image.image_url(&context.settings().get_protocol_and_hostname())?;
+  let url = image.image_url(&protocol_and_hostname)?;
   Ok(UploadImageResponse {
     image_url: url,
     filename: image.file,
@@ -1,43 +1,78 @@
 use actix_web::{
+  body::BodyStream,
   http::{
-    header::{HeaderName, ACCEPT_ENCODING, HOST},
-    Method,
+    header::{HeaderName, ACCEPT_ENCODING, CACHE_CONTROL, HOST, LOCATION},
+    StatusCode,
   },
   web::Data,
   HttpRequest,
+  HttpResponse,
 };
 use diesel::NotFound;
 use futures::stream::{Stream, StreamExt};
-use http::HeaderValue;
-use lemmy_api_utils::{context::LemmyContext, request::delete_image_alias};
+use http::{HeaderMap, HeaderValue};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  media_storage::MediaResponse,
+  request::delete_image_alias,
+};
 use lemmy_db_schema::newtypes::DbUrl;
-use lemmy_utils::{error::LemmyResult, REQWEST_TIMEOUT};
-use reqwest_middleware::RequestBuilder;
+use lemmy_utils::error::LemmyResult;
 
-pub(super) fn adapt_request(
-  request: &HttpRequest,
-  url: String,
-  context: &LemmyContext,
-) -> RequestBuilder {
+/// Headers of the client request which are passed on to pict-rs.
+pub(super) fn forwarded_headers(request: &HttpRequest) -> HeaderMap {
   // remove accept-encoding header so that pictrs doesn't compress the response
   const INVALID_HEADERS: &[HeaderName] = &[ACCEPT_ENCODING, HOST];
 
-  let client_request = context
-    .pictrs_client()
-    .request(convert_method(request.method()), url)
-    .timeout(REQWEST_TIMEOUT);
+  let mut headers = HeaderMap::new();
+  for (key, value) in request.headers() {
+    if INVALID_HEADERS.contains(key) {
+      continue;
+    }
+    // TODO: remove these conversions after actix-web upgrades to http 1.0
+    if let (Ok(key), Ok(value)) = (
+      http::HeaderName::from_bytes(key.as_str().as_bytes()),
+      HeaderValue::from_bytes(value.as_bytes()),
+    ) {
+      headers.append(key, value);
+    }
+  }
+  if let Some(addr) = request.head().peer_addr {
+    if let Ok(addr) = HeaderValue::from_str(&addr.to_string()) {
+      headers.insert("X-Forwarded-For", addr);
+    }
+  }
+  headers
+}
+
+pub(super) fn media_response(response: MediaResponse) -> LemmyResult<HttpResponse> {
+  Ok(match response {
+    MediaResponse::Pictrs(res) => {
+      if res.status() == http::StatusCode::NOT_FOUND {
+        return Ok(HttpResponse::NotFound().finish());
+      }
+
+      let mut client_res = HttpResponse::build(StatusCode::from_u16(res.status().as_u16())?);
 
-  request
-    .headers()
-    .iter()
-    .fold(client_request, |client_req, (key, value)| {
-      if INVALID_HEADERS.contains(key) {
-        client_req
-      } else {
-        // TODO: remove as_str and as_bytes conversions after actix-web upgrades to http 1.0
-        client_req.header(key.as_str(), value.as_bytes())
+      for (name, value) in res.headers().iter().filter(|(h, _)| *h != "connection") {
+        client_res.insert_header(convert_header(name, value));
       }
-    })
+
+      client_res.body(BodyStream::new(res.bytes_stream()))
+    }
+    // Aliases are never reused, so files can be cached forever
+    MediaResponse::Stream { content_type, body } => HttpResponse::Ok()
+      .content_type(content_type)
+      .insert_header((CACHE_CONTROL, "public, max-age=31536000, immutable"))
+      .body(BodyStream::new(body)),
+    MediaResponse::File { content_type, data } => HttpResponse::Ok()
+      .content_type(content_type)
+      .insert_header((CACHE_CONTROL, "public, max-age=31536000, immutable"))
+      .body(data),
+    MediaResponse::Redirect(url) => HttpResponse::Found()
+      .insert_header((LOCATION, url.as_str()))
+      .finish(),
+  })
 }
 
 pub(super) fn make_send<S>(mut stream: S) -> impl Stream<Item = S::Item> + Send + Unpin + 'static
@@ -82,16 +117,8 @@ where
   }
 }
 
-// TODO: remove these conversions after actix-web upgrades to http 1.0
-#[allow(clippy::expect_used)]
-pub(super) fn convert_method(method: &Method) -> http::Method {
-  http::Method::from_bytes(method.as_str().as_bytes()).expect("method can be converted")
-}
-
-pub(super) fn convert_header<'a>(
-  name: &'a http::HeaderName,
-  value: &'a HeaderValue,
-) -> (&'a str, &'a [u8]) {
+// TODO: remove this conversion after actix-web upgrades to http 1.0
+fn convert_header<'a>(name: &'a http::HeaderName, value: &'a HeaderValue) -> (&'a str, &'a [u8]) {
   (name.as_str(), value.as_bytes())
 }
 
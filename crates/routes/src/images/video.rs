@@ -0,0 +1,467 @@
+//! Duration and transcoding of uploaded videos, using ffprobe and ffmpeg. Poster frames are
+//! generated by pict-rs.
+use actix_web::web::{Bytes, Data};
+use futures::stream::{self, StreamExt};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  request::{delete_image_alias, PictrsFile, PictrsResponse},
+  send_activity::{ActivityChannel, SendActivityData},
+  utils::media_quota,
+};
+use lemmy_db_schema::{
+  newtypes::{DbUrl, PersonId},
+  source::{
+    comment::Comment,
+    images::{
+      ImageDetails,
+      ImageDetailsInsertForm,
+      ImageRedirect,
+      ImageRedirectForm,
+      LocalImage,
+      LocalImageForm,
+    },
+    post::Post,
+  },
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  settings::structs::VideoConfig,
+  spawn_try_task,
+};
+use reqwest::Body;
+use serde::Deserialize;
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  process::Output,
+  time::Duration,
+};
+use tokio::{
+  fs::{self, File},
+  io::{AsyncReadExt, AsyncWriteExt},
+  process::Command,
+  time::timeout,
+};
+use url::Url;
+use uuid::Uuid;
+
+/// Image format of poster frames. pict-rs returns the first frame when a video is requested in an
+/// image format.
+const POSTER_FORMAT: &str = "webp";
+/// Content type of transcoded videos.
+const TRANSCODED_CONTENT_TYPE: &str = "video/mp4";
+/// Video codecs which play in all common browsers, other videos are transcoded if enabled.
+const WEB_SAFE_CODECS: [&str; 3] = ["h264", "vp9", "av1"];
+/// Only local files may be opened, so that crafted playlists can't make ffmpeg fetch other urls.
+const PROTOCOL_WHITELIST: &str = "file,pipe";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+const TRANSCODE_TIMEOUT: Duration = Duration::from_secs(600);
+const MULTIPART_BOUNDARY: &str = "lemmy-video-upload";
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+struct Probe {
+  format: ProbeFormat,
+  #[serde(default)]
+  streams: Vec<ProbeStream>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+  /// Seconds as decimal string, eg `12.480000`
+  duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+  codec_type: String,
+  codec_name: Option<String>,
+  height: Option<u32>,
+}
+
+impl Probe {
+  /// Duration in whole seconds.
+  fn duration(&self) -> Option<i32> {
+    self
+      .format
+      .duration
+      .as_ref()?
+      .split('.')
+      .next()?
+      .parse()
+      .ok()
+  }
+
+  fn needs_transcode(&self, max_height: u32) -> bool {
+    let Some(video) = self.streams.iter().find(|s| s.codec_type == "video") else {
+      return false;
+    };
+    let web_safe = video
+      .codec_name
+      .as_deref()
+      .is_some_and(|c| WEB_SAFE_CODECS.contains(&c));
+    !web_safe || video.height.is_some_and(|h| h > max_height)
+  }
+}
+
+pub(super) struct VideoInfo {
+  /// In seconds
+  pub duration: Option<i32>,
+  /// Whether the video should be replaced with a transcoded version, see [spawn_transcode].
+  pub needs_transcode: bool,
+}
+
+/// Reads the duration of an uploaded video, and whether it needs transcoding. Returns `None` if
+/// video processing is disabled.
+pub(super) async fn probe_video(
+  file: &PictrsFile,
+  context: &LemmyContext,
+) -> LemmyResult<Option<VideoInfo>> {
+  let pictrs = context.settings().pictrs()?;
+  let Some(config) = &pictrs.video else {
+    return Ok(None);
+  };
+
+  let input = temp_path();
+  let probe = async {
+    download_original(&file.file, &input, context).await?;
+    let output = run(
+      Command::new(&config.ffprobe_path)
+        .args([
+          "-v",
+          "error",
+          "-protocol_whitelist",
+          PROTOCOL_WHITELIST,
+          "-print_format",
+          "json",
+          "-show_format",
+          "-show_streams",
+        ])
+        .arg(&input),
+      PROBE_TIMEOUT,
+    )
+    .await?;
+    Ok::<Probe, LemmyError>(serde_json::from_slice(&output.stdout)?)
+  }
+  .await;
+  remove_temp_file(&input).await;
+
+  let probe = probe?;
+  Ok(Some(VideoInfo {
+    duration: probe.duration(),
+    needs_transcode: config.transcode && probe.needs_transcode(config.max_height),
+  }))
+}
+
+/// Stores the details of the video's poster frame, which is used as post thumbnail and so needs its
+/// own details for the post view. The duration and poster url are added to the video details.
+pub(super) async fn create_poster_details(
+  video_url: &Url,
+  video_details: &mut ImageDetailsInsertForm,
+  duration: Option<i32>,
+  context: &LemmyContext,
+) -> LemmyResult<Url> {
+  let poster_url = Url::parse(&format!("{video_url}?file_type={POSTER_FORMAT}"))?;
+  let poster_form = ImageDetailsInsertForm {
+    link: poster_url.clone().into(),
+    content_type: format!("image/{POSTER_FORMAT}"),
+    duration: None,
+    poster_url: None,
+    // The poster is served under the alias of the video, so it is covered by its quarantine
+    quarantined: false,
+    ..video_details.clone()
+  };
+  ImageDetails::create(&mut context.pool(), &poster_form).await?;
+  video_details.duration = duration;
+  video_details.poster_url = Some(poster_url.clone().into());
+  Ok(poster_url)
+}
+
+/// Transcodes the uploaded video in a background task, as this can take several minutes. Once it
+/// is done, the transcoded video replaces the original, see [replace_original].
+pub(super) fn spawn_transcode(
+  alias: String,
+  person_id: PersonId,
+  duration: Option<i32>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let Some(config) = context.settings().pictrs()?.video.clone() else {
+    return Ok(());
+  };
+  let context = context.clone();
+  spawn_try_task(async move {
+    let input = temp_path();
+    let output = temp_path();
+    let res = async {
+      download_original(&alias, &input, &context).await?;
+      transcode(&config, &input, &output).await?;
+      let transcoded = upload(&output, &context).await?;
+      replace_original(&alias, transcoded, person_id, duration, &context).await
+    }
+    .await;
+    remove_temp_file(&input).await;
+    remove_temp_file(&output).await;
+    res
+  });
+  Ok(())
+}
+
+/// Transcodes the video to mp4 with H.264 and AAC.
+async fn transcode(config: &VideoConfig, input: &Path, output: &Path) -> LemmyResult<()> {
+  let scale = format!("scale=-2:min(ih\\,{})", config.max_height);
+  run(
+    Command::new(&config.ffmpeg_path)
+      .args([
+        "-nostdin",
+        "-v",
+        "error",
+        "-protocol_whitelist",
+        PROTOCOL_WHITELIST,
+        "-i",
+      ])
+      .arg(input)
+      .args([
+        "-map",
+        "0:v:0",
+        "-map",
+        "0:a:0?",
+        "-vf",
+        scale.as_str(),
+        "-c:v",
+        "libx264",
+        "-preset",
+        "veryfast",
+        "-crf",
+        "23",
+        "-pix_fmt",
+        "yuv420p",
+        "-c:a",
+        "aac",
+        "-b:a",
+        "128k",
+        "-movflags",
+        "+faststart",
+        "-f",
+        "mp4",
+        "-y",
+      ])
+      .arg(output),
+    TRANSCODE_TIMEOUT,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Stores the transcoded video like a regular upload, and replaces the original with it. Links in
+/// the uploader's posts and comments are rewritten, and requests for the original alias are
+/// redirected to the transcoded video, so that the original can be deleted.
+async fn replace_original(
+  alias: &str,
+  transcoded: PictrsFile,
+  person_id: PersonId,
+  duration: Option<i32>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  // The original may have been deleted while it was transcoded
+  let Some(original_image) = LocalImage::read_by_alias(&mut context.pool(), alias).await? else {
+    return delete_image_alias(&transcoded.file, context).await;
+  };
+  // The transcoded video takes the place of the original in the quota. If it is larger and
+  // doesn't fit, the original is kept.
+  let file_size = transcoded.details.size.unwrap_or_default();
+  let local_user_view = LocalUserView::read_person(&mut context.pool(), person_id).await?;
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  if let Some(quota) = media_quota(&local_user_view, &local_site) {
+    let usage = LocalImage::get_usage(&mut context.pool(), person_id).await?;
+    if usage - original_image.file_size + file_size > quota {
+      delete_image_alias(&transcoded.file, context).await?;
+      Err(LemmyErrorType::MediaQuotaExceeded)?
+    }
+  }
+
+  let protocol_and_hostname = context.settings().get_protocol_and_hostname();
+  let original_url: DbUrl =
+    Url::parse(&format!("{protocol_and_hostname}/api/v4/image/{alias}"))?.into();
+  let video_url = transcoded.image_url(&protocol_and_hostname)?;
+
+  // The transcoded video has the same content, so it keeps the moderation results
+  let original = ImageDetails::read(&mut context.pool(), &original_url).await?;
+  let mut details_form = transcoded.details.build_image_details_form(&video_url);
+  details_form.perceptual_hash = original.as_ref().and_then(|o| o.perceptual_hash);
+  details_form.nsfw = original.as_ref().is_some_and(|o| o.nsfw);
+  details_form.quarantined = original.is_some_and(|o| o.quarantined);
+  let poster_url = create_poster_details(&video_url, &mut details_form, duration, context).await?;
+
+  let form = LocalImageForm {
+    pictrs_alias: transcoded.file.clone(),
+    person_id,
+    thumbnail_for_post_id: None,
+    file_size,
+  };
+  LocalImage::create(&mut context.pool(), &form, &details_form).await?;
+  let redirect_form = ImageRedirectForm {
+    alias: alias.to_string(),
+    target_alias: transcoded.file.clone(),
+  };
+  ImageRedirect::create(&mut context.pool(), &redirect_form).await?;
+
+  let video_url: DbUrl = video_url.into();
+  let mut posts: HashMap<_, _> = Post::update_url_for_creator(
+    &mut context.pool(),
+    person_id,
+    &original_url,
+    &video_url,
+    &poster_url.into(),
+    TRANSCODED_CONTENT_TYPE,
+  )
+  .await?
+  .into_iter()
+  .map(|p| (p.id, p))
+  .collect();
+  // Posts which link the video and embed it in the body are updated twice, the second result
+  // contains both changes
+  posts.extend(
+    Post::replace_url_in_body_for_creator(
+      &mut context.pool(),
+      person_id,
+      &original_url,
+      &video_url,
+    )
+    .await?
+    .into_iter()
+    .map(|p| (p.id, p)),
+  );
+  for post in posts.into_values() {
+    // Scheduled posts are federated once they are published
+    if post.scheduled_publish_time_at.is_none() {
+      ActivityChannel::submit_activity(SendActivityData::UpdatePost(post), context)?;
+    }
+  }
+  let comments =
+    Comment::replace_url_for_creator(&mut context.pool(), person_id, &original_url, &video_url)
+      .await?;
+  for comment in comments {
+    ActivityChannel::submit_activity(SendActivityData::UpdateComment(comment), context)?;
+  }
+
+  delete_image_alias(alias, context).await
+}
+
+/// Writes the original upload from pict-rs to the given file.
+async fn download_original(alias: &str, path: &Path, context: &LemmyContext) -> LemmyResult<()> {
+  let pictrs = context.settings().pictrs()?;
+  let mut stream = context
+    .pictrs_client()
+    .get(format!("{}image/original/{alias}", pictrs.url))
+    .timeout(Duration::from_secs(pictrs.upload_timeout))
+    .send()
+    .await?
+    .error_for_status()?
+    .bytes_stream();
+  let mut file = File::create(path).await?;
+  while let Some(chunk) = stream.next().await {
+    file.write_all(&chunk?).await?;
+  }
+  file.flush().await?;
+  Ok(())
+}
+
+/// Uploads the transcoded video to pict-rs, wrapped in a multipart form as expected by the upload
+/// endpoint.
+async fn upload(path: &Path, context: &LemmyContext) -> LemmyResult<PictrsFile> {
+  let size = i64::try_from(fs::metadata(path).await?.len())?;
+  let file = File::open(path).await?;
+  let video = stream::try_unfold(file, |mut file| async move {
+    let mut buf = vec![0; READ_CHUNK_SIZE];
+    let len = file.read(&mut buf).await?;
+    if len == 0 {
+      return Ok::<_, std::io::Error>(None);
+    }
+    buf.truncate(len);
+    Ok(Some((Bytes::from(buf), file)))
+  });
+  let head = format!(
+    "--{MULTIPART_BOUNDARY}\r\nContent-Disposition: form-data; name=\"images[]\"; \
+     filename=\"video.mp4\"\r\nContent-Type: {TRANSCODED_CONTENT_TYPE}\r\n\r\n"
+  );
+  let tail = format!("\r\n--{MULTIPART_BOUNDARY}--\r\n");
+  let body = stream::once(async { Ok(Bytes::from(head)) })
+    .chain(video)
+    .chain(stream::once(async { Ok(Bytes::from(tail)) }));
+
+  let pictrs = context.settings().pictrs()?;
+  let mut res = context
+    .pictrs_client()
+    .post(format!("{}image", pictrs.url))
+    .query(&[("allow_video", "true")])
+    .header(
+      "Content-Type",
+      format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+    )
+    .timeout(Duration::from_secs(pictrs.upload_timeout))
+    .body(Body::wrap_stream(body))
+    .send()
+    .await?
+    .error_for_status()?
+    .json::<PictrsResponse>()
+    .await?;
+  let mut file = res
+    .files
+    .pop()
+    .ok_or(LemmyErrorType::PictrsResponseError(res.msg))?;
+  // The size is counted towards the upload quota
+  file.details.size = Some(size);
+  Ok(file)
+}
+
+/// Runs the command and returns its output, if it exits successfully within the timeout.
+async fn run(command: &mut Command, limit: Duration) -> LemmyResult<Output> {
+  let output = timeout(limit, command.kill_on_drop(true).output()).await??;
+  if !output.status.success() {
+    Err(LemmyErrorType::CouldntProcessVideo)?
+  }
+  Ok(output)
+}
+
+fn temp_path() -> PathBuf {
+  std::env::temp_dir().join(format!("lemmy-video-{}", Uuid::new_v4()))
+}
+
+/// Removes a temporary file, which may not exist if processing failed early.
+async fn remove_temp_file(path: &Path) {
+  fs::remove_file(path).await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_probe() -> LemmyResult<()> {
+    let probe: Probe = serde_json::from_str(
+      r#"{
+        "streams": [
+          { "codec_type": "audio", "codec_name": "opus" },
+          { "codec_type": "video", "codec_name": "hevc", "width": 1920, "height": 1080 }
+        ],
+        "format": { "duration": "12.480000", "format_name": "matroska,webm" }
+      }"#,
+    )?;
+    assert_eq!(Some(12), probe.duration());
+    assert!(probe.needs_transcode(1080));
+
+    let probe: Probe = serde_json::from_str(
+      r#"{
+        "streams": [{ "codec_type": "video", "codec_name": "h264", "height": 720 }],
+        "format": {}
+      }"#,
+    )?;
+    assert_eq!(None, probe.duration());
+    assert!(!probe.needs_transcode(720));
+    assert!(probe.needs_transcode(480));
+    Ok(())
+  }
+}
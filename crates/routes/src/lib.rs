@@ -1,6 +1,9 @@
 pub mod feeds;
+pub mod html;
 pub mod images;
+pub mod mastodon;
 pub mod middleware;
 pub mod nodeinfo;
+pub mod oembed;
 pub mod utils;
 pub mod webfinger;
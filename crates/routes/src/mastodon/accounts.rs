@@ -0,0 +1,109 @@
+use super::{
+  entities::{Account, CredentialAccount, Status},
+  paginated_response,
+  PageParams,
+};
+use activitypub_federation::config::Data;
+use actix_web::{
+  web::{Json, Path, Query},
+  HttpRequest,
+  HttpResponse,
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub::api::{list_person_content::list_person_content, read_person::read_person};
+use lemmy_db_schema::newtypes::PersonId;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person::api::GetPersonDetails;
+use lemmy_db_views_person_content_combined::{ListPersonContent, PersonContentCombinedView};
+use lemmy_utils::error::{LemmyErrorExt, LemmyErrorType, LemmyResult};
+use serde::Deserialize;
+
+pub(super) async fn verify_credentials(
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<CredentialAccount>> {
+  Ok(Json(CredentialAccount::from_person(
+    &local_user_view.person,
+    context.settings(),
+  )?))
+}
+
+fn parse_person_id(id: &str) -> LemmyResult<PersonId> {
+  Ok(PersonId(
+    id.parse().with_lemmy_type(LemmyErrorType::NotFound)?,
+  ))
+}
+
+pub(super) async fn get_account(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<Account>> {
+  let data = GetPersonDetails {
+    person_id: Some(parse_person_id(&id)?),
+    username: None,
+  };
+  let res = read_person(Query(data), context.reset_request_count(), local_user_view).await?;
+  Ok(Json(Account::from_person(
+    &res.person_view.person,
+    context.settings(),
+  )?))
+}
+
+#[derive(Deserialize)]
+pub(super) struct LookupParams {
+  acct: String,
+}
+
+pub(super) async fn lookup_account(
+  params: Query<LookupParams>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<Account>> {
+  let data = GetPersonDetails {
+    person_id: None,
+    username: Some(params.acct.trim_start_matches('@').to_string()),
+  };
+  let res = read_person(Query(data), context.reset_request_count(), local_user_view).await?;
+  Ok(Json(Account::from_person(
+    &res.person_view.person,
+    context.settings(),
+  )?))
+}
+
+pub(super) async fn get_account_statuses(
+  id: Path<String>,
+  params: Query<PageParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<HttpResponse> {
+  let params = params.into_inner();
+  let data = ListPersonContent {
+    type_: None,
+    person_id: Some(parse_person_id(&id)?),
+    username: None,
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+    limit: params.limit,
+  };
+  let res = list_person_content(Query(data), context.reset_request_count(), local_user_view)
+    .await?
+    .into_inner();
+
+  let statuses = res
+    .content
+    .iter()
+    .map(|c| match c {
+      PersonContentCombinedView::Post(v) => Status::from_post_view(v, context.settings()),
+      PersonContentCombinedView::Comment(v) => Status::from_comment_view(v, context.settings()),
+    })
+    .collect::<LemmyResult<Vec<_>>>()?;
+  Ok(paginated_response(
+    &req,
+    &context,
+    statuses,
+    res.next_page,
+    res.prev_page,
+  ))
+}
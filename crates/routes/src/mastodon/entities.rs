@@ -0,0 +1,535 @@
+//! Mastodon API entities, converted from the corresponding Lemmy views.
+//!
+//! https://docs.joinmastodon.org/entities/
+use super::StatusId;
+use crate::utils::escape_html;
+use chrono::{DateTime, Utc};
+use lemmy_db_schema::{
+  source::{
+    comment::{Comment, CommentActions},
+    community::Community,
+    oauth_client::OAuthClient,
+    person::Person,
+    post::{Post, PostActions},
+  },
+  traits::ApubActor,
+};
+use lemmy_db_views_comment::CommentView;
+use lemmy_db_views_inbox_combined::InboxCombinedView;
+use lemmy_db_views_post::PostView;
+use lemmy_utils::{
+  error::LemmyResult,
+  settings::structs::Settings,
+  utils::markdown::markdown_to_html,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize)]
+pub(super) struct Account {
+  id: String,
+  username: String,
+  acct: String,
+  url: String,
+  display_name: String,
+  note: String,
+  avatar: String,
+  avatar_static: String,
+  header: String,
+  header_static: String,
+  locked: bool,
+  bot: bool,
+  group: bool,
+  discoverable: bool,
+  created_at: DateTime<Utc>,
+  statuses_count: i64,
+  followers_count: i64,
+  following_count: i64,
+  emojis: Vec<Value>,
+  fields: Vec<Value>,
+}
+
+impl Account {
+  pub(super) fn from_person(person: &Person, settings: &Settings) -> LemmyResult<Self> {
+    let acct = if person.local {
+      person.name.clone()
+    } else {
+      format!(
+        "{}@{}",
+        person.name,
+        person.ap_id.inner().domain().unwrap_or_default()
+      )
+    };
+    let avatar = person
+      .avatar
+      .as_ref()
+      .map(ToString::to_string)
+      .unwrap_or_default();
+    let header = person
+      .banner
+      .as_ref()
+      .map(ToString::to_string)
+      .unwrap_or_default();
+    Ok(Account {
+      id: person.id.0.to_string(),
+      username: person.name.clone(),
+      acct,
+      url: person.actor_url(settings)?.to_string(),
+      display_name: person
+        .display_name
+        .clone()
+        .unwrap_or_else(|| person.name.clone()),
+      note: person
+        .bio
+        .as_deref()
+        .map(markdown_to_html)
+        .unwrap_or_default(),
+      avatar: avatar.clone(),
+      avatar_static: avatar,
+      header: header.clone(),
+      header_static: header,
+      locked: false,
+      bot: person.bot_account,
+      group: false,
+      discoverable: true,
+      created_at: person.published_at,
+      statuses_count: person.post_count + person.comment_count,
+      followers_count: 0,
+      following_count: 0,
+      emojis: vec![],
+      fields: vec![],
+    })
+  }
+}
+
+/// The account of the logged in user, including the source of the editable profile fields.
+#[derive(Serialize)]
+pub(super) struct CredentialAccount {
+  #[serde(flatten)]
+  account: Account,
+  source: AccountSource,
+}
+
+#[derive(Serialize)]
+struct AccountSource {
+  note: String,
+  privacy: &'static str,
+  sensitive: bool,
+  fields: Vec<Value>,
+}
+
+impl CredentialAccount {
+  pub(super) fn from_person(person: &Person, settings: &Settings) -> LemmyResult<Self> {
+    Ok(CredentialAccount {
+      account: Account::from_person(person, settings)?,
+      source: AccountSource {
+        note: person.bio.clone().unwrap_or_default(),
+        privacy: "public",
+        sensitive: false,
+        fields: vec![],
+      },
+    })
+  }
+}
+
+#[derive(Serialize)]
+pub(super) struct Status {
+  id: String,
+  uri: String,
+  url: String,
+  created_at: DateTime<Utc>,
+  edited_at: Option<DateTime<Utc>>,
+  account: Account,
+  content: String,
+  visibility: &'static str,
+  sensitive: bool,
+  spoiler_text: String,
+  in_reply_to_id: Option<String>,
+  in_reply_to_account_id: Option<String>,
+  language: Option<String>,
+  replies_count: i64,
+  reblogs_count: i64,
+  favourites_count: i64,
+  favourited: bool,
+  reblogged: bool,
+  muted: bool,
+  bookmarked: bool,
+  pinned: bool,
+  reblog: Option<Value>,
+  poll: Option<Value>,
+  card: Option<PreviewCard>,
+  media_attachments: Vec<MediaAttachment>,
+  mentions: Vec<Value>,
+  tags: Vec<Tag>,
+  emojis: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct PreviewCard {
+  url: String,
+  title: String,
+  description: String,
+  #[serde(rename = "type")]
+  type_: &'static str,
+  image: Option<String>,
+  author_name: String,
+  author_url: String,
+  provider_name: String,
+  provider_url: String,
+  html: String,
+  width: i32,
+  height: i32,
+  embed_url: String,
+  blurhash: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MediaAttachment {
+  id: String,
+  #[serde(rename = "type")]
+  type_: &'static str,
+  url: String,
+  preview_url: String,
+  remote_url: Option<String>,
+  description: Option<String>,
+  meta: Option<Value>,
+  blurhash: Option<String>,
+}
+
+/// The community of a post is shown as hashtag.
+#[derive(Serialize)]
+struct Tag {
+  name: String,
+  url: String,
+}
+
+impl Status {
+  pub(super) fn from_post_view(view: &PostView, settings: &Settings) -> LemmyResult<Self> {
+    Self::from_post(
+      &view.post,
+      &view.creator,
+      &view.community,
+      view.post_actions.as_ref(),
+      settings,
+    )
+  }
+
+  pub(super) fn from_post(
+    post: &Post,
+    creator: &Person,
+    community: &Community,
+    post_actions: Option<&PostActions>,
+    settings: &Settings,
+  ) -> LemmyResult<Self> {
+    let post_url = post.local_url(settings)?;
+
+    let mut content = format!(
+      "<p><a href=\"{post_url}\"><strong>{}</strong></a></p>",
+      escape_html(&post.name)
+    );
+    let mut card = None;
+    let mut media_attachments = vec![];
+    if let Some(url) = &post.url {
+      let url = url.to_string();
+      let content_type = post.url_content_type.as_deref().unwrap_or_default();
+      let media_type = if content_type.starts_with("image/") {
+        Some("image")
+      } else if content_type.starts_with("video/") {
+        Some("video")
+      } else {
+        None
+      };
+      if let Some(media_type) = media_type {
+        media_attachments.push(MediaAttachment {
+          id: post.id.0.to_string(),
+          type_: media_type,
+          preview_url: post
+            .thumbnail_url
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| url.clone()),
+          url,
+          remote_url: None,
+          description: post.alt_text.clone(),
+          meta: None,
+          blurhash: None,
+        });
+      } else {
+        content.push_str(&format!(
+          "<p><a href=\"{0}\">{0}</a></p>",
+          escape_html(&url)
+        ));
+        card = Some(PreviewCard {
+          title: post.embed_title.clone().unwrap_or_else(|| url.clone()),
+          url,
+          description: post.embed_description.clone().unwrap_or_default(),
+          type_: "link",
+          image: post.thumbnail_url.as_ref().map(ToString::to_string),
+          author_name: String::new(),
+          author_url: String::new(),
+          provider_name: String::new(),
+          provider_url: String::new(),
+          html: String::new(),
+          width: 0,
+          height: 0,
+          embed_url: String::new(),
+          blurhash: None,
+        });
+      }
+    }
+    if let Some(body) = &post.body {
+      content.push_str(&markdown_to_html(body));
+    }
+
+    Ok(Status {
+      id: StatusId::Post(post.id).to_string(),
+      uri: post.ap_id.to_string(),
+      url: post_url.to_string(),
+      created_at: post.published_at,
+      edited_at: post.updated_at,
+      account: Account::from_person(creator, settings)?,
+      content,
+      visibility: "public",
+      sensitive: post.nsfw,
+      spoiler_text: if post.nsfw {
+        "NSFW".to_string()
+      } else {
+        String::new()
+      },
+      in_reply_to_id: None,
+      in_reply_to_account_id: None,
+      language: None,
+      replies_count: post.comments,
+      reblogs_count: 0,
+      favourites_count: post.upvotes,
+      favourited: post_actions.and_then(|a| a.like_score) == Some(1),
+      reblogged: false,
+      muted: false,
+      bookmarked: post_actions.and_then(|a| a.saved_at).is_some(),
+      pinned: post.featured_community,
+      reblog: None,
+      poll: None,
+      card,
+      media_attachments,
+      mentions: vec![],
+      tags: vec![Tag::from_community(community, settings)?],
+      emojis: vec![],
+    })
+  }
+
+  pub(super) fn from_comment_view(view: &CommentView, settings: &Settings) -> LemmyResult<Self> {
+    Self::from_comment(
+      &view.comment,
+      &view.creator,
+      &view.post,
+      &view.community,
+      view.comment_actions.as_ref(),
+      settings,
+    )
+  }
+
+  pub(super) fn from_comment(
+    comment: &Comment,
+    creator: &Person,
+    post: &Post,
+    community: &Community,
+    comment_actions: Option<&CommentActions>,
+    settings: &Settings,
+  ) -> LemmyResult<Self> {
+    // Top level comments are replies to the post. For nested comments the author of the parent
+    // is unknown here, so it is left empty.
+    let (in_reply_to_id, in_reply_to_account_id) = match comment.parent_comment_id() {
+      Some(parent_id) => (StatusId::Comment(parent_id), None),
+      None => (StatusId::Post(post.id), Some(post.creator_id.0.to_string())),
+    };
+
+    Ok(Status {
+      id: StatusId::Comment(comment.id).to_string(),
+      uri: comment.ap_id.to_string(),
+      url: comment.local_url(settings)?.to_string(),
+      created_at: comment.published_at,
+      edited_at: comment.updated_at,
+      account: Account::from_person(creator, settings)?,
+      content: markdown_to_html(&comment.content),
+      visibility: "public",
+      sensitive: post.nsfw,
+      spoiler_text: String::new(),
+      in_reply_to_id: Some(in_reply_to_id.to_string()),
+      in_reply_to_account_id,
+      language: None,
+      replies_count: comment.child_count.into(),
+      reblogs_count: 0,
+      favourites_count: comment.upvotes,
+      favourited: comment_actions.and_then(|a| a.like_score) == Some(1),
+      reblogged: false,
+      muted: false,
+      bookmarked: comment_actions.and_then(|a| a.saved_at).is_some(),
+      pinned: false,
+      reblog: None,
+      poll: None,
+      card: None,
+      media_attachments: vec![],
+      mentions: vec![],
+      tags: vec![Tag::from_community(community, settings)?],
+      emojis: vec![],
+    })
+  }
+}
+
+impl Tag {
+  fn from_community(community: &Community, settings: &Settings) -> LemmyResult<Self> {
+    Ok(Tag {
+      name: community.name.clone(),
+      url: community.actor_url(settings)?.to_string(),
+    })
+  }
+}
+
+/// Thread of a status, as returned by the `context` endpoint.
+#[derive(Serialize)]
+pub(super) struct Context {
+  pub(super) ancestors: Vec<Status>,
+  pub(super) descendants: Vec<Status>,
+}
+
+#[derive(Serialize)]
+pub(super) struct Notification {
+  id: String,
+  #[serde(rename = "type")]
+  type_: &'static str,
+  created_at: DateTime<Utc>,
+  account: Account,
+  status: Option<Status>,
+}
+
+impl Notification {
+  /// Replies and mentions are both shown as mentions. Private messages are not supported, so
+  /// `None` is returned for them.
+  ///
+  /// As with status ids, the ids of the different inbox item types are interleaved.
+  pub(super) fn from_inbox_view(
+    view: &InboxCombinedView,
+    settings: &Settings,
+  ) -> LemmyResult<Option<Self>> {
+    let (id, created_at, creator, status) = match view {
+      InboxCombinedView::CommentReply(v) => (
+        i64::from(v.comment_reply.id.0) * 3,
+        v.comment_reply.published_at,
+        &v.creator,
+        Status::from_comment(
+          &v.comment,
+          &v.creator,
+          &v.post,
+          &v.community,
+          v.comment_actions.as_ref(),
+          settings,
+        )?,
+      ),
+      InboxCombinedView::CommentMention(v) => (
+        i64::from(v.person_comment_mention.id.0) * 3 + 1,
+        v.person_comment_mention.published_at,
+        &v.creator,
+        Status::from_comment(
+          &v.comment,
+          &v.creator,
+          &v.post,
+          &v.community,
+          v.comment_actions.as_ref(),
+          settings,
+        )?,
+      ),
+      InboxCombinedView::PostMention(v) => (
+        i64::from(v.person_post_mention.id.0) * 3 + 2,
+        v.person_post_mention.published_at,
+        &v.creator,
+        Status::from_post(
+          &v.post,
+          &v.creator,
+          &v.community,
+          v.post_actions.as_ref(),
+          settings,
+        )?,
+      ),
+      InboxCombinedView::PrivateMessage(_) => return Ok(None),
+    };
+    Ok(Some(Notification {
+      id: id.to_string(),
+      type_: "mention",
+      created_at,
+      account: Account::from_person(creator, settings)?,
+      status: Some(status),
+    }))
+  }
+}
+
+#[derive(Serialize)]
+pub(super) struct Application {
+  id: String,
+  name: String,
+  website: Option<String>,
+  redirect_uri: String,
+  client_id: String,
+  client_secret: String,
+  vapid_key: String,
+}
+
+impl Application {
+  /// The secret is only stored as a hash, so it needs to be passed separately after registration.
+  pub(super) fn new(client: OAuthClient, client_secret: String) -> Self {
+    Application {
+      id: client.id.0.to_string(),
+      name: client.name,
+      website: client.website,
+      redirect_uri: client.redirect_uris,
+      client_id: client.client_id,
+      client_secret,
+      vapid_key: String::new(),
+    }
+  }
+}
+
+#[derive(Serialize)]
+pub(super) struct Token {
+  pub(super) access_token: String,
+  pub(super) token_type: &'static str,
+  pub(super) scope: String,
+  pub(super) created_at: i64,
+}
+
+#[derive(Serialize)]
+pub(super) struct Instance {
+  pub(super) uri: String,
+  pub(super) title: String,
+  pub(super) short_description: String,
+  pub(super) description: String,
+  pub(super) email: String,
+  pub(super) version: String,
+  pub(super) urls: Value,
+  pub(super) stats: InstanceStats,
+  pub(super) thumbnail: Option<String>,
+  pub(super) languages: Vec<String>,
+  pub(super) registrations: bool,
+  pub(super) approval_required: bool,
+  pub(super) invites_enabled: bool,
+  pub(super) configuration: InstanceConfiguration,
+  pub(super) contact_account: Option<Account>,
+}
+
+#[derive(Serialize)]
+pub(super) struct InstanceStats {
+  pub(super) user_count: i64,
+  pub(super) status_count: i64,
+  pub(super) domain_count: i64,
+}
+
+#[derive(Serialize)]
+pub(super) struct InstanceConfiguration {
+  pub(super) statuses: StatusConfiguration,
+}
+
+#[derive(Serialize)]
+pub(super) struct StatusConfiguration {
+  pub(super) max_characters: i32,
+  pub(super) max_media_attachments: i32,
+  pub(super) characters_reserved_per_url: i32,
+}
@@ -0,0 +1,47 @@
+use super::entities::{Instance, InstanceConfiguration, InstanceStats, StatusConfiguration};
+use activitypub_federation::config::Data;
+use actix_web::web::Json;
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema_file::enums::RegistrationMode;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{error::LemmyResult, VERSION};
+use serde_json::json;
+
+/// Maximum length of a comment, which is the only kind of status that can be created.
+const MAX_CHARACTERS: i32 = 10000;
+
+pub(super) async fn get_instance(context: Data<LemmyContext>) -> LemmyResult<Json<Instance>> {
+  let site_view = SiteView::read_local(&mut context.pool()).await?;
+  let site = site_view.site;
+  let local_site = site_view.local_site;
+
+  Ok(Json(Instance {
+    uri: context.settings().hostname.clone(),
+    title: site.name,
+    short_description: site.description.clone().unwrap_or_default(),
+    description: site.sidebar.or(site.description).unwrap_or_default(),
+    email: String::new(),
+    // Clients use this to detect supported features, so claim compatibility with a Mastodon
+    // version which is old enough to not require any newer endpoints.
+    version: format!("3.5.0 (compatible; Lemmy {VERSION})"),
+    urls: json!({}),
+    stats: InstanceStats {
+      user_count: local_site.users,
+      status_count: local_site.posts + local_site.comments,
+      domain_count: 0,
+    },
+    thumbnail: site.icon.map(|i| i.to_string()),
+    languages: vec![],
+    registrations: local_site.registration_mode != RegistrationMode::Closed,
+    approval_required: local_site.registration_mode == RegistrationMode::RequireApplication,
+    invites_enabled: false,
+    configuration: InstanceConfiguration {
+      statuses: StatusConfiguration {
+        max_characters: MAX_CHARACTERS,
+        max_media_attachments: 0,
+        characters_reserved_per_url: 0,
+      },
+    },
+    contact_account: None,
+  }))
+}
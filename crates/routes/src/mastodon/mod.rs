@@ -0,0 +1,209 @@
+//! A subset of the Mastodon client API, so that Mastodon apps can be used to browse Lemmy.
+//!
+//! Posts and comments are both exposed as statuses, persons as accounts and the inbox as
+//! notifications. All handlers delegate to the regular Lemmy API handlers, so that the same
+//! permission checks apply.
+//!
+//! https://docs.joinmastodon.org/client/intro/
+use actix_web::{
+  guard,
+  http::header::LINK,
+  web::{get, post, resource, scope, Data, Form, Json, ServiceConfig},
+  Either,
+  HttpRequest,
+  HttpResponse,
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema::newtypes::{CommentId, PaginationCursor, PostId};
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  rate_limit::RateLimit,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use url::form_urlencoded;
+
+mod accounts;
+mod entities;
+mod instance;
+mod notifications;
+mod oauth;
+mod statuses;
+mod timelines;
+
+pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
+  cfg
+    .service(
+      scope("/api/v1")
+        .wrap(rate_limit.message())
+        .route("/instance", get().to(instance::get_instance))
+        .route("/apps", post().to(oauth::register_app))
+        .route(
+          "/accounts/verify_credentials",
+          get().to(accounts::verify_credentials),
+        )
+        .route("/accounts/lookup", get().to(accounts::lookup_account))
+        .route("/accounts/{id}", get().to(accounts::get_account))
+        .route(
+          "/accounts/{id}/statuses",
+          get().to(accounts::get_account_statuses),
+        )
+        .route("/timelines/home", get().to(timelines::home_timeline))
+        .route("/timelines/public", get().to(timelines::public_timeline))
+        .service(
+          resource("/statuses")
+            .guard(guard::Post())
+            .wrap(rate_limit.comment())
+            .route(post().to(statuses::create_status)),
+        )
+        .route("/statuses/{id}", get().to(statuses::get_status))
+        .route("/statuses/{id}/context", get().to(statuses::get_context))
+        .route(
+          "/statuses/{id}/favourite",
+          post().to(statuses::favourite_status),
+        )
+        .route(
+          "/statuses/{id}/unfavourite",
+          post().to(statuses::unfavourite_status),
+        )
+        .route(
+          "/statuses/{id}/bookmark",
+          post().to(statuses::bookmark_status),
+        )
+        .route(
+          "/statuses/{id}/unbookmark",
+          post().to(statuses::unbookmark_status),
+        )
+        .route("/favourites", get().to(statuses::list_favourites))
+        .route("/bookmarks", get().to(statuses::list_bookmarks))
+        .route(
+          "/notifications",
+          get().to(notifications::list_notifications),
+        ),
+    )
+    .service(
+      scope("/oauth")
+        .wrap(rate_limit.register())
+        .route("/authorize", get().to(oauth::authorize_form))
+        .route("/authorize", post().to(oauth::authorize))
+        .route("/token", post().to(oauth::token))
+        .route("/revoke", post().to(oauth::revoke)),
+    );
+}
+
+/// Posts and comments are both represented as statuses, so their ids are interleaved. Even
+/// status ids belong to posts, odd ones to comments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StatusId {
+  Post(PostId),
+  Comment(CommentId),
+}
+
+impl StatusId {
+  fn parse(id: &str) -> LemmyResult<Self> {
+    let id: i64 = id.parse().with_lemmy_type(LemmyErrorType::NotFound)?;
+    let inner = i32::try_from(id.div_euclid(2)).with_lemmy_type(LemmyErrorType::NotFound)?;
+    Ok(if id.rem_euclid(2) == 0 {
+      StatusId::Post(PostId(inner))
+    } else {
+      StatusId::Comment(CommentId(inner))
+    })
+  }
+}
+
+impl Display for StatusId {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      StatusId::Post(id) => write!(f, "{}", i64::from(id.0) * 2),
+      StatusId::Comment(id) => write!(f, "{}", i64::from(id.0) * 2 + 1),
+    }
+  }
+}
+
+/// Mastodon paginates with `max_id` and `min_id`, but clients are supposed to follow the `Link`
+/// header instead. This allows passing through the regular Lemmy pagination cursors.
+#[derive(Deserialize)]
+pub(crate) struct PageParams {
+  limit: Option<i64>,
+  page_cursor: Option<PaginationCursor>,
+  page_back: Option<bool>,
+}
+
+/// Builds a json response for a list, with `Link` headers pointing to the next and previous
+/// pages.
+fn paginated_response<T: Serialize>(
+  req: &HttpRequest,
+  context: &LemmyContext,
+  items: Vec<T>,
+  next_page: Option<PaginationCursor>,
+  prev_page: Option<PaginationCursor>,
+) -> HttpResponse {
+  let base_url = format!(
+    "{}{}",
+    context.settings().get_protocol_and_hostname(),
+    req.path()
+  );
+  // Keep all other query parameters, for example `local` for the public timeline
+  let params: Vec<(String, String)> = form_urlencoded::parse(req.query_string().as_bytes())
+    .filter(|(k, _)| k != "page_cursor" && k != "page_back")
+    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+    .collect();
+  let page_link = |cursor: &PaginationCursor, page_back: bool, rel: &str| {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    query.extend_pairs(&params);
+    query.append_pair("page_cursor", &cursor.0);
+    if page_back {
+      query.append_pair("page_back", "true");
+    }
+    format!("<{base_url}?{}>; rel=\"{rel}\"", query.finish())
+  };
+
+  let mut links = vec![];
+  if !items.is_empty() {
+    if let Some(next_page) = &next_page {
+      links.push(page_link(next_page, false, "next"));
+    }
+    if let Some(prev_page) = &prev_page {
+      links.push(page_link(prev_page, true, "prev"));
+    }
+  }
+
+  let mut res = HttpResponse::Ok();
+  if !links.is_empty() {
+    res.insert_header((LINK, links.join(", ")));
+  }
+  res.json(items)
+}
+
+/// Clients may send parameters either as json or as form data.
+type JsonOrForm<T> = Either<Json<T>, Form<T>>;
+
+fn into_inner<T>(data: JsonOrForm<T>) -> T {
+  match data {
+    Either::Left(json) => json.into_inner(),
+    Either::Right(form) => form.into_inner(),
+  }
+}
+
+/// Handlers in the api crates take actix data instead of federation data.
+fn web_data(context: &LemmyContext) -> Data<LemmyContext> {
+  Data::new(context.clone())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_status_id() -> LemmyResult<()> {
+    let post = StatusId::Post(PostId(5));
+    let comment = StatusId::Comment(CommentId(5));
+    assert_eq!("10", post.to_string());
+    assert_eq!("11", comment.to_string());
+    assert_eq!(post, StatusId::parse(&post.to_string())?);
+    assert_eq!(comment, StatusId::parse(&comment.to_string())?);
+    assert!(StatusId::parse("abc").is_err());
+    Ok(())
+  }
+}
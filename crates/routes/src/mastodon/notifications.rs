@@ -0,0 +1,41 @@
+use super::{entities::Notification, paginated_response, web_data, PageParams};
+use activitypub_federation::config::Data;
+use actix_web::{web::Query, HttpRequest, HttpResponse};
+use lemmy_api::local_user::notifications::list_inbox::list_inbox;
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_views_inbox_combined::ListInbox;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_utils::error::LemmyResult;
+
+pub(super) async fn list_notifications(
+  params: Query<PageParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<HttpResponse> {
+  let params = params.into_inner();
+  let data = ListInbox {
+    type_: None,
+    unread_only: None,
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+    limit: params.limit,
+  };
+  let res = list_inbox(Query(data), web_data(&context), local_user_view)
+    .await?
+    .into_inner();
+
+  let notifications = res
+    .inbox
+    .iter()
+    .map(|i| Notification::from_inbox_view(i, context.settings()))
+    .filter_map(Result::transpose)
+    .collect::<LemmyResult<Vec<_>>>()?;
+  Ok(paginated_response(
+    &req,
+    &context,
+    notifications,
+    res.next_page,
+    res.prev_page,
+  ))
+}
@@ -0,0 +1,368 @@
+use super::{
+  entities::{Application, Token},
+  into_inner,
+  JsonOrForm,
+};
+use crate::utils::escape_html;
+use activitypub_federation::config::Data;
+use actix_web::{
+  http::header::LOCATION,
+  web::{Form, Json, Query},
+  HttpRequest,
+  HttpResponse,
+};
+use chrono::Utc;
+use lemmy_api::local_user::login::check_login;
+use lemmy_api_utils::{claims::Claims, context::LemmyContext};
+use lemmy_db_schema::{
+  newtypes::LocalUserId,
+  source::{
+    login_token::LoginToken,
+    oauth_client::{OAuthClient, OAuthClientInsertForm},
+  },
+};
+use lemmy_db_views_site::api::{Login, SuccessResponse};
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+use moka::future::Cache;
+use rand::{distr::Alphanumeric, Rng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{sync::LazyLock, time::Duration};
+use subtle::ConstantTimeEq;
+use url::Url;
+
+/// Redirect uri which means that the authorization code should be shown to the user, instead of
+/// redirecting.
+const OUT_OF_BAND_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+/// Access tokens are regular Lemmy login tokens, which allow everything. So this is the only scope
+/// which can be granted, and requests for narrower scopes are rejected.
+const GRANTED_SCOPES: &str = "read write follow";
+
+/// Authorization codes are only valid for a short time until they are exchanged for an access
+/// token, so there is no need to store them in the database.
+static AUTHORIZATION_CODES: LazyLock<Cache<String, AuthorizationGrant>> = LazyLock::new(|| {
+  Cache::builder()
+    .max_capacity(10_000)
+    .time_to_live(Duration::from_secs(10 * 60))
+    .build()
+});
+
+#[derive(Clone)]
+struct AuthorizationGrant {
+  client_id: String,
+  redirect_uri: String,
+  local_user_id: LocalUserId,
+}
+
+fn random_string(len: usize) -> String {
+  rand::rng()
+    .sample_iter(&Alphanumeric)
+    .take(len)
+    .map(char::from)
+    .collect()
+}
+
+/// Secrets are random, so a plain hash is enough to store them.
+fn hash_secret(secret: &str) -> String {
+  format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Checks that the requested scopes include all scopes which are granted. `push` is allowed as
+/// well, but not supported.
+fn check_scopes(scopes: Option<&str>) -> LemmyResult<()> {
+  let Some(scopes) = scopes.filter(|s| !s.trim().is_empty()) else {
+    return Ok(());
+  };
+  let requested: Vec<&str> = scopes.split_whitespace().collect();
+  let covers_granted = GRANTED_SCOPES
+    .split_whitespace()
+    .all(|scope| requested.contains(&scope));
+  let only_known = requested
+    .iter()
+    .all(|scope| GRANTED_SCOPES.split_whitespace().any(|s| s == *scope) || *scope == "push");
+  if !covers_granted || !only_known {
+    Err(LemmyErrorType::OauthScopeNotSupported)?
+  }
+  Ok(())
+}
+
+#[derive(Deserialize)]
+pub(super) struct RegisterApp {
+  client_name: String,
+  redirect_uris: String,
+  scopes: Option<String>,
+  website: Option<String>,
+}
+
+pub(super) async fn register_app(
+  data: JsonOrForm<RegisterApp>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<Application>> {
+  let data = into_inner(data);
+  check_scopes(data.scopes.as_deref())?;
+  let client_secret = random_string(43);
+  let form = OAuthClientInsertForm {
+    website: data.website,
+    ..OAuthClientInsertForm::new(
+      data.client_name,
+      data.redirect_uris,
+      GRANTED_SCOPES.to_string(),
+      random_string(43),
+      hash_secret(&client_secret),
+    )
+  };
+  let client = OAuthClient::create(&mut context.pool(), &form).await?;
+  Ok(Json(Application::new(client, client_secret)))
+}
+
+#[derive(Deserialize)]
+pub(super) struct AuthorizeParams {
+  client_id: String,
+  redirect_uri: String,
+  scope: Option<String>,
+  state: Option<String>,
+}
+
+/// Shows a login form, which is submitted to [authorize].
+pub(super) async fn authorize_form(
+  params: Query<AuthorizeParams>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let client = OAuthClient::read_from_client_id(&mut context.pool(), &params.client_id).await?;
+  if !client.allows_redirect_uri(&params.redirect_uri) {
+    Err(LemmyErrorType::OauthAuthorizationInvalid)?
+  }
+  check_scopes(params.scope.as_deref())?;
+
+  let hidden_input = |name: &str, value: &str| {
+    format!(
+      "<input type=\"hidden\" name=\"{name}\" value=\"{}\">",
+      escape_html(value)
+    )
+  };
+  let html = format!(
+    "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><title>Authorize {app}</title></head>
+<body>
+<h1>Authorize {app} to access your account on {host}</h1>
+<form method=\"post\" action=\"/oauth/authorize\">
+{client_id}
+{redirect_uri}
+{state}
+<p><label>Username or email <input name=\"username_or_email\" required></label></p>
+<p><label>Password <input name=\"password\" type=\"password\" required></label></p>
+<p><label>2FA token (if enabled) <input name=\"totp_2fa_token\"></label></p>
+<p><button type=\"submit\">Authorize</button></p>
+</form>
+</body>
+</html>",
+    app = escape_html(&client.name),
+    host = escape_html(&context.settings().hostname),
+    client_id = hidden_input("client_id", &params.client_id),
+    redirect_uri = hidden_input("redirect_uri", &params.redirect_uri),
+    state = hidden_input("state", params.state.as_deref().unwrap_or_default()),
+  );
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/html; charset=utf-8")
+      .body(html),
+  )
+}
+
+#[derive(Deserialize)]
+pub(super) struct AuthorizeForm {
+  client_id: String,
+  redirect_uri: String,
+  state: Option<String>,
+  username_or_email: String,
+  password: String,
+  totp_2fa_token: Option<String>,
+}
+
+/// Checks the login of the user and redirects back to the client with an authorization code. The
+/// session is only created once the code is exchanged for a token.
+pub(super) async fn authorize(
+  form: Form<AuthorizeForm>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let form = form.into_inner();
+  let client = OAuthClient::read_from_client_id(&mut context.pool(), &form.client_id).await?;
+  if !client.allows_redirect_uri(&form.redirect_uri) {
+    Err(LemmyErrorType::OauthAuthorizationInvalid)?
+  }
+
+  let local_user_id = check_credentials(
+    form.username_or_email,
+    form.password,
+    form.totp_2fa_token.filter(|t| !t.is_empty()),
+    &context,
+  )
+  .await?;
+
+  let code = random_string(43);
+  let grant = AuthorizationGrant {
+    client_id: client.client_id,
+    redirect_uri: form.redirect_uri.clone(),
+    local_user_id,
+  };
+  AUTHORIZATION_CODES.insert(code.clone(), grant).await;
+
+  if form.redirect_uri == OUT_OF_BAND_URI {
+    let html = format!(
+      "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><title>Authorization code</title></head>
+<body><p>Copy this authorization code to the application:</p><pre>{code}</pre></body>
+</html>"
+    );
+    return Ok(
+      HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html),
+    );
+  }
+
+  let mut redirect = Url::parse(&form.redirect_uri)?;
+  redirect.query_pairs_mut().append_pair("code", &code);
+  if let Some(state) = form.state.filter(|s| !s.is_empty()) {
+    redirect.query_pairs_mut().append_pair("state", &state);
+  }
+  Ok(
+    HttpResponse::Found()
+      .insert_header((LOCATION, redirect.as_str()))
+      .finish(),
+  )
+}
+
+#[derive(Deserialize)]
+pub(super) struct TokenParams {
+  grant_type: String,
+  client_id: String,
+  client_secret: String,
+  code: Option<String>,
+  redirect_uri: Option<String>,
+  username: Option<String>,
+  password: Option<String>,
+  scope: Option<String>,
+}
+
+/// Exchanges an authorization code, or username and password, for an access token. The access
+/// token is a regular Lemmy login token.
+pub(super) async fn token(
+  data: JsonOrForm<TokenParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<Token>> {
+  let data = into_inner(data);
+  let client = authenticate_client(&data.client_id, &data.client_secret, &context).await?;
+
+  check_scopes(data.scope.as_deref())?;
+
+  let local_user_id = match data.grant_type.as_str() {
+    "authorization_code" => {
+      let code = data.code.ok_or(LemmyErrorType::OauthAuthorizationInvalid)?;
+      let grant = AUTHORIZATION_CODES
+        .remove(&code)
+        .await
+        .ok_or(LemmyErrorType::OauthAuthorizationInvalid)?;
+      if grant.client_id != client.client_id
+        || Some(&grant.redirect_uri) != data.redirect_uri.as_ref()
+      {
+        Err(LemmyErrorType::OauthAuthorizationInvalid)?
+      }
+      grant.local_user_id
+    }
+    "password" => {
+      let (Some(username), Some(password)) = (data.username, data.password) else {
+        return Err(LemmyErrorType::IncorrectLogin.into());
+      };
+      check_credentials(username, password, None, &context).await?
+    }
+    _ => Err(LemmyErrorType::OauthAuthorizationInvalid)?,
+  };
+  let jwt = Claims::generate(local_user_id, req, &context).await?;
+
+  Ok(Json(Token {
+    access_token: jwt.into_inner(),
+    token_type: "Bearer",
+    scope: GRANTED_SCOPES.to_string(),
+    created_at: Utc::now().timestamp(),
+  }))
+}
+
+#[derive(Deserialize)]
+pub(super) struct RevokeParams {
+  client_id: String,
+  client_secret: String,
+  token: String,
+}
+
+/// Revokes an access token. Like for the token endpoint, the client needs to authenticate
+/// (RFC 7009).
+pub(super) async fn revoke(
+  data: JsonOrForm<RevokeParams>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<Json<SuccessResponse>> {
+  let data = into_inner(data);
+  authenticate_client(&data.client_id, &data.client_secret, &context).await?;
+  LoginToken::invalidate(&mut context.pool(), &data.token).await?;
+  Ok(Json(SuccessResponse::default()))
+}
+
+/// Reads the client and checks its secret against the stored hash. The comparison takes constant
+/// time, so that the hash can't be guessed from response times.
+async fn authenticate_client(
+  client_id: &str,
+  client_secret: &str,
+  context: &LemmyContext,
+) -> LemmyResult<OAuthClient> {
+  let client = OAuthClient::read_from_client_id(&mut context.pool(), client_id).await?;
+  if !bool::from(
+    client
+      .client_secret_hash
+      .as_bytes()
+      .ct_eq(hash_secret(client_secret).as_bytes()),
+  ) {
+    Err(LemmyErrorType::OauthAuthorizationInvalid)?
+  }
+  Ok(client)
+}
+
+/// Uses the same checks as the regular login (email verification, 2fa etc).
+async fn check_credentials(
+  username_or_email: String,
+  password: String,
+  totp_2fa_token: Option<String>,
+  context: &LemmyContext,
+) -> LemmyResult<LocalUserId> {
+  let login_data = Login {
+    username_or_email: username_or_email.into(),
+    password: password.into(),
+    totp_2fa_token,
+  };
+  let local_user_view = check_login(&login_data, context).await?;
+  Ok(local_user_view.local_user.id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_check_scopes() {
+    assert!(check_scopes(None).is_ok());
+    assert!(check_scopes(Some("read write follow")).is_ok());
+    assert!(check_scopes(Some("read write follow push")).is_ok());
+    assert!(check_scopes(Some("read")).is_err());
+    assert!(check_scopes(Some("read:statuses write follow")).is_err());
+    assert!(check_scopes(Some("read write follow admin:read")).is_err());
+  }
+
+  #[test]
+  fn test_hash_secret() {
+    assert_eq!(64, hash_secret("secret").len());
+    assert_ne!(hash_secret("secret"), hash_secret("secret2"));
+  }
+}
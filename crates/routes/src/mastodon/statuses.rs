@@ -0,0 +1,337 @@
+use super::{
+  entities::{Context, Status},
+  into_inner,
+  paginated_response,
+  web_data,
+  JsonOrForm,
+  PageParams,
+  StatusId,
+};
+use activitypub_federation::config::Data;
+use actix_web::{
+  web::{Json, Path, Query},
+  HttpRequest,
+  HttpResponse,
+};
+use lemmy_api::{
+  comment::{like::like_comment, save::save_comment},
+  local_user::{list_liked::list_person_liked, list_saved::list_person_saved},
+  post::{like::like_post, save::save_post},
+};
+use lemmy_api_crud::{
+  comment::{create::create_comment, read::get_comment},
+  post::read::get_post,
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub::api::list_comments::list_comments;
+use lemmy_db_schema::{newtypes::CommentId, source::comment::Comment, LikeType};
+use lemmy_db_views_comment::api::{
+  CreateComment,
+  CreateCommentLike,
+  GetComment,
+  GetComments,
+  SaveComment,
+};
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_person_liked_combined::{ListPersonLiked, PersonLikedCombinedView};
+use lemmy_db_views_person_saved_combined::{ListPersonSaved, PersonSavedCombinedView};
+use lemmy_db_views_post::api::{CreatePostLike, GetPost, SavePost};
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+use serde::Deserialize;
+
+/// How many levels of replies are included in the context of a status.
+const CONTEXT_MAX_DEPTH: i32 = 8;
+
+async fn read_status(
+  status_id: StatusId,
+  context: &LemmyContext,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Status> {
+  match status_id {
+    StatusId::Post(post_id) => {
+      let data = GetPost {
+        id: Some(post_id),
+        comment_id: None,
+      };
+      let res = get_post(Query(data), web_data(context), local_user_view).await?;
+      Status::from_post_view(&res.post_view, context.settings())
+    }
+    StatusId::Comment(comment_id) => {
+      let data = GetComment { id: comment_id };
+      let res = get_comment(Query(data), web_data(context), local_user_view).await?;
+      Status::from_comment_view(&res.comment_view, context.settings())
+    }
+  }
+}
+
+pub(super) async fn get_status(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<Status>> {
+  let status_id = StatusId::parse(&id)?;
+  Ok(Json(
+    read_status(status_id, &context, local_user_view).await?,
+  ))
+}
+
+/// Ids of all parent comments, starting at the top level.
+fn parent_comment_ids(comment: &Comment) -> Vec<CommentId> {
+  comment
+    .path
+    .0
+    .split('.')
+    // Skip the root "0"
+    .skip(1)
+    .filter_map(|id| id.parse().ok())
+    .map(CommentId)
+    .filter(|id| *id != comment.id)
+    .collect()
+}
+
+pub(super) async fn get_context(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<Json<Context>> {
+  let (ancestors, post_id, parent_id) = match StatusId::parse(&id)? {
+    StatusId::Post(post_id) => (vec![], post_id, None),
+    StatusId::Comment(comment_id) => {
+      let data = GetComment { id: comment_id };
+      let comment_view = get_comment(Query(data), web_data(&context), local_user_view.clone())
+        .await?
+        .into_inner()
+        .comment_view;
+      let post_id = comment_view.post.id;
+
+      let mut ancestors =
+        vec![read_status(StatusId::Post(post_id), &context, local_user_view.clone()).await?];
+      for parent_id in parent_comment_ids(&comment_view.comment) {
+        let status = read_status(
+          StatusId::Comment(parent_id),
+          &context,
+          local_user_view.clone(),
+        )
+        .await?;
+        ancestors.push(status);
+      }
+      (ancestors, post_id, Some(comment_id))
+    }
+  };
+
+  let data = GetComments {
+    post_id: Some(post_id),
+    parent_id,
+    max_depth: Some(CONTEXT_MAX_DEPTH),
+    ..Default::default()
+  };
+  let comments = list_comments(Query(data), context.reset_request_count(), local_user_view)
+    .await?
+    .into_inner()
+    .comments;
+  let descendants = comments
+    .iter()
+    // The parent itself is included in the result
+    .filter(|c| Some(c.comment.id) != parent_id)
+    .map(|c| Status::from_comment_view(c, context.settings()))
+    .collect::<LemmyResult<Vec<_>>>()?;
+
+  Ok(Json(Context {
+    ancestors,
+    descendants,
+  }))
+}
+
+#[derive(Deserialize)]
+pub(super) struct CreateStatus {
+  status: String,
+  in_reply_to_id: Option<String>,
+  visibility: Option<String>,
+}
+
+/// Creates a comment. Posts can't be created this way, because they need a title and community.
+pub(super) async fn create_status(
+  data: JsonOrForm<CreateStatus>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  let data = into_inner(data);
+  // Everything is public in Lemmy, so don't publish something which was meant to be private
+  if matches!(data.visibility.as_deref(), Some("private" | "direct")) {
+    Err(LemmyErrorType::StatusMustBePublic)?
+  }
+  let in_reply_to_id = data
+    .in_reply_to_id
+    .ok_or(LemmyErrorType::StatusMustBeReply)?;
+
+  let (post_id, parent_id) = match StatusId::parse(&in_reply_to_id)? {
+    StatusId::Post(post_id) => (post_id, None),
+    StatusId::Comment(comment_id) => {
+      let data = GetComment { id: comment_id };
+      let res = get_comment(
+        Query(data),
+        web_data(&context),
+        Some(local_user_view.clone()),
+      )
+      .await?;
+      (res.comment_view.post.id, Some(comment_id))
+    }
+  };
+
+  let data = CreateComment {
+    content: data.status,
+    post_id,
+    parent_id,
+    language_id: None,
+  };
+  let res = create_comment(Json(data), context.reset_request_count(), local_user_view).await?;
+  Ok(Json(Status::from_comment_view(
+    &res.comment_view,
+    context.settings(),
+  )?))
+}
+
+async fn vote_status(
+  id: &str,
+  score: i16,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  let status = match StatusId::parse(id)? {
+    StatusId::Post(post_id) => {
+      let data = CreatePostLike { post_id, score };
+      let res = like_post(Json(data), context.reset_request_count(), local_user_view).await?;
+      Status::from_post_view(&res.post_view, context.settings())?
+    }
+    StatusId::Comment(comment_id) => {
+      let data = CreateCommentLike { comment_id, score };
+      let res = like_comment(Json(data), context.reset_request_count(), local_user_view).await?;
+      Status::from_comment_view(&res.comment_view, context.settings())?
+    }
+  };
+  Ok(Json(status))
+}
+
+pub(super) async fn favourite_status(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  vote_status(&id, 1, context, local_user_view).await
+}
+
+pub(super) async fn unfavourite_status(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  vote_status(&id, 0, context, local_user_view).await
+}
+
+async fn save_status(
+  id: &str,
+  save: bool,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  let status = match StatusId::parse(id)? {
+    StatusId::Post(post_id) => {
+      let data = SavePost { post_id, save };
+      let res = save_post(Json(data), web_data(&context), local_user_view).await?;
+      Status::from_post_view(&res.post_view, context.settings())?
+    }
+    StatusId::Comment(comment_id) => {
+      let data = SaveComment { comment_id, save };
+      let res = save_comment(Json(data), web_data(&context), local_user_view).await?;
+      Status::from_comment_view(&res.comment_view, context.settings())?
+    }
+  };
+  Ok(Json(status))
+}
+
+pub(super) async fn bookmark_status(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  save_status(&id, true, context, local_user_view).await
+}
+
+pub(super) async fn unbookmark_status(
+  id: Path<String>,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<Json<Status>> {
+  save_status(&id, false, context, local_user_view).await
+}
+
+/// Upvoted posts and comments.
+pub(super) async fn list_favourites(
+  params: Query<PageParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<HttpResponse> {
+  let params = params.into_inner();
+  let data = ListPersonLiked {
+    type_: None,
+    like_type: Some(LikeType::LikedOnly),
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+    limit: params.limit,
+  };
+  let res = list_person_liked(Query(data), context.reset_request_count(), local_user_view)
+    .await?
+    .into_inner();
+
+  let statuses = res
+    .liked
+    .iter()
+    .map(|l| match l {
+      PersonLikedCombinedView::Post(v) => Status::from_post_view(v, context.settings()),
+      PersonLikedCombinedView::Comment(v) => Status::from_comment_view(v, context.settings()),
+    })
+    .collect::<LemmyResult<Vec<_>>>()?;
+  Ok(paginated_response(
+    &req,
+    &context,
+    statuses,
+    res.next_page,
+    res.prev_page,
+  ))
+}
+
+/// Saved posts and comments.
+pub(super) async fn list_bookmarks(
+  params: Query<PageParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<HttpResponse> {
+  let params = params.into_inner();
+  let data = ListPersonSaved {
+    type_: None,
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+    limit: params.limit,
+  };
+  let res = list_person_saved(Query(data), context.reset_request_count(), local_user_view)
+    .await?
+    .into_inner();
+
+  let statuses = res
+    .saved
+    .iter()
+    .map(|s| match s {
+      PersonSavedCombinedView::Post(v) => Status::from_post_view(v, context.settings()),
+      PersonSavedCombinedView::Comment(v) => Status::from_comment_view(v, context.settings()),
+    })
+    .collect::<LemmyResult<Vec<_>>>()?;
+  Ok(paginated_response(
+    &req,
+    &context,
+    statuses,
+    res.next_page,
+    res.prev_page,
+  ))
+}
@@ -0,0 +1,88 @@
+use super::{entities::Status, paginated_response, PageParams};
+use activitypub_federation::config::Data;
+use actix_web::{web::Query, HttpRequest, HttpResponse};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_apub::api::list_posts::list_posts;
+use lemmy_db_schema::newtypes::PaginationCursor;
+use lemmy_db_schema_file::enums::ListingType;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_db_views_post::api::GetPosts;
+use lemmy_utils::error::LemmyResult;
+use serde::Deserialize;
+
+/// Posts from the communities which the user follows.
+pub(super) async fn home_timeline(
+  params: Query<PageParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: LocalUserView,
+) -> LemmyResult<HttpResponse> {
+  timeline(
+    ListingType::Subscribed,
+    params.into_inner(),
+    req,
+    context,
+    Some(local_user_view),
+  )
+  .await
+}
+
+#[derive(Deserialize)]
+pub(super) struct PublicTimelineParams {
+  local: Option<bool>,
+  limit: Option<i64>,
+  page_cursor: Option<PaginationCursor>,
+  page_back: Option<bool>,
+}
+
+pub(super) async fn public_timeline(
+  params: Query<PublicTimelineParams>,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<HttpResponse> {
+  let params = params.into_inner();
+  let listing_type = if params.local.unwrap_or_default() {
+    ListingType::Local
+  } else {
+    ListingType::All
+  };
+  let page = PageParams {
+    limit: params.limit,
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+  };
+  timeline(listing_type, page, req, context, local_user_view).await
+}
+
+async fn timeline(
+  listing_type: ListingType,
+  params: PageParams,
+  req: HttpRequest,
+  context: Data<LemmyContext>,
+  local_user_view: Option<LocalUserView>,
+) -> LemmyResult<HttpResponse> {
+  let data = GetPosts {
+    type_: Some(listing_type),
+    page_cursor: params.page_cursor,
+    page_back: params.page_back,
+    limit: params.limit,
+    ..Default::default()
+  };
+  let res = list_posts(Query(data), context.reset_request_count(), local_user_view)
+    .await?
+    .into_inner();
+
+  let statuses = res
+    .posts
+    .iter()
+    .map(|p| Status::from_post_view(p, context.settings()))
+    .collect::<LemmyResult<Vec<_>>>()?;
+  Ok(paginated_response(
+    &req,
+    &context,
+    statuses,
+    res.next_page,
+    res.prev_page,
+  ))
+}
@@ -0,0 +1,498 @@
+//! oEmbed provider for posts and comments, so that they can be embedded by chat tools and blogs.
+//! The embed code is an iframe which shows a minimal html page of the post or comment.
+//!
+//! https://oembed.com/
+use crate::utils::escape_html;
+use activitypub_federation::config::Data;
+use actix_web::{
+  web::{get, scope, Path, Query, ServiceConfig},
+  HttpResponse,
+};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema::{
+  newtypes::{CommentId, DbUrl, PostId},
+  source::{comment::Comment, community::Community, person::Person, post::Post},
+  traits::Crud,
+};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorType, LemmyResult},
+  rate_limit::RateLimit,
+  utils::markdown::markdown_to_html,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Default size of the iframe, can be reduced with `maxwidth` and `maxheight`.
+const DEFAULT_WIDTH: u32 = 550;
+const DEFAULT_HEIGHT: u32 = 300;
+/// How long consumers may cache the response, in seconds.
+const CACHE_AGE: u32 = 3600;
+
+const STYLE: &str = "body{margin:0;padding:.5rem 1rem;font-family:sans-serif;line-height:1.4}\
+h1{font-size:1.2em;margin:.3rem 0}img{max-width:100%}.meta{color:#666;font-size:.9em}";
+const NSFW_NOTICE: &str = "<p class=\"meta\">This content is marked as NSFW.</p>\n";
+
+pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
+  cfg.service(
+    scope("/embed")
+      .wrap(rate_limit.message())
+      .route("/post/{post_id}", get().to(embed_post))
+      .route("/comment/{comment_id}", get().to(embed_comment)),
+  );
+}
+
+#[derive(Deserialize)]
+pub struct OEmbedParams {
+  url: String,
+  maxwidth: Option<u32>,
+  maxheight: Option<u32>,
+  format: Option<String>,
+}
+
+#[derive(Serialize)]
+struct OEmbedResponse {
+  #[serde(rename = "type")]
+  type_: &'static str,
+  version: &'static str,
+  title: String,
+  author_name: String,
+  author_url: DbUrl,
+  provider_name: String,
+  provider_url: String,
+  cache_age: u32,
+  html: String,
+  width: u32,
+  height: u32,
+}
+
+/// Returns a `rich` oEmbed response for the url of a local post or comment, or of a remote one
+/// which is known to this instance.
+pub async fn get_oembed(
+  params: Query<OEmbedParams>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  // Only json is supported, the spec requires this status for other formats
+  if params.format.as_deref().is_some_and(|f| f != "json") {
+    return Ok(HttpResponse::NotImplemented().finish());
+  }
+  let url = Url::parse(&params.url)?;
+  let (post, comment) = match local_object(&url, &context.settings().get_protocol_and_hostname()) {
+    Some(LocalObject::Post(post_id)) => (Post::read(&mut context.pool(), post_id).await?, None),
+    Some(LocalObject::Comment(comment_id)) => {
+      let comment = Comment::read(&mut context.pool(), comment_id).await?;
+      let post = Post::read(&mut context.pool(), comment.post_id).await?;
+      (post, Some(comment))
+    }
+    None => match Post::read_from_apub_id(&mut context.pool(), url.clone()).await? {
+      Some(post) => (post, None),
+      None => {
+        let comment = Comment::read_from_apub_id(&mut context.pool(), url)
+          .await?
+          .ok_or(LemmyErrorType::NotFound)?;
+        let post = Post::read(&mut context.pool(), comment.post_id).await?;
+        (post, Some(comment))
+      }
+    },
+  };
+  let embed = Embed::load(post, comment, &context).await?;
+
+  let width = params.maxwidth.unwrap_or(DEFAULT_WIDTH).min(DEFAULT_WIDTH);
+  let height = params
+    .maxheight
+    .unwrap_or(DEFAULT_HEIGHT)
+    .min(DEFAULT_HEIGHT);
+  let provider_url = context.settings().get_protocol_and_hostname();
+  let embed_url = match &embed.comment {
+    Some(comment) => format!("{provider_url}/embed/comment/{}", comment.id.0),
+    None => format!("{provider_url}/embed/post/{}", embed.post.id.0),
+  };
+  let html = format!(
+    "<iframe src=\"{}\" width=\"{width}\" height=\"{height}\" style=\"border:0;max-width:100%\" \
+     sandbox=\"allow-popups allow-popups-to-escape-sandbox\" loading=\"lazy\"></iframe>",
+    escape_html(&embed_url)
+  );
+  Ok(HttpResponse::Ok().json(OEmbedResponse {
+    type_: "rich",
+    version: "1.0",
+    title: embed.title(),
+    author_name: display_name(&embed.creator).to_string(),
+    author_url: embed.creator.ap_id.clone(),
+    provider_name: embed.site_name,
+    provider_url,
+    cache_age: CACHE_AGE,
+    html,
+    width,
+    height,
+  }))
+}
+
+async fn embed_post(
+  post_id: Path<String>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let post_id = PostId(post_id.parse().with_lemmy_type(LemmyErrorType::NotFound)?);
+  let post = Post::read(&mut context.pool(), post_id).await?;
+  let embed = Embed::load(post, None, &context).await?;
+  Ok(html_response(&embed, &context))
+}
+
+async fn embed_comment(
+  comment_id: Path<String>,
+  context: Data<LemmyContext>,
+) -> LemmyResult<HttpResponse> {
+  let comment_id = CommentId(
+    comment_id
+      .parse()
+      .with_lemmy_type(LemmyErrorType::NotFound)?,
+  );
+  let comment = Comment::read(&mut context.pool(), comment_id).await?;
+  let post = Post::read(&mut context.pool(), comment.post_id).await?;
+  let embed = Embed::load(post, Some(comment), &context).await?;
+  Ok(html_response(&embed, &context))
+}
+
+fn html_response(embed: &Embed, context: &LemmyContext) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("text/html; charset=utf-8")
+    .body(embed.render(&context.settings().get_protocol_and_hostname()))
+}
+
+#[derive(Debug, PartialEq)]
+enum LocalObject {
+  Post(PostId),
+  Comment(CommentId),
+}
+
+/// Parses urls of local posts and comments like `https://lemmy.example/post/1`.
+fn local_object(url: &Url, protocol_and_hostname: &str) -> Option<LocalObject> {
+  let local = Url::parse(protocol_and_hostname).ok()?;
+  if url.origin() != local.origin() {
+    return None;
+  }
+  let segments: Vec<_> = url.path_segments()?.collect();
+  match segments.as_slice() {
+    ["post", id] => Some(LocalObject::Post(PostId(id.parse().ok()?))),
+    ["comment", id] => Some(LocalObject::Comment(CommentId(id.parse().ok()?))),
+    _ => None,
+  }
+}
+
+/// A post or comment which can be embedded, with the data needed to render it.
+struct Embed {
+  post: Post,
+  comment: Option<Comment>,
+  creator: Person,
+  community: Community,
+  site_name: String,
+}
+
+impl Embed {
+  /// Load the creator and community. Content which is not publicly visible, removed or deleted
+  /// can't be embedded.
+  async fn load(post: Post, comment: Option<Comment>, context: &LemmyContext) -> LemmyResult<Self> {
+    let site_view = SiteView::read_local(&mut context.pool()).await?;
+    let community = Community::read(&mut context.pool(), post.community_id).await?;
+    let hidden = site_view.local_site.private_instance
+      || !community.visibility.can_view_without_login()
+      || community.removed
+      || community.deleted
+      || post.removed
+      || post.deleted
+      || post.scheduled_publish_time_at.is_some()
+      || comment.as_ref().is_some_and(|c| c.removed || c.deleted);
+    if hidden {
+      Err(LemmyErrorType::NotFound)?
+    }
+    let creator_id = comment.as_ref().map_or(post.creator_id, |c| c.creator_id);
+    let creator = Person::read(&mut context.pool(), creator_id).await?;
+    Ok(Embed {
+      post,
+      comment,
+      creator,
+      community,
+      site_name: site_view.site.name,
+    })
+  }
+
+  /// NSFW content is embedded without its text, links and images, like the thumbnails of the html
+  /// pages. Consumers can't know if their readers want to see it.
+  fn nsfw(&self) -> bool {
+    self.post.nsfw || self.community.nsfw
+  }
+
+  fn title(&self) -> String {
+    match self.comment {
+      Some(_) => format!("Comment on {}", self.post.name),
+      None => self.post.name.clone(),
+    }
+  }
+
+  /// A standalone html page for the iframe. Links open in a new tab.
+  fn render(&self, provider_url: &str) -> String {
+    let post_link = format!(
+      "<a href=\"{}\">{}</a>",
+      escape_html(self.post.ap_id.as_str()),
+      escape_html(&self.post.name)
+    );
+    let (score, content) = match &self.comment {
+      Some(comment) if self.nsfw() => (
+        comment.score,
+        format!("<p class=\"meta\">Comment on {post_link}</p>\n{NSFW_NOTICE}"),
+      ),
+      Some(comment) => (
+        comment.score,
+        format!(
+          "<p class=\"meta\">Comment on {post_link}</p>\n{}",
+          markdown_to_html(&comment.content)
+        ),
+      ),
+      None => {
+        let mut content = format!("<h1>{post_link}</h1>\n");
+        if self.nsfw() {
+          content.push_str(NSFW_NOTICE);
+        } else {
+          if let Some(url) = &self.post.url {
+            content.push_str(&format!(
+              "<p><a href=\"{url}\" rel=\"nofollow noopener\">{url}</a></p>\n",
+              url = escape_html(url.as_str())
+            ));
+          }
+          if let Some(body) = &self.post.body {
+            content.push_str(&markdown_to_html(body));
+          }
+        }
+        (self.post.score, content)
+      }
+    };
+    format!(
+      "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<base target=\"_blank\">
+<title>{title}</title>
+<style>{STYLE}</style>
+</head>
+<body>
+<article>
+<p class=\"meta\"><a href=\"{creator_url}\">{creator}</a> in <a href=\"{community_url}\">{community}</a> · {score} points</p>
+{content}
+<p class=\"meta\">via <a href=\"{provider_url}\">{site}</a></p>
+</article>
+</body>
+</html>",
+      title = escape_html(&self.title()),
+      creator_url = escape_html(self.creator.ap_id.as_str()),
+      creator = escape_html(display_name(&self.creator)),
+      community_url = escape_html(self.community.ap_id.as_str()),
+      community = escape_html(&self.community.title),
+      provider_url = escape_html(provider_url),
+      site = escape_html(&self.site_name),
+    )
+  }
+}
+
+fn display_name(person: &Person) -> &str {
+  person.display_name.as_deref().unwrap_or(&person.name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::body::to_bytes;
+  use chrono::{TimeDelta, Utc};
+  use lemmy_db_schema::{
+    source::{
+      comment::CommentInsertForm,
+      community::CommunityInsertForm,
+      person::PersonInsertForm,
+      post::PostInsertForm,
+    },
+    test_data::TestData,
+  };
+  use lemmy_db_schema_file::enums::CommunityVisibility;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+
+  #[test]
+  fn test_local_object() -> LemmyResult<()> {
+    let object = |url: &str| -> LemmyResult<Option<LocalObject>> {
+      Ok(local_object(&Url::parse(url)?, "https://lemmy.example"))
+    };
+    assert_eq!(
+      Some(LocalObject::Post(PostId(12))),
+      object("https://lemmy.example/post/12")?
+    );
+    assert_eq!(
+      Some(LocalObject::Comment(CommentId(3))),
+      object("https://lemmy.example/comment/3?context=1")?
+    );
+    assert_eq!(None, object("https://lemmy.example/post/12/edit")?);
+    assert_eq!(None, object("https://lemmy.example/post/abc")?);
+    // Remote objects are looked up by their ActivityPub id
+    assert_eq!(None, object("https://other.example/post/12")?);
+    Ok(())
+  }
+
+  async fn body(res: HttpResponse) -> LemmyResult<String> {
+    let body = to_bytes(res.into_body()).await.unwrap_or_default();
+    Ok(String::from_utf8(body.to_vec())?)
+  }
+
+  async fn oembed(url: &DbUrl, context: &Data<LemmyContext>) -> LemmyResult<serde_json::Value> {
+    let params = OEmbedParams {
+      url: url.to_string(),
+      maxwidth: Some(400),
+      maxheight: None,
+      format: None,
+    };
+    let res = get_oembed(Query(params), context.reset_request_count()).await?;
+    Ok(serde_json::from_str(&body(res).await?)?)
+  }
+
+  async fn embed(post: &Post, context: &Data<LemmyContext>) -> LemmyResult<String> {
+    let res = embed_post(
+      Path::from(post.id.0.to_string()),
+      context.reset_request_count(),
+    )
+    .await?;
+    body(res).await
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_embed() -> LemmyResult<()> {
+    let context = LemmyContext::init_test_context().await;
+    let pool = &mut context.pool();
+    let data = TestData::create(pool).await?;
+    let person = Person::create(
+      pool,
+      &PersonInsertForm::test_form(data.instance.id, "embed"),
+    )
+    .await?;
+    let community_form = |name: &str| {
+      CommunityInsertForm::new(
+        data.instance.id,
+        name.to_string(),
+        name.to_owned(),
+        "pubkey".to_string(),
+      )
+    };
+    let community = Community::create(pool, &community_form("embed")).await?;
+    let new_post = |community_id| PostInsertForm {
+      body: Some("Post body".to_string()),
+      ..PostInsertForm::new("Embedded post".into(), person.id, community_id)
+    };
+    let post = Post::create(pool, &new_post(community.id)).await?;
+    let comment_form = CommentInsertForm::new(person.id, post.id, "Comment text".to_string());
+    let comment = Comment::create(pool, &comment_form, None).await?;
+
+    let json = oembed(&post.ap_id, &context).await?;
+    assert_eq!(
+      Some("rich"),
+      json.get("type").and_then(serde_json::Value::as_str)
+    );
+    assert_eq!(
+      Some("Embedded post"),
+      json.get("title").and_then(serde_json::Value::as_str)
+    );
+    assert_eq!(
+      Some("embed"),
+      json.get("author_name").and_then(serde_json::Value::as_str)
+    );
+    assert_eq!(
+      Some(400),
+      json.get("width").and_then(serde_json::Value::as_u64)
+    );
+    let html = json
+      .get("html")
+      .and_then(serde_json::Value::as_str)
+      .unwrap_or_default();
+    assert!(html.contains(&format!("/embed/post/{}", post.id.0)));
+
+    let json = oembed(&comment.ap_id, &context).await?;
+    assert_eq!(
+      Some("Comment on Embedded post"),
+      json.get("title").and_then(serde_json::Value::as_str)
+    );
+    let html = json
+      .get("html")
+      .and_then(serde_json::Value::as_str)
+      .unwrap_or_default();
+    assert!(html.contains(&format!("/embed/comment/{}", comment.id.0)));
+
+    let html = embed(&post, &context).await?;
+    assert!(html.contains("Embedded post"));
+    assert!(html.contains("<p>Post body</p>"));
+
+    // Content which is not public can't be embedded
+    let mut hidden = vec![];
+    for visibility in [
+      CommunityVisibility::Unlisted,
+      CommunityVisibility::LocalOnlyPrivate,
+      CommunityVisibility::Private,
+    ] {
+      let form = CommunityInsertForm {
+        visibility: Some(visibility),
+        ..community_form(&format!("embed_{visibility}").to_lowercase())
+      };
+      let community = Community::create(pool, &form).await?;
+      hidden.push(Post::create(pool, &new_post(community.id)).await?);
+    }
+    let removed_community = CommunityInsertForm {
+      removed: Some(true),
+      ..community_form("embed_removed")
+    };
+    let removed_community = Community::create(pool, &removed_community).await?;
+    hidden.push(Post::create(pool, &new_post(removed_community.id)).await?);
+    let deleted_community = CommunityInsertForm {
+      deleted: Some(true),
+      ..community_form("embed_deleted")
+    };
+    let deleted_community = Community::create(pool, &deleted_community).await?;
+    hidden.push(Post::create(pool, &new_post(deleted_community.id)).await?);
+    let removed = PostInsertForm {
+      removed: Some(true),
+      ..new_post(community.id)
+    };
+    hidden.push(Post::create(pool, &removed).await?);
+    let deleted = PostInsertForm {
+      deleted: Some(true),
+      ..new_post(community.id)
+    };
+    hidden.push(Post::create(pool, &deleted).await?);
+    let scheduled = PostInsertForm {
+      scheduled_publish_time_at: Some(Utc::now() + TimeDelta::days(1)),
+      ..new_post(community.id)
+    };
+    hidden.push(Post::create(pool, &scheduled).await?);
+    for post in &hidden {
+      assert!(oembed(&post.ap_id, &context).await.is_err());
+      assert!(embed(post, &context).await.is_err());
+    }
+
+    // NSFW content is embedded without body
+    let nsfw = PostInsertForm {
+      nsfw: Some(true),
+      ..new_post(community.id)
+    };
+    let nsfw = Post::create(pool, &nsfw).await?;
+    let nsfw_community = CommunityInsertForm {
+      nsfw: Some(true),
+      ..community_form("embed_nsfw")
+    };
+    let nsfw_community = Community::create(pool, &nsfw_community).await?;
+    let in_nsfw_community = Post::create(pool, &new_post(nsfw_community.id)).await?;
+    for post in [nsfw, in_nsfw_community] {
+      let html = embed(&post, &context).await?;
+      assert!(html.contains("Embedded post"));
+      assert!(html.contains("marked as NSFW"));
+      assert!(!html.contains("Post body"));
+    }
+
+    data.delete(pool).await?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,403 @@
+use chrono::{TimeDelta, Utc};
+use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema::{
+  impls::actor_language::UNDETERMINED_ID,
+  newtypes::InstanceId,
+  source::{
+    community_directory::{CommunityDirectoryEntry, CommunityDirectoryEntryForm},
+    instance::Instance,
+    language::Language,
+  },
+};
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::{info, warn};
+use url::Url;
+
+/// Number of communities which are requested at once
+const PAGE_LIMIT: i64 = 50;
+/// Only the largest communities of each instance are listed in the directory. This also prevents
+/// endless crawling if an instance ignores the page parameter.
+const MAX_PAGES: i64 = 40;
+/// Limit of the varchar columns in the directory
+const MAX_COLUMN_LENGTH: usize = 255;
+/// Maximum size of an API response from another instance
+const MAX_RESPONSE_SIZE: usize = 2 * 1024 * 1024;
+
+/// Collects the public communities of other instances running Lemmy or compatible software for
+/// the community directory. Only lightweight entries are stored, the actual communities are
+/// fetched when a user resolves them.
+pub(crate) async fn crawl_community_directory(context: &LemmyContext) -> LemmyResult<()> {
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  if !local_site.community_directory_enabled {
+    return Ok(());
+  }
+  info!("Crawling community directory...");
+
+  let dead_after = TimeDelta::days(context.settings().federation.dead_instance_days.into());
+  let instances =
+    Instance::read_federated_with_blocked_and_dead(&mut context.pool(), dead_after).await?;
+  for (instance, allowed, is_dead) in instances {
+    if !allowed {
+      CommunityDirectoryEntry::delete_for_instance(&mut context.pool(), instance.id, None).await?;
+      continue;
+    }
+    let apis = instance
+      .software
+      .as_deref()
+      .map(RemoteApi::for_software)
+      .unwrap_or_default();
+    if apis.is_empty() || is_dead || instance.domain == context.settings().hostname {
+      continue;
+    }
+    crawl_instance(&instance, apis, context)
+      .await
+      .inspect_err(|e| warn!("Failed to crawl communities of {}: {e}", instance.domain))
+      .ok();
+  }
+
+  info!("Finished crawling community directory");
+  Ok(())
+}
+
+/// Lemmy-compatible APIs of other instances.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RemoteApi {
+  LemmyV4,
+  /// Lemmy 0.19 and older
+  LemmyV3,
+  Piefed,
+}
+
+impl RemoteApi {
+  /// APIs which the given software may have, the preferred one first.
+  fn for_software(software: &str) -> &'static [RemoteApi] {
+    match software {
+      "lemmy" => &[RemoteApi::LemmyV4, RemoteApi::LemmyV3],
+      "piefed" => &[RemoteApi::Piefed],
+      _ => &[],
+    }
+  }
+
+  fn path(self) -> &'static str {
+    match self {
+      RemoteApi::LemmyV4 => "api/v4",
+      RemoteApi::LemmyV3 => "api/v3",
+      RemoteApi::Piefed => "api/alpha",
+    }
+  }
+
+  /// Sort by number of subscribers, or the closest sort which the API has.
+  fn sort(self) -> &'static str {
+    match self {
+      RemoteApi::LemmyV4 => "Subscribers",
+      RemoteApi::LemmyV3 | RemoteApi::Piefed => "TopAll",
+    }
+  }
+}
+
+async fn crawl_instance(
+  instance: &Instance,
+  apis: &[RemoteApi],
+  context: &LemmyContext,
+) -> LemmyResult<()> {
+  let crawl_start = Utc::now();
+
+  // Use the first API which the instance has. The API doesn't return languages for each
+  // community, so use the languages which are allowed on the instance.
+  let mut remote: LemmyResult<(RemoteApi, String, RemoteSite)> =
+    Err(LemmyErrorType::NotFound.into());
+  for api in apis {
+    let base_url = format!("https://{}/{}", instance.domain, api.path());
+    remote = get_json::<RemoteSite>(&format!("{base_url}/site"), &[], context)
+      .await
+      .map(|site| (*api, base_url, site));
+    if remote.is_ok() {
+      break;
+    }
+  }
+  let (api, base_url, site) = remote?;
+  let mut language_ids = vec![];
+  for code in site.discussion_language_codes() {
+    let language_id = Language::read_id_from_code(&mut context.pool(), code).await?;
+    if language_id != UNDETERMINED_ID {
+      language_ids.push(language_id);
+    }
+  }
+
+  let limit = PAGE_LIMIT.to_string();
+  let mut page_cursor: Option<String> = None;
+  for page in 1..=MAX_PAGES {
+    let page = page.to_string();
+    let mut query = vec![
+      ("type_", "Local"),
+      ("sort", api.sort()),
+      ("show_nsfw", "true"),
+      ("limit", limit.as_str()),
+    ];
+    // The v4 API uses cursors for pagination, the others page numbers
+    match (api, &page_cursor) {
+      (RemoteApi::LemmyV4, Some(cursor)) => query.push(("page_cursor", cursor.as_str())),
+      (RemoteApi::LemmyV4, None) => {}
+      _ => query.push(("page", page.as_str())),
+    }
+    let list: RemoteCommunityList =
+      get_json(&format!("{base_url}/community/list"), &query, context).await?;
+    if list.communities.is_empty() {
+      break;
+    }
+    let forms: Vec<_> = list
+      .communities
+      .into_iter()
+      .filter_map(|c| c.into_form(instance.id, &instance.domain))
+      .collect();
+    CommunityDirectoryEntry::upsert(&mut context.pool(), &forms, &language_ids).await?;
+    if api == RemoteApi::LemmyV4 {
+      match list.next_page {
+        Some(next_page) => page_cursor = Some(next_page),
+        None => break,
+      }
+    }
+  }
+
+  // Remove communities which were deleted or made private since the last crawl
+  CommunityDirectoryEntry::delete_for_instance(&mut context.pool(), instance.id, Some(crawl_start))
+    .await?;
+  Ok(())
+}
+
+/// Fetch a JSON response, rejecting responses which are too large.
+async fn get_json<T: DeserializeOwned>(
+  url: &str,
+  query: &[(&str, &str)],
+  context: &LemmyContext,
+) -> LemmyResult<T> {
+  let mut response = context
+    .client()
+    .get(url)
+    .query(query)
+    .send()
+    .await?
+    .error_for_status()?;
+  let mut body = Vec::new();
+  while let Some(chunk) = response.chunk().await? {
+    body.extend_from_slice(&chunk);
+    if body.len() > MAX_RESPONSE_SIZE {
+      Err(LemmyErrorType::ResponseTooLarge)?
+    }
+  }
+  Ok(serde_json::from_slice(&body)?)
+}
+
+#[derive(Deserialize)]
+struct RemoteSite {
+  #[serde(default)]
+  all_languages: Vec<RemoteLanguage>,
+  #[serde(default)]
+  discussion_languages: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+struct RemoteLanguage {
+  id: i32,
+  code: String,
+}
+
+impl RemoteSite {
+  /// Language ids are different on each instance, so they need to be converted to codes.
+  fn discussion_language_codes(&self) -> impl Iterator<Item = &str> {
+    self
+      .all_languages
+      .iter()
+      .filter(|l| self.discussion_languages.contains(&l.id))
+      .map(|l| l.code.as_str())
+  }
+}
+
+#[derive(Deserialize)]
+struct RemoteCommunityList {
+  communities: Vec<RemoteCommunityView>,
+  /// Cursor of the next page, only returned by the v4 API
+  next_page: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RemoteCommunityView {
+  community: RemoteCommunity,
+  /// Lemmy 0.19 returns the counts separately, newer versions include them in the community
+  counts: Option<RemoteCommunityCounts>,
+}
+
+#[derive(Deserialize)]
+struct RemoteCommunityCounts {
+  #[serde(default)]
+  subscribers: i32,
+  #[serde(default)]
+  users_active_month: i32,
+}
+
+#[derive(Deserialize)]
+struct RemoteCommunity {
+  #[serde(alias = "actor_id")]
+  ap_id: Url,
+  name: String,
+  title: String,
+  description: Option<String>,
+  icon: Option<Url>,
+  #[serde(default)]
+  nsfw: bool,
+  #[serde(default)]
+  removed: bool,
+  #[serde(default)]
+  deleted: bool,
+  visibility: Option<String>,
+  #[serde(flatten)]
+  counts: RemoteCommunityCounts,
+}
+
+impl RemoteCommunityView {
+  /// Returns None for communities which shouldn't be listed, or which don't fit into the
+  /// directory.
+  fn into_form(self, instance_id: InstanceId, domain: &str) -> Option<CommunityDirectoryEntryForm> {
+    let community = self.community;
+    let public = community
+      .visibility
+      .as_deref()
+      .map_or(true, |v| v == "Public");
+    let too_long = [
+      community.ap_id.as_str(),
+      community.name.as_str(),
+      community.title.as_str(),
+    ]
+    .iter()
+    .any(|s| s.chars().count() > MAX_COLUMN_LENGTH);
+    if community.removed
+      || community.deleted
+      || !public
+      || too_long
+      || community.ap_id.domain() != Some(domain)
+    {
+      return None;
+    }
+    let counts = self.counts.unwrap_or(community.counts);
+    Some(CommunityDirectoryEntryForm {
+      instance_id,
+      ap_id: community.ap_id.into(),
+      name: community.name,
+      title: community.title,
+      description: community.description,
+      icon: community.icon.map(Into::into),
+      nsfw: community.nsfw,
+      subscribers: counts.subscribers,
+      users_active_month: counts.users_active_month,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  #[test]
+  fn test_remote_community_list() -> LemmyResult<()> {
+    let list: RemoteCommunityList = serde_json::from_value(json!({
+      "communities": [
+        {
+          "community": {
+            "actor_id": "https://lemmy.ml/c/rust",
+            "name": "rust",
+            "title": "Rust",
+            "nsfw": false,
+            "visibility": "Public"
+          },
+          "counts": { "subscribers": 100, "users_active_month": 10 }
+        },
+        {
+          "community": {
+            "ap_id": "https://lemmy.ml/c/memes",
+            "name": "memes",
+            "title": "Memes",
+            "subscribers": 50,
+            "users_active_month": 5
+          }
+        },
+        {
+          "community": {
+            "actor_id": "https://lemmy.ml/c/secret",
+            "name": "secret",
+            "title": "Secret",
+            "visibility": "Private"
+          }
+        },
+        {
+          "community": {
+            "actor_id": "https://other.example/c/rust",
+            "name": "rust",
+            "title": "Rust"
+          }
+        }
+      ]
+    }))?;
+    let forms: Vec<_> = list
+      .communities
+      .into_iter()
+      .filter_map(|c| c.into_form(InstanceId(1), "lemmy.ml"))
+      .map(|f| (f.name, f.subscribers, f.users_active_month))
+      .collect();
+    assert_eq!(
+      vec![("rust".to_string(), 100, 10), ("memes".to_string(), 50, 5)],
+      forms
+    );
+
+    let site: RemoteSite = serde_json::from_value(json!({
+      "all_languages": [
+        { "id": 0, "code": "und" },
+        { "id": 37, "code": "en" },
+        { "id": 39, "code": "eo" }
+      ],
+      "discussion_languages": [0, 37]
+    }))?;
+    assert_eq!(
+      vec!["und", "en"],
+      site.discussion_language_codes().collect::<Vec<_>>()
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_remote_community_list_v4() -> LemmyResult<()> {
+    let list: RemoteCommunityList = serde_json::from_value(json!({
+      "communities": [
+        {
+          "community": {
+            "ap_id": "https://lemmy.ml/c/rust",
+            "name": "rust",
+            "title": "Rust",
+            "visibility": "Public",
+            "subscribers": 100,
+            "users_active_month": 10
+          }
+        }
+      ],
+      "next_page": "abc"
+    }))?;
+    assert_eq!(Some("abc".to_string()), list.next_page);
+    let forms: Vec<_> = list
+      .communities
+      .into_iter()
+      .filter_map(|c| c.into_form(InstanceId(1), "lemmy.ml"))
+      .map(|f| (f.name, f.subscribers))
+      .collect();
+    assert_eq!(vec![("rust".to_string(), 100)], forms);
+
+    assert_eq!(
+      &[RemoteApi::LemmyV4, RemoteApi::LemmyV3],
+      RemoteApi::for_software("lemmy")
+    );
+    assert!(RemoteApi::for_software("mastodon").is_empty());
+    Ok(())
+  }
+}
@@ -0,0 +1,359 @@
+use activitypub_federation::config::Data;
+use chrono::{DateTime, TimeDelta, Utc};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  plugins::{plugin_hook_after, plugin_hook_before},
+  request::{check_url_is_public, client_builder, generate_post_link_metadata},
+  send_activity::SendActivityData,
+  utils::{check_community_user_action, get_url_blocklist, slur_regex},
+};
+use lemmy_db_schema::{
+  newtypes::DbUrl,
+  source::{
+    community::Community,
+    community_feed::CommunityFeed,
+    post::{Post, PostActions, PostInsertForm, PostLikeForm},
+    post_tag::{PostTag, PostTagForm},
+  },
+  traits::{Crud, Likeable},
+};
+use lemmy_db_views_community_moderator::CommunityModeratorView;
+use lemmy_db_views_local_user::LocalUserView;
+use lemmy_utils::{
+  error::{LemmyErrorExt, LemmyErrorExt2, LemmyErrorType, LemmyResult},
+  utils::{
+    slurs::check_slurs,
+    validation::{build_and_check_regex, is_url_blocked, is_valid_post_title, is_valid_url},
+  },
+};
+use reqwest::{header::LOCATION, Client};
+use std::io::Cursor;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Feeds are fetched again after this time.
+const FETCH_INTERVAL: TimeDelta = TimeDelta::minutes(30);
+/// Only the newest items of each feed are considered, so that registering a feed with a long
+/// history doesn't flood the community.
+const MAX_ITEMS_PER_FETCH: usize = 10;
+/// Maximum length of the stored error message.
+const MAX_ERROR_LENGTH: usize = 500;
+/// Feeds which are larger than this are rejected.
+const MAX_FEED_SIZE: usize = 5 * 1024 * 1024;
+const MAX_REDIRECTS: usize = 5;
+
+/// Fetch the RSS and Atom feeds which are registered by community moderators, and create posts
+/// for new items.
+pub(crate) async fn import_community_feeds(context: &Data<LemmyContext>) -> LemmyResult<()> {
+  let feeds = CommunityFeed::list_due(&mut context.pool(), Utc::now() - FETCH_INTERVAL).await?;
+  if feeds.is_empty() {
+    return Ok(());
+  }
+  info!("Importing {} community feeds...", feeds.len());
+
+  let client = client_builder(context.settings()).build()?;
+  for feed in feeds {
+    let error = import_feed(&feed, &client, context)
+      .await
+      .inspect_err(|e| warn!("Failed to import community feed {}: {e}", feed.url))
+      .err()
+      .map(|e| e.to_string().chars().take(MAX_ERROR_LENGTH).collect());
+    CommunityFeed::mark_fetched(&mut context.pool(), feed.id, error).await?;
+  }
+
+  info!("Finished importing community feeds");
+  Ok(())
+}
+
+async fn import_feed(
+  feed: &CommunityFeed,
+  client: &Client,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let community = Community::read(&mut context.pool(), feed.community_id).await?;
+  let bot_view = LocalUserView::read_person(&mut context.pool(), feed.bot_person_id)
+    .await
+    .with_lemmy_type(LemmyErrorType::InvalidFeedBot)?;
+  let bot = &bot_view.person;
+  if !bot.bot_account || bot.deleted {
+    Err(LemmyErrorType::InvalidFeedBot)?
+  }
+  // The bot needs to be allowed to post in the community, like any other user
+  check_community_user_action(&bot_view, &community, &mut context.pool()).await?;
+  if community.posting_restricted_to_mods {
+    CommunityModeratorView::check_is_community_moderator(&mut context.pool(), community.id, bot.id)
+      .await?;
+  }
+  let title_filter = match &feed.title_filter {
+    Some(f) => Some(build_and_check_regex(Some(f))?),
+    None => None,
+  };
+  let slur_regex = slur_regex(context).await?;
+  let url_blocklist = get_url_blocklist(context).await?;
+
+  let mut items = fetch_feed(client, feed.url.inner()).await?;
+  items.truncate(MAX_ITEMS_PER_FETCH);
+  // Import the oldest items first, so that the post order matches the feed
+  items.reverse();
+  items.sort_by_key(|i| i.published);
+
+  for item in items {
+    let title = item.title.trim().to_string();
+    if title_filter.as_ref().is_some_and(|f| !f.is_match(&title)) {
+      continue;
+    }
+    let url: Option<DbUrl> = item.url.map(Into::into);
+    if CommunityFeed::is_item_imported(&mut context.pool(), feed.id, &item.guid, url.as_ref())
+      .await?
+    {
+      continue;
+    }
+    let valid = check_slurs(&title, &slur_regex)
+      .and_then(|_| is_valid_post_title(&title))
+      .and_then(|_| match &url {
+        Some(url) => is_valid_url(url).and_then(|_| is_url_blocked(url, &url_blocklist)),
+        None => Ok(()),
+      });
+    if let Err(e) = valid {
+      debug!("Skipping item {} of feed {}: {e}", item.guid, feed.url);
+      continue;
+    }
+
+    let mut post_form = PostInsertForm {
+      url: url.clone(),
+      nsfw: Some(community.nsfw),
+      language_id: feed.language_id,
+      ..PostInsertForm::new(title, bot.id, community.id)
+    };
+    post_form = plugin_hook_before("before_create_local_post", post_form).await?;
+    let post = Post::create(&mut context.pool(), &post_form).await?;
+    plugin_hook_after("after_create_local_post", &post)?;
+
+    // Like in the create post API, the bot likes its own post
+    let like_form = PostLikeForm::new(post.id, bot.id, 1);
+    PostActions::like(&mut context.pool(), &like_form).await?;
+    if let Some(tag_id) = feed.tag_id {
+      PostTag::set(
+        &mut context.pool(),
+        &[PostTagForm {
+          post_id: post.id,
+          tag_id,
+        }],
+      )
+      .await?;
+    }
+    CommunityFeed::mark_item_imported(
+      &mut context.pool(),
+      feed.id,
+      &item.guid,
+      url.as_ref(),
+      post.id,
+    )
+    .await?;
+
+    generate_post_link_metadata(
+      post,
+      None,
+      |post| Some(SendActivityData::CreatePost(post)),
+      context.clone(),
+    )
+    .await?;
+  }
+  Ok(())
+}
+
+/// An item of an RSS or Atom feed.
+#[derive(Debug, PartialEq)]
+struct FeedItem {
+  guid: String,
+  title: String,
+  url: Option<Url>,
+  published: Option<DateTime<Utc>>,
+}
+
+/// Fetch a feed and return its items in the same order, usually newest first.
+async fn fetch_feed(client: &Client, url: &Url) -> LemmyResult<Vec<FeedItem>> {
+  // Feeds often redirect, eg from http to https. Follow redirects manually, so that none of them
+  // can point to the local network.
+  let mut url = url.clone();
+  let mut redirects = 0;
+  let mut response = loop {
+    check_url_is_public(&url).await?;
+    let response = client.get(url.as_str()).send().await?;
+    let location = response
+      .headers()
+      .get(LOCATION)
+      .and_then(|l| l.to_str().ok())
+      .filter(|_| response.status().is_redirection())
+      .map(str::to_string);
+    match location {
+      Some(location) if redirects < MAX_REDIRECTS => {
+        url = url.join(&location)?;
+        redirects += 1;
+      }
+      _ => break response.error_for_status()?,
+    }
+  };
+  // Read the body in chunks, so that huge responses are rejected without keeping them in memory
+  let mut body = Vec::new();
+  while let Some(chunk) = response.chunk().await? {
+    body.extend_from_slice(&chunk);
+    if body.len() > MAX_FEED_SIZE {
+      Err(LemmyErrorType::FeedTooLarge)?
+    }
+  }
+  parse_feed(&body)
+}
+
+fn parse_feed(body: &[u8]) -> LemmyResult<Vec<FeedItem>> {
+  if let Ok(channel) = rss::Channel::read_from(Cursor::new(body)) {
+    let items = channel
+      .items()
+      .iter()
+      .filter_map(|item| {
+        let url = item.link().and_then(|l| Url::parse(l).ok());
+        let guid = item
+          .guid()
+          .map(|g| g.value().to_string())
+          .or(url.as_ref().map(Url::to_string))?;
+        Some(FeedItem {
+          guid,
+          title: item.title()?.to_string(),
+          url,
+          published: item
+            .pub_date()
+            .and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+            .map(|d| d.to_utc()),
+        })
+      })
+      .collect();
+    return Ok(items);
+  }
+
+  let feed = atom_syndication::Feed::read_from(Cursor::new(body))
+    .with_lemmy_type(LemmyErrorType::CouldntParseFeed)?;
+  let items = feed
+    .entries()
+    .iter()
+    .map(|entry| {
+      let url = entry
+        .links()
+        .iter()
+        .find(|l| l.rel() == "alternate")
+        .or(entry.links().first())
+        .and_then(|l| Url::parse(l.href()).ok());
+      FeedItem {
+        guid: entry.id().to_string(),
+        title: entry.title().value.clone(),
+        url,
+        published: Some(entry.published().unwrap_or(entry.updated()).to_utc()),
+      }
+    })
+    .collect();
+  Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+  };
+
+  const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>News</title>
+    <link>https://news.example</link>
+    <description>News</description>
+    <item>
+      <title>Rust 2.0 released</title>
+      <link>https://news.example/rust-2</link>
+      <guid>news-2</guid>
+      <pubDate>Tue, 15 Jul 2025 10:00:00 +0000</pubDate>
+    </item>
+    <item>
+      <title>Untitled link</title>
+      <link>https://news.example/1</link>
+    </item>
+    <item>
+      <description>Item without title</description>
+    </item>
+  </channel>
+</rss>"#;
+
+  /// Serve a single HTTP response with the given body on a local port, as a stand-in for an
+  /// external feed.
+  async fn serve_once(body: &'static str) -> LemmyResult<Url> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse(&format!("http://{}/feed.xml", listener.local_addr()?))?;
+    tokio::spawn(async move {
+      let (mut stream, _) = listener.accept().await?;
+      let mut request = [0; 1024];
+      let _ = stream.read(&mut request).await?;
+      let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+      );
+      stream.write_all(response.as_bytes()).await
+    });
+    Ok(url)
+  }
+
+  #[tokio::test]
+  async fn test_fetch_rss_feed() -> LemmyResult<()> {
+    let url = serve_once(RSS).await?;
+    let items = fetch_feed(&Client::new(), &url).await?;
+    assert_eq!(
+      vec![
+        FeedItem {
+          guid: "news-2".to_string(),
+          title: "Rust 2.0 released".to_string(),
+          url: Some(Url::parse("https://news.example/rust-2")?),
+          published: Some(DateTime::parse_from_rfc3339("2025-07-15T10:00:00Z")?.to_utc()),
+        },
+        // Without guid the link is used instead
+        FeedItem {
+          guid: "https://news.example/1".to_string(),
+          title: "Untitled link".to_string(),
+          url: Some(Url::parse("https://news.example/1")?),
+          published: None,
+        },
+      ],
+      items
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_atom_feed() -> LemmyResult<()> {
+    let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Blog</title>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <updated>2025-07-16T18:30:02Z</updated>
+  <entry>
+    <title>Release notes</title>
+    <link rel="alternate" href="https://blog.example/release"/>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+    <updated>2025-07-16T18:30:02Z</updated>
+  </entry>
+</feed>"#;
+    let items = parse_feed(atom.as_bytes())?;
+    assert_eq!(
+      vec![FeedItem {
+        guid: "urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a".to_string(),
+        title: "Release notes".to_string(),
+        url: Some(Url::parse("https://blog.example/release")?),
+        published: Some(DateTime::parse_from_rfc3339("2025-07-16T18:30:02Z")?.to_utc()),
+      }],
+      items
+    );
+
+    assert!(parse_feed(b"<html></html>").is_err());
+    Ok(())
+  }
+}
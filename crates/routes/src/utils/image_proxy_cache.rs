@@ -0,0 +1,152 @@
+use chrono::{TimeDelta, Utc};
+use lemmy_api_utils::{
+  context::LemmyContext,
+  media_storage::media_storage,
+  request::fetch_pictrs_proxied_image_details,
+};
+use lemmy_db_schema::source::images::RemoteImage;
+use lemmy_db_views_site::SiteView;
+use lemmy_utils::{error::LemmyResult, settings::structs::PictrsImageMode};
+use tracing::{info, warn};
+
+/// Number of images which are evicted at once
+const BATCH_SIZE: i64 = 100;
+
+/// Removes proxied images from the pict-rs cache which weren't requested within the maximum age
+/// set by admins. Afterwards the least recently requested images are removed until the cache fits
+/// into the maximum size. Evicted images can still be requested, in which case pict-rs fetches
+/// them again. Images which were cached before sizes were tracked get their size from pict-rs
+/// first.
+pub(crate) async fn clean_image_proxy_cache(context: &LemmyContext) -> LemmyResult<()> {
+  if context.settings().pictrs()?.image_mode != PictrsImageMode::ProxyAllImages {
+    return Ok(());
+  }
+  let local_site = SiteView::read_local(&mut context.pool()).await?.local_site;
+  let mut evicted = 0;
+
+  loop {
+    let images = RemoteImage::list_unknown_size(&mut context.pool(), BATCH_SIZE).await?;
+    if images.is_empty() {
+      break;
+    }
+    for image in &images {
+      let size = fetch_pictrs_proxied_image_details(image.link.inner(), context)
+        .await
+        .ok()
+        .and_then(|details| details.size);
+      match size {
+        Some(size) => {
+          RemoteImage::set_cached(&mut context.pool(), &image.link, Some(size)).await?;
+        }
+        // Without a size the image would be listed again, so it is evicted instead
+        None => {
+          evict_image(image, context).await?;
+          evicted += 1;
+        }
+      }
+    }
+  }
+
+  let accessed_before = local_site
+    .image_proxy_max_age_days
+    .and_then(|days| Utc::now().checked_sub_signed(TimeDelta::days(days.into())));
+  if let Some(accessed_before) = accessed_before {
+    loop {
+      let images =
+        RemoteImage::list_cached(&mut context.pool(), Some(accessed_before), BATCH_SIZE).await?;
+      if images.is_empty() {
+        break;
+      }
+      for image in &images {
+        evict_image(image, context).await?;
+        evicted += 1;
+      }
+    }
+  }
+
+  if let Some(max_cache_mb) = local_site.image_proxy_max_cache_mb {
+    let max_size = i64::from(max_cache_mb) * 1024 * 1024;
+    let mut total_size = RemoteImage::cache_stats(&mut context.pool())
+      .await?
+      .total_size;
+    while total_size > max_size {
+      let images = RemoteImage::list_cached(&mut context.pool(), None, BATCH_SIZE).await?;
+      if images.is_empty() {
+        break;
+      }
+      for image in images_over_limit(&images, total_size, max_size) {
+        evict_image(image, context).await?;
+        total_size -= image.file_size.unwrap_or_default();
+        evicted += 1;
+      }
+    }
+  }
+
+  if evicted > 0 {
+    info!("Evicted {evicted} images from the image proxy cache");
+  }
+  Ok(())
+}
+
+/// Removes the image from the pict-rs cache, but keeps it in the list of images which may be
+/// proxied.
+async fn evict_image(image: &RemoteImage, context: &LemmyContext) -> LemmyResult<()> {
+  // Usually the image was already removed from pict-rs. It is marked as evicted anyway, otherwise
+  // the image would be listed again and block the cleanup.
+  if let Err(e) = media_storage(context.settings())?
+    .purge_remote(image.link.inner(), context)
+    .await
+  {
+    warn!(
+      "Failed to evict {} from the image proxy cache: {e}",
+      image.link
+    );
+  }
+  RemoteImage::set_evicted(&mut context.pool(), &image.link).await?;
+  Ok(())
+}
+
+/// The images at the start of the list which need to be evicted, so that the cache is at most
+/// `max_size` bytes large.
+fn images_over_limit(images: &[RemoteImage], total_size: i64, max_size: i64) -> &[RemoteImage] {
+  let mut size = total_size;
+  let count = images
+    .iter()
+    .take_while(|image| {
+      let over_limit = size > max_size;
+      size -= image.file_size.unwrap_or_default();
+      over_limit
+    })
+    .count();
+  images.get(..count).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use url::Url;
+
+  #[test]
+  fn test_images_over_limit() -> LemmyResult<()> {
+    let images = [100, 200, 300]
+      .into_iter()
+      .enumerate()
+      .map(|(i, size)| {
+        Ok(RemoteImage {
+          link: Url::parse(&format!("https://example.com/{i}.png"))?.into(),
+          published_at: Utc::now(),
+          last_accessed_at: Utc::now(),
+          cached: true,
+          file_size: Some(size),
+        })
+      })
+      .collect::<LemmyResult<Vec<_>>>()?;
+
+    assert_eq!(0, images_over_limit(&images, 600, 600).len());
+    assert_eq!(1, images_over_limit(&images, 600, 500).len());
+    assert_eq!(2, images_over_limit(&images, 600, 499).len());
+    assert_eq!(3, images_over_limit(&images, 600, 0).len());
+    Ok(())
+  }
+}
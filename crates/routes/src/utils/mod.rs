@@ -1,6 +1,9 @@
 use actix_cors::Cors;
 use lemmy_utils::settings::structs::Settings;
 
+pub mod community_directory;
+pub mod feed_import;
+pub mod image_proxy_cache;
 pub mod prometheus_metrics;
 pub mod scheduled_tasks;
 pub mod setup_local_site;
@@ -28,3 +31,27 @@ pub fn cors_config(settings: &Settings) -> Cors {
   }
   cors
 }
+
+/// Escape a value for use in a html attribute or text node.
+pub(crate) fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_escape_html() {
+    assert_eq!(
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;",
+      escape_html("<a href=\"x\">Tom & Jerry's</a>")
+    );
+  }
+}
@@ -1,11 +1,12 @@
 use actix_web::{rt::System, web, App, HttpServer};
 use actix_web_prom::{PrometheusMetrics, PrometheusMetricsBuilder};
 use lemmy_api_utils::context::LemmyContext;
+use lemmy_db_schema::source::activity::ReceivedActivity;
 use lemmy_utils::{
   error::{LemmyErrorType, LemmyResult},
   settings::structs::PrometheusConfig,
 };
-use prometheus::{default_registry, Encoder, Gauge, Opts, TextEncoder};
+use prometheus::{default_registry, Encoder, Gauge, IntGauge, Opts, TextEncoder};
 use std::{sync::Arc, thread};
 use tracing::error;
 
@@ -22,6 +23,7 @@ pub fn new_prometheus_metrics() -> LemmyResult<PrometheusMetrics> {
 struct PromContext {
   lemmy: LemmyContext,
   db_pool_metrics: DbPoolMetrics,
+  inbox_queue_metrics: InboxQueueMetrics,
 }
 
 struct DbPoolMetrics {
@@ -30,10 +32,16 @@ struct DbPoolMetrics {
   available: Gauge,
 }
 
+struct InboxQueueMetrics {
+  queued: IntGauge,
+  dead_lettered: IntGauge,
+}
+
 pub fn serve_prometheus(config: PrometheusConfig, lemmy_context: LemmyContext) -> LemmyResult<()> {
   let context = Arc::new(PromContext {
     lemmy: lemmy_context,
     db_pool_metrics: create_db_pool_metrics()?,
+    inbox_queue_metrics: create_inbox_queue_metrics()?,
   });
 
   // spawn thread that blocks on handling requests
@@ -62,6 +70,7 @@ pub fn serve_prometheus(config: PrometheusConfig, lemmy_context: LemmyContext) -
 async fn metrics(context: web::Data<Arc<PromContext>>) -> LemmyResult<String> {
   // collect metrics
   collect_db_pool_metrics(&context);
+  collect_inbox_queue_metrics(&context).await?;
 
   let mut buffer = Vec::new();
   let encoder = TextEncoder::new();
@@ -113,3 +122,29 @@ fn collect_db_pool_metrics(context: &PromContext) {
     .available
     .set(pool_status.available as f64);
 }
+
+// create lemmy_inbox_queue_* metrics and register them with the default registry
+fn create_inbox_queue_metrics() -> LemmyResult<InboxQueueMetrics> {
+  let metrics = InboxQueueMetrics {
+    queued: IntGauge::with_opts(Opts::new(
+      "lemmy_inbox_queue_activities",
+      "Number of received activities waiting to be processed",
+    ))?,
+    dead_lettered: IntGauge::with_opts(Opts::new(
+      "lemmy_inbox_queue_dead_lettered_activities",
+      "Number of received activities which failed processing too often",
+    ))?,
+  };
+
+  default_registry().register(Box::new(metrics.queued.clone()))?;
+  default_registry().register(Box::new(metrics.dead_lettered.clone()))?;
+
+  Ok(metrics)
+}
+
+async fn collect_inbox_queue_metrics(context: &PromContext) -> LemmyResult<()> {
+  let (queued, dead_lettered) = ReceivedActivity::count_queue(&mut context.lemmy.pool()).await?;
+  context.inbox_queue_metrics.queued.set(queued);
+  context.inbox_queue_metrics.dead_lettered.set(dead_lettered);
+  Ok(())
+}
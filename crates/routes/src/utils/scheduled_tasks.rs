@@ -1,6 +1,13 @@
-use crate::nodeinfo::{NodeInfo, NodeInfoWellKnown};
+use crate::{
+  nodeinfo::{NodeInfo, NodeInfoWellKnown},
+  utils::{
+    community_directory::crawl_community_directory,
+    feed_import::import_community_feeds,
+    image_proxy_cache::clean_image_proxy_cache,
+  },
+};
 use activitypub_federation::config::Data;
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
 use clokwerk::{AsyncScheduler, TimeUnits as CTimeUnits};
 use diesel::{
   dsl::{count, exists, not, update, IntervalDsl},
@@ -14,14 +21,17 @@ use diesel::{
   QueryableByName,
 };
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use lemmy_api::sitemap::update_sitemaps;
 use lemmy_api_utils::{
   context::LemmyContext,
   send_activity::{ActivityChannel, SendActivityData},
   utils::send_webmention,
 };
+use lemmy_apub::fetcher::backfill::restart_stalled_backfills;
 use lemmy_db_schema::{
   source::{
     community::Community,
+    federation_queue_state::{FederationQueueState, InstanceHealth},
     instance::{Instance, InstanceForm},
     local_user::LocalUser,
     post::{Post, PostUpdateForm},
@@ -57,7 +67,8 @@ pub async fn setup(context: Data<LemmyContext>) -> LemmyResult<()> {
   let mut scheduler = AsyncScheduler::with_tz(Utc);
 
   let context_1 = context.clone();
-  // Every 10 minutes update hot ranks, delete expired captchas and publish scheduled posts
+  // Every 10 minutes update hot ranks, delete expired captchas, publish scheduled posts and import
+  // community feeds
   scheduler.every(CTimeUnits::minutes(10)).run(move || {
     let context = context_1.clone();
 
@@ -74,11 +85,16 @@ pub async fn setup(context: Data<LemmyContext>) -> LemmyResult<()> {
         .await
         .inspect_err(|e| warn!("Failed to publish scheduled posts: {e}"))
         .ok();
+      import_community_feeds(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to import community feeds: {e}"))
+        .ok();
     }
   });
 
   let context_1 = context.clone();
-  // Update active counts expired bans and unpublished posts every hour
+  // Update active counts expired bans and unpublished posts, probe dead instances, restart
+  // stalled community backfills, update sitemaps and clean the image proxy cache every hour
   scheduler.every(CTimeUnits::hour(1)).run(move || {
     let context = context_1.clone();
 
@@ -95,6 +111,22 @@ pub async fn setup(context: Data<LemmyContext>) -> LemmyResult<()> {
         .await
         .inspect_err(|e| warn!("Failed to delete expired instance bans: {e}"))
         .ok();
+      probe_dead_instances(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to probe dead instances: {e}"))
+        .ok();
+      restart_stalled_backfills(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to restart community backfills: {e}"))
+        .ok();
+      update_sitemaps(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to update sitemaps: {e}"))
+        .ok();
+      clean_image_proxy_cache(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to clean image proxy cache: {e}"))
+        .ok();
     }
   });
 
@@ -104,6 +136,7 @@ pub async fn setup(context: Data<LemmyContext>) -> LemmyResult<()> {
   // - Delete old denied users
   // - Update instance software
   // - Delete old outgoing activities
+  // - Crawl other instances for the community directory
   scheduler.every(CTimeUnits::days(1)).run(move || {
     let context = context_1.reset_request_count();
 
@@ -120,10 +153,14 @@ pub async fn setup(context: Data<LemmyContext>) -> LemmyResult<()> {
         .await
         .inspect_err(|e| warn!("Failed to update instance software: {e}"))
         .ok();
-      clear_old_activities(&mut context.pool())
+      clear_old_activities(&context)
         .await
         .inspect_err(|e| warn!("Failed to clear old activities: {e}"))
         .ok();
+      crawl_community_directory(&context)
+        .await
+        .inspect_err(|e| warn!("Failed to crawl community directory: {e}"))
+        .ok();
     }
   });
 
@@ -274,10 +311,13 @@ async fn delete_expired_captcha_answers(pool: &mut DbPool<'_>) -> LemmyResult<()
   Ok(())
 }
 
-/// Clear old activities (this table gets very large)
-async fn clear_old_activities(pool: &mut DbPool<'_>) -> LemmyResult<()> {
+/// Clear old activities (this table gets very large). Received activities which are still queued
+/// are kept, and dead-lettered ones are kept for their own retention period.
+async fn clear_old_activities(context: &LemmyContext) -> LemmyResult<()> {
   info!("Clearing old activities...");
-  let mut conn = get_conn(pool).await?;
+  let dead_letter_retention_days =
+    i32::from(context.settings().federation.dead_letter_retention_days);
+  let mut conn = get_conn(&mut context.pool()).await?;
 
   diesel::delete(
     sent_activity::table.filter(sent_activity::published_at.lt(now() - IntervalDsl::days(7))),
@@ -285,10 +325,18 @@ async fn clear_old_activities(pool: &mut DbPool<'_>) -> LemmyResult<()> {
   .execute(&mut conn)
   .await?;
 
-  diesel::delete(
-    received_activity::table
-      .filter(received_activity::published_at.lt(now() - IntervalDsl::days(7))),
-  )
+  let processed = received_activity::table
+    .filter(received_activity::published_at.lt(now() - IntervalDsl::days(7)))
+    .filter(
+      received_activity::data
+        .is_null()
+        .or(received_activity::processed_at.is_not_null()),
+    );
+  diesel::delete(processed).execute(&mut conn).await?;
+
+  diesel::delete(received_activity::table.filter(
+    received_activity::dead_lettered_at.lt(now() - IntervalDsl::days(dead_letter_retention_days)),
+  ))
   .execute(&mut conn)
   .await?;
   info!("Done.");
@@ -499,6 +547,31 @@ async fn update_instance_software(
   Ok(())
 }
 
+/// Checks if instances which are considered dead because sending to them kept failing are
+/// reachable again, and resumes federation to them. Only activities from the catch-up period are
+/// sent, so that the instance isn't flooded with old activities.
+async fn probe_dead_instances(context: &LemmyContext) -> LemmyResult<()> {
+  let config = &context.settings().federation;
+  let dead_after = TimeDelta::days(config.dead_instance_days.into());
+  let catch_up_since = Utc::now() - TimeDelta::days(config.catch_up_days.into());
+
+  let dead = FederationQueueState::list_with_control(&mut context.pool())
+    .await?
+    .into_iter()
+    .filter(|(_, state, _)| state.health(dead_after) == InstanceHealth::Dead);
+  for (instance, _, _) in dead {
+    if let Some(form) = build_update_instance_form(&instance.domain, context.client()).await {
+      info!(
+        "{} is reachable again, resuming federation",
+        instance.domain
+      );
+      Instance::update(&mut context.pool(), instance.id, form).await?;
+      FederationQueueState::resume(&mut context.pool(), instance.id, catch_up_since).await?;
+    }
+  }
+  Ok(())
+}
+
 /// This builds an instance update form, for a given domain.
 /// If the instance sends a response, but doesn't have a well-known or nodeinfo,
 /// Then return a default form with only the updated field.
@@ -608,7 +681,7 @@ mod tests {
     update_hot_ranks(&mut context.pool()).await?;
     update_banned_when_expired(&mut context.pool()).await?;
     delete_instance_block_when_expired(&mut context.pool()).await?;
-    clear_old_activities(&mut context.pool()).await?;
+    clear_old_activities(&context).await?;
     overwrite_deleted_posts_and_comments(&mut context.pool()).await?;
     delete_old_denied_users(&mut context.pool()).await?;
     update_instance_software(&mut context.pool(), context.client()).await?;
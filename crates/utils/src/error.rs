@@ -35,6 +35,11 @@ pub enum LemmyErrorType {
   NotAnImageType,
   InvalidImageUpload,
   ImageUploadDisabled,
+  CouldntProcessVideo,
+  MediaTooLarge,
+  MediaQuotaExceeded,
+  InvalidMediaQuota,
+  ImageBlocked,
   NotAModOrAdmin,
   NotTopMod,
   NotLoggedIn,
@@ -146,6 +151,7 @@ pub enum LemmyErrorType {
   OauthAuthorizationInvalid,
   OauthLoginFailed,
   OauthRegistrationClosed,
+  OauthScopeNotSupported,
   CouldntCreateOauthProvider,
   CouldntUpdateOauthProvider,
   NotFound,
@@ -180,9 +186,25 @@ pub enum LemmyErrorType {
   CouldntCreateTagline,
   CouldntUpdateTagline,
   CouldntCreateImage,
+  CouldntUpdateImage,
   CouldntAllowInstance,
   CouldntBlockInstance,
   CouldntInsertActivity,
+  CouldntUpdateActivity,
+  CouldntUpdateCommunityDirectory,
+  CouldntUpdateCommunityBackfill,
+  CouldntCreateCommunityFeed,
+  CouldntUpdateCommunityFeed,
+  CouldntParseFeed,
+  FeedTooLarge,
+  ResponseTooLarge,
+  InvalidFeedBot,
+  TooManyCommunityFeeds,
+  CouldntUpdateDeviceKeys,
+  CouldntUpdateSitemap,
+  InvalidDeviceKey,
+  TooManyDeviceKeys,
+  RecipientHasNoDeviceKeys,
   CouldntCreateRateLimit,
   CouldntCreateCaptchaAnswer,
   CouldntUpdateFederationQueueState,
@@ -195,6 +217,15 @@ pub enum LemmyErrorType {
   MultiCommunityUpdateWrongUser,
   CannotCombineCommunityIdAndMultiCommunityId,
   MultiCommunityEntryLimitReached,
+  CouldntCreateOauthClient,
+  StatusMustBeReply,
+  StatusMustBePublic,
+  CantFollowYourself,
+  PersonFollowsHidden,
+  CouldntCreateRelay,
+  CouldntUpdateRelay,
+  CouldntUpdateInstancePolicy,
+  InvalidLinkRewrite,
 }
 
 /// Federation related errors, these dont need to be translated.
@@ -221,6 +252,9 @@ pub enum FederationError {
   ContradictingFilters,
   UrlWithoutDomain,
   InboxTimeout,
+  ActivitySignedByOtherActor,
+  InvalidHttpSignature,
+  InvalidRelayedActivity,
   CantDeleteSite,
   ObjectIsNotPublic,
   ObjectIsNotPrivate,
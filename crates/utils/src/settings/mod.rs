@@ -105,6 +105,16 @@ fn pictrs_placeholder_url() -> Url {
   Url::parse("http://localhost:8080").expect("parse pictrs url")
 }
 
+#[allow(clippy::expect_used)]
+fn s3_placeholder_url() -> Url {
+  Url::parse("http://localhost:9000").expect("parse s3 url")
+}
+
+#[allow(clippy::expect_used)]
+fn classifier_placeholder_url() -> Url {
+  Url::parse("http://localhost:5000/classify").expect("parse classifier url")
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1,4 +1,4 @@
-use super::pictrs_placeholder_url;
+use super::{classifier_placeholder_url, pictrs_placeholder_url, s3_placeholder_url};
 use doku::Document;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
@@ -16,6 +16,9 @@ pub struct Settings {
   /// Pictrs image server configuration.
   #[default(Some(Default::default()))]
   pub(crate) pictrs: Option<PictrsConfig>,
+  /// Where uploaded images and videos are stored. The size limits from the pictrs section also
+  /// apply to the other backends, which read the dimensions of videos with ffprobe.
+  pub media_storage: MediaStorageConfig,
   /// Email sending configuration. All options except login/password are mandatory
   #[doku(example = "Some(Default::default())")]
   pub email: Option<EmailConfig>,
@@ -51,6 +54,13 @@ pub struct Settings {
   cors_origin: Vec<String>,
   /// Print logs in JSON format. You can also disable ANSI colors in logs with env var `NO_COLOR`.
   pub json_logging: bool,
+  /// Enable a subset of the Mastodon client API under `/api/v1`, so that Mastodon apps can be
+  /// used to browse and reply.
+  pub mastodon_api: bool,
+  /// Render minimal HTML pages for posts, communities and users when they are requested by a
+  /// browser. This keeps the instance readable and link previews working without lemmy-ui, for
+  /// example when the ui container is down.
+  pub html_fallback: bool,
 }
 
 impl Settings {
@@ -108,6 +118,11 @@ pub struct PictrsConfig {
   #[doku(example = "1024")]
   pub max_upload_size: Option<u32>,
 
+  /// Maximum file size of uploads and proxied images, in megabytes. Only used by the Filesystem
+  /// and S3 backends, pict-rs has its own limit in `media.max_file_size`.
+  #[default(40)]
+  pub max_file_size: u32,
+
   /// Whether users can upload videos as post image or markdown embed.
   #[default(true)]
   pub allow_video_uploads: bool,
@@ -116,6 +131,59 @@ pub struct PictrsConfig {
   /// banners can still be uploaded.
   #[default(false)]
   pub image_upload_disabled: bool,
+
+  /// Read the duration of uploaded videos with ffprobe, and optionally transcode them with ffmpeg.
+  /// Both need to be installed on the Lemmy server. Poster frames are generated by pict-rs and
+  /// don't need this.
+  #[doku(example = "Some(Default::default())")]
+  pub video: Option<VideoConfig>,
+
+  /// Send uploaded images to an external classifier, and act on the labels which it returns.
+  /// Plugins can also classify images with the `classify_image` hook.
+  #[doku(example = "Some(Default::default())")]
+  pub classifier: Option<ImageClassifierConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageClassifierConfig {
+  /// Endpoint which receives the image as request body, and responds with a json list of labels
+  /// like `[{"name": "nsfw", "score": 0.93}]`.
+  #[default(classifier_placeholder_url())]
+  #[doku(example = "http://localhost:5000/classify")]
+  pub url: Url,
+  /// Images with any of these labels are marked as NSFW.
+  #[default(vec!["nsfw".to_string()])]
+  #[doku(example = "nsfw")]
+  pub nsfw_labels: Vec<String>,
+  /// Uploads with any of these labels are rejected and logged in the admin modlog.
+  #[doku(example = "csam")]
+  pub reject_labels: Vec<String>,
+  /// Uploads with any of these labels are quarantined: only admins can view them until one of
+  /// them approves the upload or purges it.
+  #[doku(example = "violence")]
+  pub quarantine_labels: Vec<String>,
+  /// Labels with a lower score are ignored.
+  #[default(0.8)]
+  pub min_score: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoConfig {
+  /// Path of the ffprobe binary
+  #[default("ffprobe")]
+  pub ffprobe_path: String,
+  /// Path of the ffmpeg binary
+  #[default("ffmpeg")]
+  pub ffmpeg_path: String,
+  /// Transcode uploaded videos which browsers can't play, or which are larger than `max_height`,
+  /// to mp4 with H.264 and AAC.
+  #[default(false)]
+  pub transcode: bool,
+  /// Transcoded videos are downscaled to this height.
+  #[default(720)]
+  pub max_height: u32,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default, Document, PartialEq)]
@@ -140,6 +208,50 @@ pub enum PictrsImageMode {
   ProxyAllImages,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct MediaStorageConfig {
+  pub backend: MediaStorageBackend,
+  /// Directory for the Filesystem backend
+  #[default("media")]
+  pub path: String,
+  /// Bucket for the S3 backend
+  #[doku(example = "Some(Default::default())")]
+  pub s3: Option<S3Config>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, Document, PartialEq)]
+pub enum MediaStorageBackend {
+  /// Store media in pict-rs, which also handles resizing, format conversion and the image proxy.
+  #[default]
+  Pictrs,
+  /// Store media as files in a local directory. Images are resized by Lemmy.
+  Filesystem,
+  /// Store media in S3-compatible object storage, like AWS S3, MinIO or Garage. Originals are
+  /// served through signed urls, resized images through Lemmy.
+  S3,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+  /// Address of the S3 API. Buckets are addressed by path, eg `http://localhost:9000/lemmy`.
+  #[default(s3_placeholder_url())]
+  #[doku(example = "http://localhost:9000")]
+  pub endpoint: Url,
+  #[default("us-east-1")]
+  pub region: String,
+  #[default("lemmy")]
+  pub bucket: String,
+  #[doku(example = "access_key")]
+  pub access_key: String,
+  #[doku(example = "secret_key")]
+  pub secret_key: String,
+  /// How long signed urls for media are valid (in seconds)
+  #[default(3600)]
+  pub signed_url_expiry: u32,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
 #[serde(default, deny_unknown_fields)]
 pub struct DatabaseConfig {
@@ -206,9 +318,43 @@ pub struct PrometheusConfig {
 #[serde(default, deny_unknown_fields)]
 // named federation"worker"config to disambiguate from the activitypub library configuration
 pub struct FederationWorkerConfig {
-  /// Limit to the number of concurrent outgoing federation requests per target instance.
+  /// Limit to the number of concurrent outgoing federation requests per target instance. The
+  /// limit applies separately to moderation actions, other content and votes.
   /// Set this to a higher value than 1 (e.g. 6) only if you have a huge instance (>10 activities
   /// per second) and if a receiving instance is not keeping up.
   #[default(1)]
   pub concurrent_sends_per_instance: i8,
+  /// Stop sending activities to an instance after it has been unreachable for this many days.
+  /// Dead instances are checked every hour, and sending resumes once they are reachable again.
+  #[default(3)]
+  pub dead_instance_days: u16,
+  /// When a dead instance becomes reachable again, only activities from this many days are sent
+  /// to it. Older activities are skipped.
+  #[default(1)]
+  pub catch_up_days: u16,
+  /// If a receiving instance is behind, only send the latest of multiple queued votes by the same
+  /// user on the same object.
+  #[default(true)]
+  pub coalesce_votes: bool,
+  /// Number of actors whose incoming activities are processed concurrently. Activities by the same
+  /// actor are always processed in order.
+  #[default(8)]
+  pub inbox_workers: u16,
+  /// Processing of an incoming activity is retried this many times before it is dead-lettered.
+  #[default(5)]
+  pub inbox_max_attempts: u16,
+  /// Dead-lettered incoming activities are kept for this many days, so that admins can inspect
+  /// them.
+  #[default(30)]
+  pub dead_letter_retention_days: u16,
+  /// Maximum number of older posts which are fetched when the first local user follows a remote
+  /// community. Set to 0 to disable backfilling.
+  #[default(100)]
+  pub backfill_posts: u32,
+  /// Number of top comments which are fetched for each backfilled post.
+  #[default(20)]
+  pub backfill_comments_per_post: u32,
+  /// Number of posts which are backfilled concurrently.
+  #[default(4)]
+  pub backfill_concurrency: u16,
 }
@@ -21,6 +21,9 @@ const ALLOWED_POST_URL_SCHEMES: [&str; 3] = ["http", "https", "magnet"];
 
 const BODY_MAX_LENGTH: usize = 10000;
 const POST_BODY_MAX_LENGTH: usize = 50000;
+const ENCRYPTED_BODY_MAX_LENGTH: usize = 30000;
+const DEVICE_KEY_MAX_LENGTH: usize = 10000;
+const DEVICE_NAME_MAX_LENGTH: usize = 50;
 const BIO_MAX_LENGTH: usize = 1000;
 const URL_MAX_LENGTH: usize = 2000;
 const ALT_TEXT_MAX_LENGTH: usize = 1500;
@@ -116,6 +119,46 @@ pub fn is_valid_body_field(body: &str, post: bool) -> LemmyResult<()> {
   Ok(())
 }
 
+fn is_base64(item: &str) -> bool {
+  !item.is_empty()
+    && item
+      .trim_end_matches('=')
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+/// Encrypted private messages contain base64 encoded ciphertext instead of markdown.
+pub fn is_valid_encrypted_body_field(body: &str) -> LemmyResult<()> {
+  max_length_check(
+    body,
+    ENCRYPTED_BODY_MAX_LENGTH,
+    LemmyErrorType::InvalidBodyField,
+  )?;
+  if !is_base64(body) {
+    Err(LemmyErrorType::InvalidBodyField)?
+  }
+  Ok(())
+}
+
+pub fn is_valid_device_key(public_key: &str, device_name: Option<&str>) -> LemmyResult<()> {
+  max_length_check(
+    public_key,
+    DEVICE_KEY_MAX_LENGTH,
+    LemmyErrorType::InvalidDeviceKey,
+  )?;
+  if !is_base64(public_key) {
+    Err(LemmyErrorType::InvalidDeviceKey)?
+  }
+  if let Some(device_name) = device_name {
+    max_length_check(
+      device_name,
+      DEVICE_NAME_MAX_LENGTH,
+      LemmyErrorType::InvalidDeviceKey,
+    )?;
+  }
+  Ok(())
+}
+
 pub fn is_valid_bio_field(bio: &str) -> LemmyResult<()> {
   max_length_check(bio, BIO_MAX_LENGTH, LemmyErrorType::BioLengthOverflow)
 }
@@ -373,6 +416,7 @@ mod tests {
       is_url_blocked,
       is_valid_actor_name,
       is_valid_bio_field,
+      is_valid_device_key,
       is_valid_display_name,
       is_valid_matrix_id,
       is_valid_post_title,
@@ -381,6 +425,7 @@ mod tests {
       site_or_community_description_length_check,
       truncate_for_db,
       BIO_MAX_LENGTH,
+      DEVICE_NAME_MAX_LENGTH,
       SITE_DESCRIPTION_MAX_LENGTH,
       SITE_NAME_MAX_LENGTH,
       URL_MAX_LENGTH,
@@ -546,6 +591,16 @@ Line3",
     Ok(())
   }
 
+  #[test]
+  fn test_valid_device_key() {
+    assert!(is_valid_device_key("AAECAwQ=", Some("Phone")).is_ok());
+    assert!(is_valid_device_key("not base64!", None).is_err());
+    assert!(is_valid_device_key("", None).is_err());
+    assert!(
+      is_valid_device_key("AAECAwQ=", Some(&"a".repeat(DEVICE_NAME_MAX_LENGTH + 1))).is_err()
+    );
+  }
+
   #[test]
   fn test_valid_bio() {
     assert!(is_valid_bio_field(&(0..BIO_MAX_LENGTH).map(|_| 'A').collect::<String>()).is_ok());
@@ -0,0 +1,7 @@
+ALTER TABLE local_site
+    DROP COLUMN edit_history_public;
+
+DROP TABLE comment_revision;
+
+DROP TABLE post_revision;
+
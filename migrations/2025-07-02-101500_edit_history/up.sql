@@ -0,0 +1,25 @@
+CREATE TABLE post_revision (
+    id serial PRIMARY KEY,
+    post_id int NOT NULL REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE,
+    editor_id int REFERENCES person ON UPDATE CASCADE ON DELETE SET NULL,
+    name varchar(200) NOT NULL,
+    url varchar(2000),
+    body text,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE comment_revision (
+    id serial PRIMARY KEY,
+    comment_id int NOT NULL REFERENCES comment ON UPDATE CASCADE ON DELETE CASCADE,
+    editor_id int REFERENCES person ON UPDATE CASCADE ON DELETE SET NULL,
+    content text NOT NULL,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_post_revision_post_published ON post_revision (post_id, published_at DESC, id DESC);
+
+CREATE INDEX idx_comment_revision_comment_published ON comment_revision (comment_id, published_at DESC, id DESC);
+
+ALTER TABLE local_site
+    ADD COLUMN edit_history_public bool NOT NULL DEFAULT FALSE;
+
@@ -0,0 +1,2 @@
+DROP TABLE oauth_client;
+
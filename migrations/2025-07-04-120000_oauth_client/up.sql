@@ -0,0 +1,13 @@
+-- Client applications registered through the Mastodon-compatible API
+CREATE TABLE oauth_client (
+    id serial PRIMARY KEY,
+    name text NOT NULL,
+    website text,
+    redirect_uris text NOT NULL,
+    scopes text NOT NULL,
+    client_id text NOT NULL UNIQUE,
+    -- SHA-256 of the client secret, which is only shown once on registration
+    client_secret_hash text NOT NULL,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
@@ -0,0 +1,6 @@
+ALTER TABLE local_user
+    ADD COLUMN hide_follows boolean NOT NULL DEFAULT FALSE;
+
+ALTER TYPE listing_type_enum
+    ADD VALUE 'FollowedPeople';
+
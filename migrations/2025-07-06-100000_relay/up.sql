@@ -0,0 +1,23 @@
+CREATE TYPE relay_type_enum AS ENUM (
+    'Mastodon',
+    'LitePub'
+);
+
+-- ActivityPub relays which the site actor is subscribed to
+CREATE TABLE relay (
+    id serial PRIMARY KEY,
+    ap_id text NOT NULL UNIQUE,
+    inbox_url text NOT NULL,
+    public_key text NOT NULL,
+    instance_id int NOT NULL REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE,
+    relay_type relay_type_enum NOT NULL,
+    follow_accepted boolean NOT NULL DEFAULT FALSE,
+    publish boolean NOT NULL DEFAULT FALSE,
+    received_count bigint NOT NULL DEFAULT 0,
+    last_received_at timestamptz,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz
+);
+
+CREATE INDEX idx_relay_instance ON relay (instance_id);
+
@@ -0,0 +1,16 @@
+-- Rules which are applied to content and activities received from a given instance
+CREATE TABLE instance_policy (
+    instance_id int PRIMARY KEY REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE,
+    reject_media boolean NOT NULL DEFAULT FALSE,
+    force_nsfw boolean NOT NULL DEFAULT FALSE,
+    strip_votes boolean NOT NULL DEFAULT FALSE,
+    silence boolean NOT NULL DEFAULT FALSE,
+    reject_reports boolean NOT NULL DEFAULT FALSE,
+    quarantine_communities boolean NOT NULL DEFAULT FALSE,
+    rewrite_links_from text,
+    rewrite_links_to text,
+    reason text,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz
+);
+
@@ -0,0 +1,6 @@
+DROP TABLE federation_queue_control;
+
+ALTER TABLE federation_queue_state
+    DROP COLUMN last_error,
+    DROP COLUMN last_error_at;
+
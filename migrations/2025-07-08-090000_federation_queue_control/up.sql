@@ -0,0 +1,16 @@
+-- Last error which happened while sending to an instance
+ALTER TABLE federation_queue_state
+    ADD COLUMN last_error text,
+    ADD COLUMN last_error_at timestamptz;
+
+-- Changes to the federation queue requested by admins. The federation worker for the instance is
+-- restarted to apply pending changes, and clears them afterwards.
+CREATE TABLE federation_queue_control (
+    instance_id int PRIMARY KEY REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE,
+    paused boolean NOT NULL DEFAULT FALSE,
+    skip_backlog boolean NOT NULL DEFAULT FALSE,
+    reset_fail_count boolean NOT NULL DEFAULT FALSE,
+    resend_activity_id bigint,
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
+
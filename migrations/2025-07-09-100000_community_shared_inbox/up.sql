@@ -0,0 +1,32 @@
+-- Shared inboxes of remote instances which follow local communities, with the number of followers
+-- behind each inbox. Maintained by triggers on community_actions and person.
+CREATE TABLE community_shared_inbox (
+    community_id int REFERENCES community ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    instance_id int REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    shared_inbox_url varchar(255) NOT NULL,
+    follower_count int NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (community_id, instance_id, shared_inbox_url)
+);
+
+CREATE INDEX idx_community_shared_inbox_instance_updated ON community_shared_inbox (instance_id, updated_at);
+
+INSERT INTO community_shared_inbox (community_id, instance_id, shared_inbox_url, follower_count)
+SELECT
+    community_actions.community_id,
+    person.instance_id,
+    person.inbox_url,
+    count(*)
+FROM
+    community_actions
+    INNER JOIN community ON community.id = community_actions.community_id
+    INNER JOIN person ON person.id = community_actions.person_id
+WHERE
+    community_actions.followed_at IS NOT NULL
+    AND community.local
+    AND NOT person.local
+GROUP BY
+    community_actions.community_id,
+    person.instance_id,
+    person.inbox_url;
+
@@ -0,0 +1,14 @@
+DROP INDEX idx_received_activity_queued;
+
+DROP INDEX idx_received_activity_dead_lettered;
+
+ALTER TABLE received_activity
+    DROP COLUMN id,
+    DROP COLUMN data,
+    DROP COLUMN actor_ap_id,
+    DROP COLUMN attempts,
+    DROP COLUMN next_attempt_at,
+    DROP COLUMN last_error,
+    DROP COLUMN processed_at,
+    DROP COLUMN dead_lettered_at;
+
@@ -0,0 +1,21 @@
+-- Received activities are stored and processed asynchronously, after the inbox request has
+-- returned. Processed activities keep only the ap_id for deduplication.
+ALTER TABLE received_activity
+    ADD COLUMN id bigserial NOT NULL UNIQUE,
+    ADD COLUMN data jsonb,
+    ADD COLUMN actor_ap_id text,
+    ADD COLUMN attempts int NOT NULL DEFAULT 0,
+    ADD COLUMN next_attempt_at timestamptz,
+    ADD COLUMN last_error text,
+    ADD COLUMN processed_at timestamptz,
+    ADD COLUMN dead_lettered_at timestamptz;
+
+CREATE INDEX idx_received_activity_queued ON received_activity (id)
+WHERE
+    data IS NOT NULL AND processed_at IS NULL AND dead_lettered_at IS NULL;
+
+-- Used for metrics and the retention of dead-lettered activities
+CREATE INDEX idx_received_activity_dead_lettered ON received_activity (dead_lettered_at)
+WHERE
+    dead_lettered_at IS NOT NULL;
+
@@ -0,0 +1,3 @@
+ALTER TABLE local_site
+    ADD COLUMN federation_authorized_fetch bool NOT NULL DEFAULT FALSE;
+
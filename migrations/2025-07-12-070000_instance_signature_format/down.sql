@@ -0,0 +1,6 @@
+ALTER TABLE instance
+    DROP COLUMN signature_format,
+    DROP COLUMN rfc9421_rejected_at;
+
+DROP TYPE signature_format_enum;
+
@@ -0,0 +1,11 @@
+CREATE TYPE signature_format_enum AS enum (
+    'Cavage',
+    'Rfc9421'
+);
+
+-- signature_format is used for outgoing activities. rfc9421_rejected_at is set when the instance
+-- rejected our RFC 9421 signatures, so that incoming RFC 9421 requests don't switch back to it.
+ALTER TABLE instance
+    ADD COLUMN signature_format signature_format_enum NOT NULL DEFAULT 'Cavage',
+    ADD COLUMN rfc9421_rejected_at timestamptz;
+
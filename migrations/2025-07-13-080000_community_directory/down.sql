@@ -0,0 +1,7 @@
+ALTER TABLE local_site
+    DROP COLUMN community_directory_enabled;
+
+DROP TABLE community_directory_language;
+
+DROP TABLE community_directory;
+
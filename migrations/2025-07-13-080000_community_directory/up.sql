@@ -0,0 +1,33 @@
+-- Lightweight entries for communities on other instances, filled by a background crawler. These
+-- are only used for the community directory, and are not full community actors.
+CREATE TABLE community_directory (
+    id serial PRIMARY KEY,
+    instance_id int NOT NULL REFERENCES instance ON UPDATE CASCADE ON DELETE CASCADE,
+    ap_id varchar(255) NOT NULL UNIQUE,
+    name varchar(255) NOT NULL,
+    title varchar(255) NOT NULL,
+    description text,
+    icon text,
+    nsfw bool NOT NULL DEFAULT FALSE,
+    subscribers int NOT NULL DEFAULT 0,
+    users_active_month int NOT NULL DEFAULT 0,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_community_directory_instance ON community_directory (instance_id);
+
+CREATE INDEX idx_community_directory_subscribers ON community_directory (subscribers DESC, id DESC);
+
+CREATE INDEX idx_community_directory_trigram ON community_directory USING gin (name gin_trgm_ops, title gin_trgm_ops);
+
+CREATE TABLE community_directory_language (
+    community_directory_id int NOT NULL REFERENCES community_directory ON UPDATE CASCADE ON DELETE CASCADE,
+    language_id int NOT NULL REFERENCES
+    LANGUAGE ON UPDATE CASCADE ON DELETE CASCADE,
+    PRIMARY KEY (community_directory_id, language_id)
+);
+
+ALTER TABLE local_site
+    ADD COLUMN community_directory_enabled bool NOT NULL DEFAULT FALSE;
+
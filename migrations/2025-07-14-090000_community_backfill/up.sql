@@ -0,0 +1,12 @@
+-- Progress of fetching older posts and comments of a remote community, which starts when the
+-- first local user follows it.
+CREATE TABLE community_backfill (
+    community_id int PRIMARY KEY REFERENCES community ON UPDATE CASCADE ON DELETE CASCADE,
+    posts_fetched int NOT NULL DEFAULT 0,
+    comments_fetched int NOT NULL DEFAULT 0,
+    error text,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    finished_at timestamptz
+);
+
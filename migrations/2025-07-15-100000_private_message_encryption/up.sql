@@ -0,0 +1,17 @@
+-- Public keys of user devices, used by clients to encrypt private messages. The server only stores
+-- and federates them, private keys never leave the device.
+CREATE TABLE person_device_key (
+    id serial PRIMARY KEY,
+    person_id int NOT NULL REFERENCES person ON UPDATE CASCADE ON DELETE CASCADE,
+    ap_id varchar(255) NOT NULL UNIQUE,
+    device_name varchar(255),
+    public_key text NOT NULL,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_person_device_key_person ON person_device_key (person_id);
+
+-- Encrypted messages store the ciphertext in the content column
+ALTER TABLE private_message
+    ADD COLUMN encrypted bool NOT NULL DEFAULT FALSE;
+
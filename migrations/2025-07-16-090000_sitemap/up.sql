@@ -0,0 +1,10 @@
+-- Generated sitemap files. They are updated by a scheduled task when their urls change.
+CREATE TABLE sitemap (
+    name varchar(50) PRIMARY KEY,
+    content text NOT NULL,
+    url_count int NOT NULL,
+    -- Newest modification date of the contained urls, used for the sitemap index
+    last_modified_at timestamptz NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now()
+);
+
@@ -0,0 +1,4 @@
+DROP TABLE community_feed_item;
+
+DROP TABLE community_feed;
+
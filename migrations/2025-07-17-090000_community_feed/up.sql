@@ -0,0 +1,29 @@
+-- External RSS or Atom feeds, whose items are posted to a community by a bot account.
+CREATE TABLE community_feed (
+    id serial PRIMARY KEY,
+    community_id int NOT NULL REFERENCES community ON UPDATE CASCADE ON DELETE CASCADE,
+    url text NOT NULL,
+    bot_person_id int NOT NULL REFERENCES person ON UPDATE CASCADE ON DELETE CASCADE,
+    title_filter text,
+    tag_id int REFERENCES tag ON UPDATE CASCADE ON DELETE SET NULL,
+    language_id int REFERENCES
+    LANGUAGE ON UPDATE CASCADE ON DELETE SET NULL,
+    last_fetched_at timestamptz,
+    last_error text,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz,
+    UNIQUE (community_id, url)
+);
+
+-- Feed items which were already imported, to avoid duplicate posts.
+CREATE TABLE community_feed_item (
+    community_feed_id int NOT NULL REFERENCES community_feed ON UPDATE CASCADE ON DELETE CASCADE,
+    guid text NOT NULL,
+    url text,
+    post_id int REFERENCES post ON UPDATE CASCADE ON DELETE SET NULL,
+    published_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (community_feed_id, guid)
+);
+
+CREATE INDEX idx_community_feed_item_url ON community_feed_item (community_feed_id, url);
+
@@ -0,0 +1,6 @@
+DROP TABLE image_redirect;
+
+ALTER TABLE image_details
+    DROP COLUMN duration,
+    DROP COLUMN poster_url;
+
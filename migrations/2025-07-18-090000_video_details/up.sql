@@ -0,0 +1,15 @@
+-- Duration of uploaded videos in seconds, and the url of a still frame which is used as thumbnail.
+ALTER TABLE image_details
+    ADD COLUMN duration int,
+    ADD COLUMN poster_url text;
+
+-- Uploads which were replaced by another one, eg videos after transcoding. Requests for the old
+-- alias are redirected, so that existing links keep working.
+CREATE TABLE image_redirect (
+    alias text PRIMARY KEY,
+    target_alias text NOT NULL REFERENCES local_image (pictrs_alias) ON UPDATE CASCADE ON DELETE CASCADE,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_image_redirect_target_alias ON image_redirect (target_alias);
+
@@ -0,0 +1,11 @@
+ALTER TABLE local_user
+    DROP COLUMN media_quota_mb;
+
+ALTER TABLE local_site
+    DROP COLUMN media_quota_mb,
+    DROP COLUMN new_account_media_quota_mb,
+    DROP COLUMN new_account_media_quota_days;
+
+ALTER TABLE local_image
+    DROP COLUMN file_size;
+
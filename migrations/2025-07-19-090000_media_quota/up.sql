@@ -0,0 +1,15 @@
+-- Size of uploaded files in bytes, zero for uploads from before this was recorded.
+ALTER TABLE local_image
+    ADD COLUMN file_size bigint NOT NULL DEFAULT 0;
+
+-- Upload quotas in megabytes, null means unlimited. Accounts younger than
+-- new_account_media_quota_days use the separate quota for new accounts.
+ALTER TABLE local_site
+    ADD COLUMN media_quota_mb int,
+    ADD COLUMN new_account_media_quota_mb int,
+    ADD COLUMN new_account_media_quota_days int NOT NULL DEFAULT 7;
+
+-- Overrides the quota of the site for a single user.
+ALTER TABLE local_user
+    ADD COLUMN media_quota_mb int;
+
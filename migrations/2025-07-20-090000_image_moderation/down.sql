@@ -0,0 +1,18 @@
+DELETE FROM modlog_combined
+WHERE admin_image_moderation_id IS NOT NULL;
+
+ALTER TABLE modlog_combined
+    DROP CONSTRAINT modlog_combined_check,
+    DROP COLUMN admin_image_moderation_id,
+    ADD CONSTRAINT modlog_combined_check CHECK ((num_nonnulls (admin_allow_instance_id, admin_block_instance_id, admin_purge_comment_id, admin_purge_community_id, admin_purge_person_id, admin_purge_post_id, mod_add_id, mod_add_community_id, mod_ban_id, mod_ban_from_community_id, mod_feature_post_id, mod_change_community_visibility_id, mod_lock_post_id, mod_remove_comment_id, mod_remove_community_id, mod_remove_post_id, mod_transfer_community_id) = 1));
+
+DROP TABLE admin_image_moderation;
+
+DROP TYPE image_moderation_action_enum;
+
+DROP TABLE image_hash_block;
+
+ALTER TABLE image_details
+    DROP COLUMN perceptual_hash,
+    DROP COLUMN nsfw,
+    DROP COLUMN quarantined;
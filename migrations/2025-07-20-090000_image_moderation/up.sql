@@ -0,0 +1,42 @@
+-- 64-bit perceptual hash of the image, used to match it against the blocklist.
+-- Quarantined uploads can only be viewed by admins until they are approved.
+ALTER TABLE image_details
+    ADD COLUMN perceptual_hash bigint,
+    ADD COLUMN nsfw bool NOT NULL DEFAULT FALSE,
+    ADD COLUMN quarantined bool NOT NULL DEFAULT FALSE;
+
+-- Perceptual hashes of known-bad images, managed by admins.
+CREATE TABLE image_hash_block (
+    id serial PRIMARY KEY,
+    hash bigint NOT NULL UNIQUE,
+    reason text,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TYPE image_moderation_action_enum AS ENUM (
+    'BlockHash',
+    'UnblockHash',
+    'RejectUpload',
+    'PurgeImage',
+    'MarkNsfw',
+    'QuarantineUpload',
+    'ApproveUpload'
+);
+
+-- Audit trail for image moderation. The admin is null for actions taken
+-- automatically by hash matching or the classifier.
+CREATE TABLE admin_image_moderation (
+    id serial PRIMARY KEY,
+    admin_person_id int REFERENCES person (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    person_id int REFERENCES person (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    action image_moderation_action_enum NOT NULL,
+    hash bigint,
+    image_url text,
+    reason text,
+    published_at timestamptz NOT NULL DEFAULT now()
+);
+
+ALTER TABLE modlog_combined
+    ADD COLUMN admin_image_moderation_id int UNIQUE REFERENCES admin_image_moderation (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    DROP CONSTRAINT modlog_combined_check,
+    ADD CONSTRAINT modlog_combined_check CHECK ((num_nonnulls (admin_allow_instance_id, admin_block_instance_id, admin_image_moderation_id, admin_purge_comment_id, admin_purge_community_id, admin_purge_person_id, admin_purge_post_id, mod_add_id, mod_add_community_id, mod_ban_id, mod_ban_from_community_id, mod_feature_post_id, mod_change_community_visibility_id, mod_lock_post_id, mod_remove_comment_id, mod_remove_community_id, mod_remove_post_id, mod_transfer_community_id) = 1));
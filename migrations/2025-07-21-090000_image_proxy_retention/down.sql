@@ -0,0 +1,12 @@
+ALTER TABLE instance_policy
+    DROP COLUMN never_cache_media;
+
+ALTER TABLE local_site
+    DROP COLUMN image_proxy_max_age_days,
+    DROP COLUMN image_proxy_max_cache_mb;
+
+ALTER TABLE remote_image
+    DROP COLUMN last_accessed_at,
+    DROP COLUMN cached,
+    DROP COLUMN file_size;
+
@@ -0,0 +1,20 @@
+-- Track when proxied images were last requested, whether they are currently cached and the size
+-- of the cached original. Images which were cached before have an unknown size, which is read from
+-- pict-rs by the cleanup task.
+ALTER TABLE remote_image
+    ADD COLUMN last_accessed_at timestamptz NOT NULL DEFAULT now(),
+    ADD COLUMN cached bool NOT NULL DEFAULT TRUE,
+    ADD COLUMN file_size bigint;
+
+CREATE INDEX idx_remote_image_last_accessed_at ON remote_image (last_accessed_at)
+WHERE
+    cached;
+
+-- Limits for the image proxy cache, null means unlimited.
+ALTER TABLE local_site
+    ADD COLUMN image_proxy_max_age_days int,
+    ADD COLUMN image_proxy_max_cache_mb int;
+
+ALTER TABLE instance_policy
+    ADD COLUMN never_cache_media bool NOT NULL DEFAULT FALSE;
+
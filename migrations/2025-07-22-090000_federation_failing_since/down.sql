@@ -0,0 +1,3 @@
+ALTER TABLE federation_queue_state
+    DROP COLUMN failing_since;
+
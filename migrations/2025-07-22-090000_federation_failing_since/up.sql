@@ -0,0 +1,13 @@
+-- Start of the current streak of failed send attempts, null if the last attempt succeeded.
+ALTER TABLE federation_queue_state
+    ADD COLUMN failing_since timestamptz;
+
+-- For instances which are currently failing, the last successfully sent activity is the best
+-- estimate.
+UPDATE
+    federation_queue_state
+SET
+    failing_since = coalesce(last_successful_published_time_at, last_retry_at)
+WHERE
+    fail_count > 0;
+
@@ -4,12 +4,19 @@ use lemmy_api::{
     distinguish::distinguish_comment,
     like::like_comment,
     list_comment_likes::list_comment_likes,
+    list_revisions::list_comment_revisions,
     save::save_comment,
   },
   community::{
     add_mod::add_mod_to_community,
     ban::ban_from_community,
     block::user_block_community,
+    feed::{
+      create_community_feed,
+      delete_community_feed,
+      edit_community_feed,
+      list_community_feeds,
+    },
     follow::follow_community,
     multi_community_follow::follow_multi_community,
     pending_follows::{
@@ -27,14 +34,17 @@ use lemmy_api::{
     block::user_block_person,
     change_password::change_password,
     change_password_after_reset::change_password_after_reset,
+    device_key::{add_device_key, delete_device_key},
     donation_dialog_shown::donation_dialog_shown,
     export_data::export_data,
+    follow_person::follow_person,
     generate_totp_secret::generate_totp_secret,
     get_captcha::get_captcha,
     list_hidden::list_person_hidden,
     list_liked::list_person_liked,
     list_logins::list_logins,
     list_media::list_media,
+    list_person_follows::{list_person_followers, list_person_following},
     list_read::list_person_read,
     list_saved::list_person_saved,
     login::login,
@@ -63,6 +73,7 @@ use lemmy_api::{
     hide::hide_post,
     like::like_post,
     list_post_likes::list_post_likes,
+    list_revisions::list_post_revisions,
     lock::lock_post,
     mark_many_read::mark_posts_as_read,
     mark_read::mark_post_as_read,
@@ -79,10 +90,16 @@ use lemmy_api::{
   site::{
     admin_allow_instance::admin_allow_instance,
     admin_block_instance::admin_block_instance,
+    admin_federation_queue::{admin_edit_federation_queue, admin_list_federation_queues},
+    admin_image_hash::{block_image_hash, list_image_hash_blocks, unblock_image_hash},
+    admin_image_proxy::get_image_proxy_stats,
+    admin_image_quarantine::{approve_quarantined_image, list_quarantined_images},
+    admin_instance_policy::admin_set_instance_policy,
     admin_list_users::admin_list_users,
+    admin_media_quota::admin_set_media_quota,
     federated_instances::get_federated_instances,
     leave_admin::leave_admin,
-    list_all_media::list_all_media,
+    list_all_media::{list_all_media, list_media_uploaders},
     mod_log::get_mod_log,
     purge::{
       comment::purge_comment,
@@ -109,6 +126,7 @@ use lemmy_api_crud::{
   community::{
     create::create_community,
     delete::delete_community,
+    directory::list_community_directory,
     list::list_communities,
     remove::remove_community,
     update::update_community,
@@ -159,36 +177,41 @@ use lemmy_api_crud::{
 };
 use lemmy_apub::api::{
   list_comments::{list_comments, list_comments_slim},
+  list_device_keys::list_device_keys,
   list_person_content::list_person_content,
   list_posts::list_posts,
   read_community::get_community,
   read_person::read_person,
+  relay::{add_relay, edit_relay, list_relays, remove_relay},
   resolve_object::resolve_object,
   search::search,
   user_settings_backup::{export_settings, import_settings},
 };
-use lemmy_routes::images::{
-  delete::{
-    delete_community_banner,
-    delete_community_icon,
-    delete_image,
-    delete_image_admin,
-    delete_site_banner,
-    delete_site_icon,
-    delete_user_avatar,
-    delete_user_banner,
-  },
-  download::{get_image, image_proxy},
-  pictrs_health,
-  upload::{
-    upload_community_banner,
-    upload_community_icon,
-    upload_image,
-    upload_site_banner,
-    upload_site_icon,
-    upload_user_avatar,
-    upload_user_banner,
+use lemmy_routes::{
+  images::{
+    delete::{
+      delete_community_banner,
+      delete_community_icon,
+      delete_image,
+      delete_image_admin,
+      delete_site_banner,
+      delete_site_icon,
+      delete_user_avatar,
+      delete_user_banner,
+    },
+    download::{get_image, image_proxy},
+    pictrs_health,
+    upload::{
+      upload_community_banner,
+      upload_community_icon,
+      upload_image,
+      upload_site_banner,
+      upload_site_icon,
+      upload_user_avatar,
+      upload_user_banner,
+    },
   },
+  oembed::get_oembed,
 };
 use lemmy_utils::rate_limit::RateLimit;
 
@@ -208,6 +231,7 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("/banner", delete().to(delete_site_banner)),
       )
       .route("/modlog", get().to(get_mod_log))
+      .route("/oembed", get().to(get_oembed))
       .service(
         resource("/search")
           .wrap(rate_limit.search())
@@ -231,6 +255,7 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("", put().to(update_community))
           .route("/random", get().to(get_random_community))
           .route("/list", get().to(list_communities))
+          .route("/directory", get().to(list_community_directory))
           .route("/follow", post().to(follow_community))
           .route("/report", post().to(create_community_report))
           .route("/report/resolve", put().to(resolve_community_report))
@@ -247,6 +272,10 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("/tag", post().to(create_community_tag))
           .route("/tag", put().to(update_community_tag))
           .route("/tag", delete().to(delete_community_tag))
+          .route("/feed", post().to(create_community_feed))
+          .route("/feed", put().to(edit_community_feed))
+          .route("/feed", delete().to(delete_community_feed))
+          .route("/feed/list", get().to(list_community_feeds))
           .service(
             scope("/pending_follows")
               .route("/count", get().to(get_pending_follows_count))
@@ -292,6 +321,7 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("/list", get().to(list_posts))
           .route("/like", post().to(like_post))
           .route("/like/list", get().to(list_post_likes))
+          .route("/revision/list", get().to(list_post_revisions))
           .route("/save", put().to(save_post))
           .route("/report", post().to(create_post_report))
           .route("/report/resolve", put().to(resolve_post_report)),
@@ -314,6 +344,7 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("/distinguish", post().to(distinguish_comment))
           .route("/like", post().to(like_comment))
           .route("/like/list", get().to(list_comment_likes))
+          .route("/revision/list", get().to(list_comment_revisions))
           .route("/save", put().to(save_comment))
           .route("/list", get().to(list_comments))
           .route("/list/slim", get().to(list_comments_slim))
@@ -380,6 +411,8 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           .route("/list_logins", get().to(list_logins))
           .route("/validate_auth", get().to(validate_auth))
           .route("/donation_dialog_shown", post().to(donation_dialog_shown))
+          .route("/device_key", post().to(add_device_key))
+          .route("/device_key/delete", post().to(delete_device_key))
           .route("/avatar", post().to(upload_user_avatar))
           .route("/avatar", delete().to(delete_user_avatar))
           .route("/banner", post().to(upload_user_banner))
@@ -413,7 +446,11 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
         scope("/person")
           .route("", get().to(read_person))
           .route("/content", get().to(list_person_content))
-          .route("/note", post().to(user_note_person)),
+          .route("/note", post().to(user_note_person))
+          .route("/device_keys", get().to(list_device_keys))
+          .route("/follow", post().to(follow_person))
+          .route("/followers", get().to(list_person_followers))
+          .route("/following", get().to(list_person_following)),
       )
       // Admin Actions
       .service(
@@ -451,11 +488,36 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
           )
           .route("/ban", post().to(ban_from_site))
           .route("/users", get().to(admin_list_users))
+          .route("/media_quota", put().to(admin_set_media_quota))
           .route("/leave", post().to(leave_admin))
           .service(
             scope("/instance")
               .route("/block", post().to(admin_block_instance))
-              .route("/allow", post().to(admin_allow_instance)),
+              .route("/allow", post().to(admin_allow_instance))
+              .route("/policy", put().to(admin_set_instance_policy)),
+          )
+          .service(
+            scope("/image_hash")
+              .route("", post().to(block_image_hash))
+              .route("/remove", post().to(unblock_image_hash))
+              .route("/list", get().to(list_image_hash_blocks)),
+          )
+          .service(
+            scope("/image_quarantine")
+              .route("/approve", post().to(approve_quarantined_image))
+              .route("/list", get().to(list_quarantined_images)),
+          )
+          .service(
+            scope("/federation_queue")
+              .route("", put().to(admin_edit_federation_queue))
+              .route("/list", get().to(admin_list_federation_queues)),
+          )
+          .service(
+            scope("/relay")
+              .route("", post().to(add_relay))
+              .route("", put().to(edit_relay))
+              .route("/remove", post().to(remove_relay))
+              .route("/list", get().to(list_relays)),
           ),
       )
       .service(
@@ -485,8 +547,10 @@ pub fn config(cfg: &mut ServiceConfig, rate_limit: &RateLimit) {
               .route(delete().to(delete_image_admin)),
           )
           .route("/proxy", get().to(image_proxy))
+          .route("/proxy/stats", get().to(get_image_proxy_stats))
           .route("/health", get().to(pictrs_health))
           .route("/list", get().to(list_all_media))
+          .route("/uploaders", get().to(list_media_uploaders))
           .route("/{filename}", get().to(get_image)),
       ),
   );
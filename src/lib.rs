@@ -10,7 +10,7 @@ use actix_web::{
   HttpServer,
 };
 use clap::{Parser, Subcommand};
-use lemmy_api::sitemap::get_sitemap;
+use lemmy_api::sitemap::{get_sitemap, get_sitemap_file};
 use lemmy_api_utils::{
   context::LemmyContext,
   request::client_builder,
@@ -20,6 +20,7 @@ use lemmy_api_utils::{
 use lemmy_apub::{
   activities::{handle_outgoing_activities, match_outgoing_activities},
   collections::fetch_community_collections,
+  http::inbox_queue::process_received_activities,
   VerifyUrlData,
   FEDERATION_HTTP_FETCH_LIMIT,
 };
@@ -29,11 +30,14 @@ use lemmy_db_views_site::SiteView;
 use lemmy_federate::{Opts, SendManager};
 use lemmy_routes::{
   feeds,
+  html,
+  mastodon,
   middleware::{
     idempotency::{IdempotencyMiddleware, IdempotencySet},
     session::SessionMiddleware,
   },
   nodeinfo,
+  oembed,
   utils::{
     cors_config,
     prometheus_metrics::{new_prometheus_metrics, serve_prometheus},
@@ -94,6 +98,12 @@ pub struct CmdArgs {
   /// See https://join-lemmy.org/docs/administration/horizontal_scaling.html for details.
   #[arg(long, default_value_t = false, env = "LEMMY_DISABLE_ACTIVITY_SENDING")]
   disable_activity_sending: bool,
+  /// Don't process incoming ActivityPub messages.
+  ///
+  /// Received activities are stored by the HTTP server and processed in the background. If you are
+  /// running multiple Lemmy server processes, pass this to all but one of them.
+  #[arg(long, default_value_t = false, env = "LEMMY_DISABLE_INBOX_PROCESSING")]
+  disable_inbox_processing: bool,
   /// The index of this outgoing federation process.
   ///
   /// Defaults to 1/1. If you want to split the federation workload onto n servers, run each server
@@ -244,6 +254,10 @@ pub async fn start_lemmy_server(args: CmdArgs) -> LemmyResult<()> {
   let outgoing_activities_task =
     tokio::task::spawn(handle_outgoing_activities(request_data.clone()));
 
+  if !args.disable_inbox_processing {
+    let _inbox_queue_task = tokio::task::spawn(process_received_activities(request_data.clone()));
+  }
+
   if !args.disable_scheduled_tasks {
     // Schedules various cleanup tasks for the DB
     let _scheduled_tasks = tokio::task::spawn(scheduled_tasks::setup(request_data.clone()));
@@ -368,6 +382,17 @@ fn create_http_server(
     // The routes
     app
       .configure(|cfg| api_routes::config(cfg, &rate_limit))
+      .configure(|cfg| {
+        if settings.mastodon_api {
+          mastodon::config(cfg, &rate_limit);
+        }
+      })
+      .configure(|cfg| {
+        // Must come before the federation routes which use the same paths
+        if settings.html_fallback {
+          html::config(cfg, &rate_limit);
+        }
+      })
       .configure(|cfg| {
         if site_view.local_site.federation_enabled {
           lemmy_apub::http::routes::config(cfg);
@@ -376,11 +401,17 @@ fn create_http_server(
       })
       .configure(feeds::config)
       .configure(nodeinfo::config)
+      .configure(|cfg| oembed::config(cfg, &rate_limit))
       .service(
         scope("/sitemap.xml")
           .wrap(rate_limit.message())
           .route("", get().to(get_sitemap)),
       )
+      .service(
+        scope("/sitemap")
+          .wrap(rate_limit.message())
+          .route("/{name}.xml", get().to(get_sitemap_file)),
+      )
   })
   .disable_signals()
   .bind(bind)?